@@ -1,34 +1,159 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
+use axum::Extension;
 use axum::extract::Query;
 use axum::http::{HeaderMap, StatusCode, header};
 use axum::response::{IntoResponse, Response};
+use database_connection::Database;
 use futures_util::StreamExt;
 use reqwest::Client;
+use scraper_core::{ScraperManager, metrics, rate_limit};
+use scraper_types::{ChapterPage, PageImage};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::Config;
+use crate::objects::chapters::Chapter;
 
 const MAX_IMAGE_SIZE: usize = 10 * 1024 * 1024; // 10MB
 const CACHE_DURATION: u64 = 3600; // 1 hour
 
-pub async fn proxy_image(Query(params): Query<std::collections::HashMap<String, String>>, headers: HeaderMap) -> Response {
-	let url = match params.get("url") {
-		Some(url) => url,
-		None => return (StatusCode::BAD_REQUEST, "Missing URL parameter").into_response(),
+pub async fn proxy_image(
+	Query(params): Query<std::collections::HashMap<String, String>>,
+	Extension(db): Extension<Arc<Database>>,
+	Extension(config): Extension<Arc<Config>>,
+	Extension(scraper_manager): Extension<Arc<ScraperManager>>,
+	headers: HeaderMap,
+) -> Response {
+	// Chapter pages are looked up by position so the proxy can send their headers and fall back to mirrors.
+	let chapter_page = match (params.get("chapter"), params.get("page")) {
+		(Some(chapter_id), Some(index)) => {
+			let (Ok(chapter_id), Ok(index)) = (chapter_id.parse::<i32>(), index.parse::<usize>()) else {
+				return (StatusCode::BAD_REQUEST, "Invalid chapter or page parameter").into_response();
+			};
+			let pages = match Chapter::load_pages(&db, &config, &scraper_manager, chapter_id).await {
+				Ok(pages) => pages,
+				Err(e) => {
+					tracing::error!("failed to load pages of chapter {}: {}", chapter_id, e.message);
+					return (StatusCode::BAD_GATEWAY, "Failed to load chapter pages").into_response();
+				}
+			};
+			match pages.into_iter().nth(index) {
+				Some(page) => Some(page),
+				None => return (StatusCode::NOT_FOUND, "Page not found").into_response(),
+			}
+		}
+		_ => None,
 	};
 
-	if url.is_empty() {
-		return (StatusCode::BAD_REQUEST, "Empty URL parameter").into_response();
-	}
+	let page = match chapter_page {
+		Some(page) => page,
+		None => match params.get("url") {
+			Some(url) if !url.is_empty() => ChapterPage::from(url.clone()),
+			Some(_) => return (StatusCode::BAD_REQUEST, "Empty URL parameter").into_response(),
+			None => return (StatusCode::BAD_REQUEST, "Missing URL parameter").into_response(),
+		},
+	};
+	let url = &page.url;
 
-	let referer = match params.get("referer") {
-		Some(referer) => Some(referer.clone()),
+	let referer = match page.referer.clone().or_else(|| params.get("referer").cloned()) {
+		Some(referer) => Some(referer),
 		None => headers
 			.get(header::REFERER)
 			.and_then(|h| h.to_str().ok())
 			.map(|s| s.to_string()),
 	};
 
+	// Pages of scrapers that transform them are run through the plugin before being served.
+	let transformer = match params.get("scraper") {
+		Some(scraper_id) => {
+			let Some(plugin) = scraper_manager.get_plugin(scraper_id).await else {
+				return (StatusCode::BAD_REQUEST, "Unknown scraper").into_response();
+			};
+			plugin.transforms_pages().then(|| (scraper_id.clone(), plugin))
+		}
+		None => None,
+	};
+
+	let cache_key = transformer
+		.as_ref()
+		.map(|(scraper_id, _)| format!("page_transform:{}:{}", scraper_id, url));
+	if let Some(ref key) = cache_key
+		&& let Some((content_type, bytes)) = cached_image(&db, key).await
+	{
+		return image_response(bytes, &content_type);
+	}
+
+	// Unknown scrapers were turned away above, so this only labels metrics with loaded ones.
+	let scraper = params.get("scraper").map(String::as_str);
+	let (bytes, final_mime) = match fetch_page(&page, referer.clone(), scraper).await {
+		Ok(image) => image,
+		Err((status, message)) => return (status, message).into_response(),
+	};
+
+	let (Some((scraper_id, plugin)), Some(key)) = (transformer, cache_key) else {
+		return image_response(bytes, &final_mime);
+	};
+
+	let page = PageImage {
+		url: url.clone(),
+		referer,
+		content_type: final_mime.clone(),
+		data: bytes.clone(),
+	};
+	match plugin.transform_page(page).await {
+		Ok(Some(processed)) => {
+			if !is_valid_image_type(&processed.content_type) || processed.data.len() > MAX_IMAGE_SIZE {
+				tracing::error!(
+					"scraper {} returned an unusable page for {} ('{}', {} bytes)",
+					scraper_id,
+					url,
+					processed.content_type,
+					processed.data.len()
+				);
+				return (StatusCode::BAD_GATEWAY, "Invalid transformed image").into_response();
+			}
+
+			store_image(&db, &config, key, &processed.content_type, &processed.data).await;
+			image_response(processed.data, &processed.content_type)
+		}
+		Ok(None) => image_response(bytes, &final_mime),
+		Err(e) => {
+			tracing::error!("scraper {} failed to transform {}: {:#}", scraper_id, url, e);
+			(StatusCode::BAD_GATEWAY, "Failed to transform image").into_response()
+		}
+	}
+}
+
+/// Tries the page's url, then each mirror, returning the last error if none of them work. `scraper` is the one the
+/// page belongs to, if known.
+async fn fetch_page(
+	page: &ChapterPage,
+	referer: Option<String>,
+	scraper: Option<&str>,
+) -> Result<(Vec<u8>, String), (StatusCode, &'static str)> {
 	let client = Client::new();
+	let mut last_error = (StatusCode::BAD_GATEWAY, "Failed to fetch image");
+
+	for url in page.sources() {
+		match fetch_image(&client, url, referer.as_deref(), &page.headers, scraper).await {
+			Ok(image) => return Ok(image),
+			Err(e) => last_error = e,
+		}
+	}
 
+	Err(last_error)
+}
+
+async fn fetch_image(
+	client: &Client,
+	url: &str,
+	referer: Option<&str>,
+	extra_headers: &BTreeMap<String, String>,
+	scraper: Option<&str>,
+) -> Result<(Vec<u8>, String), (StatusCode, &'static str)> {
 	let mut request_builder = client.get(url);
 
 	if let Some(referer) = referer {
@@ -39,17 +164,28 @@ pub async fn proxy_image(Query(params): Query<std::collections::HashMap<String,
 		"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/139.0.0.0 Safari/537.36";
 	request_builder = request_builder.header(header::USER_AGENT, ua);
 
+	// Sent last so a page can override the referer or user agent.
+	for (name, value) in extra_headers {
+		request_builder = request_builder.header(name.as_str(), value.as_str());
+	}
+
+	// Shares the host's budget with the scrapers, so readers paging through a chapter cannot get us banned.
+	rate_limit::limiter().acquire(url).await;
 	let response = match request_builder.send().await {
 		Ok(res) => res,
 		Err(err) => {
 			tracing::error!("fetch error for {}: {}", url, err);
-			return (StatusCode::BAD_GATEWAY, "Failed to fetch image").into_response();
+			metrics::record_proxied_response("image_proxy", scraper, None);
+			return Err((StatusCode::BAD_GATEWAY, "Failed to fetch image"));
 		}
 	};
+	let retry_after = response.headers().get(header::RETRY_AFTER).and_then(|h| h.to_str().ok());
+	rate_limit::limiter().record_response(url, response.status().as_u16(), retry_after);
+	metrics::record_proxied_response("image_proxy", scraper, Some(response.status().as_u16()));
 
 	if !response.status().is_success() {
 		tracing::error!("upstream returned non-success: {} for {}", response.status(), url);
-		return (StatusCode::BAD_GATEWAY, "Upstream returned error").into_response();
+		return Err((StatusCode::BAD_GATEWAY, "Upstream returned error"));
 	}
 
 	let header_ct = response
@@ -67,11 +203,11 @@ pub async fn proxy_image(Query(params): Query<std::collections::HashMap<String,
 			Ok(c) => c,
 			Err(e) => {
 				tracing::error!("error reading chunk: {}", e);
-				return (StatusCode::BAD_GATEWAY, "Error reading image data").into_response();
+				return Err((StatusCode::BAD_GATEWAY, "Error reading image data"));
 			}
 		};
 		if bytes.len() + chunk.len() > MAX_IMAGE_SIZE {
-			return (StatusCode::PAYLOAD_TOO_LARGE, "Image too large").into_response();
+			return Err((StatusCode::PAYLOAD_TOO_LARGE, "Image too large"));
 		}
 		bytes.extend_from_slice(&chunk);
 	}
@@ -102,13 +238,17 @@ pub async fn proxy_image(Query(params): Query<std::collections::HashMap<String,
 
 	if final_mime.is_empty() {
 		tracing::error!("unsupported or unknown image type for url {}. header: '{}'", url, header_ct);
-		return (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Unsupported image format").into_response();
+		return Err((StatusCode::UNSUPPORTED_MEDIA_TYPE, "Unsupported image format"));
 	}
 
+	Ok((bytes, final_mime))
+}
+
+fn image_response(bytes: Vec<u8>, content_type: &str) -> Response {
 	let mut response_builder = Response::builder()
 		.status(StatusCode::OK)
 		.header(header::CACHE_CONTROL, format!("public, max-age={}", CACHE_DURATION))
-		.header(header::CONTENT_TYPE, &final_mime);
+		.header(header::CONTENT_TYPE, content_type);
 
 	if let Some(expires) = SystemTime::now().checked_add(Duration::from_secs(CACHE_DURATION)) {
 		let formatted = httpdate::fmt_http_date(expires);
@@ -124,6 +264,41 @@ pub async fn proxy_image(Query(params): Query<std::collections::HashMap<String,
 	}
 }
 
+/// Transformed pages are cached in the temp table as the content type, a newline, then the image bytes.
+async fn cached_image(db: &Database, key: &str) -> Option<(String, Vec<u8>)> {
+	let cached = database_entities::temp::Entity::find()
+		.filter(database_entities::temp::Column::Key.eq(key))
+		.filter(database_entities::temp::Column::ExpiresAt.gt(chrono::Utc::now().naive_utc()))
+		.one(&db.conn)
+		.await
+		.inspect_err(|e| tracing::warn!("failed to read cached page {}: {}", key, e))
+		.ok()?;
+	crate::metrics::record_cache_lookup("page_transform", cached.is_some());
+	let cached = cached?;
+
+	let split = cached.value.iter().position(|b| *b == b'\n')?;
+	let content_type = String::from_utf8(cached.value[..split].to_vec()).ok()?;
+	Some((content_type, cached.value[split + 1..].to_vec()))
+}
+
+async fn store_image(db: &Database, config: &Config, key: String, content_type: &str, data: &[u8]) {
+	let mut value = Vec::with_capacity(content_type.len() + 1 + data.len());
+	value.extend_from_slice(content_type.as_bytes());
+	value.push(b'\n');
+	value.extend_from_slice(data);
+
+	let active_model = database_entities::temp::ActiveModel {
+		key: Set(key.clone()),
+		value: Set(value),
+		expires_at: Set((chrono::Utc::now() + chrono::Duration::minutes(config.cache.images_minutes as i64)).naive_utc()),
+		..Default::default()
+	};
+
+	if let Err(e) = database_entities::temp::Entity::insert(active_model).exec(&db.conn).await {
+		tracing::warn!("failed to cache transformed page {}: {}", key, e);
+	}
+}
+
 fn is_valid_image_type(content_type: &str) -> bool {
 	if content_type.is_empty() {
 		return false;
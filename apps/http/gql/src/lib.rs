@@ -4,8 +4,8 @@ use std::sync::Arc;
 use std::{env, fs};
 
 use anyhow::Context;
-use async_graphql::{EmptySubscription, Schema};
-use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use async_graphql::Schema;
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 use axum::http::{HeaderMap, HeaderValue, Method, header};
 use axum::routing::{get, post};
 use axum::{Extension, Router};
@@ -14,6 +14,7 @@ use jsonwebtoken::{DecodingKey, Validation, decode};
 use rand::Rng;
 use rustls::pki_types::CertificateDer;
 use rustls_pemfile::certs;
+use scheduler::{MaintenanceScheduler, SchedulerControl};
 use scraper_core::ScraperManager;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
@@ -23,12 +24,15 @@ use crate::mutations::MutationRoot;
 use crate::mutations::auth::Claims;
 use crate::objects::users::User;
 use crate::queries::QueryRoot;
+use crate::subscriptions::SubscriptionRoot;
 
 mod image_proxy;
+mod metrics;
 mod mutations;
 mod objects;
 mod queries;
 mod serve_file;
+mod subscriptions;
 
 use axum::extract::{DefaultBodyLimit, State};
 
@@ -64,11 +68,16 @@ pub struct Config {
 	#[serde(default)]
 	pub cache: CacheConfig,
 	#[serde(default)]
+	pub global_search: GlobalSearchConfig,
+	#[serde(default)]
 	pub cors_allow_origins: Vec<String>,
 	#[serde(default)]
 	pub cert_path: Option<String>,
 	#[serde(default)]
 	pub key_path: Option<String>,
+	/// Users allowed to inspect and steer the scheduler. When empty, only the first registered user is.
+	#[serde(default)]
+	pub admin_usernames: Vec<String>,
 }
 
 impl Default for Config {
@@ -80,9 +89,11 @@ impl Default for Config {
 			max_file_size: 10 * 1024 * 1024, // 10 MB
 			uploads_folder: format!("{}/uploads", current_exe_parent_dir().display()),
 			cache: CacheConfig::default(),
+			global_search: GlobalSearchConfig::default(),
 			cors_allow_origins: vec!["http://localhost:5227".into()],
 			cert_path: None,
 			key_path: None,
+			admin_usernames: Vec::new(),
 		}
 	}
 }
@@ -99,6 +110,11 @@ pub struct CacheConfig {
 	pub images_minutes: u64,
 	#[serde(default = "default_novel_minutes")]
 	pub novel_minutes: u64,
+	#[serde(default = "default_genre_minutes")]
+	pub genre_minutes: u64,
+	/// How long a scraper's persisted genre list is used before it is scraped again.
+	#[serde(default = "default_genre_catalog_minutes")]
+	pub genre_catalog_minutes: u64,
 }
 
 impl Default for CacheConfig {
@@ -109,6 +125,8 @@ impl Default for CacheConfig {
 			trending_minutes: default_trending_minutes(),
 			images_minutes: default_images_minutes(),
 			novel_minutes: default_novel_minutes(),
+			genre_minutes: default_genre_minutes(),
+			genre_catalog_minutes: default_genre_catalog_minutes(),
 		}
 	}
 }
@@ -128,6 +146,38 @@ fn default_images_minutes() -> u64 {
 fn default_novel_minutes() -> u64 {
 	10080
 }
+fn default_genre_minutes() -> u64 {
+	20
+}
+fn default_genre_catalog_minutes() -> u64 {
+	10080
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GlobalSearchConfig {
+	/// Scrapers that have not answered by then are reported as timed out.
+	#[serde(default = "default_global_search_timeout_seconds")]
+	pub timeout_seconds: u64,
+	/// Scrapers searched at the same time. Per-scraper limits of the scheduler still apply on top.
+	#[serde(default = "default_global_search_max_concurrency")]
+	pub max_concurrency: usize,
+}
+
+impl Default for GlobalSearchConfig {
+	fn default() -> Self {
+		Self {
+			timeout_seconds: default_global_search_timeout_seconds(),
+			max_concurrency: default_global_search_max_concurrency(),
+		}
+	}
+}
+
+fn default_global_search_timeout_seconds() -> u64 {
+	20
+}
+fn default_global_search_max_concurrency() -> usize {
+	8
+}
 
 impl Config {
 	pub fn use_tls(&self) -> bool {
@@ -136,7 +186,7 @@ impl Config {
 }
 
 async fn graphql_handler(
-	State(schema): State<Schema<QueryRoot, MutationRoot, EmptySubscription>>,
+	State(schema): State<Schema<QueryRoot, MutationRoot, SubscriptionRoot>>,
 	Extension(config): Extension<Arc<Config>>,
 	Extension(db): Extension<Arc<Database>>,
 	headers: HeaderMap,
@@ -185,25 +235,15 @@ async fn graphql_handler(
 
 async fn graphql_playground() -> axum::response::Html<String> {
 	axum::response::Html(async_graphql::http::playground_source(
-		async_graphql::http::GraphQLPlaygroundConfig::new("/"),
+		async_graphql::http::GraphQLPlaygroundConfig::new("/").subscription_endpoint("/ws"),
 	))
 }
 
 pub async fn run(db: Arc<Database>, scraper_manager: Arc<ScraperManager>) -> anyhow::Result<()> {
 	let config = Arc::new(Config::load());
 
-	let cleanup_db = db.clone();
-	tokio::spawn(async move {
-		loop {
-			database_entities::temp::Entity::delete_many()
-				.filter(database_entities::temp::Column::ExpiresAt.lt(chrono::Utc::now().naive_utc()))
-				.exec(&cleanup_db.conn)
-				.await
-				.expect("Failed to clean up expired temp entries");
-
-			tokio::time::sleep(std::time::Duration::from_secs(60)).await;
-		}
-	});
+	let maintenance = MaintenanceScheduler::new(db.clone(), scraper_manager.clone());
+	tokio::spawn(maintenance.clone().start());
 
 	let cors = if config.cors_allow_origins.iter().any(|o| o == "*") {
 		tracing::warn!("CORS is set to allow all origins.");
@@ -221,21 +261,27 @@ pub async fn run(db: Arc<Database>, scraper_manager: Arc<ScraperManager>) -> any
 	.allow_methods([Method::GET, Method::POST, Method::OPTIONS])
 	.allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT, header::COOKIE]);
 
-	let schema = Schema::build(QueryRoot::default(), MutationRoot::default(), EmptySubscription)
+	let schema = Schema::build(QueryRoot::default(), MutationRoot::default(), SubscriptionRoot::default())
 		.data(db.clone())
-		.data(scraper_manager)
+		.data(scraper_manager.clone())
 		.data(config.clone())
+		.data(Arc::new(SchedulerControl::new(db.clone())))
+		.data(maintenance)
 		.finish();
 
 	let app = Router::new()
 		.route("/playground", get(graphql_playground))
 		.route("/", post(graphql_handler))
+		.route_service("/ws", GraphQLSubscription::new(schema.clone()))
 		.layer(DefaultBodyLimit::max(config.max_file_size as usize))
 		.route("/files/{file_id}", get(serve_file::serve_file))
 		.route("/proxy", get(image_proxy::proxy_image))
+		.route("/metrics", get(telemetry::render_metrics))
 		.layer(cors)
+		.layer(axum::middleware::from_fn(metrics::track_requests))
 		.layer(Extension(config.clone()))
 		.layer(Extension(db))
+		.layer(Extension(scraper_manager))
 		.with_state(schema)
 		.into_make_service();
 
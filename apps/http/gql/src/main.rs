@@ -1,14 +1,12 @@
 use database_connection::Database;
 use scraper_core::ScraperManager;
-use tracing_subscriber::FmtSubscriber;
 
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-	let subscriber = FmtSubscriber::builder().with_max_level(tracing::Level::INFO).finish();
-	let _ = tracing::subscriber::set_global_default(subscriber);
+	let _telemetry = telemetry::init("manga-vault-gql");
 
 	let db = Database::new().await?;
 
@@ -0,0 +1,41 @@
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use telemetry::metrics::registry;
+
+/// Counts every request by route, method and response status. Unmatched paths share one label so scanners
+/// cannot grow the registry.
+pub(crate) async fn track_requests(request: Request, next: Next) -> Response {
+	let route = request
+		.extensions()
+		.get::<MatchedPath>()
+		.map_or("unmatched", |path| path.as_str())
+		.to_string();
+	let method = request.method().to_string();
+	let started = std::time::Instant::now();
+
+	let response = next.run(request).await;
+
+	let status = response.status().as_u16().to_string();
+	registry().increment(
+		"http_requests_total",
+		"API requests, by route, method and response status.",
+		&[("route", &route), ("method", &method), ("status", &status)],
+	);
+	registry().observe(
+		"http_request_duration_seconds",
+		"Duration of API requests, by route.",
+		&[("route", &route)],
+		started.elapsed().as_secs_f64(),
+	);
+	response
+}
+
+/// Counts a lookup in the temp table, by what is cached there.
+pub(crate) fn record_cache_lookup(cache: &str, hit: bool) {
+	registry().increment(
+		"temp_cache_lookups_total",
+		"Lookups in the temp table cache, by cache and result.",
+		&[("cache", cache), ("result", if hit { "hit" } else { "miss" })],
+	);
+}
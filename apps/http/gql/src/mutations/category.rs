@@ -3,6 +3,7 @@ use std::sync::Arc;
 use async_graphql::{Context, InputObject, Object, Result};
 use chrono::Utc;
 use database_connection::Database;
+use scheduler::SchedulerControl;
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set};
 
 use crate::objects::categories::Category;
@@ -11,11 +12,14 @@ use crate::objects::users::User;
 #[derive(InputObject)]
 struct CreateCategoryInput {
 	name: String,
+	auto_update: Option<bool>,
 }
 
 #[derive(InputObject, Default)]
 struct UpdateCategoryInput {
 	name: Option<String>,
+	/// Turns scheduled updates of the category's mangas and novels off or back on.
+	auto_update: Option<bool>,
 }
 
 #[derive(Default)]
@@ -30,6 +34,7 @@ impl CategoryMutation {
 		let category = database_entities::categories::ActiveModel {
 			user_id: Set(current_user.id),
 			name: Set(input.name),
+			auto_update: Set(input.auto_update.unwrap_or(true)),
 			created_at: Set(Utc::now().naive_utc()),
 			..Default::default()
 		};
@@ -54,6 +59,7 @@ impl CategoryMutation {
 		let category_update = database_entities::categories::ActiveModel {
 			id: Set(category.id),
 			name: Set(input.name.unwrap_or(category.name)),
+			auto_update: Set(input.auto_update.unwrap_or(category.auto_update)),
 			..Default::default()
 		};
 
@@ -61,6 +67,24 @@ impl CategoryMutation {
 		Ok(Category::from(updated))
 	}
 
+	/// Queues updates of every manga and novel in the category ahead of scheduled ones. Returns how many were queued.
+	async fn update_category_now(&self, ctx: &Context<'_>, id: i32) -> Result<usize> {
+		let db = ctx.data::<Arc<Database>>()?;
+		let current_user = ctx.data::<User>().cloned()?;
+
+		let category = database_entities::categories::Entity::find_by_id(id)
+			.one(&db.conn)
+			.await?
+			.ok_or_else(|| async_graphql::Error::new("Category not found"))?;
+
+		if current_user.id != category.user_id {
+			return Err(async_graphql::Error::new("Unauthorized"));
+		}
+
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.enqueue_category(category.id).await?)
+	}
+
 	async fn delete_category(&self, ctx: &Context<'_>, id: i32) -> Result<bool> {
 		let db = ctx.data::<Arc<Database>>()?;
 		let current_user = ctx.data::<User>().cloned()?;
@@ -0,0 +1,20 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::objects::users::require_admin;
+
+#[derive(Default)]
+pub struct LoggingMutation;
+
+#[Object]
+impl LoggingMutation {
+	/// Applies `filter` directives such as `info,plugin::mangadex=debug` until the next reload, or reads `logging`
+	/// from `telemetry.json` again without one. Only affects this process. Returns the filter now in effect.
+	async fn reload_logging(&self, ctx: &Context<'_>, filter: Option<String>) -> Result<String> {
+		require_admin(ctx).await?;
+		let directives = match filter {
+			Some(filter) => telemetry::set_log_filter(&filter)?,
+			None => telemetry::reload_logging()?,
+		};
+		Ok(directives)
+	}
+}
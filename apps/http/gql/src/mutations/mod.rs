@@ -6,11 +6,13 @@ mod chapter;
 mod favorite_manga;
 mod favorite_novel;
 mod file;
+mod logging;
 mod manga;
 mod manga_pack;
 mod novel;
 mod novel_chapter;
 mod profile;
+mod scheduler;
 
 #[derive(SimpleObject, Default)]
 pub struct MutationRoot {
@@ -25,4 +27,6 @@ pub struct MutationRoot {
 	chapter: chapter::ChapterMutation,
 	novel_chapter: novel_chapter::NovelChapterMutation,
 	files: file::FileMutation,
+	scheduler: scheduler::SchedulerMutation,
+	logging: logging::LoggingMutation,
 }
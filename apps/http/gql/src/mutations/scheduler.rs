@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use scheduler::{ALL_SCRAPERS, ItemType, MaintenanceScheduler, MaintenanceTask, SchedulerControl};
+
+use crate::objects::users::require_admin;
+
+#[derive(Default)]
+pub struct SchedulerMutation;
+
+#[Object]
+impl SchedulerMutation {
+	/// Stops scheduling and running updates for every scraper until resumed.
+	async fn pause_scheduler(&self, ctx: &Context<'_>, reason: Option<String>) -> Result<bool> {
+		let admin = require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		control.pause(ALL_SCRAPERS, reason, Some(admin.username)).await?;
+		Ok(true)
+	}
+
+	async fn resume_scheduler(&self, ctx: &Context<'_>) -> Result<bool> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.resume(ALL_SCRAPERS).await?)
+	}
+
+	/// Stops scheduling and running updates for one scraper, e.g. while its source is down.
+	async fn pause_scraper(&self, ctx: &Context<'_>, scraper_id: String, reason: Option<String>) -> Result<bool> {
+		let admin = require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		control.pause(&scraper_id, reason, Some(admin.username)).await?;
+		Ok(true)
+	}
+
+	async fn resume_scraper(&self, ctx: &Context<'_>, scraper_id: String) -> Result<bool> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.resume(&scraper_id).await?)
+	}
+
+	/// Queues an update of the manga at the highest priority and returns the job key.
+	async fn enqueue_manga_update(&self, ctx: &Context<'_>, manga_id: i32) -> Result<String> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.enqueue(ItemType::Manga, manga_id).await?)
+	}
+
+	/// Queues an update of the novel at the highest priority and returns the job key.
+	async fn enqueue_novel_update(&self, ctx: &Context<'_>, novel_id: i32) -> Result<String> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.enqueue(ItemType::Novel, novel_id).await?)
+	}
+
+	/// Starts a maintenance task, e.g. `vacuum`, right away. Returns false while another process is running it.
+	async fn run_maintenance_task(&self, ctx: &Context<'_>, name: String) -> Result<bool> {
+		require_admin(ctx).await?;
+		let task = MaintenanceTask::from_name(&name)
+			.ok_or_else(|| async_graphql::Error::new(format!("Unknown maintenance task `{}`", name)))?;
+		let maintenance = ctx.data::<Arc<MaintenanceScheduler>>()?;
+		Ok(maintenance.trigger(task).await?)
+	}
+
+	/// Drops a job that has not started yet. Returns false if there was no such job.
+	async fn drop_scheduler_job(&self, ctx: &Context<'_>, key: String) -> Result<bool> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.drop_job(&key).await?)
+	}
+
+	/// Queues a dead-lettered job again with its attempts reset. Returns false if there was no such job.
+	async fn replay_scheduler_job(&self, ctx: &Context<'_>, key: String) -> Result<bool> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.replay(&key).await?)
+	}
+
+	/// Queues every dead-lettered job again, or only those of one scraper. Returns how many were replayed.
+	async fn replay_dead_letters(&self, ctx: &Context<'_>, scraper_id: Option<String>) -> Result<u64> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.replay_all(scraper_id.as_deref()).await?)
+	}
+
+	/// Deletes a dead-lettered job. Returns false if there was no such job.
+	async fn discard_scheduler_job(&self, ctx: &Context<'_>, key: String) -> Result<bool> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.discard(&key).await?)
+	}
+}
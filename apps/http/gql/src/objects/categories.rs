@@ -14,6 +14,8 @@ pub struct Category {
 	pub name: String,
 	pub user_id: i32,
 	pub created_at: NaiveDateTime,
+	/// Whether the scheduler keeps the mangas and novels in it up to date.
+	pub auto_update: bool,
 }
 
 impl From<database_entities::categories::Model> for Category {
@@ -23,6 +25,7 @@ impl From<database_entities::categories::Model> for Category {
 			name: category.name,
 			user_id: category.user_id,
 			created_at: category.created_at,
+			auto_update: category.auto_update,
 		}
 	}
 }
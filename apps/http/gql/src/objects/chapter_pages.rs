@@ -0,0 +1,41 @@
+use async_graphql::SimpleObject;
+
+#[derive(SimpleObject, Clone)]
+pub struct PageHeader {
+	pub name: String,
+	pub value: String,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct ChapterPage {
+	/// Position in the chapter. Pass it with the chapter id to the image proxy to fetch the page with its headers
+	/// and mirrors.
+	pub index: usize,
+	pub url: String,
+	pub headers: Vec<PageHeader>,
+	pub referer: Option<String>,
+	pub width: Option<u32>,
+	pub height: Option<u32>,
+	pub mirrors: Vec<String>,
+	/// Whether the size hints describe a double-page spread.
+	pub spread: bool,
+}
+
+impl From<(usize, scraper_types::ChapterPage)> for ChapterPage {
+	fn from((index, page): (usize, scraper_types::ChapterPage)) -> Self {
+		Self {
+			index,
+			spread: page.is_spread(),
+			url: page.url,
+			headers: page
+				.headers
+				.into_iter()
+				.map(|(name, value)| PageHeader { name, value })
+				.collect(),
+			referer: page.referer,
+			width: page.width,
+			height: page.height,
+			mirrors: page.mirrors,
+		}
+	}
+}
@@ -8,6 +8,7 @@ use sea_orm::ActiveValue::Set;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
 
 use crate::objects::chapter_number::sort_by_chapter_title;
+use crate::objects::chapter_pages::ChapterPage;
 use crate::objects::mangas::Manga;
 use crate::objects::scraper::Scraper;
 
@@ -50,58 +51,18 @@ impl Chapter {
 	}
 
 	async fn images(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<Vec<String>> {
-		let db = ctx.data::<Arc<Database>>()?;
-
-		let chapter = database_entities::chapters::Entity::find_by_id(self.id)
-			.one(&db.conn)
-			.await?
-			.ok_or_else(|| async_graphql::Error::new("Chapter not found"))?;
-
-		let manga = self.manga(ctx).await?;
-
-		let cached_urls = database_entities::temp::Entity::find()
-			.filter(database_entities::temp::Column::Key.like(format!("chapter_{}_%", chapter.id)))
-			.all(&db.conn)
-			.await?;
-
-		let mut urls: Vec<String> = Vec::new();
-
-		if cached_urls.is_empty() {
-			let scraper = ctx
-				.data::<Arc<ScraperManager>>()?
-				.get_plugin(&manga.scraper)
-				.await
-				.ok_or_else(|| async_graphql::Error::new("Scraper not found"))?;
-
-			let scraped_content = scraper.scrape_chapter(chapter.url).await?;
-
-			let mut active_models = Vec::new();
-
-			let config = ctx.data::<Arc<crate::Config>>()?;
-			for (index, url) in scraped_content.iter().enumerate() {
-				active_models.push(database_entities::temp::ActiveModel {
-					key: Set(format!("chapter_{}_{}", self.id, index)),
-					value: Set(url.clone().into_bytes()),
-					expires_at: Set(
-						(chrono::Utc::now() + chrono::Duration::minutes(config.cache.images_minutes as i64)).naive_utc(),
-					),
-					..Default::default()
-				});
-			}
-
-			database_entities::temp::Entity::insert_many(active_models)
-				.exec(&db.conn)
-				.await?;
+		let pages = self.pages(ctx).await?;
+		Ok(pages.into_iter().map(|page| page.url).collect())
+	}
 
-			urls = scraped_content;
-		} else {
-			cached_urls.iter().for_each(|cached| {
-				let s = String::from_utf8(cached.value.clone()).unwrap_or_default();
-				urls.push(s);
-			});
-		}
+	/// The chapter's pages with the metadata the image proxy uses to fetch them.
+	async fn pages(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<Vec<ChapterPage>> {
+		let db = ctx.data::<Arc<Database>>()?;
+		let config = ctx.data::<Arc<crate::Config>>()?;
+		let scraper_manager = ctx.data::<Arc<ScraperManager>>()?;
 
-		Ok(urls)
+		let pages = Chapter::load_pages(db, config, scraper_manager, self.id).await?;
+		Ok(pages.into_iter().enumerate().map(ChapterPage::from).collect())
 	}
 
 	async fn next_chapter(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<Option<Chapter>> {
@@ -169,6 +130,79 @@ impl Chapter {
 }
 
 impl Chapter {
+	/// Scraped pages of a chapter, cached in the temp table as one JSON [`scraper_types::ChapterPage`] per page.
+	pub(crate) async fn load_pages(
+		db: &Database,
+		config: &crate::Config,
+		scraper_manager: &ScraperManager,
+		chapter_id: i32,
+	) -> async_graphql::Result<Vec<scraper_types::ChapterPage>> {
+		let key_prefix = format!("chapter_{}_", chapter_id);
+		let cached = database_entities::temp::Entity::find()
+			.filter(database_entities::temp::Column::Key.starts_with(&key_prefix))
+			.filter(database_entities::temp::Column::ExpiresAt.gt(chrono::Utc::now().naive_utc()))
+			.all(&db.conn)
+			.await?;
+
+		// `starts_with` also matches other chapters, e.g. `chapter_12_` for chapter 1, so only numeric suffixes count.
+		let mut cached_pages: Vec<(usize, scraper_types::ChapterPage)> = cached
+			.into_iter()
+			.filter_map(|entry| {
+				let index = entry.key.strip_prefix(&key_prefix)?.parse().ok()?;
+				// Entries cached before pages carried metadata are bare URLs.
+				let page = serde_json::from_slice(&entry.value).unwrap_or_else(|_| {
+					scraper_types::ChapterPage::from(String::from_utf8_lossy(&entry.value).into_owned())
+				});
+				Some((index, page))
+			})
+			.collect();
+
+		crate::metrics::record_cache_lookup("chapter_pages", !cached_pages.is_empty());
+		if !cached_pages.is_empty() {
+			cached_pages.sort_by_key(|(index, _)| *index);
+			return Ok(cached_pages.into_iter().map(|(_, page)| page).collect());
+		}
+
+		let chapter = database_entities::chapters::Entity::find_by_id(chapter_id)
+			.one(&db.conn)
+			.await?
+			.ok_or_else(|| async_graphql::Error::new("Chapter not found"))?;
+
+		let scraper: String = database_entities::mangas::Entity::find_by_id(chapter.manga_id)
+			.select_only()
+			.column(database_entities::mangas::Column::Scraper)
+			.into_tuple()
+			.one(&db.conn)
+			.await?
+			.ok_or_else(|| async_graphql::Error::new("Manga not found"))?;
+
+		let plugin = scraper_manager
+			.get_plugin(&scraper)
+			.await
+			.ok_or_else(|| async_graphql::Error::new("Scraper not found"))?;
+
+		let pages = plugin.scrape_chapter_pages(chapter.url).await?;
+
+		let expires_at = (chrono::Utc::now() + chrono::Duration::minutes(config.cache.images_minutes as i64)).naive_utc();
+		let mut active_models = Vec::with_capacity(pages.len());
+		for (index, page) in pages.iter().enumerate() {
+			active_models.push(database_entities::temp::ActiveModel {
+				key: Set(format!("{}{}", key_prefix, index)),
+				value: Set(serde_json::to_vec(page)?),
+				expires_at: Set(expires_at),
+				..Default::default()
+			});
+		}
+
+		if !active_models.is_empty() {
+			database_entities::temp::Entity::insert_many(active_models)
+				.exec(&db.conn)
+				.await?;
+		}
+
+		Ok(pages)
+	}
+
 	pub fn sort_chapters(chapters: &mut [Chapter]) {
 		sort_by_chapter_title(chapters, |c: &Chapter| c.title.as_str());
 	}
@@ -1,5 +1,6 @@
 pub mod categories;
 pub mod chapter_number;
+pub mod chapter_pages;
 pub mod chapters;
 pub mod favorite_mangas;
 pub mod favorite_novels;
@@ -10,6 +11,8 @@ pub mod novel_chapters;
 pub mod novels;
 pub mod read_chapters;
 pub mod read_novel_chapters;
+pub mod scheduler;
 pub mod scraper;
+pub mod scraper_genres;
 pub mod temp;
 pub mod users;
@@ -41,11 +41,12 @@ impl NovelChapter {
 		let config = ctx.data::<std::sync::Arc<crate::Config>>()?;
 
 		let cache_key = format!("novel_chapter_{}", self.id);
-		if let Some(cached) = database_entities::temp::Entity::find()
+		let cached = database_entities::temp::Entity::find()
 			.filter(database_entities::temp::Column::Key.eq(cache_key.clone()))
 			.one(&db.conn)
-			.await?
-		{
+			.await?;
+		crate::metrics::record_cache_lookup("novel_chapter", cached.is_some());
+		if let Some(cached) = cached {
 			let decompressed = decode_all(cached.value.as_slice())
 				.map_err(|e| async_graphql::Error::new(format!("Decompression error: {}", e)))?;
 			let s = String::from_utf8(decompressed).unwrap_or_default();
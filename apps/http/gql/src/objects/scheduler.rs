@@ -0,0 +1,243 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::NaiveDateTime;
+use scheduler::{ALL_SCRAPERS, ItemType, MaintenanceTaskStatus, QueuedJob, StoredJobInfo, WorkerInfo};
+use scraper_core::limits::LimiterSnapshot;
+use scraper_core::rate_limit::HostLimitSnapshot;
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum SchedulerItemType {
+	Manga,
+	Novel,
+}
+
+impl From<ItemType> for SchedulerItemType {
+	fn from(item_type: ItemType) -> Self {
+		match item_type {
+			ItemType::Manga => SchedulerItemType::Manga,
+			ItemType::Novel => SchedulerItemType::Novel,
+		}
+	}
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum SchedulerJobStatus {
+	Pending,
+	Leased,
+	/// Given up on; the dead-letter list.
+	Failed,
+}
+
+impl SchedulerJobStatus {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			SchedulerJobStatus::Pending => scheduler::STATUS_PENDING,
+			SchedulerJobStatus::Leased => scheduler::STATUS_LEASED,
+			SchedulerJobStatus::Failed => scheduler::STATUS_FAILED,
+		}
+	}
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct SchedulerPause {
+	/// `None` when the whole scheduler is paused.
+	pub scraper_id: Option<String>,
+	pub reason: Option<String>,
+	pub paused_by: Option<String>,
+	pub created_at: NaiveDateTime,
+}
+
+impl From<database_entities::scheduler_pauses::Model> for SchedulerPause {
+	fn from(pause: database_entities::scheduler_pauses::Model) -> Self {
+		Self {
+			scraper_id: Some(pause.scope).filter(|scope| scope != ALL_SCRAPERS),
+			reason: pause.reason,
+			paused_by: pause.paused_by,
+			created_at: pause.created_at,
+		}
+	}
+}
+
+/// A job in the memory of the scheduler running in the API's process.
+#[derive(SimpleObject, Clone)]
+pub struct QueuedSchedulerJob {
+	pub key: String,
+	pub item_type: SchedulerItemType,
+	pub item_id: i32,
+	pub scraper_id: String,
+	pub priority: u8,
+	pub attempts: u32,
+	pub due_in_ms: u64,
+	pub running_for_ms: Option<u64>,
+}
+
+impl From<QueuedJob> for QueuedSchedulerJob {
+	fn from(job: QueuedJob) -> Self {
+		Self {
+			key: job.key,
+			item_type: job.item_type.into(),
+			item_id: job.item_id,
+			scraper_id: job.scraper,
+			priority: job.priority,
+			attempts: job.attempts,
+			due_in_ms: job.due_in.as_millis() as u64,
+			running_for_ms: job.running_for.map(|d| d.as_millis() as u64),
+		}
+	}
+}
+
+/// A job as stored for all scheduler processes.
+#[derive(SimpleObject, Clone)]
+pub struct StoredSchedulerJob {
+	pub key: String,
+	pub item_type: Option<SchedulerItemType>,
+	pub item_id: Option<i32>,
+	pub scraper_id: Option<String>,
+	pub priority: u8,
+	pub status: String,
+	pub attempts: u32,
+	pub retry_at: NaiveDateTime,
+	/// The scheduler process working on the job.
+	pub lease_owner: Option<String>,
+	pub lease_expires_at: Option<NaiveDateTime>,
+	pub last_error: Option<String>,
+	pub updated_at: NaiveDateTime,
+}
+
+impl From<StoredJobInfo> for StoredSchedulerJob {
+	fn from(job: StoredJobInfo) -> Self {
+		Self {
+			key: job.key,
+			item_type: job.item_type.map(SchedulerItemType::from),
+			item_id: job.item_id,
+			scraper_id: job.scraper,
+			priority: job.priority,
+			status: job.status,
+			attempts: job.attempts,
+			retry_at: job.retry_at,
+			lease_owner: job.lease_owner,
+			lease_expires_at: job.lease_expires_at,
+			last_error: job.last_error,
+			updated_at: job.updated_at,
+		}
+	}
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct SchedulerJobCount {
+	pub status: String,
+	pub count: i64,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct ScraperLimit {
+	pub scraper_id: String,
+	pub max_concurrency: usize,
+	pub in_use: usize,
+	pub cooldown_remaining_ms: u64,
+}
+
+impl From<LimiterSnapshot> for ScraperLimit {
+	fn from(snapshot: LimiterSnapshot) -> Self {
+		Self {
+			scraper_id: snapshot.scraper,
+			max_concurrency: snapshot.max_concurrency,
+			in_use: snapshot.in_use,
+			cooldown_remaining_ms: snapshot.cooldown_remaining.as_millis() as u64,
+		}
+	}
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct HostRateLimit {
+	pub host: String,
+	pub requests_per_minute: u32,
+	pub burst: u32,
+	/// Requests that may be sent right away.
+	pub available: u32,
+	/// Time left before a `Retry-After` from the host lets requests through again.
+	pub blocked_for_ms: u64,
+}
+
+impl From<HostLimitSnapshot> for HostRateLimit {
+	fn from(snapshot: HostLimitSnapshot) -> Self {
+		Self {
+			host: snapshot.host,
+			requests_per_minute: snapshot.limit.requests_per_minute,
+			burst: snapshot.limit.burst,
+			available: snapshot.available,
+			blocked_for_ms: snapshot.blocked_for.as_millis() as u64,
+		}
+	}
+}
+
+/// A scheduler process, as its last heartbeat described it.
+#[derive(SimpleObject, Clone)]
+pub struct SchedulerWorker {
+	pub instance_id: String,
+	pub hostname: String,
+	pub pid: u32,
+	/// Whether it scans for due mangas and novels. Every worker runs stored jobs.
+	pub leader: bool,
+	/// False once it missed its heartbeats.
+	pub alive: bool,
+	pub queued_jobs: u32,
+	pub running_jobs: u32,
+	pub started_at: NaiveDateTime,
+	pub heartbeat_at: NaiveDateTime,
+}
+
+impl From<WorkerInfo> for SchedulerWorker {
+	fn from(worker: WorkerInfo) -> Self {
+		Self {
+			instance_id: worker.instance_id,
+			hostname: worker.hostname,
+			pid: worker.pid,
+			leader: worker.leader,
+			alive: worker.alive,
+			queued_jobs: worker.queued_jobs,
+			running_jobs: worker.running_jobs,
+			started_at: worker.started_at,
+			heartbeat_at: worker.heartbeat_at,
+		}
+	}
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct MaintenanceTask {
+	pub name: String,
+	/// Cron expression in the server's local time, `None` when the task is disabled.
+	pub schedule: Option<String>,
+	pub next_run_at: Option<NaiveDateTime>,
+	/// Whether every process runs the task rather than one at a time.
+	pub per_process: bool,
+	/// `running`, `succeeded` or `failed` for the last run, `None` until the task first ran.
+	pub status: Option<String>,
+	/// `schedule` or `manual`.
+	pub trigger: Option<String>,
+	/// Instance id of the process that ran it last.
+	pub run_by: Option<String>,
+	pub started_at: Option<NaiveDateTime>,
+	pub finished_at: Option<NaiveDateTime>,
+	pub duration_ms: Option<i64>,
+	/// What the last run did, or why it failed.
+	pub message: Option<String>,
+}
+
+impl From<MaintenanceTaskStatus> for MaintenanceTask {
+	fn from(status: MaintenanceTaskStatus) -> Self {
+		let run = status.last_run;
+		Self {
+			name: status.task.name().to_string(),
+			schedule: status.schedule,
+			next_run_at: status.next_run_at,
+			per_process: status.task.per_process(),
+			status: run.as_ref().map(|run| run.status.clone()),
+			trigger: run.as_ref().map(|run| run.trigger.clone()),
+			run_by: run.as_ref().map(|run| run.run_by.clone()),
+			started_at: run.as_ref().map(|run| run.started_at),
+			finished_at: run.as_ref().and_then(|run| run.finished_at),
+			duration_ms: run.as_ref().and_then(|run| run.duration_ms),
+			message: run.and_then(|run| run.message),
+		}
+	}
+}
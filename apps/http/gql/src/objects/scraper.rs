@@ -1,9 +1,15 @@
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use anyhow::Result;
 use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use scraper_core::health::{HealthSnapshot, HealthState};
 use scraper_core::plugins::Plugin;
-use scraper_types::ScraperType as CoreScraperType;
+use scraper_types::{
+	PageStatus as CorePageStatus, ScraperType as CoreScraperType, SearchFilterSpec as CoreSearchFilterSpec,
+	SortOrder as CoreSortOrder,
+};
 
 #[derive(Enum, Copy, Clone, Eq, PartialEq)]
 pub enum ScraperType {
@@ -18,6 +24,10 @@ pub struct Scraper {
 	pub image_url: String,
 	pub referer_url: Option<String>,
 	pub r#type: ScraperType,
+	/// Filters the `search` query accepts for this scraper. `None` when it only supports a text query.
+	pub search_filters: Option<SearchFilterSpec>,
+	/// Chapter pages must be loaded through `/proxy` with `scraper` set to this scraper's id.
+	pub transforms_pages: bool,
 }
 
 impl Scraper {
@@ -35,6 +45,148 @@ impl Scraper {
 			image_url: info.img_url,
 			referer_url: info.referer_url,
 			r#type: gql_type,
+			search_filters: info.search_filters.map(SearchFilterSpec::from),
+			transforms_pages: info.transforms_pages,
 		})
 	}
 }
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum PageStatus {
+	Ongoing,
+	Completed,
+	Hiatus,
+	Cancelled,
+}
+
+impl From<CorePageStatus> for PageStatus {
+	fn from(status: CorePageStatus) -> Self {
+		match status {
+			CorePageStatus::Ongoing => PageStatus::Ongoing,
+			CorePageStatus::Completed => PageStatus::Completed,
+			CorePageStatus::Hiatus => PageStatus::Hiatus,
+			CorePageStatus::Cancelled => PageStatus::Cancelled,
+		}
+	}
+}
+
+impl From<PageStatus> for CorePageStatus {
+	fn from(status: PageStatus) -> Self {
+		match status {
+			PageStatus::Ongoing => CorePageStatus::Ongoing,
+			PageStatus::Completed => CorePageStatus::Completed,
+			PageStatus::Hiatus => CorePageStatus::Hiatus,
+			PageStatus::Cancelled => CorePageStatus::Cancelled,
+		}
+	}
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum SortOrder {
+	Relevance,
+	Latest,
+	Popular,
+	Alphabetical,
+	Rating,
+}
+
+impl From<CoreSortOrder> for SortOrder {
+	fn from(sort: CoreSortOrder) -> Self {
+		match sort {
+			CoreSortOrder::Relevance => SortOrder::Relevance,
+			CoreSortOrder::Latest => SortOrder::Latest,
+			CoreSortOrder::Popular => SortOrder::Popular,
+			CoreSortOrder::Alphabetical => SortOrder::Alphabetical,
+			CoreSortOrder::Rating => SortOrder::Rating,
+		}
+	}
+}
+
+impl From<SortOrder> for CoreSortOrder {
+	fn from(sort: SortOrder) -> Self {
+		match sort {
+			SortOrder::Relevance => CoreSortOrder::Relevance,
+			SortOrder::Latest => CoreSortOrder::Latest,
+			SortOrder::Popular => CoreSortOrder::Popular,
+			SortOrder::Alphabetical => CoreSortOrder::Alphabetical,
+			SortOrder::Rating => CoreSortOrder::Rating,
+		}
+	}
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct SearchFilterSpec {
+	pub genres: Vec<String>,
+	pub exclude_genres: bool,
+	pub statuses: Vec<PageStatus>,
+	pub types: Vec<String>,
+	pub sort_orders: Vec<SortOrder>,
+	pub year: bool,
+}
+
+impl From<CoreSearchFilterSpec> for SearchFilterSpec {
+	fn from(spec: CoreSearchFilterSpec) -> Self {
+		Self {
+			genres: spec.genres,
+			exclude_genres: spec.exclude_genres,
+			statuses: spec.statuses.into_iter().map(PageStatus::from).collect(),
+			types: spec.types,
+			sort_orders: spec.sort_orders.into_iter().map(SortOrder::from).collect(),
+			year: spec.year,
+		}
+	}
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum ScraperHealthState {
+	Healthy,
+	Failing,
+	Quarantined,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct ScraperHealth {
+	pub scraper_id: String,
+	pub state: ScraperHealthState,
+	pub successes: u64,
+	pub failures: u64,
+	pub consecutive_failures: u32,
+	pub p50_ms: Option<u64>,
+	pub p90_ms: Option<u64>,
+	pub p99_ms: Option<u64>,
+	pub last_error_kind: Option<String>,
+	pub last_error: Option<String>,
+	pub last_success_at: Option<NaiveDateTime>,
+	pub last_failure_at: Option<NaiveDateTime>,
+	pub quarantined_until: Option<NaiveDateTime>,
+}
+
+fn naive(time: Option<SystemTime>) -> Option<NaiveDateTime> {
+	time.map(|time| DateTime::<Utc>::from(time).naive_utc())
+}
+
+impl From<HealthSnapshot> for ScraperHealth {
+	fn from(snapshot: HealthSnapshot) -> Self {
+		let state = match snapshot.state {
+			HealthState::Healthy => ScraperHealthState::Healthy,
+			HealthState::Failing => ScraperHealthState::Failing,
+			HealthState::Quarantined => ScraperHealthState::Quarantined,
+		};
+
+		Self {
+			scraper_id: snapshot.scraper,
+			state,
+			successes: snapshot.successes,
+			failures: snapshot.failures,
+			consecutive_failures: snapshot.consecutive_failures,
+			p50_ms: snapshot.p50_ms,
+			p90_ms: snapshot.p90_ms,
+			p99_ms: snapshot.p99_ms,
+			last_error_kind: snapshot.last_error_kind,
+			last_error: snapshot.last_error,
+			last_success_at: naive(snapshot.last_success_at),
+			last_failure_at: naive(snapshot.last_failure_at),
+			quarantined_until: naive(snapshot.quarantined_until),
+		}
+	}
+}
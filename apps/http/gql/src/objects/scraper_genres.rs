@@ -0,0 +1,24 @@
+use async_graphql::SimpleObject;
+use chrono::NaiveDateTime;
+
+#[derive(SimpleObject, Clone)]
+pub struct ScraperGenre {
+	pub id: i32,
+	pub scraper: String,
+	pub name: String,
+	/// Pass to `browseGenre` to list the genre's works.
+	pub url: String,
+	pub updated_at: NaiveDateTime,
+}
+
+impl From<database_entities::scraper_genres::Model> for ScraperGenre {
+	fn from(genre: database_entities::scraper_genres::Model) -> Self {
+		Self {
+			id: genre.id,
+			scraper: genre.scraper,
+			name: genre.name,
+			url: genre.url,
+			updated_at: genre.updated_at,
+		}
+	}
+}
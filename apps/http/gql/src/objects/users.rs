@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
-use async_graphql::SimpleObject;
+use async_graphql::{Context, SimpleObject};
 use chrono::NaiveDateTime;
 use database_connection::Database;
-use sea_orm::EntityTrait;
+use sea_orm::{EntityTrait, QueryOrder};
 
 use crate::objects::files::File;
 
@@ -60,6 +60,28 @@ impl From<RawUser> for User {
 	}
 }
 
+/// The signed-in user, if they may administrate the server. See `Config::admin_usernames`.
+pub(crate) async fn require_admin(ctx: &Context<'_>) -> async_graphql::Result<User> {
+	let user = ctx.data::<User>().cloned()?;
+	let config = ctx.data::<Arc<crate::Config>>()?;
+
+	let is_admin = if config.admin_usernames.is_empty() {
+		let db = ctx.data::<Arc<Database>>()?;
+		let first = database_entities::users::Entity::find()
+			.order_by_asc(database_entities::users::Column::Id)
+			.one(&db.conn)
+			.await?;
+		first.is_some_and(|first| first.id == user.id)
+	} else {
+		config.admin_usernames.contains(&user.username)
+	};
+
+	if !is_admin {
+		return Err(async_graphql::Error::new("Only admins can do this"));
+	}
+	Ok(user)
+}
+
 #[async_graphql::ComplexObject]
 impl User {
 	async fn image_from_image_id(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<File> {
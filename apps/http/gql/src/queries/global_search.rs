@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql::SimpleObject;
+use database_connection::Database;
+use futures_util::{Stream, StreamExt};
+use scraper_core::ScraperManager;
+use scraper_core::health::{self, Admission};
+use scraper_core::plugins::Plugin;
+
+use super::scraping::{ScrapeItem, ScrapingQuery};
+use crate::objects::scraper::{Scraper, ScraperType};
+
+/// What one scraper returned for a global search.
+#[derive(SimpleObject, Clone)]
+pub struct ScraperSearchResult {
+	pub scraper_id: String,
+	pub scraper_name: String,
+	pub items: Vec<ScrapeItem>,
+	pub error: Option<String>,
+	pub timed_out: bool,
+}
+
+#[derive(SimpleObject, Clone)]
+pub struct SeriesMatch {
+	pub scraper_id: String,
+	pub item: ScrapeItem,
+}
+
+/// Results from different scrapers whose titles look like the same series.
+#[derive(SimpleObject, Clone)]
+pub struct SeriesGroup {
+	pub title: String,
+	pub matches: Vec<SeriesMatch>,
+}
+
+#[derive(SimpleObject)]
+pub struct GlobalSearchResult {
+	pub scrapers: Vec<ScraperSearchResult>,
+	pub groups: Vec<SeriesGroup>,
+}
+
+/// Sent by the `globalSearch` subscription each time a scraper finishes.
+#[derive(SimpleObject)]
+pub struct GlobalSearchUpdate {
+	pub result: ScraperSearchResult,
+	/// Groups over every result received so far.
+	pub groups: Vec<SeriesGroup>,
+	/// Scrapers that have not answered yet.
+	pub pending: usize,
+}
+
+/// Installed scrapers matching the optional `types` and `scrapers` selections.
+pub(crate) async fn search_targets(
+	scraper_manager: &ScraperManager,
+	types: Option<Vec<ScraperType>>,
+	scrapers: Option<Vec<String>>,
+) -> async_graphql::Result<Vec<(Arc<Plugin>, Scraper)>> {
+	let plugins: Vec<(String, Arc<Plugin>)> = match scrapers {
+		Some(ids) => {
+			let mut selected = Vec::with_capacity(ids.len());
+			for id in ids {
+				let plugin = scraper_manager
+					.get_plugin(&id)
+					.await
+					.ok_or_else(|| async_graphql::Error::new(format!("Scraper not found: {}", id)))?;
+				selected.push((id, plugin));
+			}
+			selected
+		}
+		None => {
+			let plugins = scraper_manager.get_plugins().await;
+			plugins.read().await.iter().map(|(id, p)| (id.clone(), p.clone())).collect()
+		}
+	};
+
+	let mut targets = Vec::with_capacity(plugins.len());
+	for (id, plugin) in plugins {
+		let mut scraper = Scraper::from_plugin(plugin.clone()).await?;
+		if types.as_ref().is_some_and(|types| !types.contains(&scraper.r#type)) {
+			continue;
+		}
+		scraper.id = id;
+		targets.push((plugin, scraper));
+	}
+
+	Ok(targets)
+}
+
+/// Searches every target concurrently, yielding each scraper's result as soon as it is done.
+pub(crate) fn search_all(
+	db: Arc<Database>,
+	config: Arc<crate::Config>,
+	targets: Vec<(Arc<Plugin>, Scraper)>,
+	query: String,
+) -> impl Stream<Item = ScraperSearchResult> + Send + 'static {
+	let timeout = Duration::from_secs(config.global_search.timeout_seconds);
+	let max_concurrency = config.global_search.max_concurrency.max(1);
+
+	futures_util::stream::iter(targets)
+		.map(move |(plugin, scraper)| {
+			let db = db.clone();
+			let config = config.clone();
+			let query = query.clone();
+			async move { search_one(db, &config, plugin, scraper, query, timeout).await }
+		})
+		.buffer_unordered(max_concurrency)
+}
+
+async fn search_one(
+	db: Arc<Database>,
+	config: &crate::Config,
+	plugin: Arc<Plugin>,
+	scraper: Scraper,
+	query: String,
+	timeout: Duration,
+) -> ScraperSearchResult {
+	let mut result = ScraperSearchResult {
+		scraper_id: scraper.id.clone(),
+		scraper_name: scraper.name.clone(),
+		items: Vec::new(),
+		error: None,
+		timed_out: false,
+	};
+
+	if let Admission::Quarantined { remaining } = health::tracker().admit(&scraper.id) {
+		result.error = Some(format!("Scraper is quarantined for another {}s", remaining.as_secs()));
+		return result;
+	}
+
+	// The timeout also covers waiting for a slot, so a scraper busy with scheduled updates cannot stall the search.
+	let search = async {
+		let _permit = scraper_core::limits::limiter().acquire(&scraper.id).await;
+		ScrapingQuery
+			.cached_search(
+				db,
+				config,
+				plugin,
+				scraper.r#type,
+				&scraper.id,
+				query,
+				1,
+				scraper_types::SearchFilters::default(),
+			)
+			.await
+	};
+
+	match tokio::time::timeout(timeout, search).await {
+		Ok(Ok(items)) => result.items = items,
+		Ok(Err(e)) => result.error = Some(e.message),
+		Err(_) => result.timed_out = true,
+	}
+
+	result
+}
+
+/// Folds case, punctuation and a leading article so "The Beginning After the End" and
+/// "beginning after the end!" share a key.
+fn series_key(title: &str) -> String {
+	let cleaned: String = title
+		.to_lowercase()
+		.chars()
+		.map(|c| if c.is_alphanumeric() { c } else { ' ' })
+		.collect();
+	let words: Vec<&str> = cleaned.split_whitespace().collect();
+	match words.split_first() {
+		Some((&"the", rest)) if !rest.is_empty() => rest.join(" "),
+		_ => words.join(" "),
+	}
+}
+
+/// Groups results by series, listing series found on the most scrapers first.
+pub(crate) fn group_results(results: &[ScraperSearchResult]) -> Vec<SeriesGroup> {
+	let mut groups: Vec<SeriesGroup> = Vec::new();
+	let mut index: HashMap<String, usize> = HashMap::new();
+
+	for result in results {
+		for item in &result.items {
+			let key = series_key(&item.title);
+			if key.is_empty() {
+				continue;
+			}
+
+			let series_match = SeriesMatch {
+				scraper_id: result.scraper_id.clone(),
+				item: item.clone(),
+			};
+			match index.get(&key) {
+				Some(&i) => groups[i].matches.push(series_match),
+				None => {
+					index.insert(key, groups.len());
+					groups.push(SeriesGroup {
+						title: item.title.trim().to_string(),
+						matches: vec![series_match],
+					});
+				}
+			}
+		}
+	}
+
+	// Stable, so series with the same number of sources keep the order scrapers answered in.
+	groups.sort_by_key(|group| std::cmp::Reverse(group.matches.len()));
+	groups
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn item(title: &str) -> ScrapeItem {
+		ScrapeItem {
+			title: title.to_string(),
+			url: format!("https://example.com/{}", title),
+			img_url: None,
+			manga_id: None,
+			novel_id: None,
+		}
+	}
+
+	fn result(scraper_id: &str, titles: &[&str]) -> ScraperSearchResult {
+		ScraperSearchResult {
+			scraper_id: scraper_id.to_string(),
+			scraper_name: scraper_id.to_string(),
+			items: titles.iter().map(|t| item(t)).collect(),
+			error: None,
+			timed_out: false,
+		}
+	}
+
+	#[test]
+	fn test_series_key() {
+		assert_eq!(series_key("The Beginning After the End"), "beginning after the end");
+		assert_eq!(series_key("  beginning after the END! "), "beginning after the end");
+		assert_eq!(series_key("The"), "the");
+		assert_eq!(series_key("?!"), "");
+	}
+
+	#[test]
+	fn test_group_results() {
+		let results = vec![
+			result("a", &["Solo Leveling", "Omniscient Reader"]),
+			result("b", &["omniscient reader", "Tower of God"]),
+			result("c", &["Omniscient Reader!", "Solo Leveling"]),
+		];
+
+		let groups = group_results(&results);
+		let summary: Vec<(&str, Vec<&str>)> = groups
+			.iter()
+			.map(|g| (g.title.as_str(), g.matches.iter().map(|m| m.scraper_id.as_str()).collect()))
+			.collect();
+
+		assert_eq!(
+			summary,
+			vec![
+				("Omniscient Reader", vec!["a", "b", "c"]),
+				("Solo Leveling", vec!["a", "c"]),
+				("Tower of God", vec!["b"]),
+			]
+		);
+	}
+}
@@ -0,0 +1,15 @@
+use async_graphql::{Context, Object, Result};
+
+use crate::objects::users::require_admin;
+
+#[derive(Default)]
+pub struct LoggingQuery;
+
+#[Object]
+impl LoggingQuery {
+	/// Log filter of the API's process, e.g. `info,plugin::mangadex=debug`.
+	async fn log_filter(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+		require_admin(ctx).await?;
+		Ok(telemetry::log_filter())
+	}
+}
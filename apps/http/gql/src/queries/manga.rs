@@ -83,7 +83,7 @@ impl MangaQuery {
 	) -> Result<database_entities::mangas::Model> {
 		let db = ctx.data::<Arc<Database>>()?;
 		let scraper = self.get_scraper(ctx, &manga.scraper).await?;
-		let scraped_manga = scraper.scrape(manga.url.clone()).await?;
+		let scraped_manga = scraper.scrape_normalized(manga.url.clone()).await?;
 
 		let release_date = scraped_manga.parse_release_date();
 		let alternative_names = scraped_manga.alternative_names.join(", ");
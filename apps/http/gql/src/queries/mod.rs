@@ -5,11 +5,14 @@ mod chapter;
 mod favorite_manga;
 mod favorite_novel;
 mod file;
+pub(crate) mod global_search;
+mod logging;
 mod manga;
 mod manga_pack;
 mod novel;
 mod read_chapter;
 mod read_novel_chapter;
+mod scheduler;
 mod scraping;
 mod user;
 
@@ -27,4 +30,6 @@ pub struct QueryRoot {
 	manga_packs: manga_pack::MangaPackQuery,
 	files: file::FileQuery,
 	scraping: scraping::ScrapingQuery,
+	scheduler: scheduler::SchedulerQuery,
+	logging: logging::LoggingQuery,
 }
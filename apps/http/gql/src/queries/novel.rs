@@ -91,7 +91,7 @@ impl NovelQuery {
 	) -> Result<database_entities::novels::Model> {
 		let db = ctx.data::<Arc<Database>>()?;
 		let scraper = self.get_scraper(ctx, &novel.scraper).await?;
-		let scraped_novel = scraper.scrape(novel.url.clone()).await?;
+		let scraped_novel = scraper.scrape_normalized(novel.url.clone()).await?;
 
 		let release_date = scraped_novel.parse_release_date();
 		let alternative_names = scraped_novel.alternative_names.join(", ");
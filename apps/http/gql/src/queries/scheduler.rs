@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result};
+use scheduler::{MaintenanceScheduler, SchedulerControl};
+
+use crate::objects::scheduler::{
+	HostRateLimit, MaintenanceTask, QueuedSchedulerJob, SchedulerJobCount, SchedulerJobStatus, SchedulerPause,
+	SchedulerWorker, ScraperLimit, StoredSchedulerJob,
+};
+use crate::objects::users::require_admin;
+
+/// Most jobs a single scheduler query lists.
+const MAX_LISTED_JOBS: usize = 500;
+
+#[derive(Default)]
+pub struct SchedulerQuery;
+
+#[Object]
+impl SchedulerQuery {
+	async fn scheduler_pauses(&self, ctx: &Context<'_>) -> Result<Vec<SchedulerPause>> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.pauses().await?.into_iter().map(SchedulerPause::from).collect())
+	}
+
+	/// Whether a scheduler runs in the API's process. The in-memory queue, in-flight jobs and scraper limits are only
+	/// known for that one.
+	async fn scheduler_running_here(&self, ctx: &Context<'_>) -> Result<bool> {
+		require_admin(ctx).await?;
+		Ok(scheduler::local().is_some())
+	}
+
+	/// Scheduler processes sharing the database, including ones on other machines.
+	async fn scheduler_workers(&self, ctx: &Context<'_>) -> Result<Vec<SchedulerWorker>> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control.workers().await?.into_iter().map(SchedulerWorker::from).collect())
+	}
+
+	/// Housekeeping tasks with their schedules and last runs.
+	async fn maintenance_tasks(&self, ctx: &Context<'_>) -> Result<Vec<MaintenanceTask>> {
+		require_admin(ctx).await?;
+		let maintenance = ctx.data::<Arc<MaintenanceScheduler>>()?;
+		Ok(maintenance.statuses().await?.into_iter().map(MaintenanceTask::from).collect())
+	}
+
+	/// The next jobs of the local scheduler in the order they would run, at most 500.
+	async fn scheduler_queue(
+		&self,
+		ctx: &Context<'_>,
+		#[graphql(default = 50)] limit: usize,
+	) -> Result<Vec<QueuedSchedulerJob>> {
+		require_admin(ctx).await?;
+		let Some(scheduler) = scheduler::local() else {
+			return Ok(vec![]);
+		};
+		Ok(scheduler
+			.queued(limit.min(MAX_LISTED_JOBS))
+			.await
+			.into_iter()
+			.map(QueuedSchedulerJob::from)
+			.collect())
+	}
+
+	async fn scheduler_in_flight(&self, ctx: &Context<'_>) -> Result<Vec<QueuedSchedulerJob>> {
+		require_admin(ctx).await?;
+		let Some(scheduler) = scheduler::local() else {
+			return Ok(vec![]);
+		};
+		Ok(scheduler.in_flight().into_iter().map(QueuedSchedulerJob::from).collect())
+	}
+
+	/// Jobs stored for all scheduler processes, at most 500. Leased jobs are the ones in flight anywhere.
+	async fn scheduler_jobs(
+		&self,
+		ctx: &Context<'_>,
+		status: Option<SchedulerJobStatus>,
+		#[graphql(default = 50)] limit: u32,
+	) -> Result<Vec<StoredSchedulerJob>> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		let jobs = control
+			.stored_jobs(
+				status.as_ref().map(SchedulerJobStatus::as_str),
+				limit.min(MAX_LISTED_JOBS as u32),
+			)
+			.await?;
+		Ok(jobs.into_iter().map(StoredSchedulerJob::from).collect())
+	}
+
+	async fn scheduler_job_counts(&self, ctx: &Context<'_>) -> Result<Vec<SchedulerJobCount>> {
+		require_admin(ctx).await?;
+		let control = ctx.data::<Arc<SchedulerControl>>()?;
+		Ok(control
+			.job_counts()
+			.await?
+			.into_iter()
+			.map(|(status, count)| SchedulerJobCount { status, count })
+			.collect())
+	}
+
+	/// Per-scraper concurrency slots and cooldowns of this process.
+	async fn scraper_limits(&self, ctx: &Context<'_>) -> Result<Vec<ScraperLimit>> {
+		require_admin(ctx).await?;
+		Ok(scraper_core::limits::limiter()
+			.snapshots()
+			.into_iter()
+			.map(ScraperLimit::from)
+			.collect())
+	}
+
+	/// Request budgets of the upstream hosts this process talked to, shared by scrapers and the image proxy.
+	async fn host_rate_limits(&self, ctx: &Context<'_>) -> Result<Vec<HostRateLimit>> {
+		require_admin(ctx).await?;
+		Ok(scraper_core::rate_limit::limiter()
+			.snapshots()
+			.into_iter()
+			.map(HostRateLimit::from)
+			.collect())
+	}
+}
@@ -1,12 +1,16 @@
 use std::sync::Arc;
 
-use async_graphql::{Context, Object, Result, SimpleObject};
+use async_graphql::{Context, InputObject, Object, Result, SimpleObject};
 use database_connection::Database;
+use futures_util::StreamExt;
 use scraper_core::ScraperManager;
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, TransactionTrait};
 
-use crate::objects::scraper::Scraper;
+use super::global_search::{self, GlobalSearchResult};
+use crate::metrics;
+use crate::objects::scraper::{PageStatus, Scraper, ScraperHealth, ScraperType, SortOrder};
+use crate::objects::scraper_genres::ScraperGenre;
 
 #[derive(SimpleObject, Clone)]
 pub struct ScrapeItem {
@@ -17,50 +21,95 @@ pub struct ScrapeItem {
 	pub novel_id: Option<i32>,
 }
 
+/// Only filters the scraper declares in `Scraper.searchFilters` are accepted.
+#[derive(InputObject, Default)]
+struct SearchFiltersInput {
+	#[graphql(default)]
+	include_genres: Vec<String>,
+	#[graphql(default)]
+	exclude_genres: Vec<String>,
+	status: Option<PageStatus>,
+	page_type: Option<String>,
+	sort: Option<SortOrder>,
+	year: Option<u32>,
+}
+
+impl From<SearchFiltersInput> for scraper_types::SearchFilters {
+	fn from(input: SearchFiltersInput) -> Self {
+		Self {
+			include_genres: input.include_genres,
+			exclude_genres: input.exclude_genres,
+			status: input.status.map(Into::into),
+			page_type: input.page_type,
+			sort: input.sort.map(Into::into),
+			year: input.year,
+		}
+	}
+}
+
 #[derive(Default)]
 pub struct ScrapingQuery;
 
 #[Object]
 impl ScrapingQuery {
-	async fn search(&self, ctx: &Context<'_>, scraper_id: String, query: String, page: u32) -> Result<Vec<ScrapeItem>> {
+	async fn search(
+		&self,
+		ctx: &Context<'_>,
+		scraper_id: String,
+		query: String,
+		page: u32,
+		search_filters: Option<SearchFiltersInput>,
+	) -> Result<Vec<ScrapeItem>> {
+		let db = ctx.data::<Arc<Database>>()?;
+		let config = ctx.data::<Arc<crate::Config>>()?;
+		let scraper_plugin = self.get_scraper(ctx, &scraper_id).await?;
+		let scraper = Scraper::from_plugin(scraper_plugin.clone()).await?;
+
+		self.cached_search(
+			db.clone(),
+			config,
+			scraper_plugin,
+			scraper.r#type,
+			&scraper_id,
+			query,
+			page,
+			search_filters.unwrap_or_default().into(),
+		)
+		.await
+	}
+
+	async fn scrape_latest(&self, ctx: &Context<'_>, scraper_id: String, page: u32) -> Result<Vec<ScrapeItem>> {
 		let db = ctx.data::<Arc<Database>>()?;
 
 		let fetched_result = database_entities::temp::Entity::find()
-			.filter(database_entities::temp::Column::Key.eq(format!(
-				"search:{}:{}",
-				scraper_id.clone(),
-				query.clone().replace(" ", "_")
-			)))
+			.filter(database_entities::temp::Column::Key.eq(format!("latest:{}:{}", scraper_id.clone(), page)))
 			.one(&db.conn)
 			.await?;
+		metrics::record_cache_lookup("latest", fetched_result.is_some());
 
 		let scraper_plugin = self.get_scraper(ctx, &scraper_id).await?;
 		let scraper = Scraper::from_plugin(scraper_plugin.clone()).await?;
 
 		if fetched_result.is_none() {
-			let searched_items = scraper_plugin.scrape_search(query.clone(), page).await?;
-			if searched_items.is_empty() {
-				return Ok(vec![]);
-			}
-
+			let latest_items = scraper_plugin.scrape_latest(page).await?;
 			let items = match scraper.r#type {
 				crate::objects::scraper::ScraperType::Manga => {
-					self.process_mangas(db.clone(), &scraper_id, searched_items).await?
+					self.process_mangas(db.clone(), &scraper_id, latest_items).await?
 				}
 				crate::objects::scraper::ScraperType::Novel => {
-					self.process_novels(db.clone(), &scraper_id, searched_items).await?
+					self.process_novels(db.clone(), &scraper_id, latest_items).await?
 				}
 			};
 
 			let config = ctx.data::<Arc<crate::Config>>()?;
 			let active_model = database_entities::temp::ActiveModel {
-				key: Set(format!("search:{}:{}", scraper_id, query.replace(" ", "_"))),
+				key: Set(format!("latest:{}:{}", scraper_id, page)),
 				value: Set(
 					serde_json::to_vec(&items.iter().filter_map(|i| i.manga_id.or(i.novel_id)).collect::<Vec<_>>())
 						.map_err(|_| async_graphql::Error::new("Failed to serialize work ids"))?,
 				),
 				expires_at: Set(
-					(chrono::Utc::now() + chrono::Duration::minutes(config.cache.search_minutes as i64)).naive_utc(),
+					(chrono::Utc::now() + chrono::Duration::minutes(config.cache.latest_minutes as i64)).naive_utc(),
 				),
 				..Default::default()
 			};
@@ -71,7 +120,7 @@ impl ScrapingQuery {
 		}
 
 		let ids = serde_json::from_slice::<Vec<i32>>(&fetched_result.unwrap().value)
-			.map_err(|_| async_graphql::Error::new("Failed to parse search result"))?;
+			.map_err(|_| async_graphql::Error::new("Failed to parse latest result"))?;
 
 		if ids.is_empty() {
 			return Ok(vec![]);
@@ -115,37 +164,38 @@ impl ScrapingQuery {
 		}
 	}
 
-	async fn scrape_latest(&self, ctx: &Context<'_>, scraper_id: String, page: u32) -> Result<Vec<ScrapeItem>> {
+	async fn scrape_trending(&self, ctx: &Context<'_>, scraper_id: String, page: u32) -> Result<Vec<ScrapeItem>> {
 		let db = ctx.data::<Arc<Database>>()?;
 
 		let fetched_result = database_entities::temp::Entity::find()
-			.filter(database_entities::temp::Column::Key.eq(format!("latest:{}:{}", scraper_id.clone(), page)))
+			.filter(database_entities::temp::Column::Key.eq(format!("trending:{}:{}", scraper_id.clone(), page)))
 			.one(&db.conn)
 			.await?;
+		metrics::record_cache_lookup("trending", fetched_result.is_some());
 
 		let scraper_plugin = self.get_scraper(ctx, &scraper_id).await?;
 		let scraper = Scraper::from_plugin(scraper_plugin.clone()).await?;
 
 		if fetched_result.is_none() {
-			let latest_items = scraper_plugin.scrape_latest(page).await?;
+			let trending = scraper_plugin.scrape_trending(page).await?;
 			let items = match scraper.r#type {
 				crate::objects::scraper::ScraperType::Manga => {
-					self.process_mangas(db.clone(), &scraper_id, latest_items).await?
+					self.process_mangas(db.clone(), &scraper_id, trending).await?
 				}
 				crate::objects::scraper::ScraperType::Novel => {
-					self.process_novels(db.clone(), &scraper_id, latest_items).await?
+					self.process_novels(db.clone(), &scraper_id, trending).await?
 				}
 			};
 
 			let config = ctx.data::<Arc<crate::Config>>()?;
 			let active_model = database_entities::temp::ActiveModel {
-				key: Set(format!("latest:{}:{}", scraper_id, page)),
+				key: Set(format!("trending:{}:{}", scraper_id, page)),
 				value: Set(
 					serde_json::to_vec(&items.iter().filter_map(|i| i.manga_id.or(i.novel_id)).collect::<Vec<_>>())
 						.map_err(|_| async_graphql::Error::new("Failed to serialize work ids"))?,
 				),
 				expires_at: Set(
-					(chrono::Utc::now() + chrono::Duration::minutes(config.cache.latest_minutes as i64)).naive_utc(),
+					(chrono::Utc::now() + chrono::Duration::minutes(config.cache.trending_minutes as i64)).naive_utc(),
 				),
 				..Default::default()
 			};
@@ -156,7 +206,7 @@ impl ScrapingQuery {
 		}
 
 		let ids = serde_json::from_slice::<Vec<i32>>(&fetched_result.unwrap().value)
-			.map_err(|_| async_graphql::Error::new("Failed to parse latest result"))?;
+			.map_err(|_| async_graphql::Error::new("Failed to parse trending result"))?;
 
 		if ids.is_empty() {
 			return Ok(vec![]);
@@ -200,55 +250,245 @@ impl ScrapingQuery {
 		}
 	}
 
-	async fn scrape_trending(&self, ctx: &Context<'_>, scraper_id: String, page: u32) -> Result<Vec<ScrapeItem>> {
+	/// Works listed under a genre `url` from `scraperGenres`. Urls missing from the scraper's stored genres are
+	/// rejected.
+	async fn browse_genre(
+		&self,
+		ctx: &Context<'_>,
+		scraper_id: String,
+		genre_url: String,
+		page: u32,
+	) -> Result<Vec<ScrapeItem>> {
 		let db = ctx.data::<Arc<Database>>()?;
+		let config = ctx.data::<Arc<crate::Config>>()?;
 
-		let fetched_result = database_entities::temp::Entity::find()
-			.filter(database_entities::temp::Column::Key.eq(format!("trending:{}:{}", scraper_id.clone(), page)))
+		// Only urls the scraper listed itself, so callers cannot make it fetch arbitrary pages.
+		let known = database_entities::scraper_genres::Entity::find()
+			.filter(database_entities::scraper_genres::Column::Scraper.eq(scraper_id.clone()))
+			.filter(database_entities::scraper_genres::Column::Url.eq(genre_url.clone()))
 			.one(&db.conn)
 			.await?;
+		if known.is_none() {
+			return Err(async_graphql::Error::new("Unknown genre, list the scraper's genres first"));
+		}
 
 		let scraper_plugin = self.get_scraper(ctx, &scraper_id).await?;
 		let scraper = Scraper::from_plugin(scraper_plugin.clone()).await?;
 
-		if fetched_result.is_none() {
-			let trending = scraper_plugin.scrape_trending(page).await?;
-			let items = match scraper.r#type {
-				crate::objects::scraper::ScraperType::Manga => {
-					self.process_mangas(db.clone(), &scraper_id, trending).await?
-				}
-				crate::objects::scraper::ScraperType::Novel => {
-					self.process_novels(db.clone(), &scraper_id, trending).await?
-				}
+		let cache_key = format!("genre:{}:{}:{}", scraper_id, genre_url, page);
+		self.cached_items(
+			db.clone(),
+			scraper.r#type,
+			&scraper_id,
+			"genre",
+			cache_key,
+			config.cache.genre_minutes,
+			async { Ok(scraper_plugin.scrape_genre(genre_url, page).await?) },
+		)
+		.await
+	}
+
+	/// Genres of a scraper, kept in the database and scraped again once older than the configured catalog age.
+	async fn scraper_genres(&self, ctx: &Context<'_>, scraper_id: String) -> Result<Vec<ScraperGenre>> {
+		let db = ctx.data::<Arc<Database>>()?;
+		let config = ctx.data::<Arc<crate::Config>>()?;
+
+		let stored = database_entities::scraper_genres::Entity::find()
+			.filter(database_entities::scraper_genres::Column::Scraper.eq(scraper_id.clone()))
+			.order_by_asc(database_entities::scraper_genres::Column::Name)
+			.all(&db.conn)
+			.await?;
+
+		let stale_before =
+			chrono::Utc::now().naive_utc() - chrono::Duration::minutes(config.cache.genre_catalog_minutes as i64);
+		let fresh = !stored.is_empty() && stored.iter().all(|g| g.updated_at > stale_before);
+		if fresh {
+			return Ok(stored.into_iter().map(ScraperGenre::from).collect());
+		}
+
+		let scraper_plugin = self.get_scraper(ctx, &scraper_id).await?;
+		let genres = match scraper_plugin.scrape_genres_list().await {
+			Ok(genres) => genres,
+			Err(e) if !stored.is_empty() => {
+				tracing::warn!("keeping stale genres of {}, refresh failed: {}", scraper_id, e);
+				return Ok(stored.into_iter().map(ScraperGenre::from).collect());
+			}
+			Err(e) => return Err(e.into()),
+		};
+
+		let now = chrono::Utc::now().naive_utc();
+		let mut seen = std::collections::HashSet::new();
+		let models: Vec<_> = genres
+			.into_iter()
+			.filter(|g| !g.name.trim().is_empty() && !g.url.is_empty() && seen.insert(g.url.clone()))
+			.map(|g| database_entities::scraper_genres::ActiveModel {
+				scraper: Set(scraper_id.clone()),
+				name: Set(g.name.trim().to_string()),
+				url: Set(g.url),
+				updated_at: Set(now),
+				..Default::default()
+			})
+			.collect();
+
+		let txn = db.conn.begin().await?;
+		database_entities::scraper_genres::Entity::delete_many()
+			.filter(database_entities::scraper_genres::Column::Scraper.eq(scraper_id.clone()))
+			.exec(&txn)
+			.await?;
+		if !models.is_empty() {
+			database_entities::scraper_genres::Entity::insert_many(models)
+				.exec(&txn)
+				.await?;
+		}
+		txn.commit().await?;
+
+		let stored = database_entities::scraper_genres::Entity::find()
+			.filter(database_entities::scraper_genres::Column::Scraper.eq(scraper_id))
+			.order_by_asc(database_entities::scraper_genres::Column::Name)
+			.all(&db.conn)
+			.await?;
+
+		Ok(stored.into_iter().map(ScraperGenre::from).collect())
+	}
+
+	/// Searches every installed scraper, or only `scrapers`, and groups results that look like the same series.
+	/// The `globalSearch` subscription delivers the same results as each scraper finishes.
+	async fn global_search(
+		&self,
+		ctx: &Context<'_>,
+		query: String,
+		types: Option<Vec<ScraperType>>,
+		scrapers: Option<Vec<String>>,
+	) -> Result<GlobalSearchResult> {
+		let db = ctx.data::<Arc<Database>>()?;
+		let config = ctx.data::<Arc<crate::Config>>()?;
+		let scraper_manager = ctx.data::<Arc<ScraperManager>>()?;
+
+		let targets = global_search::search_targets(scraper_manager, types, scrapers).await?;
+		let mut results: Vec<_> = global_search::search_all(db.clone(), config.clone(), targets, query)
+			.collect()
+			.await;
+		results.sort_by(|a, b| a.scraper_id.cmp(&b.scraper_id));
+
+		Ok(GlobalSearchResult {
+			groups: global_search::group_results(&results),
+			scrapers: results,
+		})
+	}
+
+	async fn scrapers(&self, ctx: &Context<'_>) -> Result<Vec<Scraper>> {
+		let scraper_manager = ctx.data::<Arc<ScraperManager>>()?;
+
+		let scrapers = scraper_manager.get_plugins().await;
+		let plugins = scrapers.read().await.values().cloned().collect::<Vec<_>>();
+		let scraper_futures = plugins.into_iter().map(Scraper::from_plugin);
+		let scraper_vec: Vec<Scraper> = futures_util::future::try_join_all(scraper_futures).await?;
+		Ok(scraper_vec)
+	}
+
+	async fn scraper(&self, ctx: &Context<'_>, scraper_id: String) -> Result<Scraper> {
+		let scraper = self.get_scraper(ctx, &scraper_id).await?;
+
+		Ok(Scraper::from_plugin(scraper).await?)
+	}
+
+	/// Call statistics of every scraper that has been used since startup, or only of `scraper_id`.
+	async fn scraper_health(&self, scraper_id: Option<String>) -> Vec<ScraperHealth> {
+		let tracker = scraper_core::health::tracker();
+		match scraper_id {
+			Some(scraper_id) => tracker.snapshot(&scraper_id).into_iter().map(ScraperHealth::from).collect(),
+			None => tracker.snapshots().into_iter().map(ScraperHealth::from).collect(),
+		}
+	}
+}
+
+impl ScrapingQuery {
+	/// Searches one scraper, going through the same cache as the `search` query.
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) async fn cached_search(
+		&self,
+		db: Arc<Database>,
+		config: &crate::Config,
+		scraper_plugin: Arc<scraper_core::plugins::Plugin>,
+		scraper_type: ScraperType,
+		scraper_id: &str,
+		query: String,
+		page: u32,
+		filters: scraper_types::SearchFilters,
+	) -> Result<Vec<ScrapeItem>> {
+		let mut cache_key = format!("search:{}:{}", scraper_id, query.replace(" ", "_"));
+		if !filters.is_empty() {
+			let filters_json = serde_json::to_string(&filters)
+				.map_err(|_| async_graphql::Error::new("Failed to serialize search filters"))?;
+			cache_key = format!("{}:{}", cache_key, filters_json);
+		}
+
+		self.cached_items(
+			db,
+			scraper_type,
+			scraper_id,
+			"search",
+			cache_key,
+			config.cache.search_minutes,
+			async { Ok(scraper_plugin.scrape_search(query, page, filters).await?) },
+		)
+		.await
+	}
+
+	/// Works cached under `cache_key`, or the ones `fetch` scrapes, which are stored and cached for `ttl_minutes`.
+	/// Empty results aren't cached. `cache` labels the lookup in the metrics.
+	#[allow(clippy::too_many_arguments)]
+	async fn cached_items(
+		&self,
+		db: Arc<Database>,
+		scraper_type: ScraperType,
+		scraper_id: &str,
+		cache: &str,
+		cache_key: String,
+		ttl_minutes: u64,
+		fetch: impl Future<Output = Result<Vec<scraper_types::Item>>>,
+	) -> Result<Vec<ScrapeItem>> {
+		let fetched_result = database_entities::temp::Entity::find()
+			.filter(database_entities::temp::Column::Key.eq(cache_key.clone()))
+			.one(&db.conn)
+			.await?;
+		metrics::record_cache_lookup(cache, fetched_result.is_some());
+
+		let Some(fetched_result) = fetched_result else {
+			let scraped_items = fetch.await?;
+			if scraped_items.is_empty() {
+				return Ok(vec![]);
+			}
+
+			let items = match scraper_type {
+				ScraperType::Manga => self.process_mangas(db.clone(), scraper_id, scraped_items).await?,
+				ScraperType::Novel => self.process_novels(db.clone(), scraper_id, scraped_items).await?,
 			};
 
-			let config = ctx.data::<Arc<crate::Config>>()?;
 			let active_model = database_entities::temp::ActiveModel {
-				key: Set(format!("trending:{}:{}", scraper_id, page)),
+				key: Set(cache_key),
 				value: Set(
 					serde_json::to_vec(&items.iter().filter_map(|i| i.manga_id.or(i.novel_id)).collect::<Vec<_>>())
 						.map_err(|_| async_graphql::Error::new("Failed to serialize work ids"))?,
 				),
-				expires_at: Set(
-					(chrono::Utc::now() + chrono::Duration::minutes(config.cache.trending_minutes as i64)).naive_utc(),
-				),
+				expires_at: Set((chrono::Utc::now() + chrono::Duration::minutes(ttl_minutes as i64)).naive_utc()),
 				..Default::default()
 			};
 
 			database_entities::temp::Entity::insert(active_model).exec(&db.conn).await?;
 
 			return Ok(items);
-		}
+		};
 
-		let ids = serde_json::from_slice::<Vec<i32>>(&fetched_result.unwrap().value)
-			.map_err(|_| async_graphql::Error::new("Failed to parse trending result"))?;
+		let ids = serde_json::from_slice::<Vec<i32>>(&fetched_result.value)
+			.map_err(|_| async_graphql::Error::new(format!("Failed to parse {} result", cache)))?;
 
 		if ids.is_empty() {
 			return Ok(vec![]);
 		}
 
-		match scraper.r#type {
-			crate::objects::scraper::ScraperType::Manga => {
+		match scraper_type {
+			ScraperType::Manga => {
 				let mangas = database_entities::mangas::Entity::find()
 					.filter(database_entities::mangas::Column::Id.is_in(ids))
 					.all(&db.conn)
@@ -265,7 +505,7 @@ impl ScrapingQuery {
 					})
 					.collect())
 			}
-			crate::objects::scraper::ScraperType::Novel => {
+			ScraperType::Novel => {
 				let novels = database_entities::novels::Entity::find()
 					.filter(database_entities::novels::Column::Id.is_in(ids))
 					.all(&db.conn)
@@ -285,24 +525,6 @@ impl ScrapingQuery {
 		}
 	}
 
-	async fn scrapers(&self, ctx: &Context<'_>) -> Result<Vec<Scraper>> {
-		let scraper_manager = ctx.data::<Arc<ScraperManager>>()?;
-
-		let scrapers = scraper_manager.get_plugins().await;
-		let plugins = scrapers.read().await.values().cloned().collect::<Vec<_>>();
-		let scraper_futures = plugins.into_iter().map(Scraper::from_plugin);
-		let scraper_vec: Vec<Scraper> = futures_util::future::try_join_all(scraper_futures).await?;
-		Ok(scraper_vec)
-	}
-
-	async fn scraper(&self, ctx: &Context<'_>, scraper_id: String) -> Result<Scraper> {
-		let scraper = self.get_scraper(ctx, &scraper_id).await?;
-
-		Ok(Scraper::from_plugin(scraper).await?)
-	}
-}
-
-impl ScrapingQuery {
 	async fn get_scraper(&self, ctx: &Context<'_>, scraper_id: &str) -> Result<Arc<scraper_core::plugins::Plugin>> {
 		ctx.data::<Arc<ScraperManager>>()?
 			.get_plugin(scraper_id)
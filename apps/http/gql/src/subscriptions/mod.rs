@@ -0,0 +1,6 @@
+use async_graphql::MergedSubscription;
+
+mod scraping;
+
+#[derive(MergedSubscription, Default)]
+pub struct SubscriptionRoot(scraping::ScrapingSubscription);
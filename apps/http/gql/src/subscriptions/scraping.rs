@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Result, Subscription};
+use database_connection::Database;
+use futures_util::{Stream, StreamExt};
+use scraper_core::ScraperManager;
+
+use crate::objects::scraper::ScraperType;
+use crate::queries::global_search::{self, GlobalSearchUpdate};
+
+#[derive(Default)]
+pub struct ScrapingSubscription;
+
+#[Subscription]
+impl ScrapingSubscription {
+	/// Streams the `globalSearch` query, one update per scraper as it finishes.
+	async fn global_search(
+		&self,
+		ctx: &Context<'_>,
+		query: String,
+		types: Option<Vec<ScraperType>>,
+		scrapers: Option<Vec<String>>,
+	) -> Result<impl Stream<Item = GlobalSearchUpdate>> {
+		let db = ctx.data::<Arc<Database>>()?;
+		let config = ctx.data::<Arc<crate::Config>>()?;
+		let scraper_manager = ctx.data::<Arc<ScraperManager>>()?;
+
+		let targets = global_search::search_targets(scraper_manager, types, scrapers).await?;
+		let mut pending = targets.len();
+		let mut received = Vec::with_capacity(pending);
+
+		Ok(
+			global_search::search_all(db.clone(), config.clone(), targets, query).map(move |result| {
+				pending -= 1;
+				received.push(result.clone());
+				GlobalSearchUpdate {
+					result,
+					groups: global_search::group_results(&received),
+					pending,
+				}
+			}),
+		)
+	}
+}
@@ -0,0 +1,160 @@
+use chrono::{Datelike, NaiveDateTime, TimeDelta, Weekday};
+use scraper_types::PageStatus;
+
+/// Chapters found within this window of each other were released together, e.g. on the first sync.
+const BATCH_WINDOW: TimeDelta = TimeDelta::hours(6);
+/// How many of the latest releases describe the current schedule.
+const HISTORY: usize = 12;
+/// Share of releases that must fall on the same weekday to call a series weekly.
+const WEEKDAY_SHARE: f64 = 0.6;
+
+const MIN_INTERVAL: TimeDelta = TimeDelta::hours(1);
+const MAX_INTERVAL: TimeDelta = TimeDelta::days(30);
+/// Used until a series has released at least twice.
+const DEFAULT_INTERVAL: TimeDelta = TimeDelta::hours(12);
+const HIATUS_INTERVAL: TimeDelta = TimeDelta::days(7);
+/// Upper bound for how often a late release is checked for.
+const LATE_INTERVAL: TimeDelta = TimeDelta::hours(6);
+
+/// How often a series released chapters recently, derived from when its chapters were first seen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleasePattern {
+	pub last_release: Option<NaiveDateTime>,
+	pub average_gap: Option<TimeDelta>,
+	/// Set for roughly weekly series that mostly release on the same day.
+	pub weekday: Option<Weekday>,
+	pub status: Option<PageStatus>,
+}
+
+impl ReleasePattern {
+	/// `release_times` are the `created_at`s of the series' chapters in any order; `status` is the stored status.
+	pub fn from_history(mut release_times: Vec<NaiveDateTime>, status: Option<&str>) -> Self {
+		release_times.sort();
+
+		let mut releases: Vec<NaiveDateTime> = Vec::new();
+		for at in release_times {
+			match releases.last() {
+				Some(last) if at - *last < BATCH_WINDOW => {}
+				_ => releases.push(at),
+			}
+		}
+
+		let recent = &releases[releases.len().saturating_sub(HISTORY)..];
+		let average_gap = match recent {
+			[first, .., last] => Some((*last - *first) / (recent.len() as i32 - 1)),
+			_ => None,
+		};
+
+		let weekday = average_gap
+			.filter(|gap| *gap >= TimeDelta::days(5) && *gap <= TimeDelta::days(9))
+			.and_then(|_| dominant_weekday(recent));
+
+		Self {
+			last_release: releases.last().copied(),
+			average_gap,
+			weekday,
+			status: status.and_then(PageStatus::parse),
+		}
+	}
+
+	/// When the series should be checked next. Completed series are checked rarely, series with a pattern around
+	/// their next expected release, and series that went quiet less often the longer they stay quiet.
+	pub fn next_check_at(&self, now: NaiveDateTime) -> NaiveDateTime {
+		match self.status {
+			Some(PageStatus::Completed | PageStatus::Cancelled) => now + MAX_INTERVAL,
+			Some(PageStatus::Hiatus) => now + HIATUS_INTERVAL,
+			_ => self.expected_check(now).clamp(now + MIN_INTERVAL, now + MAX_INTERVAL),
+		}
+	}
+
+	fn expected_check(&self, now: NaiveDateTime) -> NaiveDateTime {
+		let Some(last) = self.last_release else {
+			return now + DEFAULT_INTERVAL;
+		};
+
+		let quiet = now - last;
+		let Some(gap) = self.average_gap.filter(|gap| quiet <= *gap * 3) else {
+			// No pattern yet, or the series stopped following it.
+			return now + (quiet / 4).max(DEFAULT_INTERVAL);
+		};
+
+		let expected = match self.weekday {
+			Some(weekday) => nearest_weekday(last + gap, weekday),
+			None => last + gap,
+		};
+
+		if expected > now {
+			expected
+		} else {
+			// Late: keep looking until it shows up.
+			now + (gap / 8).clamp(MIN_INTERVAL, LATE_INTERVAL)
+		}
+	}
+}
+
+fn dominant_weekday(releases: &[NaiveDateTime]) -> Option<Weekday> {
+	let mut counts = [0usize; 7];
+	for at in releases {
+		counts[at.weekday().num_days_from_monday() as usize] += 1;
+	}
+
+	let (day, count) = counts.iter().enumerate().max_by_key(|(_, count)| **count)?;
+	if (*count as f64) < releases.len() as f64 * WEEKDAY_SHARE {
+		return None;
+	}
+
+	Weekday::try_from(day as u8).ok()
+}
+
+/// The time within three days of `at` that falls on `weekday`.
+fn nearest_weekday(at: NaiveDateTime, weekday: Weekday) -> NaiveDateTime {
+	(-3..=3)
+		.map(|days| at + TimeDelta::days(days))
+		.find(|candidate| candidate.weekday() == weekday)
+		.unwrap_or(at)
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::NaiveDate;
+
+	use super::*;
+
+	fn at(day: u32, hour: u32) -> NaiveDateTime {
+		NaiveDate::from_ymd_opt(2026, 3, day)
+			.unwrap()
+			.and_hms_opt(hour, 0, 0)
+			.unwrap()
+	}
+
+	#[test]
+	fn test_weekly_series_is_checked_on_release_day() {
+		// Mondays, plus the first sync that found the older chapters at once.
+		let history = vec![at(2, 10), at(2, 10), at(2, 10), at(9, 12), at(16, 9), at(23, 11)];
+		let pattern = ReleasePattern::from_history(history, Some("ongoing"));
+
+		assert_eq!(pattern.average_gap, Some(TimeDelta::days(7) + TimeDelta::minutes(20)));
+		assert_eq!(pattern.weekday, Some(Weekday::Mon));
+
+		let next = pattern.next_check_at(at(24, 8));
+		assert_eq!(next.date(), at(30, 0).date());
+
+		// Late releases are looked for every few hours.
+		assert_eq!(pattern.next_check_at(at(30, 20)), at(30, 20) + LATE_INTERVAL);
+	}
+
+	#[test]
+	fn test_quiet_and_completed_series_are_checked_rarely() {
+		let history = vec![at(1, 0), at(2, 0), at(3, 0)];
+		let now = at(1, 0) + TimeDelta::days(400);
+
+		let dormant = ReleasePattern::from_history(history.clone(), None);
+		assert_eq!(dormant.next_check_at(now), now + MAX_INTERVAL);
+
+		let completed = ReleasePattern::from_history(history, Some("Completed"));
+		assert_eq!(completed.next_check_at(at(4, 0)), at(4, 0) + MAX_INTERVAL);
+
+		let unknown = ReleasePattern::from_history(Vec::new(), None);
+		assert_eq!(unknown.next_check_at(now), now + DEFAULT_INTERVAL);
+	}
+}
@@ -1,11 +1,18 @@
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use database_connection::Database;
-use scraper_types::{ScraperError, ScraperErrorKind};
-use sea_orm::sea_query::OnConflict;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use scraper_types::{ScraperError, ScraperErrorKind, resolve_url};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait};
 use thiserror::Error;
 use url::Url;
 
+pub use crate::cadence::ReleasePattern;
+
+mod cadence;
+
+/// Chapters looked at when working out a series' release pattern.
+const RELEASE_HISTORY_LIMIT: u64 = 200;
+
 #[derive(Debug, Error)]
 pub enum SyncError {
 	#[error("Manga {manga_id} not found")]
@@ -164,7 +171,7 @@ pub async fn sync_manga_with_scraper(
 		}
 	}
 
-	let scraped_manga = plugin.scrape(manga.url.clone()).await.map_err(map_plugin_error)?;
+	let scraped_manga = plugin.scrape_normalized(manga.url.clone()).await.map_err(map_plugin_error)?;
 
 	let manga_created_at = manga.created_at.clone();
 	let mut manga: database_entities::mangas::ActiveModel = manga.into();
@@ -176,7 +183,10 @@ pub async fn sync_manga_with_scraper(
 	manga.alternative_names = Set(Some(scraped_manga.alternative_names.join(", ")));
 	manga.authors = Set(Some(scraped_manga.authors.join(", ")));
 	manga.artists = Set(scraped_manga.artists.map(|artists| artists.join(", ")));
-	manga.status = Set(scraped_manga.status);
+	// An unknown status is dropped by normalization; keep the one already stored.
+	if let Some(status) = scraped_manga.status {
+		manga.status = Set(Some(status));
+	}
 	manga.manga_type = Set(scraped_manga.page_type);
 	manga.release_date = Set(parsed_date);
 	manga.genres = Set(Some(scraped_manga.genres.join(", ")));
@@ -189,19 +199,13 @@ pub async fn sync_manga_with_scraper(
 	let manga = manga.update(&db.conn).await?;
 
 	let mut active_models: Vec<database_entities::chapters::ActiveModel> = Vec::new();
-	let chapter_urls: Vec<String> = scraped_manga.chapters.iter().map(|c| c.url.clone()).collect();
-
-	let mut existing_chapters: Vec<database_entities::chapters::Model> = Vec::new();
-	let chunk_size = 800usize;
-	for chunk in chapter_urls.chunks(chunk_size) {
-		let rows = database_entities::chapters::Entity::find()
-			.filter(database_entities::chapters::Column::Url.is_in(chunk.to_vec()))
-			.all(&db.conn)
-			.await?;
-		existing_chapters.extend(rows);
-	}
 
-	let existing_urls: std::collections::HashSet<String> = existing_chapters.into_iter().map(|c| c.url).collect();
+	let existing_chapters: Vec<database_entities::chapters::Model> = database_entities::chapters::Entity::find()
+		.filter(database_entities::chapters::Column::MangaId.eq(manga.id))
+		.all(&db.conn)
+		.await?;
+
+	let existing_urls = normalized_urls(existing_chapters.into_iter().map(|c| c.url), &manga.url);
 
 	for chapter in scraped_manga.chapters {
 		if !existing_urls.contains(&chapter.url) {
@@ -239,6 +243,8 @@ pub async fn sync_manga_with_scraper(
 		}
 	}
 
+	schedule_next_manga_check(db, &manga).await?;
+
 	Ok(())
 }
 
@@ -324,7 +330,7 @@ pub async fn sync_novel_with_scraper(
 		}
 	}
 
-	let scraped_novel = plugin.scrape(novel.url.clone()).await.map_err(map_plugin_error)?;
+	let scraped_novel = plugin.scrape_normalized(novel.url.clone()).await.map_err(map_plugin_error)?;
 	let novel_created_at = novel.created_at;
 	let mut novel_active: database_entities::novels::ActiveModel = novel.into();
 	let parsed_date = scraped_novel.parse_release_date();
@@ -335,7 +341,9 @@ pub async fn sync_novel_with_scraper(
 	novel_active.alternative_names = Set(Some(scraped_novel.alternative_names.join(", ")));
 	novel_active.authors = Set(Some(scraped_novel.authors.join(", ")));
 	novel_active.artists = Set(scraped_novel.artists.map(|a| a.join(", ")));
-	novel_active.status = Set(scraped_novel.status);
+	if let Some(status) = scraped_novel.status {
+		novel_active.status = Set(Some(status));
+	}
 	novel_active.novel_type = Set(scraped_novel.page_type);
 	novel_active.release_date = Set(parsed_date);
 	novel_active.genres = Set(Some(scraped_novel.genres.join(", ")));
@@ -348,19 +356,13 @@ pub async fn sync_novel_with_scraper(
 	let novel = novel_active.update(&db.conn).await?;
 
 	let mut active_models: Vec<database_entities::novel_chapters::ActiveModel> = Vec::new();
-	let chapter_urls: Vec<String> = scraped_novel.chapters.iter().map(|c| c.url.clone()).collect();
-
-	let mut existing_chapters: Vec<database_entities::novel_chapters::Model> = Vec::new();
-	let chunk_size = 800usize;
-	for chunk in chapter_urls.chunks(chunk_size) {
-		let rows = database_entities::novel_chapters::Entity::find()
-			.filter(database_entities::novel_chapters::Column::Url.is_in(chunk.to_vec()))
-			.all(&db.conn)
-			.await?;
-		existing_chapters.extend(rows);
-	}
 
-	let existing_urls: std::collections::HashSet<String> = existing_chapters.into_iter().map(|c| c.url).collect();
+	let existing_chapters: Vec<database_entities::novel_chapters::Model> = database_entities::novel_chapters::Entity::find()
+		.filter(database_entities::novel_chapters::Column::NovelId.eq(novel.id))
+		.all(&db.conn)
+		.await?;
+
+	let existing_urls = normalized_urls(existing_chapters.into_iter().map(|c| c.url), &novel.url);
 
 	for chapter in scraped_novel.chapters {
 		if !existing_urls.contains(&chapter.url) {
@@ -397,6 +399,68 @@ pub async fn sync_novel_with_scraper(
 		}
 	}
 
+	schedule_next_novel_check(db, &novel).await?;
+
+	Ok(())
+}
+
+/// Stored chapter URLs normalized like scraped ones, so rows saved before normalization still match.
+fn normalized_urls(urls: impl IntoIterator<Item = String>, page_url: &str) -> std::collections::HashSet<String> {
+	let base = Url::parse(page_url).ok();
+	urls.into_iter()
+		.map(|url| resolve_url(&url, base.as_ref()).unwrap_or(url))
+		.collect()
+}
+
+/// Stores when the scheduler should look at the manga again, based on its release pattern.
+async fn schedule_next_manga_check(db: &Database, manga: &database_entities::mangas::Model) -> Result<(), SyncError> {
+	use database_entities::{chapters, mangas};
+
+	let release_times: Vec<NaiveDateTime> = chapters::Entity::find()
+		.select_only()
+		.column(chapters::Column::CreatedAt)
+		.filter(chapters::Column::MangaId.eq(manga.id))
+		.order_by_desc(chapters::Column::CreatedAt)
+		.limit(RELEASE_HISTORY_LIMIT)
+		.into_tuple()
+		.all(&db.conn)
+		.await?;
+
+	let pattern = ReleasePattern::from_history(release_times, manga.status.as_deref());
+	let next_check_at = pattern.next_check_at(Utc::now().naive_utc());
+
+	mangas::Entity::update_many()
+		.col_expr(mangas::Column::NextCheckAt, Expr::value(next_check_at))
+		.filter(mangas::Column::Id.eq(manga.id))
+		.exec(&db.conn)
+		.await?;
+
+	Ok(())
+}
+
+/// Stores when the scheduler should look at the novel again, based on its release pattern.
+async fn schedule_next_novel_check(db: &Database, novel: &database_entities::novels::Model) -> Result<(), SyncError> {
+	use database_entities::{novel_chapters, novels};
+
+	let release_times: Vec<NaiveDateTime> = novel_chapters::Entity::find()
+		.select_only()
+		.column(novel_chapters::Column::CreatedAt)
+		.filter(novel_chapters::Column::NovelId.eq(novel.id))
+		.order_by_desc(novel_chapters::Column::CreatedAt)
+		.limit(RELEASE_HISTORY_LIMIT)
+		.into_tuple()
+		.all(&db.conn)
+		.await?;
+
+	let pattern = ReleasePattern::from_history(release_times, novel.status.as_deref());
+	let next_check_at = pattern.next_check_at(Utc::now().naive_utc());
+
+	novels::Entity::update_many()
+		.col_expr(novels::Column::NextCheckAt, Expr::value(next_check_at))
+		.filter(novels::Column::Id.eq(novel.id))
+		.exec(&db.conn)
+		.await?;
+
 	Ok(())
 }
 
@@ -432,3 +496,29 @@ fn replace_host_preserve_path(old_url: &str, new_host: &str) -> Result<String, S
 
 	Ok(u.into())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_normalized_urls_match_scraped_ones() {
+		let stored = [
+			"//example.com/ch/1",
+			"HTTPS://Example.com/ch/2",
+			"/ch/3",
+			"https://example.com/ch/4",
+		];
+		let urls = normalized_urls(stored.map(String::from), "https://example.com/manga");
+
+		for scraped in [
+			"https://example.com/ch/1",
+			"https://example.com/ch/2",
+			"https://example.com/ch/3",
+		] {
+			assert!(urls.contains(scraped), "{} should match a stored chapter", scraped);
+		}
+		assert!(urls.contains("https://example.com/ch/4"));
+		assert_eq!(urls.len(), 4);
+	}
+}
@@ -4,17 +4,20 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
+use dashmap::DashMap;
 use futures::Future;
-use rand::Rng;
-use rand::rngs::ThreadRng;
 use tokio::sync::{Notify, Semaphore, mpsc, watch};
-use tracing::{debug, error, instrument, warn};
+use tracing::{Instrument, debug, error, info_span, instrument, warn};
 
 use crate::priority_queue_core::{InsertResult, PriorityQueueCore};
 use crate::queue_item::QueueItem;
+use crate::retry::{JobError, RetryPolicy};
+use crate::store::{QueueStore, StoredJob, VolatileStore, to_instant, to_system_time};
 
 mod priority_queue_core;
 pub mod queue_item;
+pub mod retry;
+pub mod store;
 
 pub type ProcessResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
@@ -28,12 +31,32 @@ pub enum EnqueueStrategy {
 	Block,
 }
 
+/// Runs a job, renewing its lease in the store until it finishes.
+async fn run_leased<J>(store: &dyn QueueStore<J>, key: &str, job: impl Future<Output = ProcessResult>) -> ProcessResult {
+	let Some(every) = store.renew_interval() else {
+		return job.await;
+	};
+
+	tokio::pin!(job);
+	let mut renewals = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+	loop {
+		tokio::select! {
+			result = &mut job => return result,
+			_ = renewals.tick() => match store.renew(key).await {
+				Ok(true) => {}
+				Ok(false) => warn!(key = %key, "Lost the lease of a running job"),
+				Err(e) => warn!(key = %key, "Failed to renew job lease: {}", e),
+			},
+		}
+	}
+}
+
 /// A task queue that manages job items with priorities and retries.
 #[allow(dead_code)]
 pub struct TaskQueue<J> {
 	core: Arc<PriorityQueueCore<J>>,
 	process_fn: ProcessFn<J>,
-	max_fail: u32,
+	retry_policy: RetryPolicy,
 	notifier: Arc<Notify>,
 	notifier_full: Arc<Notify>,
 	sender: mpsc::Sender<QueueItem<J>>,
@@ -42,13 +65,17 @@ pub struct TaskQueue<J> {
 	enqueue_strategy: EnqueueStrategy,
 	next_seq: AtomicU64,
 	use_aging: bool,
+	store: Arc<dyn QueueStore<J>>,
+	running: Arc<DashMap<Arc<str>, Arc<QueueItem<J>>>>,
+	/// Jobs handed to the processor that have not started yet, and whether they were removed meanwhile.
+	dispatched: Arc<DashMap<Arc<str>, bool>>,
 }
 
 impl<J> TaskQueue<J>
 where
 	J: Clone + Send + Sync + std::fmt::Debug + 'static,
 {
-	/// Create a new TaskQueue
+	/// Create a new TaskQueue that only lives in memory
 	#[allow(clippy::too_many_arguments)]
 	pub fn new(
 		process_fn: ProcessFn<J>,
@@ -58,8 +85,36 @@ where
 		max_concurrency: usize,
 		enqueue_strategy: EnqueueStrategy,
 		aging_interval: Option<u64>,
+	) -> Self {
+		Self::with_store(
+			process_fn,
+			max_size,
+			max_fail,
+			channel_capacity,
+			max_concurrency,
+			enqueue_strategy,
+			aging_interval,
+			Arc::new(VolatileStore),
+		)
+	}
+
+	/// Create a new TaskQueue that mirrors its jobs to `store`. Call [`TaskQueue::restore`] to pick up stored jobs.
+	///
+	/// Failed jobs are retried `max_fail` times with exponential backoff, unless the process function fails with a
+	/// [`JobError`] carrying its own policy. Jobs out of retries are marked as failed in the store.
+	#[allow(clippy::too_many_arguments)]
+	pub fn with_store(
+		process_fn: ProcessFn<J>,
+		max_size: usize,
+		max_fail: u32,
+		channel_capacity: usize,
+		max_concurrency: usize,
+		enqueue_strategy: EnqueueStrategy,
+		aging_interval: Option<u64>,
+		store: Arc<dyn QueueStore<J>>,
 	) -> Self {
 		let use_aging = aging_interval.is_some();
+		let retry_policy = RetryPolicy::exponential(max_fail, Duration::from_secs(2), Duration::from_secs(300));
 		let core = Arc::new(PriorityQueueCore::new(max_size, aging_interval));
 		let (tx, rx) = mpsc::channel(channel_capacity);
 		let semaphore = Arc::new(Semaphore::new(max_concurrency));
@@ -67,11 +122,15 @@ where
 		let notifier_full = Arc::new(Notify::new());
 		let (shutdown_tx, shutdown_rx) = watch::channel(false);
 		let next_seq = AtomicU64::new(1);
+		let running = Arc::new(DashMap::new());
+		let dispatched: Arc<DashMap<Arc<str>, bool>> = Arc::new(DashMap::new());
 
 		let dispatcher_core = Arc::clone(&core);
 		let dispatcher_notifier = Arc::clone(&notifier);
 		let dispatcher_notifier_full = Arc::clone(&notifier_full);
 		let dispatcher_tx = tx.clone();
+		let dispatcher_store = Arc::clone(&store);
+		let dispatcher_dispatched = Arc::clone(&dispatched);
 		let mut dispatcher_shutdown = shutdown_rx.clone();
 
 		tokio::spawn(async move {
@@ -84,6 +143,17 @@ where
 
 				match item {
 					Some(item) => {
+						match dispatcher_store.lease(&item.key).await {
+							Ok(true) => {}
+							Ok(false) => {
+								debug!(key = %item.key, "Job is gone or leased by another process, skipping");
+								dispatcher_notifier_full.notify_one();
+								continue;
+							}
+							Err(e) => warn!(key = %item.key, "Failed to lease job, running it anyway: {}", e),
+						}
+
+						dispatcher_dispatched.insert(item.key.clone(), false);
 						if dispatcher_tx.send((*item).clone()).await.is_err() {
 							break;
 						}
@@ -105,6 +175,9 @@ where
 		let processor_notifier = Arc::clone(&notifier);
 		let processor_notifier_full = Arc::clone(&notifier_full);
 		let processor_semaphore = Arc::clone(&semaphore);
+		let processor_store = Arc::clone(&store);
+		let processor_running = Arc::clone(&running);
+		let processor_dispatched = Arc::clone(&dispatched);
 		let mut processor_shutdown = shutdown_rx.clone();
 
 		tokio::spawn(async move {
@@ -122,36 +195,70 @@ where
 								}
 							};
 
+							if let Some((_, true)) = processor_dispatched.remove(&item.key) {
+								debug!(key = %item.key, "Job was removed before it started, skipping");
+								// Already leased by the dispatcher, so the store kept it when it was removed.
+								if let Err(e) = processor_store.complete(&item.key).await {
+									warn!(key = %item.key, "Failed to remove skipped job from the store: {}", e);
+								}
+								continue;
+							}
+
 							let core = Arc::clone(&processor_core);
 							let notifier = Arc::clone(&processor_notifier);
 							let notifier_full = Arc::clone(&processor_notifier_full);
 							let process_fn = Arc::clone(&processor_fn);
-							let max_fail = max_fail;
+							let store = Arc::clone(&processor_store);
+							let running = Arc::clone(&processor_running);
+							let span = info_span!("queue_job", key = %item.key, attempt = item.fail_count + 1);
 
-							tokio::spawn(async move {
+							let job = async move {
 								item.last_tried = Some(Instant::now());
-								let result = (process_fn)(item.clone()).await;
+								running.insert(item.key.clone(), Arc::new(item.clone()));
+								let result = run_leased(&*store, &item.key, (process_fn)(item.clone())).await;
+								running.remove(&item.key);
 
 								match result {
 									Ok(_) => {
 										debug!(key = %item.key, "Job succeeded");
+										if let Err(e) = store.complete(&item.key).await {
+											warn!(key = %item.key, "Failed to remove completed job from the store: {}", e);
+										}
 									}
 									Err(e) => {
-										error!(
-											key = %item.key,
-											fail_count = item.fail_count,
-											priority = item.priority,
-											retry_at_ms = item.retry_at.elapsed().as_millis(),
-											payload = ?item.payload,
-											"Job failed: {:#}",
-											e
-										);
+										let job_error = e.downcast_ref::<JobError>();
 										let mut new_item = item;
-										new_item.fail_count += 1;
+										let retry_at = match job_error.and_then(JobError::deferral) {
+											// Not a failure, so it does not use up an attempt.
+											Some(delay) => {
+												debug!(key = %new_item.key, "Job deferred for {}s: {}", delay.as_secs(), e);
+												Some(Instant::now() + delay)
+											}
+											None => {
+												error!(
+													key = %new_item.key,
+													fail_count = new_item.fail_count,
+													priority = new_item.priority,
+													retry_at_ms = new_item.retry_at.elapsed().as_millis(),
+													payload = ?new_item.payload,
+													"Job failed: {:#}",
+													e
+												);
+												let policy = job_error.map_or(retry_policy, JobError::policy);
+												new_item.fail_count += 1;
+												policy
+													.allows(new_item.fail_count)
+													.then(|| Instant::now() + policy.delay(new_item.fail_count))
+											}
+										};
 
-										if new_item.fail_count <= max_fail {
-											let backoff = calculate_backoff(new_item.fail_count);
-											new_item.retry_at = Instant::now() + backoff;
+										if let Some(retry_at) = retry_at {
+											new_item.retry_at = retry_at;
+
+											let retry_at = to_system_time(retry_at);
+											if let Err(e) = store.retry(&new_item.key, new_item.fail_count, retry_at).await {
+												warn!(key = %new_item.key, "Failed to store job retry: {}", e);
+											}
 
 											let item_arc = Arc::new(new_item);
 											match core.insert(Arc::clone(&item_arc)).await {
@@ -161,11 +268,17 @@ where
 												}
 												_ => {}
 											}
+										} else {
+											warn!(key = %new_item.key, attempts = new_item.fail_count, "Giving up on job");
+											if let Err(e) = store.fail(&new_item.key, new_item.fail_count, &e.to_string()).await {
+												warn!(key = %new_item.key, "Failed to mark job as failed in the store: {}", e);
+											}
 										}
 									}
 								}
 								drop(permit);
-							});
+							};
+							tokio::spawn(job.instrument(span));
 						} else {
 							break;
 						}
@@ -178,7 +291,7 @@ where
 		Self {
 			core,
 			process_fn,
-			max_fail,
+			retry_policy,
 			notifier,
 			notifier_full,
 			sender: tx,
@@ -187,6 +300,61 @@ where
 			enqueue_strategy,
 			next_seq,
 			use_aging,
+			store,
+			running,
+			dispatched,
+		}
+	}
+
+	/// Loads stored jobs that are not queued yet, such as jobs left over from before a restart or whose lease
+	/// expired because the process running them died. Returns how many were queued.
+	pub async fn restore(&self) -> usize {
+		let available = self.core.max_size.saturating_sub(self.core.len());
+		if available == 0 {
+			return 0;
+		}
+
+		let jobs = match self.store.load(available).await {
+			Ok(jobs) => jobs,
+			Err(e) => {
+				warn!("Failed to load stored jobs: {}", e);
+				return 0;
+			}
+		};
+
+		let mut restored = 0;
+		for job in jobs {
+			let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+			let mut item = QueueItem::new(Arc::<str>::from(job.key), job.payload, job.priority, seq);
+			item.fail_count = job.attempts;
+			item.retry_at = to_instant(job.retry_at);
+
+			if self.core.insert(Arc::new(item)).await == InsertResult::Inserted {
+				restored += 1;
+			}
+		}
+
+		if restored > 0 {
+			self.notifier.notify_one();
+		}
+		restored
+	}
+
+	/// Returns false if the store keeps the job out of the queue.
+	async fn persist(&self, item: &QueueItem<J>) -> bool {
+		let job = StoredJob {
+			key: item.key.to_string(),
+			payload: item.payload.clone(),
+			priority: item.priority,
+			attempts: item.fail_count,
+			retry_at: to_system_time(item.retry_at),
+		};
+		match self.store.save(&job).await {
+			Ok(queueable) => queueable,
+			Err(e) => {
+				warn!(key = %item.key, "Failed to store job: {}", e);
+				true
+			}
 		}
 	}
 
@@ -198,8 +366,15 @@ where
 		let key = Arc::<str>::from(key);
 		let item = Arc::new(QueueItem::new(key.clone(), payload, priority, seq));
 
+		// Stored before it is queued, so the dispatcher can always lease it. Jobs the queue drops stay stored and
+		// come back through `restore` once there is room.
+		if !self.persist(&item).await {
+			debug!(key = %item.key, "Job failed for good before, not queueing it");
+			return false;
+		}
+
 		match self.enqueue_strategy {
-			EnqueueStrategy::BestEffort => match self.core.insert(item).await {
+			EnqueueStrategy::BestEffort => match self.core.insert(item.clone()).await {
 				InsertResult::Inserted | InsertResult::Updated => {
 					self.notifier.notify_one();
 					true
@@ -245,6 +420,33 @@ where
 		self.core.peek_top_k(k).await
 	}
 
+	/// Jobs currently being processed, with `last_tried` set to when they started.
+	pub fn in_flight(&self) -> Vec<Arc<QueueItem<J>>> {
+		self.running.iter().map(|entry| entry.value().clone()).collect()
+	}
+
+	/// Drops a queued job from the queue and the store. A job that is already running finishes.
+	///
+	/// Returns true if the job was queued in this process.
+	pub async fn remove(&self, key: &str) -> bool {
+		if let Err(e) = self.store.remove(key).await {
+			warn!(key = %key, "Failed to remove job from the store: {}", e);
+		}
+
+		if self.core.remove(key).await.is_some() {
+			self.notifier_full.notify_one();
+			return true;
+		}
+
+		match self.dispatched.get_mut(key) {
+			Some(mut removed) if !*removed => {
+				*removed = true;
+				true
+			}
+			_ => false,
+		}
+	}
+
 	/// Process the next item in the queue
 	/// This will block until an item is available or the queue is shut down.
 	#[instrument(skip(self))]
@@ -262,15 +464,6 @@ where
 	}
 }
 
-/// Calculate exponential backoff with jitter
-fn calculate_backoff(fail_count: u32) -> Duration {
-	let base = 2u64.saturating_pow(fail_count.min(8));
-	let capped = base.min(300);
-	let mut rng = ThreadRng::default();
-	let jitter_ms = rng.random_range(0..1000);
-	Duration::from_secs(capped) + Duration::from_millis(jitter_ms)
-}
-
 #[cfg(test)]
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
 mod tests {
@@ -312,7 +505,17 @@ mod tests {
 
 	#[tokio::test]
 	async fn test_task_queue_exponential_backoff() {
-		let backoff = calculate_backoff(5);
+		let queue = TaskQueue::<String>::new(
+			Arc::new(|_item| Box::pin(async move { Ok(()) })),
+			10,
+			3,
+			100,
+			5,
+			EnqueueStrategy::BestEffort,
+			None,
+		);
+
+		let backoff = queue.retry_policy.delay(5);
 		assert!(backoff >= Duration::from_secs(32) && backoff < Duration::from_secs(33));
 	}
 
@@ -393,6 +596,34 @@ mod tests {
 		assert_eq!(queue.len(), 3);
 	}
 
+	#[tokio::test]
+	async fn test_task_queue_in_flight_and_remove() {
+		let process_fn = Arc::new(|_item: QueueItem<String>| {
+			Box::pin(async move {
+				sleep(Duration::from_millis(200)).await;
+				Ok(())
+			}) as Pin<Box<dyn Future<Output = ProcessResult> + Send>>
+		});
+
+		let queue = TaskQueue::<String>::new(process_fn, 10, 3, 100, 1, EnqueueStrategy::BestEffort, None);
+
+		assert!(queue.insert("first".to_string(), "payload".to_string(), 2).await);
+		assert!(queue.insert("second".to_string(), "payload".to_string(), 1).await);
+		sleep(Duration::from_millis(50)).await;
+
+		let in_flight = queue.in_flight();
+		assert_eq!(in_flight.len(), 1);
+		assert_eq!(in_flight[0].key, Arc::from("first"));
+		assert!(in_flight[0].last_tried.is_some());
+
+		assert!(queue.remove("second").await);
+		assert!(!queue.remove("second").await);
+		assert!(queue.is_empty());
+
+		sleep(Duration::from_millis(300)).await;
+		assert!(queue.in_flight().is_empty());
+	}
+
 	#[tokio::test]
 	async fn test_task_queue_enqueue_strategy() {
 		let queue = TaskQueue::<String>::new(
@@ -412,6 +643,253 @@ mod tests {
 		assert_eq!(queue.len(), 2);
 	}
 
+	/// Store that keeps jobs in a map, standing in for the database across "restarts".
+	#[derive(Default)]
+	struct MapStore {
+		jobs: std::sync::Mutex<std::collections::HashMap<String, (StoredJob<String>, bool)>>,
+		/// Last error of jobs that failed for good.
+		failed: std::sync::Mutex<std::collections::HashMap<String, String>>,
+		renew_every: Option<Duration>,
+		renewals: std::sync::atomic::AtomicU32,
+	}
+
+	impl QueueStore<String> for MapStore {
+		fn save<'a>(&'a self, job: &'a StoredJob<String>) -> futures::future::BoxFuture<'a, store::StoreResult<bool>> {
+			if self.failed.lock().unwrap().contains_key(&job.key) {
+				return Box::pin(async { Ok(false) });
+			}
+			self.jobs
+				.lock()
+				.unwrap()
+				.entry(job.key.clone())
+				.or_insert((job.clone(), false));
+			Box::pin(async { Ok(true) })
+		}
+
+		fn lease<'a>(&'a self, key: &'a str) -> futures::future::BoxFuture<'a, store::StoreResult<bool>> {
+			let leased = match self.jobs.lock().unwrap().get_mut(key) {
+				Some((_, leased)) if !*leased => {
+					*leased = true;
+					true
+				}
+				_ => false,
+			};
+			Box::pin(async move { Ok(leased) })
+		}
+
+		fn renew_interval(&self) -> Option<Duration> {
+			self.renew_every
+		}
+
+		fn renew<'a>(&'a self, _key: &'a str) -> futures::future::BoxFuture<'a, store::StoreResult<bool>> {
+			self.renewals.fetch_add(1, Ordering::Relaxed);
+			Box::pin(async { Ok(true) })
+		}
+
+		fn complete<'a>(&'a self, key: &'a str) -> futures::future::BoxFuture<'a, store::StoreResult<()>> {
+			self.jobs.lock().unwrap().remove(key);
+			Box::pin(async { Ok(()) })
+		}
+
+		fn retry<'a>(
+			&'a self,
+			key: &'a str,
+			attempts: u32,
+			retry_at: std::time::SystemTime,
+		) -> futures::future::BoxFuture<'a, store::StoreResult<()>> {
+			if let Some((job, leased)) = self.jobs.lock().unwrap().get_mut(key) {
+				job.attempts = attempts;
+				job.retry_at = retry_at;
+				*leased = false;
+			}
+			Box::pin(async { Ok(()) })
+		}
+
+		fn fail<'a>(
+			&'a self,
+			key: &'a str,
+			_attempts: u32,
+			error: &'a str,
+		) -> futures::future::BoxFuture<'a, store::StoreResult<()>> {
+			self.jobs.lock().unwrap().remove(key);
+			self.failed.lock().unwrap().insert(key.to_string(), error.to_string());
+			Box::pin(async { Ok(()) })
+		}
+
+		fn remove<'a>(&'a self, key: &'a str) -> futures::future::BoxFuture<'a, store::StoreResult<()>> {
+			self.jobs.lock().unwrap().remove(key);
+			Box::pin(async { Ok(()) })
+		}
+
+		fn load(&self, limit: usize) -> futures::future::BoxFuture<'_, store::StoreResult<Vec<StoredJob<String>>>> {
+			let jobs = self
+				.jobs
+				.lock()
+				.unwrap()
+				.values()
+				.filter(|(_, leased)| !leased)
+				.map(|(job, _)| job.clone())
+				.take(limit)
+				.collect();
+			Box::pin(async move { Ok(jobs) })
+		}
+	}
+
+	#[tokio::test]
+	async fn test_task_queue_restores_stored_jobs() {
+		let store = Arc::new(MapStore::default());
+		let never_runs = || -> ProcessFn<String> {
+			Arc::new(|_item| Box::pin(async move { futures::future::pending::<ProcessResult>().await }))
+		};
+
+		// Jobs scheduled for later survive the first queue going away.
+		store
+			.save(&StoredJob {
+				key: "later".to_string(),
+				payload: "payload".to_string(),
+				priority: 3,
+				attempts: 2,
+				retry_at: std::time::SystemTime::now() + Duration::from_secs(60),
+			})
+			.await
+			.unwrap();
+
+		let queue = TaskQueue::with_store(never_runs(), 10, 3, 10, 1, EnqueueStrategy::BestEffort, None, store.clone());
+		assert_eq!(queue.restore().await, 1);
+		assert_eq!(queue.restore().await, 0, "queued jobs are not restored twice");
+
+		let items = queue.peek_top_k(1).await;
+		assert_eq!(items[0].fail_count, 2);
+		assert!(items[0].retry_at > Instant::now());
+	}
+
+	#[tokio::test]
+	async fn test_task_queue_completes_and_leases_stored_jobs() {
+		let store = Arc::new(MapStore::default());
+		let queue = TaskQueue::with_store(
+			Arc::new(|_item| Box::pin(async move { Ok(()) })),
+			10,
+			3,
+			10,
+			5,
+			EnqueueStrategy::BestEffort,
+			None,
+			store.clone(),
+		);
+
+		assert!(queue.insert("done".to_string(), "payload".to_string(), 1).await);
+		// Another process already holds this one, so it is skipped here.
+		store
+			.save(&StoredJob {
+				key: "elsewhere".to_string(),
+				payload: "payload".to_string(),
+				priority: 1,
+				attempts: 0,
+				retry_at: std::time::SystemTime::now(),
+			})
+			.await
+			.unwrap();
+		store.lease("elsewhere").await.unwrap();
+		assert!(queue.insert("elsewhere".to_string(), "payload".to_string(), 1).await);
+
+		sleep(Duration::from_millis(200)).await;
+
+		assert!(queue.is_empty());
+		let jobs = store.jobs.lock().unwrap();
+		assert!(!jobs.contains_key("done"));
+		assert!(jobs.contains_key("elsewhere"));
+	}
+
+	#[tokio::test]
+	async fn test_task_queue_renews_leases_of_running_jobs() {
+		let store = Arc::new(MapStore {
+			renew_every: Some(Duration::from_millis(20)),
+			..Default::default()
+		});
+		let queue = TaskQueue::with_store(
+			Arc::new(|_item| {
+				Box::pin(async move {
+					sleep(Duration::from_millis(110)).await;
+					Ok(())
+				})
+			}),
+			10,
+			3,
+			10,
+			1,
+			EnqueueStrategy::BestEffort,
+			None,
+			store.clone(),
+		);
+
+		assert!(queue.insert("slow".to_string(), "payload".to_string(), 1).await);
+		sleep(Duration::from_millis(250)).await;
+
+		assert!(!store.jobs.lock().unwrap().contains_key("slow"));
+		let renewals = store.renewals.load(Ordering::Relaxed);
+		assert!((3..=6).contains(&renewals), "renewed {} times", renewals);
+	}
+
+	#[tokio::test]
+	async fn test_task_queue_retry_policy_from_job_error() {
+		let store = Arc::new(MapStore::default());
+		let process_fn = Arc::new(|item: QueueItem<String>| {
+			Box::pin(async move {
+				match item.payload.as_str() {
+					"gone" => Err(Box::new(JobError::permanent("page is gone")) as Box<dyn std::error::Error + Send + Sync>),
+					_ => Err(
+						Box::new(JobError::new("slow down", RetryPolicy::fixed(5, Duration::from_secs(60))))
+							as Box<dyn std::error::Error + Send + Sync>,
+					),
+				}
+			}) as Pin<Box<dyn Future<Output = ProcessResult> + Send>>
+		});
+
+		let queue = TaskQueue::with_store(process_fn, 10, 3, 10, 5, EnqueueStrategy::BestEffort, None, store.clone());
+		assert!(queue.insert("gone".to_string(), "gone".to_string(), 1).await);
+		assert!(queue.insert("limited".to_string(), "limited".to_string(), 1).await);
+
+		sleep(Duration::from_millis(200)).await;
+
+		// Given up on after the first failure, and kept out of the queue afterwards.
+		assert_eq!(
+			store.failed.lock().unwrap().get("gone").map(String::as_str),
+			Some("page is gone")
+		);
+		assert!(!queue.insert("gone".to_string(), "gone".to_string(), 1).await);
+
+		let items = queue.peek_top_k(1).await;
+		assert_eq!(items.len(), 1);
+		assert_eq!(items[0].key, Arc::from("limited"));
+		assert!(items[0].retry_at > Instant::now() + Duration::from_secs(59));
+	}
+
+	#[tokio::test]
+	async fn test_task_queue_deferred_jobs_keep_attempts_and_priority() {
+		let store = Arc::new(MapStore::default());
+		let process_fn = Arc::new(|_item: QueueItem<String>| {
+			Box::pin(async move {
+				Err(Box::new(JobError::defer("paused", Duration::from_secs(60))) as Box<dyn std::error::Error + Send + Sync>)
+			}) as Pin<Box<dyn Future<Output = ProcessResult> + Send>>
+		});
+
+		// Deferring more often than the queue allows failures does not give up on the job.
+		let queue = TaskQueue::with_store(process_fn, 10, 0, 10, 5, EnqueueStrategy::BestEffort, None, store.clone());
+		assert!(queue.insert("paused".to_string(), "payload".to_string(), 7).await);
+
+		sleep(Duration::from_millis(200)).await;
+
+		assert!(store.failed.lock().unwrap().is_empty());
+		let items = queue.peek_top_k(1).await;
+		assert_eq!((items[0].fail_count, items[0].priority), (0, 7));
+		assert!(items[0].retry_at > Instant::now() + Duration::from_secs(59));
+		let jobs = store.jobs.lock().unwrap();
+		assert_eq!(
+			jobs.get("paused").map(|(job, leased)| (job.attempts, *leased)),
+			Some((0, false))
+		);
+	}
+
 	#[tokio::test]
 	async fn test_task_queue_block_enqueue_strategy() {
 		let queue = Arc::new(TaskQueue::<String>::new(
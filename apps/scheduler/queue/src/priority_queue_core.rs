@@ -187,16 +187,27 @@ impl<J> PriorityQueueCore<J> {
 
 	/// Peeks at the top `k` items in the queue without removing them.
 	/// Returns a vector of `Arc<QueueItem<J>>` containing the top `k` items
-	/// based on their priority. If there are fewer than `k` items, it returns
-	/// all available items. This method is useful for inspecting the highest
-	/// priority items without modifying the queue.
+	/// in the order they would be popped, ignoring aging. If there are fewer
+	/// than `k` items, it returns all available items. This method is useful
+	/// for inspecting the highest priority items without modifying the queue.
 	pub async fn peek_top_k(&self, k: usize) -> Vec<Arc<QueueItem<J>>> {
-		let heap = self.heap.lock().await;
-		heap.iter()
-			.filter_map(|(_, _, _, _, key)| self.items.get(key).map(|r| r.value().clone()))
+		let mut entries: Vec<HeapEntry> = self.heap.lock().await.iter().cloned().collect();
+		entries.sort_unstable_by(|a, b| b.cmp(a));
+		entries
+			.into_iter()
+			.filter_map(|(_, _, _, _, key)| self.items.get(&key).map(|r| r.value().clone()))
 			.take(k)
 			.collect()
 	}
+
+	/// Removes an item by its key, returning it if it was queued.
+	pub async fn remove(&self, key: &str) -> Option<Arc<QueueItem<J>>> {
+		let (_, item) = self.items.remove(key)?;
+		if self.heap_index.remove(key).is_some() {
+			self.heap.lock().await.retain(|e| &*e.4 != key);
+		}
+		Some(item)
+	}
 }
 
 #[cfg(test)]
@@ -311,6 +322,21 @@ mod tests {
 		assert_eq!(top_items[1].key, item1.key);
 	}
 
+	#[tokio::test]
+	async fn test_remove() {
+		let queue = PriorityQueueCore::<String>::new(10, None);
+		let item1 = Arc::new(QueueItem::new(Arc::from("item1"), "payload1".to_string(), 5, 0));
+		let item2 = Arc::new(QueueItem::new(Arc::from("item2"), "payload2".to_string(), 10, 1));
+
+		queue.insert(item1.clone()).await;
+		queue.insert(item2.clone()).await;
+
+		assert!(queue.remove("item2").await.is_some());
+		assert!(queue.remove("item2").await.is_none());
+		assert_eq!(queue.len(), 1);
+		assert_eq!(queue.pop().await.unwrap().key, item1.key);
+	}
+
 	#[tokio::test]
 	async fn test_pop_with_aging() {
 		let queue = PriorityQueueCore::<String>::new(10, Some(5));
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use rand::Rng;
+use rand::rngs::ThreadRng;
+
+/// How often and how late a failed job runs again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// Failed runs retried before the job is given up on. Zero gives up on the first failure.
+	pub max_retries: u32,
+	pub base_delay: Duration,
+	pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+	/// Gives up right away, for failures that cannot go away by themselves.
+	pub const NEVER: Self = Self {
+		max_retries: 0,
+		base_delay: Duration::ZERO,
+		max_delay: Duration::ZERO,
+	};
+
+	/// Waits `base_delay` before the first retry and twice as long before each next one, up to `max_delay`.
+	pub fn exponential(max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+		Self {
+			max_retries,
+			base_delay,
+			max_delay,
+		}
+	}
+
+	/// Waits the same `delay` before every retry.
+	pub fn fixed(max_retries: u32, delay: Duration) -> Self {
+		Self::exponential(max_retries, delay, delay)
+	}
+
+	/// Whether a job that failed `fail_count` times runs again.
+	pub fn allows(&self, fail_count: u32) -> bool {
+		fail_count <= self.max_retries
+	}
+
+	/// Delay before the run following the `fail_count`th failure, with up to a second of jitter.
+	pub fn delay(&self, fail_count: u32) -> Duration {
+		let factor = 2u32.saturating_pow(fail_count.saturating_sub(1).min(16));
+		let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+		let jitter_ms = ThreadRng::default().random_range(0..1000);
+		delay + Duration::from_millis(jitter_ms)
+	}
+}
+
+/// Error a process function returns to retry its job under `policy` instead of the queue's default one.
+#[derive(Debug)]
+pub struct JobError {
+	error: Box<dyn Error + Send + Sync>,
+	policy: RetryPolicy,
+	deferral: Option<Duration>,
+}
+
+impl JobError {
+	pub fn new(error: impl Into<Box<dyn Error + Send + Sync>>, policy: RetryPolicy) -> Self {
+		Self {
+			error: error.into(),
+			policy,
+			deferral: None,
+		}
+	}
+
+	/// A job that cannot run yet, e.g. while its scraper is paused. It runs again after `delay` without using up an
+	/// attempt, and keeps its priority.
+	pub fn defer(error: impl Into<Box<dyn Error + Send + Sync>>, delay: Duration) -> Self {
+		Self {
+			deferral: Some(delay),
+			..Self::new(error, RetryPolicy::NEVER)
+		}
+	}
+
+	/// A failure not worth retrying; the job is given up on right away.
+	pub fn permanent(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+		Self::new(error, RetryPolicy::NEVER)
+	}
+
+	pub fn policy(&self) -> RetryPolicy {
+		self.policy
+	}
+
+	pub fn deferral(&self) -> Option<Duration> {
+		self.deferral
+	}
+}
+
+impl fmt::Display for JobError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(&self.error, f)
+	}
+}
+
+impl Error for JobError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		self.error.source()
+	}
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_exponential_delay_is_capped() {
+		let policy = RetryPolicy::exponential(5, Duration::from_secs(30), Duration::from_secs(300));
+
+		let first = policy.delay(1);
+		assert!(first >= Duration::from_secs(30) && first < Duration::from_secs(31));
+		let third = policy.delay(3);
+		assert!(third >= Duration::from_secs(120) && third < Duration::from_secs(121));
+		assert!(policy.delay(10) < Duration::from_secs(301));
+
+		assert!(policy.allows(5));
+		assert!(!policy.allows(6));
+		assert!(!RetryPolicy::NEVER.allows(1));
+	}
+}
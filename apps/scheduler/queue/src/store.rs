@@ -0,0 +1,102 @@
+use std::time::{Duration, Instant, SystemTime};
+
+pub use futures::future::BoxFuture;
+
+pub type StoreResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// A job as a [`QueueStore`] persists it. Times are wall-clock so they survive a restart.
+#[derive(Debug, Clone)]
+pub struct StoredJob<J> {
+	pub key: String,
+	pub payload: J,
+	pub priority: u8,
+	pub attempts: u32,
+	pub retry_at: SystemTime,
+}
+
+/// Durable storage behind a [`TaskQueue`](crate::TaskQueue).
+///
+/// The queue keeps dispatching from memory and mirrors every state change here. Before a job runs the queue takes a
+/// lease on it, so processes sharing a store never run the same job twice while the lease holds.
+pub trait QueueStore<J>: Send + Sync {
+	/// Saves a new job, or raises the priority of a pending one. Jobs leased by someone else are left alone.
+	///
+	/// Returns false for a job that failed for good, which stays out of the queue until an operator replays it.
+	fn save<'a>(&'a self, job: &'a StoredJob<J>) -> BoxFuture<'a, StoreResult<bool>>;
+
+	/// Leases `key` to this process. `false` means the job is gone or another process holds it.
+	fn lease<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<bool>>;
+
+	/// How often the lease of a running job is renewed, so a long job keeps it. `None` for leases that never expire.
+	fn renew_interval(&self) -> Option<Duration> {
+		None
+	}
+
+	/// Extends the lease this process holds on a running job. `false` means the lease is no longer ours.
+	fn renew<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, StoreResult<bool>> {
+		Box::pin(async { Ok(true) })
+	}
+
+	/// Removes a job that succeeded.
+	fn complete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<()>>;
+
+	/// Releases the lease and schedules the job to run again at `retry_at`.
+	fn retry<'a>(&'a self, key: &'a str, attempts: u32, retry_at: SystemTime) -> BoxFuture<'a, StoreResult<()>>;
+
+	/// Marks a job that used up its attempts, or failed in a way not worth retrying, as failed.
+	fn fail<'a>(&'a self, key: &'a str, attempts: u32, error: &'a str) -> BoxFuture<'a, StoreResult<()>>;
+
+	/// Deletes a job waiting to run, e.g. when an operator drops it. Running and failed jobs are kept.
+	fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<()>>;
+
+	/// Pending jobs and jobs whose lease expired, highest priority first.
+	fn load(&self, limit: usize) -> BoxFuture<'_, StoreResult<Vec<StoredJob<J>>>>;
+}
+
+/// Keeps nothing, so the queue lives only in memory and loses its jobs on restart.
+pub struct VolatileStore;
+
+impl<J> QueueStore<J> for VolatileStore {
+	fn save<'a>(&'a self, _job: &'a StoredJob<J>) -> BoxFuture<'a, StoreResult<bool>> {
+		Box::pin(async { Ok(true) })
+	}
+
+	fn lease<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, StoreResult<bool>> {
+		Box::pin(async { Ok(true) })
+	}
+
+	fn complete<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async { Ok(()) })
+	}
+
+	fn retry<'a>(&'a self, _key: &'a str, _attempts: u32, _retry_at: SystemTime) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async { Ok(()) })
+	}
+
+	fn fail<'a>(&'a self, _key: &'a str, _attempts: u32, _error: &'a str) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async { Ok(()) })
+	}
+
+	fn remove<'a>(&'a self, _key: &'a str) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async { Ok(()) })
+	}
+
+	fn load(&self, _limit: usize) -> BoxFuture<'_, StoreResult<Vec<StoredJob<J>>>> {
+		Box::pin(async { Ok(Vec::new()) })
+	}
+}
+
+/// Converts a queue deadline to wall-clock time.
+pub(crate) fn to_system_time(at: Instant) -> SystemTime {
+	let now = Instant::now();
+	if at >= now {
+		SystemTime::now() + (at - now)
+	} else {
+		SystemTime::now() - (now - at)
+	}
+}
+
+/// Converts a stored wall-clock time back to a queue deadline. Times in the past are due now.
+pub(crate) fn to_instant(at: SystemTime) -> Instant {
+	Instant::now() + at.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO)
+}
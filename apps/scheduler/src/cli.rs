@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use anyhow::{Context, bail};
+use database_connection::Database;
+use scheduler::{ALL_SCRAPERS, ItemType, SchedulerControl};
+
+pub fn print_usage() {
+	println!("Usage: manga-vault-scheduler [command]");
+	println!();
+	println!("Without a command the scheduler runs. Commands steer running schedulers through the database:");
+	println!("  status                              Show pauses and stored jobs by status");
+	println!("  workers                             List scheduler processes and their last heartbeat");
+	println!("  jobs [pending|leased|failed]        List stored jobs, highest priority first");
+	println!("  pause <scraper|all> [reason...]     Stop scheduling and running updates");
+	println!("  resume <scraper|all>                Lift a pause");
+	println!("  enqueue <manga|novel> <id>          Update a manga or novel next");
+	println!("  drop <key>                          Drop a job that has not started");
+	println!("  replay <key|all> [scraper]          Queue dead-lettered jobs again");
+	println!("  discard <key>                       Delete a dead-lettered job");
+}
+
+fn scope(arg: Option<&String>) -> anyhow::Result<&str> {
+	match arg.map(String::as_str) {
+		Some("all") => Ok(ALL_SCRAPERS),
+		Some(scraper) => Ok(scraper),
+		None => bail!("Missing scraper id, or `all`"),
+	}
+}
+
+pub async fn run(db: Arc<Database>, command: &str, args: &[String]) -> anyhow::Result<()> {
+	let control = SchedulerControl::new(db);
+
+	match command {
+		"status" => {
+			let pauses = control.pauses().await?;
+			if pauses.is_empty() {
+				println!("Not paused");
+			}
+			for pause in pauses {
+				let scope = if pause.scope == ALL_SCRAPERS {
+					"all scrapers"
+				} else {
+					pause.scope.as_str()
+				};
+				println!(
+					"Paused {} since {} by {}: {}",
+					scope,
+					pause.created_at,
+					pause.paused_by.as_deref().unwrap_or("unknown"),
+					pause.reason.as_deref().unwrap_or("no reason given")
+				);
+			}
+
+			println!();
+			for (status, count) in control.job_counts().await? {
+				println!("{:<8} {}", status, count);
+			}
+		}
+		"workers" => {
+			for worker in control.workers().await? {
+				println!(
+					"{:<32} {:<5} {:<6} pid={} queued={} running={} started={} heartbeat={}",
+					worker.instance_id,
+					if worker.alive { "alive" } else { "dead" },
+					if worker.leader { "leader" } else { "" },
+					worker.pid,
+					worker.queued_jobs,
+					worker.running_jobs,
+					worker.started_at,
+					worker.heartbeat_at
+				);
+			}
+		}
+		"jobs" => {
+			for job in control.stored_jobs(args.first().map(String::as_str), 100).await? {
+				println!(
+					"{:<3} {:<8} {:<40} attempts={} retry_at={}{}",
+					job.priority,
+					job.status,
+					job.key,
+					job.attempts,
+					job.retry_at,
+					job.last_error.map(|e| format!(" error={}", e)).unwrap_or_default()
+				);
+			}
+		}
+		"pause" => {
+			let scope = scope(args.first())?;
+			let reason = Some(args[1..].join(" ")).filter(|reason| !reason.is_empty());
+			control.pause(scope, reason, Some("cli".to_string())).await?;
+			println!("Paused {}", args[0]);
+		}
+		"resume" => {
+			let scope = scope(args.first())?;
+			if control.resume(scope).await? {
+				println!("Resumed {}", args[0]);
+			} else {
+				println!("{} was not paused", args[0]);
+			}
+		}
+		"enqueue" => {
+			let item_type = match args.first().map(String::as_str) {
+				Some("manga") => ItemType::Manga,
+				Some("novel") => ItemType::Novel,
+				_ => bail!("Expected `manga` or `novel`"),
+			};
+			let id: i32 = args.get(1).context("Missing id")?.parse().context("Invalid id")?;
+			println!("Enqueued {}", control.enqueue(item_type, id).await?);
+		}
+		"drop" => {
+			let key = args.first().context("Missing job key")?;
+			if control.drop_job(key).await? {
+				println!("Dropped {}", key);
+			} else {
+				println!("No job {}", key);
+			}
+		}
+		"replay" => match args.first().map(String::as_str) {
+			Some("all") => {
+				let replayed = control.replay_all(args.get(1).map(String::as_str)).await?;
+				println!("Replayed {} jobs", replayed);
+			}
+			Some(key) => {
+				if control.replay(key).await? {
+					println!("Replayed {}", key);
+				} else {
+					println!("No failed job {}", key);
+				}
+			}
+			None => bail!("Missing job key, or `all`"),
+		},
+		"discard" => {
+			let key = args.first().context("Missing job key")?;
+			if control.discard(key).await? {
+				println!("Discarded {}", key);
+			} else {
+				println!("No failed job {}", key);
+			}
+		}
+		_ => {
+			print_usage();
+			bail!("Unknown command: {}", command);
+		}
+	}
+
+	Ok(())
+}
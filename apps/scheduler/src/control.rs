@@ -0,0 +1,382 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::anyhow;
+use chrono::{NaiveDateTime, Utc};
+use database_connection::Database;
+use database_entities::{
+	favorite_mangas, favorite_novels, mangas, novels, scheduler_jobs, scheduler_pauses, scheduler_workers,
+};
+use queue::store::{QueueStore, StoredJob};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use tokio::sync::Mutex;
+
+use crate::store::{DatabaseQueueStore, STATUS_FAILED, STATUS_PENDING};
+use crate::{ItemType, UpdateJob, WorkerInfo};
+
+/// Pause scope that stops the whole scheduler rather than one scraper.
+pub const ALL_SCRAPERS: &str = "*";
+
+/// Priority of updates users ask for, right below the ones operators enqueue.
+const REQUESTED_PRIORITY: u8 = u8::MAX - 1;
+
+/// How long the scheduler trusts its copy of the pauses before reading them again.
+const PAUSE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Dead-lettered jobs read at once by `replay_all`.
+const REPLAY_BATCH: u64 = 500;
+
+/// A stored job, as listed to operators.
+#[derive(Debug, Clone)]
+pub struct StoredJobInfo {
+	pub key: String,
+	/// `None` when the payload cannot be read.
+	pub item_type: Option<ItemType>,
+	pub item_id: Option<i32>,
+	pub scraper: Option<String>,
+	pub priority: u8,
+	pub status: String,
+	pub attempts: u32,
+	pub retry_at: NaiveDateTime,
+	pub lease_owner: Option<String>,
+	pub lease_expires_at: Option<NaiveDateTime>,
+	pub last_error: Option<String>,
+	pub updated_at: NaiveDateTime,
+}
+
+impl From<scheduler_jobs::Model> for StoredJobInfo {
+	fn from(row: scheduler_jobs::Model) -> Self {
+		let payload = serde_json::from_str::<UpdateJob>(&row.payload).ok();
+		Self {
+			key: row.key,
+			item_type: payload.as_ref().map(|job| job.item_type),
+			item_id: payload.as_ref().map(|job| job.item_id),
+			scraper: payload.map(|job| job.scraper_name),
+			priority: row.priority.clamp(0, u8::MAX as i16) as u8,
+			status: row.status,
+			attempts: row.attempts.max(0) as u32,
+			retry_at: row.retry_at,
+			lease_owner: row.lease_owner,
+			lease_expires_at: row.lease_expires_at,
+			last_error: row.last_error,
+			updated_at: row.updated_at,
+		}
+	}
+}
+
+/// Pauses, enqueues, drops and replays scheduler jobs through the database, so it steers every scheduler process sharing it.
+/// Operations also apply to the scheduler running in this process, if any, right away.
+pub struct SchedulerControl {
+	db: Arc<Database>,
+	pauses: Mutex<Option<(Instant, HashSet<String>)>>,
+}
+
+impl SchedulerControl {
+	pub fn new(db: Arc<Database>) -> Self {
+		Self {
+			db,
+			pauses: Mutex::new(None),
+		}
+	}
+
+	pub async fn pauses(&self) -> anyhow::Result<Vec<scheduler_pauses::Model>> {
+		Ok(scheduler_pauses::Entity::find()
+			.order_by_asc(scheduler_pauses::Column::CreatedAt)
+			.all(&self.db.conn)
+			.await?)
+	}
+
+	/// Pauses one scraper, or the whole scheduler with [`ALL_SCRAPERS`]. Pausing again replaces the reason.
+	pub async fn pause(&self, scope: &str, reason: Option<String>, paused_by: Option<String>) -> anyhow::Result<()> {
+		let existing = scheduler_pauses::Entity::find()
+			.filter(scheduler_pauses::Column::Scope.eq(scope))
+			.one(&self.db.conn)
+			.await?;
+
+		match existing {
+			Some(pause) => {
+				let mut model: scheduler_pauses::ActiveModel = pause.into();
+				model.reason = Set(reason);
+				model.paused_by = Set(paused_by);
+				model.update(&self.db.conn).await?;
+			}
+			None => {
+				scheduler_pauses::ActiveModel {
+					scope: Set(scope.to_string()),
+					reason: Set(reason),
+					paused_by: Set(paused_by),
+					created_at: Set(Utc::now().naive_utc()),
+					..Default::default()
+				}
+				.insert(&self.db.conn)
+				.await?;
+			}
+		}
+
+		*self.pauses.lock().await = None;
+		Ok(())
+	}
+
+	/// Returns false if the scope was not paused.
+	pub async fn resume(&self, scope: &str) -> anyhow::Result<bool> {
+		let result = scheduler_pauses::Entity::delete_many()
+			.filter(scheduler_pauses::Column::Scope.eq(scope))
+			.exec(&self.db.conn)
+			.await?;
+
+		*self.pauses.lock().await = None;
+		Ok(result.rows_affected > 0)
+	}
+
+	/// Whether jobs of `scraper` should be held back, because it or the whole scheduler is paused. Reads the pauses
+	/// at most every few seconds, and treats them as lifted when they cannot be read.
+	pub async fn is_paused(&self, scraper: &str) -> bool {
+		let scopes = self.paused_scopes().await;
+		scopes.contains(ALL_SCRAPERS) || scopes.contains(scraper)
+	}
+
+	async fn paused_scopes(&self) -> HashSet<String> {
+		let mut cached = self.pauses.lock().await;
+		if let Some((loaded_at, scopes)) = cached.as_ref()
+			&& loaded_at.elapsed() < PAUSE_CACHE_TTL
+		{
+			return scopes.clone();
+		}
+
+		let scopes: HashSet<String> = match self.pauses().await {
+			Ok(pauses) => pauses.into_iter().map(|pause| pause.scope).collect(),
+			Err(e) => {
+				tracing::warn!("Failed to load scheduler pauses: {:#}", e);
+				HashSet::new()
+			}
+		};
+		*cached = Some((Instant::now(), scopes.clone()));
+		scopes
+	}
+
+	/// Stored jobs, highest priority first. `status` is `pending`, `leased` or `failed`.
+	pub async fn stored_jobs(&self, status: Option<&str>, limit: u32) -> anyhow::Result<Vec<StoredJobInfo>> {
+		let mut query = scheduler_jobs::Entity::find();
+		if let Some(status) = status {
+			query = query.filter(scheduler_jobs::Column::Status.eq(status));
+		}
+
+		let rows = query
+			.order_by_desc(scheduler_jobs::Column::Priority)
+			.order_by_asc(scheduler_jobs::Column::RetryAt)
+			.limit(u64::from(limit))
+			.all(&self.db.conn)
+			.await?;
+
+		Ok(rows.into_iter().map(StoredJobInfo::from).collect())
+	}
+
+	/// Number of stored jobs by status.
+	pub async fn job_counts(&self) -> anyhow::Result<Vec<(String, i64)>> {
+		Ok(scheduler_jobs::Entity::find()
+			.select_only()
+			.column(scheduler_jobs::Column::Status)
+			.column_as(scheduler_jobs::Column::Id.count(), "count")
+			.group_by(scheduler_jobs::Column::Status)
+			.order_by_asc(scheduler_jobs::Column::Status)
+			.into_tuple()
+			.all(&self.db.conn)
+			.await?)
+	}
+
+	/// Scheduler processes that reported in, the leader first. Stopped ones are kept for a day.
+	pub async fn workers(&self) -> anyhow::Result<Vec<WorkerInfo>> {
+		let mut workers: Vec<WorkerInfo> = scheduler_workers::Entity::find()
+			.order_by_asc(scheduler_workers::Column::StartedAt)
+			.all(&self.db.conn)
+			.await?
+			.into_iter()
+			.map(WorkerInfo::from)
+			.collect();
+
+		workers.sort_by_key(|worker| (!(worker.alive && worker.leader), !worker.alive));
+		Ok(workers)
+	}
+
+	/// Queues an update of the manga or novel at the highest priority and returns the job key.
+	pub async fn enqueue(&self, item_type: ItemType, item_id: i32) -> anyhow::Result<String> {
+		let job = self.update_job(item_type, item_id).await?;
+		let key = job.key();
+		// Asking for an update explicitly also brings it back from the dead-letter list.
+		self.replay(&key).await?;
+
+		self.queue_job(job, u8::MAX).await?;
+		Ok(key)
+	}
+
+	/// Queues an update of the manga or novel at `priority`, unless its job is on the dead-letter list. Returns
+	/// whether it was queued.
+	pub async fn enqueue_at(&self, item_type: ItemType, item_id: i32, priority: u8) -> anyhow::Result<bool> {
+		let job = self.update_job(item_type, item_id).await?;
+		self.queue_job(job, priority).await
+	}
+
+	/// Queues updates of every manga and novel in the category, whether or not it is auto-updated. Returns how many
+	/// were queued.
+	pub async fn enqueue_category(&self, category_id: i32) -> anyhow::Result<usize> {
+		let manga_ids: Vec<i32> = favorite_mangas::Entity::find()
+			.select_only()
+			.column(favorite_mangas::Column::MangaId)
+			.filter(favorite_mangas::Column::CategoryId.eq(category_id))
+			.into_tuple()
+			.all(&self.db.conn)
+			.await?;
+		let novel_ids: Vec<i32> = favorite_novels::Entity::find()
+			.select_only()
+			.column(favorite_novels::Column::NovelId)
+			.filter(favorite_novels::Column::CategoryId.eq(category_id))
+			.into_tuple()
+			.all(&self.db.conn)
+			.await?;
+
+		let mut queued = 0;
+		let items = manga_ids
+			.into_iter()
+			.map(|id| (ItemType::Manga, id))
+			.chain(novel_ids.into_iter().map(|id| (ItemType::Novel, id)));
+		for (item_type, item_id) in items {
+			if self.enqueue_at(item_type, item_id, REQUESTED_PRIORITY).await? {
+				queued += 1;
+			}
+		}
+		Ok(queued)
+	}
+
+	async fn update_job(&self, item_type: ItemType, item_id: i32) -> anyhow::Result<UpdateJob> {
+		let scraper = match item_type {
+			ItemType::Manga => mangas::Entity::find_by_id(item_id)
+				.one(&self.db.conn)
+				.await?
+				.map(|manga| manga.scraper),
+			ItemType::Novel => novels::Entity::find_by_id(item_id)
+				.one(&self.db.conn)
+				.await?
+				.map(|novel| novel.scraper),
+		}
+		.ok_or_else(|| anyhow!("{} {} not found", item_type, item_id))?;
+
+		Ok(UpdateJob {
+			item_id,
+			scraper_name: scraper,
+			item_type,
+			last_attempt: None,
+		})
+	}
+
+	async fn queue_job(&self, job: UpdateJob, priority: u8) -> anyhow::Result<bool> {
+		let key = job.key();
+		if let Some(scheduler) = crate::local() {
+			return Ok(scheduler.queue.insert(key, job, priority).await);
+		}
+
+		// Picked up by the scheduler processes on their next poll.
+		let store = DatabaseQueueStore::<UpdateJob>::new(self.db.clone(), String::new(), Duration::ZERO);
+		store
+			.save(&StoredJob {
+				key,
+				payload: job,
+				priority,
+				attempts: 0,
+				retry_at: SystemTime::now(),
+			})
+			.await
+			.map_err(|e| anyhow!(e))
+	}
+
+	/// Drops a job that has not started yet. Returns false if no such job was found. Running jobs finish, and failed
+	/// ones are discarded instead.
+	pub async fn drop_job(&self, key: &str) -> anyhow::Result<bool> {
+		let result = scheduler_jobs::Entity::delete_many()
+			.filter(scheduler_jobs::Column::Key.eq(key))
+			.filter(scheduler_jobs::Column::Status.eq(STATUS_PENDING))
+			.exec(&self.db.conn)
+			.await?;
+
+		let dropped_locally = match crate::local() {
+			Some(scheduler) => scheduler.queue.remove(key).await,
+			None => false,
+		};
+
+		Ok(result.rows_affected > 0 || dropped_locally)
+	}
+
+	/// Moves a dead-lettered job back to the queue with its attempts reset. Returns false if no such job was found.
+	pub async fn replay(&self, key: &str) -> anyhow::Result<bool> {
+		Ok(self.replay_keys(vec![key.to_string()]).await? > 0)
+	}
+
+	/// Replays every dead-lettered job, or only those of `scraper`, e.g. once a broken plugin is fixed. Returns how
+	/// many were replayed.
+	pub async fn replay_all(&self, scraper: Option<&str>) -> anyhow::Result<u64> {
+		let mut replayed = 0;
+		let mut after_id = None;
+		loop {
+			let mut query = scheduler_jobs::Entity::find().filter(scheduler_jobs::Column::Status.eq(STATUS_FAILED));
+			if let Some(id) = after_id {
+				query = query.filter(scheduler_jobs::Column::Id.gt(id));
+			}
+			let rows = query
+				.order_by_asc(scheduler_jobs::Column::Id)
+				.limit(REPLAY_BATCH)
+				.all(&self.db.conn)
+				.await?;
+			let Some(last) = rows.last() else {
+				break;
+			};
+			after_id = Some(last.id);
+
+			let keys = rows
+				.into_iter()
+				.map(StoredJobInfo::from)
+				.filter(|job| scraper.is_none() || job.scraper.as_deref() == scraper)
+				.map(|job| job.key)
+				.collect::<Vec<_>>();
+			if !keys.is_empty() {
+				replayed += self.replay_keys(keys).await?;
+			}
+		}
+
+		Ok(replayed)
+	}
+
+	async fn replay_keys(&self, keys: Vec<String>) -> anyhow::Result<u64> {
+		let now = Utc::now().naive_utc();
+		let result = scheduler_jobs::Entity::update_many()
+			.col_expr(scheduler_jobs::Column::Status, Expr::value(STATUS_PENDING))
+			.col_expr(scheduler_jobs::Column::Attempts, Expr::value(0))
+			.col_expr(scheduler_jobs::Column::RetryAt, Expr::value(now))
+			.col_expr(scheduler_jobs::Column::LastError, Expr::value(Option::<String>::None))
+			.col_expr(scheduler_jobs::Column::UpdatedAt, Expr::value(now))
+			.filter(scheduler_jobs::Column::Key.is_in(keys))
+			.filter(scheduler_jobs::Column::Status.eq(STATUS_FAILED))
+			.exec(&self.db.conn)
+			.await?;
+
+		if result.rows_affected > 0
+			&& let Some(scheduler) = crate::local()
+		{
+			scheduler.queue.restore().await;
+		}
+		Ok(result.rows_affected)
+	}
+
+	/// Deletes a dead-lettered job. Its manga or novel is scheduled again the next time it is due. Returns false if
+	/// no such job was found.
+	pub async fn discard(&self, key: &str) -> anyhow::Result<bool> {
+		let result = scheduler_jobs::Entity::delete_many()
+			.filter(scheduler_jobs::Column::Key.eq(key))
+			.filter(scheduler_jobs::Column::Status.eq(STATUS_FAILED))
+			.exec(&self.db.conn)
+			.await?;
+
+		Ok(result.rows_affected > 0)
+	}
+}
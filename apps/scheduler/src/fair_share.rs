@@ -0,0 +1,77 @@
+/// Priority of the favorites taken in the first round, below updates asked for by hand.
+const FIRST_ROUND_PRIORITY: u8 = 200;
+/// Priority the rounds bottom out at, still above series nobody follows.
+const LAST_ROUND_PRIORITY: u8 = 20;
+
+/// Interleaves the due favorites of every user, taking up to `weight` of each user's items per round, so a user
+/// with hundreds of favorites cannot push everyone else's updates out of the queue. Returns each item with the
+/// round that took it.
+pub(crate) fn interleave<T>(users: Vec<(u32, Vec<T>)>) -> Vec<(T, usize)> {
+	let mut queues: Vec<(usize, std::vec::IntoIter<T>)> = users
+		.into_iter()
+		.map(|(weight, items)| (weight.max(1) as usize, items.into_iter()))
+		.collect();
+
+	let mut interleaved = Vec::new();
+	let mut round = 0;
+	while !queues.is_empty() {
+		queues.retain_mut(|(weight, items)| {
+			interleaved.extend(items.by_ref().take(*weight).map(|item| (item, round)));
+			!items.as_slice().is_empty()
+		});
+		round += 1;
+	}
+
+	interleaved
+}
+
+/// Earlier rounds run first. The queue drops its lowest priorities when full, so the last rounds of the busiest
+/// users are the ones left for the next scan.
+pub(crate) fn round_priority(round: usize) -> u8 {
+	let round = round.min(u8::MAX as usize) as u8;
+	FIRST_ROUND_PRIORITY.saturating_sub(round).max(LAST_ROUND_PRIORITY)
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_interleave_shares_rounds() {
+		let heavy: Vec<String> = (0..900).map(|i| format!("heavy-{}", i)).collect();
+		let light = vec!["light-0".to_string(), "light-1".to_string()];
+		let weighted = vec!["weighted-0".to_string(), "weighted-1".to_string(), "weighted-2".to_string()];
+
+		let interleaved = interleave(vec![(1, heavy), (1, light), (2, weighted), (3, Vec::new())]);
+		assert_eq!(interleaved.len(), 905);
+
+		let first: Vec<(&str, usize)> = interleaved
+			.iter()
+			.take(8)
+			.map(|(item, round)| (item.as_str(), *round))
+			.collect();
+		assert_eq!(
+			first,
+			vec![
+				("heavy-0", 0),
+				("light-0", 0),
+				("weighted-0", 0),
+				("weighted-1", 0),
+				("heavy-1", 1),
+				("light-1", 1),
+				("weighted-2", 1),
+				("heavy-2", 2),
+			]
+		);
+		assert_eq!(interleaved.last().map(|(_, round)| *round), Some(899));
+	}
+
+	#[test]
+	fn test_round_priority() {
+		assert_eq!(round_priority(0), FIRST_ROUND_PRIORITY);
+		assert!(round_priority(1) < round_priority(0));
+		assert_eq!(round_priority(5000), LAST_ROUND_PRIORITY);
+		assert!(round_priority(5000) < u8::MAX);
+	}
+}
@@ -1,19 +1,51 @@
+#![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, Weak};
 use std::time::{Duration, Instant};
 
 use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
 use database_connection::Database;
-use database_entities::{favorite_mangas, favorite_novels, mangas, novels};
+use database_entities::{categories, favorite_mangas, favorite_novels, mangas, novels, users};
 use queue::queue_item::QueueItem;
+use queue::retry::{JobError, RetryPolicy};
+use queue::store::{QueueStore, VolatileStore};
 use queue::{EnqueueStrategy, TaskQueue};
 use scraper_core::ScraperManager;
-use sea_orm::{
-	ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Statement, TransactionTrait,
-};
+use scraper_core::health::{self, Admission};
+use scraper_core::limits;
+use scraper_types::{ScraperError, ScraperErrorKind};
+use sea_orm::sea_query::Query;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+pub use crate::control::{ALL_SCRAPERS, SchedulerControl, StoredJobInfo};
+pub use crate::maintenance::{MaintenanceConfig, MaintenanceScheduler, MaintenanceTask, MaintenanceTaskStatus};
+pub use crate::retry::RetryPolicyConfig;
+use crate::store::DatabaseQueueStore;
+pub use crate::store::{STATUS_FAILED, STATUS_LEASED, STATUS_PENDING};
+pub use crate::worker::{DatabaseLock, WorkerInfo};
+use crate::worker::{LEADER_LOCK, WorkerRegistration};
+
+mod control;
+mod fair_share;
+mod maintenance;
+mod metrics;
+mod retry;
+mod store;
+mod worker;
+
+/// How long a job of a paused scraper waits before it checks the pause again.
+const PAUSED_JOB_DELAY: Duration = Duration::from_secs(60);
+
+/// The scheduler running in this process, once it started.
+static LOCAL: OnceLock<Weak<MangaUpdateScheduler>> = OnceLock::new();
+
+/// The scheduler running in this process, if any. Its in-memory queue can only be inspected through it.
+pub fn local() -> Option<Arc<MangaUpdateScheduler>> {
+	LOCAL.get().and_then(Weak::upgrade)
+}
 
 #[allow(dead_code)]
 pub struct MangaUpdateScheduler {
@@ -21,69 +53,89 @@ pub struct MangaUpdateScheduler {
 	db: Arc<Database>,
 	interval: Duration,
 	scraper_manager: Arc<ScraperManager>,
-	scraper_limiter: Arc<Mutex<PerScraperLimiter>>,
 	favorites_only: bool,
+	claim_limit: u64,
+	user_weights: BTreeMap<String, u32>,
+	/// Set when jobs are stored under a configured `instance_id`.
+	instance_id: Option<String>,
+	control: Arc<SchedulerControl>,
+	control_poll: Duration,
+	/// Set when workers share stored jobs, so only the holder scans for due mangas and novels.
+	leader_lock: Option<DatabaseLock>,
+	leader: AtomicBool,
+	leader_elected: tokio::sync::Notify,
+	registration: WorkerRegistration,
+	heartbeat: Duration,
 }
 
-#[derive(Clone, Debug)]
-#[allow(dead_code)]
-enum ItemType {
+/// A due manga or novel in an auto-updated category of a user.
+struct DueFavorite {
+	user_id: i32,
+	item_type: ItemType,
+	item_id: i32,
+	scraper: String,
+	due_since: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ItemType {
 	Manga,
 	Novel,
 }
 
-#[derive(Clone, Debug)]
+impl std::fmt::Display for ItemType {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ItemType::Manga => write!(f, "manga"),
+			ItemType::Novel => write!(f, "novel"),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[allow(dead_code)]
 struct UpdateJob {
 	item_id: i32,
 	scraper_name: String,
 	item_type: ItemType,
+	#[serde(skip)]
 	last_attempt: Option<Instant>,
 }
 
-struct PerScraperLimiter {
-	semaphores: HashMap<String, Arc<Semaphore>>,
-	last_used: HashMap<String, Instant>,
-	cooldown: Duration,
-	per_scraper_concurrency: usize,
-	overrides: HashMap<String, usize>,
+impl UpdateJob {
+	fn key(&self) -> String {
+		format!("{}-update-{}-{}", self.item_type, self.scraper_name, self.item_id)
+	}
+}
+
+/// A job in the in-memory queue of the scheduler running in this process.
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+	pub key: String,
+	pub item_type: ItemType,
+	pub item_id: i32,
+	pub scraper: String,
+	pub priority: u8,
+	pub attempts: u32,
+	/// Zero once the job may run.
+	pub due_in: Duration,
+	/// Set for jobs that are running.
+	pub running_for: Option<Duration>,
 }
 
-impl PerScraperLimiter {
-	pub fn new(per_scraper_concurrency: usize, cooldown: Duration, overrides: HashMap<String, usize>) -> Self {
+impl From<&QueueItem<UpdateJob>> for QueuedJob {
+	fn from(item: &QueueItem<UpdateJob>) -> Self {
 		Self {
-			semaphores: HashMap::new(),
-			last_used: HashMap::new(),
-			cooldown,
-			per_scraper_concurrency,
-			overrides,
+			key: item.key.to_string(),
+			item_type: item.payload.item_type,
+			item_id: item.payload.item_id,
+			scraper: item.payload.scraper_name.clone(),
+			priority: item.priority,
+			attempts: item.fail_count,
+			due_in: item.retry_at.saturating_duration_since(Instant::now()),
+			running_for: item.last_tried.map(|at| at.elapsed()),
 		}
 	}
-
-	fn get_semaphore(&mut self, scraper: &str) -> Arc<Semaphore> {
-		let cap = self.overrides.get(scraper).cloned().unwrap_or(self.per_scraper_concurrency);
-		self.semaphores
-			.entry(scraper.to_string())
-			.or_insert_with(|| Arc::new(Semaphore::new(cap)))
-			.clone()
-	}
-
-	fn mark_used(&mut self, scraper: &str) {
-		self.last_used.insert(scraper.to_string(), Instant::now());
-	}
-
-	fn needs_cooldown(&self, scraper: &str) -> Option<Duration> {
-		self.last_used
-			.get(scraper)
-			.map(|last| {
-				if last.elapsed() < self.cooldown {
-					Some(self.cooldown - last.elapsed())
-				} else {
-					None
-				}
-			})
-			.flatten()
-	}
 }
 
 #[derive(Debug, Deserialize, Serialize, config_derive::Config)]
@@ -96,21 +148,42 @@ pub struct Config {
 	#[serde(default)]
 	pub max_concurrency: usize,
 	#[serde(default)]
-	pub default_per_scraper_concurrency: usize,
-	#[serde(default)]
-	pub per_scraper_limits: BTreeMap<String, usize>,
-	#[serde(default)]
 	pub queue_aging_interval_secs: Option<u64>,
 	#[serde(default)]
-	pub cooldown_seconds: u64,
-	#[serde(default)]
 	pub search_interval_seconds: u64,
+	/// Most favorites queued per scan.
 	#[serde(default)]
 	pub claim_limit: u64,
 	#[serde(default)]
 	pub enqueue_strategy: String,
+	/// Only update series in users' favorites. Otherwise the rest are updated too, after every favorite.
 	#[serde(default)]
 	pub favorites_only: bool,
+	/// Share of the update queue by username, relative to the default of 1. Each scan takes this many favorites of
+	/// every user in turn, so users with many favorites cannot starve the others.
+	#[serde(default)]
+	pub user_weights: BTreeMap<String, u32>,
+	/// `database` keeps queued jobs across restarts and shares them between scheduler processes; `memory` does not.
+	#[serde(default)]
+	pub queue_store: String,
+	/// How long a job stays leased after its process last renewed it, i.e. how soon others take over the jobs of a crashed
+	/// process. Running jobs renew their lease every third of this.
+	#[serde(default)]
+	pub job_lease_seconds: u64,
+	/// Names this process in job leases. Keep it stable across restarts to take back its own jobs immediately.
+	#[serde(default)]
+	pub instance_id: Option<String>,
+	/// How often stored jobs, such as ones enqueued by an operator, are picked up.
+	#[serde(default)]
+	pub control_poll_seconds: u64,
+	/// Retry policies by scraper error kind, e.g. `rate_limit` or `cloudflare`. Other retryable kinds use the
+	/// `network` policy; `not_found` and `validation` errors are never retried.
+	#[serde(default)]
+	pub retry_policies: BTreeMap<String, RetryPolicyConfig>,
+	/// How often this process reports itself alive and renews its leadership. Workers missing three heartbeats are
+	/// listed as dead and a leader missing three is replaced.
+	#[serde(default)]
+	pub heartbeat_seconds: u64,
 }
 
 impl Default for Config {
@@ -119,18 +192,27 @@ impl Default for Config {
 			queue_max_size: 100,
 			channel_capacity: 100,
 			max_concurrency: 5,
-			default_per_scraper_concurrency: 2,
-			per_scraper_limits: BTreeMap::new(),
 			queue_aging_interval_secs: Some(300),
-			cooldown_seconds: 10,
 			search_interval_seconds: 30 * 60,
 			claim_limit: 500,
 			enqueue_strategy: "best_effort".to_string(),
 			favorites_only: false,
+			user_weights: BTreeMap::new(),
+			queue_store: "database".to_string(),
+			job_lease_seconds: 10 * 60,
+			instance_id: None,
+			control_poll_seconds: 15,
+			retry_policies: retry::default_policies(),
+			heartbeat_seconds: 15,
 		}
 	}
 }
 
+/// Unique per process, so a restarted scheduler waits for the leases of its previous run to expire.
+fn default_instance_id() -> String {
+	format!("{}-{}", worker::hostname(), std::process::id())
+}
+
 impl MangaUpdateScheduler {
 	/// Creates a new `MangaUpdateScheduler`.
 	/// # Arguments
@@ -138,75 +220,57 @@ impl MangaUpdateScheduler {
 	/// * `scraper_manager` - The scraper manager to use for scraping manga.
 	/// * `max_concurrency` - The maximum number of concurrent tasks.
 	/// * `search_interval` - The interval at which to search for manga updates.
-	/// * `cooldown_duration` - The cooldown duration for each scraper.
+	///
+	/// Per-scraper limits are set by the scraper manager, from the `limits` of its plugin config.
 	pub fn new(
 		db: Arc<Database>,
 		scraper_manager: Arc<ScraperManager>,
 		max_concurrency: usize,
 		search_interval: Duration,
-		cooldown_duration: Duration,
 	) -> Self {
 		let cfg = Config::load();
 
-		let per_scraper_overrides: HashMap<String, usize> = cfg.per_scraper_limits.into_iter().collect();
-
-		let per_scraper_concurrency = if cfg.default_per_scraper_concurrency > 0 {
-			cfg.default_per_scraper_concurrency
-		} else {
-			std::cmp::max(1, max_concurrency / 2)
-		};
-
-		let cooldown = if cfg.cooldown_seconds > 0 {
-			Duration::from_secs(cfg.cooldown_seconds)
-		} else {
-			cooldown_duration
-		};
-
-		let scraper_limiter = Arc::new(Mutex::new(PerScraperLimiter::new(
-			per_scraper_concurrency,
-			cooldown,
-			per_scraper_overrides,
-		)));
-		let ignored_scrapers = Arc::new(Mutex::new(HashSet::new()));
+		let control = Arc::new(SchedulerControl::new(db.clone()));
+		let retry_policies = Arc::new(cfg.retry_policies);
 
 		let process_fn = Arc::new({
 			let db = db.clone();
 			let scraper_manager = Arc::clone(&scraper_manager);
-			let scraper_limiter = Arc::clone(&scraper_limiter);
-			let ignored_scrapers = Arc::clone(&ignored_scrapers);
+			let control = Arc::clone(&control);
 
 			move |item: QueueItem<UpdateJob>| {
 				let db = db.clone();
 				let scraper_manager = scraper_manager.clone();
-				let scraper_limiter = scraper_limiter.clone();
-				let ignored_scrapers = ignored_scrapers.clone();
+				let control = control.clone();
+				let retry_policies = retry_policies.clone();
 
 				Box::pin(async move {
-					{
-						let ignored = ignored_scrapers.lock().await;
-						if ignored.contains(&item.payload.scraper_name) {
-							tracing::debug!("Skipping ignored scraper: {}", item.payload.scraper_name);
-							return Ok(());
-						}
+					// Deferred rather than completed, so the job keeps its place and priority until the scraper resumes.
+					if control.is_paused(&item.payload.scraper_name).await {
+						metrics::record_job_outcome(&item.payload.scraper_name, "deferred");
+						let error = format!("scraper {} is paused", item.payload.scraper_name);
+						let boxed: Box<dyn std::error::Error + Send + Sync> =
+							Box::new(JobError::defer(error, PAUSED_JOB_DELAY));
+						return Err(boxed);
 					}
 
-					if let Some(delay) = {
-						let lim = scraper_limiter.lock().await;
-						lim.needs_cooldown(&item.payload.scraper_name)
-					} {
-						tokio::time::sleep(delay).await;
+					match health::tracker().admit(&item.payload.scraper_name) {
+						Admission::Allowed => {}
+						Admission::Probe => {
+							tracing::info!("Probing quarantined scraper {}", item.payload.scraper_name);
+						}
+						Admission::Quarantined { remaining } => {
+							metrics::record_job_outcome(&item.payload.scraper_name, "deferred");
+							let error = format!("scraper {} is quarantined", item.payload.scraper_name);
+							let boxed: Box<dyn std::error::Error + Send + Sync> =
+								Box::new(JobError::defer(error, remaining));
+							return Err(boxed);
+						}
 					}
 
-					let sem: Arc<Semaphore> = {
-						let mut lim = scraper_limiter.lock().await;
-						let s = lim.get_semaphore(&item.payload.scraper_name);
-						lim.mark_used(&item.payload.scraper_name);
-						s
-					};
-
-					let _permit: OwnedSemaphorePermit = match sem.clone().acquire_owned().await {
-						Ok(p) => p,
-						Err(_) => {
+					let _permit = match limits::limiter().acquire(&item.payload.scraper_name).await {
+						Some(p) => p,
+						None => {
 							tracing::warn!("Semaphore closed for scraper {}", item.payload.scraper_name);
 							return Ok(());
 						}
@@ -234,34 +298,47 @@ impl MangaUpdateScheduler {
 					};
 
 					match result {
-						Ok(()) => Ok(()),
+						Ok(()) => {
+							metrics::record_job_outcome(&item.payload.scraper_name, "succeeded");
+							Ok(())
+						}
 						Err(err) => {
 							if matches!(err, manga_sync::SyncError::ScraperNotFound { .. }) {
+								metrics::record_job_outcome(&item.payload.scraper_name, "not_found");
+								// Dead-lettered until the plugin is installed and the job replayed.
 								tracing::warn!("Scraper plugin '{}' not found, skipping update", item.payload.scraper_name);
-								ignored_scrapers.lock().await.insert(item.payload.scraper_name.clone());
-								Ok(())
+								health::tracker().record_failure(
+									&item.payload.scraper_name,
+									None,
+									&ScraperError::new(ScraperErrorKind::NotFound, err.to_string()),
+								);
+								let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(JobError::permanent(err));
+								Err(boxed)
 							} else if let manga_sync::SyncError::ScraperError(ref se) = err {
-								if se.retryable {
-									tracing::warn!(
+								metrics::record_job_outcome(&item.payload.scraper_name, se.kind.as_str());
+								let policy = retry::policy_for(se, &retry_policies);
+								if policy == RetryPolicy::NEVER {
+									tracing::error!(
 										key = %item.key,
 										scraper = %item.payload.scraper_name,
-										"Retryable scraper error: {}. Attempt {}/{}",
-										se,
-										item.fail_count + 1,
-										3
+										"Permanent scraper error: {}. Moving the job to the dead-letter list.",
+										se
 									);
-									let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(err);
-									Err(boxed)
 								} else {
-									tracing::error!(
+									tracing::warn!(
 										key = %item.key,
 										scraper = %item.payload.scraper_name,
-										"Permanent scraper error: {}. Skipping update.",
-										se
+										"Retryable {} error: {}. Attempt {}/{}",
+										se.kind,
+										se,
+										item.fail_count + 1,
+										policy.max_retries + 1
 									);
-									Ok(())
 								}
+								let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(JobError::new(err, policy));
+								Err(boxed)
 							} else {
+								metrics::record_job_outcome(&item.payload.scraper_name, "internal");
 								tracing::error!(
 									key = %item.key,
 									item_id = item.payload.item_id,
@@ -287,7 +364,22 @@ impl MangaUpdateScheduler {
 			_ => EnqueueStrategy::BestEffort,
 		};
 
-		let queue = Arc::new(TaskQueue::new(
+		let persistent = cfg.queue_store != "memory";
+		let owner = cfg.instance_id.clone().unwrap_or_else(default_instance_id);
+		let store: Arc<dyn QueueStore<UpdateJob>> = match cfg.queue_store.as_str() {
+			"memory" => Arc::new(VolatileStore),
+			_ => {
+				let lease = Duration::from_secs(cfg.job_lease_seconds.max(60));
+				Arc::new(DatabaseQueueStore::new(db.clone(), owner.clone(), lease))
+			}
+		};
+
+		// Workers keeping jobs in memory cannot share them, so each one scans for itself.
+		let heartbeat = Duration::from_secs(cfg.heartbeat_seconds.max(1));
+		let leader_lock = persistent.then(|| DatabaseLock::new(db.clone(), LEADER_LOCK, owner.clone(), heartbeat * 3));
+		let registration = WorkerRegistration::new(db.clone(), owner, heartbeat * 3);
+
+		let queue = Arc::new(TaskQueue::with_store(
 			process_fn,
 			cfg.queue_max_size,
 			3,
@@ -295,6 +387,7 @@ impl MangaUpdateScheduler {
 			std::cmp::max(cfg.max_concurrency, max_concurrency),
 			enqueue_strategy,
 			cfg.queue_aging_interval_secs,
+			store,
 		));
 
 		Self {
@@ -302,320 +395,354 @@ impl MangaUpdateScheduler {
 			db,
 			interval: search_interval,
 			scraper_manager,
-			scraper_limiter: scraper_limiter,
 			favorites_only: cfg.favorites_only,
+			claim_limit: cfg.claim_limit.max(1),
+			user_weights: cfg.user_weights,
+			instance_id: cfg.instance_id.filter(|_| persistent),
+			control,
+			control_poll: Duration::from_secs(cfg.control_poll_seconds.max(1)),
+			leader_lock,
+			leader: AtomicBool::new(false),
+			leader_elected: tokio::sync::Notify::new(),
+			registration,
+			heartbeat,
 		}
 	}
 
-	pub async fn start(self: Arc<Self>) -> anyhow::Result<()> {
-		loop {
-			if let Err(e) = self.schedule_updates().await {
-				tracing::error!("Failed to schedule manga updates: {:#}", e);
-			}
-
-			tokio::time::sleep(self.interval).await;
-		}
+	pub fn control(&self) -> &Arc<SchedulerControl> {
+		&self.control
 	}
 
-	async fn schedule_updates(&self) -> Result<(), anyhow::Error> {
-		tracing::info!("Scheduling manga updates...");
-		let threshold = Utc::now() - TimeDelta::try_hours(1).unwrap();
-
-		let claim_limit = 500u64;
-		let claimed_mangas = self.claim_mangas(threshold, claim_limit, self.favorites_only).await?;
-		let mut scheduled = self.schedule_manga_batch(claimed_mangas).await;
+	pub fn queue_len(&self) -> usize {
+		self.queue.len()
+	}
 
-		if self.queue.len() < self.queue.max_size() {
-			let available = self.queue.max_size().saturating_sub(self.queue.len());
-			let fetch_limit = (available.saturating_mul(3)).max(10) as u64;
-			let remaining = self.claim_mangas(threshold, fetch_limit, self.favorites_only).await?;
-			scheduled += self.schedule_manga_batch(remaining).await;
-		}
+	pub fn queue_capacity(&self) -> usize {
+		self.queue.max_size()
+	}
 
-		let claimed_novels = self.claim_novels(threshold, claim_limit, self.favorites_only).await?;
-		scheduled += self.schedule_novel_batch(claimed_novels).await;
+	/// Whether this process scans for due mangas and novels. Every worker runs stored jobs.
+	pub fn is_leader(&self) -> bool {
+		self.leader.load(Ordering::SeqCst)
+	}
 
-		if self.queue.len() < self.queue.max_size() {
-			let available = self.queue.max_size().saturating_sub(self.queue.len());
-			let fetch_limit = (available.saturating_mul(3)).max(10) as u64;
-			let remaining = self.claim_novels(threshold, fetch_limit, self.favorites_only).await?;
-			scheduled += self.schedule_novel_batch(remaining).await;
-		}
+	/// The next `limit` jobs in the order they would run, ignoring aging.
+	pub async fn queued(&self, limit: usize) -> Vec<QueuedJob> {
+		self.queue
+			.peek_top_k(limit)
+			.await
+			.iter()
+			.map(|item| QueuedJob::from(item.as_ref()))
+			.collect()
+	}
 
-		tracing::info!("Scheduled {} updates", scheduled);
-		Ok(())
+	pub fn in_flight(&self) -> Vec<QueuedJob> {
+		let mut jobs: Vec<QueuedJob> = self
+			.queue
+			.in_flight()
+			.iter()
+			.map(|item| QueuedJob::from(item.as_ref()))
+			.collect();
+		jobs.sort_by_key(|job| std::cmp::Reverse(job.running_for));
+		jobs
 	}
 
-	async fn claim_novels(
-		&self,
-		threshold: DateTime<Utc>,
-		limit: u64,
-		favorites_only: bool,
-	) -> Result<Vec<(novels::Model, i64)>, anyhow::Error> {
-		if self.db.db_type == "postgresql" {
-			let ts = threshold.format("%Y-%m-%d %H:%M:%S").to_string();
-			let favorites_filter = if favorites_only {
-				" AND EXISTS (SELECT 1 FROM favorite_novels f WHERE f.novel_id = novels.id)"
-			} else {
-				""
-			};
-			let sql = format!(
-				"WITH c AS (SELECT id FROM novels WHERE updated_at < '{}' AND created_at IS NOT NULL{} ORDER BY updated_at DESC LIMIT {limit} FOR UPDATE SKIP LOCKED) SELECT id FROM c",
-				ts,
-				favorites_filter,
-				limit = limit
-			);
-
-			let txn = self.db.conn.begin().await?;
-			let stmt = Statement::from_string(DbBackend::Postgres, sql);
-			let rows = txn.query_all(stmt).await?;
-
-			let mut ids: Vec<i32> = Vec::new();
-			for row in rows {
-				let id: i32 = row.try_get("", "id")?;
-				ids.push(id);
+	pub async fn start(self: Arc<Self>) -> anyhow::Result<()> {
+		if let Some(owner) = &self.instance_id {
+			let store = DatabaseQueueStore::<UpdateJob>::new(self.db.clone(), owner.clone(), Duration::ZERO);
+			match store.release_own_leases().await {
+				Ok(0) => {}
+				Ok(released) => tracing::info!("Took back {} jobs leased before the restart", released),
+				Err(e) => tracing::warn!("Failed to release leases of {}: {}", owner, e),
 			}
+		}
 
-			if ids.is_empty() {
-				txn.commit().await?;
-				return Ok(vec![]);
-			}
+		let _ = LOCAL.set(Arc::downgrade(&self));
+		metrics::register_collector();
 
-			let models = novels::Entity::find()
-				.filter(novels::Column::Id.is_in(ids.clone()))
-				.all(&txn)
-				.await?;
+		self.heartbeat().await;
+		let beater = Arc::clone(&self);
+		tokio::spawn(async move {
+			loop {
+				tokio::time::sleep(beater.heartbeat).await;
+				beater.heartbeat().await;
+			}
+		});
 
-			let fav_rows = favorite_novels::Entity::find()
-				.filter(favorite_novels::Column::NovelId.is_in(ids.clone()))
-				.all(&txn)
-				.await?;
+		let poller = Arc::clone(&self);
+		tokio::spawn(async move {
+			loop {
+				let restored = poller.queue.restore().await;
+				if restored > 0 {
+					tracing::info!("Restored {} stored jobs", restored);
+				}
 
-			let mut fav_count: HashMap<i32, i64> = HashMap::new();
-			for f in fav_rows {
-				*fav_count.entry(f.novel_id).or_insert(0) += 1;
+				tokio::time::sleep(poller.control_poll).await;
 			}
+		});
 
-			let mut model_map: HashMap<i32, novels::Model> = HashMap::new();
-			for m in models {
-				model_map.insert(m.id, m);
+		loop {
+			if self.is_leader()
+				&& let Err(e) = self.schedule_updates().await
+			{
+				tracing::error!("Failed to schedule manga updates: {:#}", e);
 			}
 
-			let mut result: Vec<(novels::Model, i64)> = Vec::new();
-			for id in ids {
-				if let Some(m) = model_map.remove(&id) {
-					let cnt = *fav_count.get(&id).unwrap_or(&0);
-					result.push((m, cnt));
-				}
+			tokio::select! {
+				_ = tokio::time::sleep(self.interval) => {}
+				_ = self.leader_elected.notified() => {}
 			}
-
-			txn.commit().await?;
-			return Ok(result);
 		}
+	}
 
-		let models = if favorites_only {
-			let ts = threshold.format("%Y-%m-%d %H:%M:%S").to_string();
-			let sql = format!(
-				"SELECT id FROM novels WHERE updated_at < '{}' AND created_at IS NOT NULL AND EXISTS (SELECT 1 FROM favorite_novels f WHERE f.novel_id = novels.id) ORDER BY updated_at DESC LIMIT {limit}",
-				ts,
-				limit = limit
-			);
-			let stmt = Statement::from_string(self.db.conn.get_database_backend(), sql);
-			let rows = self.db.conn.query_all(stmt).await?;
-
-			let mut ids: Vec<i32> = Vec::new();
-			for row in rows {
-				let id: i32 = row.try_get("", "id")?;
-				ids.push(id);
-			}
+	/// Renews or takes over the leadership and reports this worker alive. A worker that cannot reach the database
+	/// steps down, since another one may take over meanwhile.
+	async fn heartbeat(&self) {
+		let leader = match &self.leader_lock {
+			Some(lock) => lock.acquire().await.unwrap_or_else(|e| {
+				tracing::warn!("Failed to renew the scheduler leadership: {:#}", e);
+				false
+			}),
+			None => true,
+		};
 
-			if ids.is_empty() {
-				return Ok(vec![]);
+		match (self.leader.swap(leader, Ordering::SeqCst), leader) {
+			(false, true) => {
+				tracing::info!("This worker now schedules updates");
+				self.leader_elected.notify_waiters();
 			}
+			(true, false) => tracing::warn!("This worker no longer schedules updates"),
+			_ => {}
+		}
 
-			novels::Entity::find()
-				.filter(novels::Column::Id.is_in(ids.clone()))
-				.all(&self.db.conn)
-				.await?
-		} else {
-			novels::Entity::find()
-				.filter(novels::Column::UpdatedAt.lt(threshold))
-				.filter(novels::Column::CreatedAt.is_not_null())
-				.order_by_desc(novels::Column::UpdatedAt)
-				.limit(limit as u64)
-				.all(&self.db.conn)
-				.await?
-		};
+		if let Err(e) = self
+			.registration
+			.beat(leader, self.queue.len(), self.queue.in_flight().len())
+			.await
+		{
+			tracing::warn!("Failed to record the scheduler heartbeat: {:#}", e);
+		}
+	}
 
-		let ids: Vec<i32> = models.iter().map(|m| m.id).collect();
-		if ids.is_empty() {
-			return Ok(vec![]);
+	/// Queues updates of due mangas and novels. Only the leader runs it, so scans of several workers never race for
+	/// the same rows; the job leases then spread the work over all of them.
+	async fn schedule_updates(&self) -> Result<(), anyhow::Error> {
+		if self.control.is_paused(ALL_SCRAPERS).await {
+			tracing::info!("Scheduler is paused, not scheduling updates");
+			return Ok(());
 		}
 
-		let fav_rows = favorite_novels::Entity::find()
-			.filter(favorite_novels::Column::NovelId.is_in(ids.clone()))
-			.all(&self.db.conn)
-			.await?;
+		tracing::info!("Scheduling manga updates...");
+		let threshold = Utc::now() - TimeDelta::try_hours(1).unwrap();
 
-		let mut fav_count: HashMap<i32, i64> = HashMap::new();
-		for f in fav_rows {
-			*fav_count.entry(f.novel_id).or_insert(0) += 1;
-		}
+		let favorites = self.claim_favorites(threshold).await?;
+		let mut scheduled = self.schedule_favorites(favorites).await?;
 
-		let mut model_map: HashMap<i32, novels::Model> = HashMap::new();
-		for m in models {
-			model_map.insert(m.id, m);
-		}
+		// Series nobody follows only fill the room the favorites leave.
+		if !self.favorites_only && self.queue.len() < self.queue.max_size() {
+			let available = self.queue.max_size().saturating_sub(self.queue.len());
+			let fetch_limit = (available.saturating_mul(3)).max(10) as u64;
 
-		let mut result: Vec<(novels::Model, i64)> = Vec::new();
-		for id in ids {
-			if let Some(m) = model_map.remove(&id) {
-				let cnt = *fav_count.get(&id).unwrap_or(&0);
-				result.push((m, cnt));
-			}
+			let claimed_mangas = self.claim_unfollowed_mangas(threshold, fetch_limit).await?;
+			scheduled += self.schedule_manga_batch(claimed_mangas).await;
+			let claimed_novels = self.claim_unfollowed_novels(threshold, fetch_limit).await?;
+			scheduled += self.schedule_novel_batch(claimed_novels).await;
 		}
 
-		Ok(result)
+		tracing::info!("Scheduled {} updates", scheduled);
+		Ok(())
 	}
 
-	async fn claim_mangas(
-		&self,
-		threshold: DateTime<Utc>,
-		limit: u64,
-		favorites_only: bool,
-	) -> Result<Vec<(mangas::Model, i64)>, anyhow::Error> {
-		if self.db.db_type == "postgresql" {
-			let ts = threshold.format("%Y-%m-%d %H:%M:%S").to_string();
-			let favorites_filter = if favorites_only {
-				" AND EXISTS (SELECT 1 FROM favorite_mangas f WHERE f.manga_id = mangas.id)"
-			} else {
-				""
-			};
-			let sql = format!(
-				"WITH c AS (SELECT id FROM mangas WHERE updated_at < '{}' AND created_at IS NOT NULL{} ORDER BY updated_at DESC LIMIT {limit} FOR UPDATE SKIP LOCKED) SELECT id FROM c",
-				ts,
-				favorites_filter,
-				limit = limit
-			);
-
-			let txn = self.db.conn.begin().await?;
-			let stmt = Statement::from_string(DbBackend::Postgres, sql);
-			let rows = txn.query_all(stmt).await?;
-
-			let mut ids: Vec<i32> = Vec::new();
-			for row in rows {
-				let id: i32 = row.try_get("", "id")?;
-				ids.push(id);
-			}
-
-			if ids.is_empty() {
-				txn.commit().await?;
-				return Ok(vec![]);
-			}
+	/// Due mangas and novels in the auto-updated categories of every user.
+	async fn claim_favorites(&self, threshold: DateTime<Utc>) -> Result<Vec<DueFavorite>, anyhow::Error> {
+		let now = Utc::now().naive_utc();
+
+		let mangas: Vec<(i32, i32, String, Option<NaiveDateTime>, NaiveDateTime)> = favorite_mangas::Entity::find()
+			.select_only()
+			.column(favorite_mangas::Column::UserId)
+			.column(mangas::Column::Id)
+			.column(mangas::Column::Scraper)
+			.column(mangas::Column::NextCheckAt)
+			.column(mangas::Column::UpdatedAt)
+			.inner_join(mangas::Entity)
+			.inner_join(categories::Entity)
+			.filter(categories::Column::AutoUpdate.eq(true))
+			.filter(mangas::Column::UpdatedAt.lt(threshold))
+			.filter(
+				Condition::any()
+					.add(mangas::Column::NextCheckAt.is_null())
+					.add(mangas::Column::NextCheckAt.lte(now)),
+			)
+			.filter(mangas::Column::CreatedAt.is_not_null())
+			.into_tuple()
+			.all(&self.db.conn)
+			.await?;
 
-			let models = mangas::Entity::find()
-				.filter(mangas::Column::Id.is_in(ids.clone()))
-				.all(&txn)
-				.await?;
+		let novels: Vec<(i32, i32, String, Option<NaiveDateTime>, NaiveDateTime)> = favorite_novels::Entity::find()
+			.select_only()
+			.column(favorite_novels::Column::UserId)
+			.column(novels::Column::Id)
+			.column(novels::Column::Scraper)
+			.column(novels::Column::NextCheckAt)
+			.column(novels::Column::UpdatedAt)
+			.inner_join(novels::Entity)
+			.inner_join(categories::Entity)
+			.filter(categories::Column::AutoUpdate.eq(true))
+			.filter(novels::Column::UpdatedAt.lt(threshold))
+			.filter(
+				Condition::any()
+					.add(novels::Column::NextCheckAt.is_null())
+					.add(novels::Column::NextCheckAt.lte(now)),
+			)
+			.filter(novels::Column::CreatedAt.is_not_null())
+			.into_tuple()
+			.all(&self.db.conn)
+			.await?;
 
-			let fav_rows = favorite_mangas::Entity::find()
-				.filter(favorite_mangas::Column::MangaId.is_in(ids.clone()))
-				.all(&txn)
-				.await?;
+		let favorites = mangas
+			.into_iter()
+			.map(|row| (ItemType::Manga, row))
+			.chain(novels.into_iter().map(|row| (ItemType::Novel, row)))
+			.map(
+				|(item_type, (user_id, item_id, scraper, next_check_at, updated_at))| DueFavorite {
+					user_id,
+					item_type,
+					item_id,
+					scraper,
+					due_since: next_check_at.unwrap_or(updated_at),
+				},
+			)
+			.collect();
+
+		Ok(favorites)
+	}
 
-			let mut fav_count: HashMap<i32, i64> = HashMap::new();
-			for f in fav_rows {
-				*fav_count.entry(f.manga_id).or_insert(0) += 1;
-			}
+	/// Queues the favorites of every user in turn, by weight, with the longest due of each user first. Series
+	/// several users follow are queued at the earliest turn of any of them.
+	async fn schedule_favorites(&self, favorites: Vec<DueFavorite>) -> Result<usize, anyhow::Error> {
+		let mut by_user: BTreeMap<i32, Vec<DueFavorite>> = BTreeMap::new();
+		for favorite in favorites {
+			by_user.entry(favorite.user_id).or_default().push(favorite);
+		}
 
-			let mut model_map: HashMap<i32, mangas::Model> = HashMap::new();
-			for m in models {
-				model_map.insert(m.id, m);
-			}
+		let weights = self.user_weights().await?;
+		let users = by_user
+			.into_iter()
+			.map(|(user_id, mut items)| {
+				items.sort_by_key(|item| item.due_since);
+				(weights.get(&user_id).copied().unwrap_or(1), items)
+			})
+			.collect();
 
-			let mut result: Vec<(mangas::Model, i64)> = Vec::new();
-			for id in ids {
-				if let Some(m) = model_map.remove(&id) {
-					let cnt = *fav_count.get(&id).unwrap_or(&0);
-					result.push((m, cnt));
-				}
+		let mut seen = HashSet::new();
+		let mut scheduled = 0;
+		for (favorite, round) in fair_share::interleave(users) {
+			if seen.len() as u64 >= self.claim_limit {
+				break;
 			}
 
-			txn.commit().await?;
-			return Ok(result);
-		}
-
-		let models = if favorites_only {
-			let ts = threshold.format("%Y-%m-%d %H:%M:%S").to_string();
-			let sql = format!(
-				"SELECT id FROM mangas WHERE updated_at < '{}' AND created_at IS NOT NULL AND EXISTS (SELECT 1 FROM favorite_mangas f WHERE f.manga_id = mangas.id) ORDER BY updated_at DESC LIMIT {limit}",
-				ts,
-				limit = limit
-			);
-			let stmt = Statement::from_string(self.db.conn.get_database_backend(), sql);
-			let rows = self.db.conn.query_all(stmt).await?;
-
-			let mut ids: Vec<i32> = Vec::new();
-			for row in rows {
-				let id: i32 = row.try_get("", "id")?;
-				ids.push(id);
+			let job = UpdateJob {
+				item_id: favorite.item_id,
+				scraper_name: favorite.scraper,
+				item_type: favorite.item_type,
+				last_attempt: None,
+			};
+			// Paused scrapers are skipped before counting, so they don't use up the claim limit.
+			if self.control.is_paused(&job.scraper_name).await || !seen.insert(job.key()) {
+				continue;
 			}
 
-			if ids.is_empty() {
-				return Ok(vec![]);
+			if self.queue.insert(job.key(), job, fair_share::round_priority(round)).await {
+				scheduled += 1;
 			}
+		}
 
-			mangas::Entity::find()
-				.filter(mangas::Column::Id.is_in(ids.clone()))
-				.all(&self.db.conn)
-				.await?
-		} else {
-			mangas::Entity::find()
-				.filter(mangas::Column::UpdatedAt.lt(threshold))
-				.filter(mangas::Column::CreatedAt.is_not_null())
-				.order_by_desc(mangas::Column::UpdatedAt)
-				.limit(limit as u64)
-				.all(&self.db.conn)
-				.await?
-		};
+		Ok(scheduled)
+	}
 
-		let ids: Vec<i32> = models.iter().map(|m| m.id).collect();
-		if ids.is_empty() {
-			return Ok(vec![]);
+	/// Configured `user_weights`, by user id.
+	async fn user_weights(&self) -> Result<HashMap<i32, u32>, anyhow::Error> {
+		if self.user_weights.is_empty() {
+			return Ok(HashMap::new());
 		}
 
-		let fav_rows = favorite_mangas::Entity::find()
-			.filter(favorite_mangas::Column::MangaId.is_in(ids.clone()))
+		let users: Vec<(i32, String)> = users::Entity::find()
+			.select_only()
+			.column(users::Column::Id)
+			.column(users::Column::Username)
+			.filter(users::Column::Username.is_in(self.user_weights.keys().cloned()))
+			.into_tuple()
 			.all(&self.db.conn)
 			.await?;
 
-		let mut fav_count: HashMap<i32, i64> = HashMap::new();
-		for f in fav_rows {
-			*fav_count.entry(f.manga_id).or_insert(0) += 1;
-		}
-
-		let mut model_map: HashMap<i32, mangas::Model> = HashMap::new();
-		for m in models {
-			model_map.insert(m.id, m);
-		}
+		Ok(users
+			.into_iter()
+			.filter_map(|(id, username)| Some((id, *self.user_weights.get(&username)?)))
+			.collect())
+	}
 
-		let mut result: Vec<(mangas::Model, i64)> = Vec::new();
-		for id in ids {
-			if let Some(m) = model_map.remove(&id) {
-				let cnt = *fav_count.get(&id).unwrap_or(&0);
-				result.push((m, cnt));
-			}
-		}
+	/// Due novels that are not in any user's favorites.
+	async fn claim_unfollowed_novels(
+		&self,
+		threshold: DateTime<Utc>,
+		limit: u64,
+	) -> Result<Vec<novels::Model>, anyhow::Error> {
+		let followed = Query::select()
+			.column(favorite_novels::Column::NovelId)
+			.from(favorite_novels::Entity)
+			.to_owned();
+
+		Ok(novels::Entity::find()
+			.filter(novels::Column::UpdatedAt.lt(threshold))
+			.filter(
+				Condition::any()
+					.add(novels::Column::NextCheckAt.is_null())
+					.add(novels::Column::NextCheckAt.lte(Utc::now().naive_utc())),
+			)
+			.filter(novels::Column::CreatedAt.is_not_null())
+			.filter(novels::Column::Id.not_in_subquery(followed))
+			.order_by_desc(novels::Column::UpdatedAt)
+			.limit(limit)
+			.all(&self.db.conn)
+			.await?)
+	}
 
-		Ok(result)
+	/// Due mangas that are not in any user's favorites.
+	async fn claim_unfollowed_mangas(
+		&self,
+		threshold: DateTime<Utc>,
+		limit: u64,
+	) -> Result<Vec<mangas::Model>, anyhow::Error> {
+		let followed = Query::select()
+			.column(favorite_mangas::Column::MangaId)
+			.from(favorite_mangas::Entity)
+			.to_owned();
+
+		Ok(mangas::Entity::find()
+			.filter(mangas::Column::UpdatedAt.lt(threshold))
+			.filter(
+				Condition::any()
+					.add(mangas::Column::NextCheckAt.is_null())
+					.add(mangas::Column::NextCheckAt.lte(Utc::now().naive_utc())),
+			)
+			.filter(mangas::Column::CreatedAt.is_not_null())
+			.filter(mangas::Column::Id.not_in_subquery(followed))
+			.order_by_desc(mangas::Column::UpdatedAt)
+			.limit(limit)
+			.all(&self.db.conn)
+			.await?)
 	}
 
-	async fn schedule_manga_batch(&self, mangas: Vec<(mangas::Model, i64)>) -> usize {
+	async fn schedule_manga_batch(&self, mangas: Vec<mangas::Model>) -> usize {
 		let mut scheduled = 0;
 
-		for (manga, fav_count) in mangas.into_iter() {
+		for manga in mangas.into_iter() {
 			let scraper = manga.scraper.clone();
-			let priority = self.calculate_priority(fav_count, manga.updated_at);
+			if self.control.is_paused(&scraper).await {
+				continue;
+			}
+
+			let priority = self.calculate_priority(manga.next_check_at.unwrap_or(manga.updated_at));
 			let job = UpdateJob {
 				item_id: manga.id,
 				scraper_name: scraper.clone(),
@@ -623,8 +750,7 @@ impl MangaUpdateScheduler {
 				last_attempt: None,
 			};
 
-			let key = format!("manga-update-{}-{}", scraper, manga.id);
-			if self.queue.insert(key, job, priority).await {
+			if self.queue.insert(job.key(), job, priority).await {
 				scheduled += 1;
 			}
 		}
@@ -632,12 +758,16 @@ impl MangaUpdateScheduler {
 		scheduled
 	}
 
-	async fn schedule_novel_batch(&self, novels_vec: Vec<(novels::Model, i64)>) -> usize {
+	async fn schedule_novel_batch(&self, novels_vec: Vec<novels::Model>) -> usize {
 		let mut scheduled = 0;
 
-		for (novel, fav_count) in novels_vec.into_iter() {
+		for novel in novels_vec.into_iter() {
 			let scraper = novel.scraper.clone();
-			let priority = self.calculate_priority(fav_count, novel.updated_at);
+			if self.control.is_paused(&scraper).await {
+				continue;
+			}
+
+			let priority = self.calculate_priority(novel.next_check_at.unwrap_or(novel.updated_at));
 			let job = UpdateJob {
 				item_id: novel.id,
 				scraper_name: scraper.clone(),
@@ -645,8 +775,7 @@ impl MangaUpdateScheduler {
 				last_attempt: None,
 			};
 
-			let key = format!("novel-update-{}-{}", scraper, novel.id);
-			if self.queue.insert(key, job, priority).await {
+			if self.queue.insert(job.key(), job, priority).await {
 				scheduled += 1;
 			}
 		}
@@ -654,20 +783,11 @@ impl MangaUpdateScheduler {
 		scheduled
 	}
 
-	fn calculate_priority(&self, fav_count: i64, last_updated: NaiveDateTime) -> u8 {
-		let base: u8 = match fav_count {
-			0 => 1,
-			1..=5 => 2,
-			6..=20 => 3,
-			21..=50 => 4,
-			51..=100 => 5,
-			101..=500 => 6,
-			_ => 7,
-		};
-
-		let last_updated_utc = DateTime::from_naive_utc_and_offset(last_updated, Utc);
-		let hours_stale = (Utc::now() - last_updated_utc).num_hours().max(0) as u8;
+	/// Series nobody follows, the ones that have been due the longest first. Any favorite outranks them.
+	fn calculate_priority(&self, due_since: NaiveDateTime) -> u8 {
+		let due_since_utc = DateTime::from_naive_utc_and_offset(due_since, Utc);
+		let hours_stale = (Utc::now() - due_since_utc).num_hours().clamp(0, 10) as u8;
 
-		base.saturating_add(hours_stale.min(10))
+		1 + hours_stale
 	}
 }
@@ -1,19 +1,28 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use scheduler::MangaUpdateScheduler;
-use tracing_subscriber::FmtSubscriber;
+use scheduler::{MaintenanceScheduler, MangaUpdateScheduler};
+
+mod cli;
 
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-	let subscriber = FmtSubscriber::builder().with_max_level(tracing::Level::INFO).finish();
-	let _ = tracing::subscriber::set_global_default(subscriber);
+	let _telemetry = telemetry::init("manga-vault-scheduler");
 
 	let db = database_connection::Database::new().await?;
 
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+	if let Some(command) = args.first() {
+		if command == "help" || command == "--help" {
+			cli::print_usage();
+			return Ok(());
+		}
+		return cli::run(db, command, &args[1..]).await;
+	}
+
 	let latest_release = version_check::get_latest_release(PACKAGE_NAME).await;
 
 	let mut update = true;
@@ -28,17 +37,30 @@ async fn main() -> anyhow::Result<()> {
 
 	let scraper_manager = scraper_core::ScraperManager::new(update).await?;
 
+	tokio::spawn(MaintenanceScheduler::new(db.clone(), scraper_manager.clone()).start());
+	tokio::spawn(async {
+		if let Err(e) = telemetry::serve_metrics().await {
+			tracing::error!("Metrics server failed: {:#}", e);
+		}
+	});
+
 	let scheduler = Arc::new(MangaUpdateScheduler::new(
 		db.clone(),
-		scraper_manager,
+		scraper_manager.clone(),
 		5,
 		Duration::from_secs(30 * 60),
-		Duration::from_secs(10),
 	));
 
 	let scheduler_clone = Arc::clone(&scheduler);
 
-	scheduler_clone.start().await?;
+	let result = tokio::select! {
+		result = scheduler_clone.start() => result,
+		_ = tokio::signal::ctrl_c() => {
+			tracing::info!("Shutting down");
+			Ok(())
+		}
+	};
+	scraper_manager.shutdown();
 
-	Ok(())
+	result
 }
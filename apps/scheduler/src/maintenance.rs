@@ -0,0 +1,618 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use cron::Schedule;
+use database_connection::Database;
+use database_entities::{maintenance_tasks, mangas, novels, temp};
+use scraper_core::ScraperManager;
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+
+use crate::control::SchedulerControl;
+use crate::worker::DatabaseLock;
+use crate::{ItemType, default_instance_id};
+
+const STATUS_RUNNING: &str = "running";
+const STATUS_SUCCEEDED: &str = "succeeded";
+const STATUS_FAILED: &str = "failed";
+
+const TRIGGER_SCHEDULE: &str = "schedule";
+const TRIGGER_MANUAL: &str = "manual";
+
+/// How long after its last renewal the lock of a task expires, so another process considers it crashed and runs it
+/// again. Running tasks renew it every third of this.
+const TASK_LOCK_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Cover refreshes only fill gaps, so they wait behind every scheduled update.
+const COVER_REFRESH_PRIORITY: u8 = 1;
+
+/// Tables `OPTIMIZE TABLE` rebuilds on MySQL; the rest stay small.
+const MYSQL_VACUUM_TABLES: &[&str] = &[
+	"mangas",
+	"chapters",
+	"read_chapters",
+	"novels",
+	"novel_chapters",
+	"read_novel_chapters",
+	"temp",
+	"scheduler_jobs",
+];
+
+/// Rows left behind when what they belong to was deleted, by what they are.
+const ORPHAN_QUERIES: &[(&str, &str)] = &[
+	(
+		"chapters",
+		"DELETE FROM chapters WHERE NOT EXISTS (SELECT 1 FROM mangas WHERE mangas.id = chapters.manga_id)",
+	),
+	(
+		"novel chapters",
+		"DELETE FROM novel_chapters WHERE NOT EXISTS (SELECT 1 FROM novels WHERE novels.id = novel_chapters.novel_id)",
+	),
+	(
+		"read marks",
+		"DELETE FROM read_chapters WHERE NOT EXISTS (SELECT 1 FROM chapters WHERE chapters.id = read_chapters.chapter_id)",
+	),
+	(
+		"novel read marks",
+		"DELETE FROM read_novel_chapters WHERE NOT EXISTS \
+		 (SELECT 1 FROM novel_chapters WHERE novel_chapters.id = read_novel_chapters.chapter_id)",
+	),
+];
+
+#[derive(Debug, Deserialize, Serialize, config_derive::Config)]
+#[config(name = "maintenance")]
+pub struct MaintenanceConfig {
+	/// Cron expressions by task name, in the server's local time. Five fields run at second zero; six start with the
+	/// seconds. An empty expression disables the task. `backup` follows the database `backup_interval` unless set.
+	#[serde(default)]
+	pub schedules: BTreeMap<String, String>,
+	/// Mangas and novels without a cover queued for an update by each `cover_refresh` run.
+	#[serde(default)]
+	pub cover_refresh_batch: u64,
+}
+
+impl Default for MaintenanceConfig {
+	fn default() -> Self {
+		Self {
+			schedules: MaintenanceTask::ALL
+				.into_iter()
+				.filter_map(|task| Some((task.name().to_string(), task.default_schedule()?.to_string())))
+				.collect(),
+			cover_refresh_batch: 50,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaintenanceTask {
+	/// Deletes expired rows of the `temp` table.
+	TempCleanup,
+	/// Copies the SQLite database file to the backup folder.
+	Backup,
+	/// Removes backups older than the retention period.
+	BackupCleanup,
+	/// Deletes chapters and read marks whose manga, novel or chapter is gone.
+	OrphanCleanup,
+	/// Pulls plugin updates from the configured repositories.
+	PluginUpdates,
+	/// Queues updates of mangas and novels that have no cover.
+	CoverRefresh,
+	/// Reclaims free space and refreshes the query planner statistics.
+	Vacuum,
+}
+
+impl MaintenanceTask {
+	pub const ALL: [MaintenanceTask; 7] = [
+		MaintenanceTask::TempCleanup,
+		MaintenanceTask::Backup,
+		MaintenanceTask::BackupCleanup,
+		MaintenanceTask::OrphanCleanup,
+		MaintenanceTask::PluginUpdates,
+		MaintenanceTask::CoverRefresh,
+		MaintenanceTask::Vacuum,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			MaintenanceTask::TempCleanup => "temp_cleanup",
+			MaintenanceTask::Backup => "backup",
+			MaintenanceTask::BackupCleanup => "backup_cleanup",
+			MaintenanceTask::OrphanCleanup => "orphan_cleanup",
+			MaintenanceTask::PluginUpdates => "plugin_updates",
+			MaintenanceTask::CoverRefresh => "cover_refresh",
+			MaintenanceTask::Vacuum => "vacuum",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|task| task.name() == name)
+	}
+
+	/// Plugins are loaded by every process, so each one updates its own. Other tasks run in one process at a time.
+	pub fn per_process(self) -> bool {
+		self == MaintenanceTask::PluginUpdates
+	}
+
+	fn default_schedule(self) -> Option<&'static str> {
+		match self {
+			MaintenanceTask::TempCleanup => Some("0 * * * * *"),
+			MaintenanceTask::Backup => None,
+			MaintenanceTask::BackupCleanup => Some("0 30 */4 * * *"),
+			MaintenanceTask::OrphanCleanup => Some("0 0 4 * * *"),
+			MaintenanceTask::PluginUpdates => Some("0 15 */6 * * *"),
+			MaintenanceTask::CoverRefresh => Some("0 0 5 * * *"),
+			MaintenanceTask::Vacuum => Some("0 0 3 * * Sun"),
+		}
+	}
+}
+
+/// Parses a cron expression, running five-field expressions at second zero. Empty expressions disable the task.
+fn parse_schedule(expression: &str) -> Result<Option<Schedule>, cron::error::Error> {
+	let expression = expression.trim();
+	if expression.is_empty() {
+		return Ok(None);
+	}
+
+	if !expression.starts_with('@') && expression.split_whitespace().count() == 5 {
+		return Schedule::from_str(&format!("0 {}", expression)).map(Some);
+	}
+	Schedule::from_str(expression).map(Some)
+}
+
+/// Backups every `hours` hours. Cron steps restart every day, so only divisors of 24 give an even interval.
+fn backup_schedule(hours: u16) -> Option<String> {
+	match hours {
+		24 => Some("0 0 0 * * *".to_string()),
+		hours if hours > 0 && 24 % hours == 0 => Some(format!("0 0 */{} * * *", hours)),
+		_ => None,
+	}
+}
+
+/// Schedule of a task alongside its last run, as recorded in the database.
+#[derive(Debug, Clone)]
+pub struct MaintenanceTaskStatus {
+	pub task: MaintenanceTask,
+	/// `None` when the task is disabled.
+	pub schedule: Option<String>,
+	pub next_run_at: Option<NaiveDateTime>,
+	/// `None` until the task first ran.
+	pub last_run: Option<maintenance_tasks::Model>,
+}
+
+struct ScheduledTask {
+	task: MaintenanceTask,
+	schedule: Option<(String, Schedule)>,
+}
+
+/// Runs housekeeping tasks on cron schedules. Every process runs one; the database decides which of them runs each
+/// scheduled task.
+pub struct MaintenanceScheduler {
+	db: Arc<Database>,
+	scraper_manager: Arc<ScraperManager>,
+	control: SchedulerControl,
+	owner: String,
+	tasks: Vec<ScheduledTask>,
+	cover_refresh_batch: u64,
+}
+
+impl MaintenanceScheduler {
+	pub fn new(db: Arc<Database>, scraper_manager: Arc<ScraperManager>) -> Arc<Self> {
+		let config = MaintenanceConfig::load();
+		let backup_interval = db.config().backup_interval;
+		let backup_schedule = backup_schedule(backup_interval);
+
+		let tasks = MaintenanceTask::ALL
+			.into_iter()
+			.map(|task| {
+				let Some(expression) = config
+					.schedules
+					.get(task.name())
+					.map(String::as_str)
+					.or(task.default_schedule())
+					.or(backup_schedule.as_deref())
+				else {
+					tracing::error!(
+						"A backup_interval of {} hours does not divide a day, disabling backups. Give the backup task a \
+						 schedule in maintenance.json instead.",
+						backup_interval
+					);
+					return ScheduledTask { task, schedule: None };
+				};
+				let schedule = match parse_schedule(expression) {
+					Ok(schedule) => schedule.map(|schedule| (expression.trim().to_string(), schedule)),
+					Err(e) => {
+						tracing::error!("Invalid schedule `{}` for {}, disabling it: {}", expression, task.name(), e);
+						None
+					}
+				};
+				ScheduledTask { task, schedule }
+			})
+			.collect();
+
+		Arc::new(Self {
+			control: SchedulerControl::new(db.clone()),
+			db,
+			scraper_manager,
+			owner: default_instance_id(),
+			tasks,
+			cover_refresh_batch: config.cover_refresh_batch,
+		})
+	}
+
+	/// Runs each task whenever its schedule comes due. Never returns.
+	pub async fn start(self: Arc<Self>) -> anyhow::Result<()> {
+		let now = Local::now();
+		let mut upcoming: Vec<(DateTime<Local>, &ScheduledTask)> = self
+			.tasks
+			.iter()
+			.filter_map(|entry| Some((entry.schedule.as_ref()?.1.after(&now).next()?, entry)))
+			.collect();
+
+		loop {
+			let Some(index) = (0..upcoming.len()).min_by_key(|i| upcoming[*i].0) else {
+				tracing::info!("No maintenance tasks are scheduled");
+				return std::future::pending().await;
+			};
+
+			let (at, entry) = upcoming[index];
+			tokio::time::sleep((at - Local::now()).to_std().unwrap_or_default()).await;
+
+			// Runs are spawned so a long vacuum does not hold back the temp cleanup.
+			let this = Arc::clone(&self);
+			let task = entry.task;
+			tokio::spawn(async move {
+				if let Err(e) = this.run_scheduled(task, at.with_timezone(&Utc).naive_utc()).await {
+					tracing::error!("Failed to start maintenance task {}: {:#}", task.name(), e);
+				}
+			});
+
+			// Slots missed while sleeping, e.g. after the machine was suspended, are not caught up.
+			let after = at.max(Local::now());
+			match entry
+				.schedule
+				.as_ref()
+				.and_then(|(_, schedule)| schedule.after(&after).next())
+			{
+				Some(next) => upcoming[index].0 = next,
+				None => {
+					upcoming.swap_remove(index);
+				}
+			}
+		}
+	}
+
+	/// Starts `task` in the background right away. Returns false while another process is running it.
+	pub async fn trigger(self: &Arc<Self>, task: MaintenanceTask) -> anyhow::Result<bool> {
+		let Some(lock) = self.claim(task, None).await? else {
+			return Ok(false);
+		};
+		self.start_run(task, TRIGGER_MANUAL).await?;
+
+		let this = Arc::clone(self);
+		tokio::spawn(async move { this.execute(task, lock).await });
+		Ok(true)
+	}
+
+	/// Every task with its schedule and last run.
+	pub async fn statuses(&self) -> anyhow::Result<Vec<MaintenanceTaskStatus>> {
+		let mut runs: HashMap<String, maintenance_tasks::Model> = maintenance_tasks::Entity::find()
+			.all(&self.db.conn)
+			.await?
+			.into_iter()
+			.map(|run| (run.name.clone(), run))
+			.collect();
+
+		Ok(self
+			.tasks
+			.iter()
+			.map(|entry| MaintenanceTaskStatus {
+				task: entry.task,
+				schedule: entry.schedule.as_ref().map(|(expression, _)| expression.clone()),
+				next_run_at: entry
+					.schedule
+					.as_ref()
+					.and_then(|(_, schedule)| schedule.upcoming(Local).next())
+					.map(|at| at.with_timezone(&Utc).naive_utc()),
+				last_run: runs.remove(entry.task.name()),
+			})
+			.collect())
+	}
+
+	async fn run_scheduled(self: Arc<Self>, task: MaintenanceTask, slot: NaiveDateTime) -> anyhow::Result<()> {
+		let Some(lock) = self.claim(task, Some(slot)).await? else {
+			return Ok(());
+		};
+		if let Err(e) = self.start_run(task, TRIGGER_SCHEDULE).await {
+			self.release(lock).await;
+			return Err(e);
+		}
+		self.execute(task, lock).await;
+		Ok(())
+	}
+
+	/// Takes the lock of a task that runs in one process at a time. A scheduled `slot` another process already ran
+	/// is skipped. Returns `None` when this process should not run the task.
+	async fn claim(
+		&self,
+		task: MaintenanceTask,
+		slot: Option<NaiveDateTime>,
+	) -> anyhow::Result<Option<Option<DatabaseLock>>> {
+		if task.per_process() {
+			return Ok(Some(None));
+		}
+
+		let lock = DatabaseLock::new(
+			self.db.clone(),
+			format!("maintenance:{}", task.name()),
+			self.owner.clone(),
+			TASK_LOCK_TTL,
+		);
+		if !lock.acquire().await? {
+			tracing::debug!("Maintenance task {} is running elsewhere", task.name());
+			return Ok(None);
+		}
+
+		let Some(slot) = slot else {
+			return Ok(Some(Some(lock)));
+		};
+		let last_run = maintenance_tasks::Entity::find()
+			.filter(maintenance_tasks::Column::Name.eq(task.name()))
+			.one(&self.db.conn)
+			.await;
+		match last_run {
+			Ok(Some(run)) if run.started_at >= slot => {
+				self.release(Some(lock)).await;
+				Ok(None)
+			}
+			Ok(_) => Ok(Some(Some(lock))),
+			Err(e) => {
+				self.release(Some(lock)).await;
+				Err(e.into())
+			}
+		}
+	}
+
+	async fn release(&self, lock: Option<DatabaseLock>) {
+		if let Some(lock) = lock
+			&& let Err(e) = lock.release().await
+		{
+			tracing::warn!("Failed to release maintenance lock: {:#}", e);
+		}
+	}
+
+	async fn start_run(&self, task: MaintenanceTask, trigger: &str) -> anyhow::Result<()> {
+		let now = Utc::now().naive_utc();
+		let result = maintenance_tasks::Entity::update_many()
+			.col_expr(maintenance_tasks::Column::Status, Expr::value(STATUS_RUNNING))
+			.col_expr(maintenance_tasks::Column::Trigger, Expr::value(trigger))
+			.col_expr(maintenance_tasks::Column::RunBy, Expr::value(self.owner.as_str()))
+			.col_expr(maintenance_tasks::Column::StartedAt, Expr::value(now))
+			.col_expr(
+				maintenance_tasks::Column::FinishedAt,
+				Expr::value(Option::<NaiveDateTime>::None),
+			)
+			.col_expr(maintenance_tasks::Column::DurationMs, Expr::value(Option::<i64>::None))
+			.col_expr(maintenance_tasks::Column::Message, Expr::value(Option::<String>::None))
+			.filter(maintenance_tasks::Column::Name.eq(task.name()))
+			.exec(&self.db.conn)
+			.await?;
+
+		if result.rows_affected == 0 {
+			let model = maintenance_tasks::ActiveModel {
+				name: Set(task.name().to_string()),
+				status: Set(STATUS_RUNNING.to_string()),
+				trigger: Set(trigger.to_string()),
+				run_by: Set(self.owner.clone()),
+				started_at: Set(now),
+				..Default::default()
+			};
+			// Per-process tasks may race to insert the first row; either row will do.
+			if let Err(e) = maintenance_tasks::Entity::insert(model).exec(&self.db.conn).await
+				&& !task.per_process()
+			{
+				return Err(e.into());
+			}
+		}
+		Ok(())
+	}
+
+	/// Runs the task and records how it went. A panicking task is recorded as failed.
+	async fn execute(self: Arc<Self>, task: MaintenanceTask, lock: Option<DatabaseLock>) {
+		tracing::info!("Running maintenance task {}", task.name());
+		let started = Instant::now();
+		let this = Arc::clone(&self);
+		let run = tokio::spawn(async move { this.run(task).await });
+		let result = match self.renewing(task, lock.as_ref(), run).await {
+			Ok(result) => result,
+			Err(e) => Err(anyhow!("Task panicked: {}", e)),
+		};
+		let duration = started.elapsed();
+
+		let (status, message) = match result {
+			Ok(message) => {
+				tracing::info!("Maintenance task {} finished: {}", task.name(), message);
+				(STATUS_SUCCEEDED, message)
+			}
+			Err(e) => {
+				tracing::error!("Maintenance task {} failed: {:#}", task.name(), e);
+				(STATUS_FAILED, format!("{:#}", e))
+			}
+		};
+
+		let finished = maintenance_tasks::Entity::update_many()
+			.col_expr(maintenance_tasks::Column::Status, Expr::value(status))
+			.col_expr(maintenance_tasks::Column::FinishedAt, Expr::value(Utc::now().naive_utc()))
+			.col_expr(
+				maintenance_tasks::Column::DurationMs,
+				Expr::value(duration.as_millis().min(i64::MAX as u128) as i64),
+			)
+			.col_expr(maintenance_tasks::Column::Message, Expr::value(message))
+			.filter(maintenance_tasks::Column::Name.eq(task.name()))
+			.filter(maintenance_tasks::Column::RunBy.eq(self.owner.as_str()))
+			.exec(&self.db.conn)
+			.await;
+		if let Err(e) = finished {
+			tracing::warn!("Failed to record the run of maintenance task {}: {}", task.name(), e);
+		}
+
+		self.release(lock).await;
+	}
+
+	/// Waits for `run`, renewing the lock of the task meanwhile so a long run is not mistaken for a crashed one.
+	async fn renewing<T>(&self, task: MaintenanceTask, lock: Option<&DatabaseLock>, run: impl Future<Output = T>) -> T {
+		let Some(lock) = lock else {
+			return run.await;
+		};
+
+		tokio::pin!(run);
+		let every = TASK_LOCK_TTL / 3;
+		let mut renewals = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+		loop {
+			tokio::select! {
+				result = &mut run => return result,
+				_ = renewals.tick() => match lock.acquire().await {
+					Ok(true) => {}
+					Ok(false) => tracing::warn!("Lost the lock of maintenance task {} while it runs", task.name()),
+					Err(e) => tracing::warn!("Failed to renew the lock of maintenance task {}: {:#}", task.name(), e),
+				},
+			}
+		}
+	}
+
+	/// Does the work of the task and sums up what it did.
+	async fn run(&self, task: MaintenanceTask) -> anyhow::Result<String> {
+		match task {
+			MaintenanceTask::TempCleanup => {
+				let result = temp::Entity::delete_many()
+					.filter(temp::Column::ExpiresAt.lt(Utc::now().naive_utc()))
+					.exec(&self.db.conn)
+					.await?;
+				Ok(format!("Deleted {} expired temp entries", result.rows_affected))
+			}
+			MaintenanceTask::Backup => {
+				if self.db.db_type != "sqlite" {
+					return Ok("Skipped, backups are only supported for SQLite".to_string());
+				}
+				Ok(format!("Backed up to {}", self.db.backup().await?))
+			}
+			MaintenanceTask::BackupCleanup => {
+				if self.db.db_type != "sqlite" {
+					return Ok("Skipped, backups are only supported for SQLite".to_string());
+				}
+				Ok(format!("Removed {} old backups", self.db.cleanup_backups().await?))
+			}
+			MaintenanceTask::OrphanCleanup => {
+				let mut deleted = Vec::new();
+				for (what, query) in ORPHAN_QUERIES {
+					let result = self.db.conn.execute_unprepared(query).await?;
+					deleted.push(format!("{} {}", result.rows_affected(), what));
+				}
+				Ok(format!("Deleted {}", deleted.join(", ")))
+			}
+			MaintenanceTask::PluginUpdates => {
+				if self.scraper_manager.update_plugins().await? {
+					Ok("Checked the plugin repositories".to_string())
+				} else {
+					Ok("Skipped until the app itself is updated".to_string())
+				}
+			}
+			MaintenanceTask::CoverRefresh => {
+				let manga_ids: Vec<i32> = mangas::Entity::find()
+					.select_only()
+					.column(mangas::Column::Id)
+					.filter(mangas::Column::ImgUrl.eq(""))
+					.filter(mangas::Column::CreatedAt.is_not_null())
+					.order_by_asc(mangas::Column::UpdatedAt)
+					.limit(self.cover_refresh_batch)
+					.into_tuple()
+					.all(&self.db.conn)
+					.await?;
+				let novel_ids: Vec<i32> = novels::Entity::find()
+					.select_only()
+					.column(novels::Column::Id)
+					.filter(novels::Column::ImgUrl.eq(""))
+					.filter(novels::Column::CreatedAt.is_not_null())
+					.order_by_asc(novels::Column::UpdatedAt)
+					.limit(self.cover_refresh_batch)
+					.into_tuple()
+					.all(&self.db.conn)
+					.await?;
+
+				let mut queued = 0;
+				let items = manga_ids
+					.into_iter()
+					.map(|id| (ItemType::Manga, id))
+					.chain(novel_ids.into_iter().map(|id| (ItemType::Novel, id)));
+				for (item_type, id) in items {
+					if self.control.enqueue_at(item_type, id, COVER_REFRESH_PRIORITY).await? {
+						queued += 1;
+					}
+				}
+				Ok(format!("Queued updates for {} items without a cover", queued))
+			}
+			MaintenanceTask::Vacuum => {
+				let statements = match self.db.db_type.as_str() {
+					"sqlite" => vec!["VACUUM".to_string(), "ANALYZE".to_string()],
+					"postgresql" => vec!["VACUUM ANALYZE".to_string()],
+					"mysql" => vec![format!("OPTIMIZE TABLE {}", MYSQL_VACUUM_TABLES.join(", "))],
+					other => return Ok(format!("Skipped, not supported for {}", other)),
+				};
+				for statement in &statements {
+					self.db.conn.execute_unprepared(statement).await?;
+				}
+				Ok(format!("Ran {}", statements.join(", ")))
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_schedule() {
+		let five = parse_schedule("30 3 * * *").unwrap().expect("five fields");
+		let six = parse_schedule("0 30 3 * * *").unwrap().expect("six fields");
+		let after = Local::now();
+		assert_eq!(five.after(&after).next(), six.after(&after).next());
+
+		assert!(parse_schedule("  ").unwrap().is_none(), "empty expressions disable the task");
+		assert!(parse_schedule("@daily").unwrap().is_some());
+		assert!(parse_schedule("every day").is_err());
+	}
+
+	#[test]
+	fn test_default_schedules() {
+		let config = MaintenanceConfig::default();
+		for task in MaintenanceTask::ALL {
+			assert_eq!(MaintenanceTask::from_name(task.name()), Some(task));
+			match config.schedules.get(task.name()) {
+				Some(expression) => assert!(parse_schedule(expression).unwrap().is_some(), "{}", task.name()),
+				None => assert_eq!(task, MaintenanceTask::Backup, "backups follow the database config"),
+			}
+		}
+		assert_eq!(MaintenanceTask::from_name("reindex"), None);
+	}
+
+	#[test]
+	fn test_backup_schedule() {
+		assert_eq!(backup_schedule(6).as_deref(), Some("0 0 */6 * * *"));
+		for hours in [1, 8, 24] {
+			assert!(
+				parse_schedule(&backup_schedule(hours).unwrap()).unwrap().is_some(),
+				"{} hours",
+				hours
+			);
+		}
+		for hours in [0, 5, 36, 48] {
+			assert_eq!(backup_schedule(hours), None, "{} hours", hours);
+		}
+	}
+}
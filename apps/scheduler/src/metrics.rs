@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use telemetry::metrics::registry;
+
+/// Counts a finished update job. `outcome` is `succeeded`, `deferred`, `not_found`, `internal` or the kind of the
+/// scraper error the job failed with.
+pub(crate) fn record_job_outcome(scraper: &str, outcome: &str) {
+	registry().increment(
+		"scheduler_jobs_total",
+		"Update jobs run, by scraper and outcome.",
+		&[("scraper", scraper), ("outcome", outcome)],
+	);
+}
+
+/// Reports the queue of the scheduler running in this process and the stored jobs shared with the other workers.
+pub(crate) fn register_collector() {
+	registry().register_collector(
+		"scheduler",
+		Arc::new(|| {
+			Box::pin(async {
+				let Some(scheduler) = crate::local() else {
+					return String::new();
+				};
+
+				let registry = registry();
+				registry.set(
+					"scheduler_queue_depth",
+					"Jobs waiting in the queue.",
+					&[],
+					scheduler.queue_len() as f64,
+				);
+				registry.set(
+					"scheduler_queue_capacity",
+					"Jobs the queue holds before dropping the lowest priorities.",
+					&[],
+					scheduler.queue_capacity() as f64,
+				);
+				registry.set(
+					"scheduler_jobs_running",
+					"Jobs running right now.",
+					&[],
+					scheduler.in_flight().len() as f64,
+				);
+				registry.set(
+					"scheduler_leader",
+					"Whether this worker scans for due updates.",
+					&[],
+					if scheduler.is_leader() { 1.0 } else { 0.0 },
+				);
+
+				match scheduler.control().job_counts().await {
+					Ok(counts) => {
+						registry.clear("scheduler_stored_jobs");
+						for (status, count) in counts {
+							registry.set(
+								"scheduler_stored_jobs",
+								"Jobs stored in the database, by status.",
+								&[("status", &status)],
+								count as f64,
+							);
+						}
+					}
+					Err(e) => tracing::warn!("Failed to count stored jobs: {}", e),
+				}
+
+				String::new()
+			})
+		}),
+	);
+}
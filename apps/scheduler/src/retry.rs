@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use queue::retry::RetryPolicy;
+use scraper_types::{ScraperError, ScraperErrorKind};
+use serde::{Deserialize, Serialize};
+
+/// How jobs failing with one kind of scraper error are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RetryPolicyConfig {
+	pub max_retries: u32,
+	/// Delay before the first retry, doubled for each next one.
+	pub base_delay_seconds: u64,
+	pub max_delay_seconds: u64,
+}
+
+impl RetryPolicyConfig {
+	fn policy(&self) -> RetryPolicy {
+		RetryPolicy::exponential(
+			self.max_retries,
+			Duration::from_secs(self.base_delay_seconds),
+			Duration::from_secs(self.max_delay_seconds.max(self.base_delay_seconds)),
+		)
+	}
+}
+
+/// Policies by [`ScraperErrorKind`] name: rate limits back off for up to an hour, Cloudflare challenges cool down for
+/// hours, and network errors retry quickly.
+pub(crate) fn default_policies() -> BTreeMap<String, RetryPolicyConfig> {
+	BTreeMap::from([
+		(
+			ScraperErrorKind::RateLimit.as_str().to_string(),
+			RetryPolicyConfig {
+				max_retries: 6,
+				base_delay_seconds: 60,
+				max_delay_seconds: 60 * 60,
+			},
+		),
+		(
+			ScraperErrorKind::Cloudflare.as_str().to_string(),
+			RetryPolicyConfig {
+				max_retries: 3,
+				base_delay_seconds: 30 * 60,
+				max_delay_seconds: 6 * 60 * 60,
+			},
+		),
+		(
+			ScraperErrorKind::Network.as_str().to_string(),
+			RetryPolicyConfig {
+				max_retries: 3,
+				base_delay_seconds: 2,
+				max_delay_seconds: 5 * 60,
+			},
+		),
+	])
+}
+
+/// Picks the retry policy for a failed scrape. Missing pages and rejected input never come back by themselves, so
+/// those jobs go straight to the dead-letter list, as do errors the plugin marked as not retryable.
+pub(crate) fn policy_for(error: &ScraperError, policies: &BTreeMap<String, RetryPolicyConfig>) -> RetryPolicy {
+	let kind = match error.status_code {
+		Some(404 | 410) => ScraperErrorKind::NotFound,
+		Some(429) => ScraperErrorKind::RateLimit,
+		_ => error.kind.clone(),
+	};
+
+	match kind {
+		ScraperErrorKind::NotFound | ScraperErrorKind::Validation => return RetryPolicy::NEVER,
+		ScraperErrorKind::RateLimit => {}
+		_ if !error.retryable => return RetryPolicy::NEVER,
+		_ => {}
+	}
+
+	policies
+		.get(kind.as_str())
+		.or_else(|| policies.get(ScraperErrorKind::Network.as_str()))
+		.map(RetryPolicyConfig::policy)
+		.unwrap_or_else(|| RetryPolicy::exponential(3, Duration::from_secs(2), Duration::from_secs(5 * 60)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_policy_by_error_kind() {
+		let policies = default_policies();
+
+		let not_found = ScraperError::from_http_status(404, "gone");
+		assert_eq!(policy_for(&not_found, &policies), RetryPolicy::NEVER);
+		let invalid = ScraperError::with_retryable(ScraperErrorKind::Validation, "bad id", true);
+		assert_eq!(policy_for(&invalid, &policies), RetryPolicy::NEVER);
+
+		let limited = ScraperError::with_status(ScraperErrorKind::Network, "slow down", 429);
+		assert_eq!(policy_for(&limited, &policies).base_delay, Duration::from_secs(60));
+
+		let challenged = ScraperError::from_http_status(403, "challenge");
+		assert_eq!(policy_for(&challenged, &policies).base_delay, Duration::from_secs(30 * 60));
+
+		// Retryable kinds without a policy of their own retry like network errors.
+		let parse = ScraperError::with_retryable(ScraperErrorKind::Parse, "layout changed", true);
+		assert_eq!(policy_for(&parse, &policies).base_delay, Duration::from_secs(2));
+		assert_eq!(
+			policy_for(&ScraperError::parse("layout changed"), &policies),
+			RetryPolicy::NEVER
+		);
+	}
+}
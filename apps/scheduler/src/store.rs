@@ -0,0 +1,274 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use database_connection::Database;
+use database_entities::scheduler_jobs;
+use queue::store::{BoxFuture, QueueStore, StoreResult, StoredJob};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_LEASED: &str = "leased";
+/// Jobs given up on, kept with their last error as the dead-letter list until an operator replays or discards them.
+pub const STATUS_FAILED: &str = "failed";
+
+/// Keeps queue jobs in the `scheduler_jobs` table so they survive restarts and can be shared by several scheduler
+/// processes. Payloads are stored as JSON.
+pub struct DatabaseQueueStore<J> {
+	db: Arc<Database>,
+	owner: String,
+	lease_duration: Duration,
+	_payload: PhantomData<fn() -> J>,
+}
+
+impl<J> DatabaseQueueStore<J> {
+	pub fn new(db: Arc<Database>, owner: String, lease_duration: Duration) -> Self {
+		Self {
+			db,
+			owner,
+			lease_duration,
+			_payload: PhantomData,
+		}
+	}
+
+	/// Returns jobs this owner leased before a restart to the pending state, so they run again right away instead of
+	/// after their lease expires. Only useful when the owner name is stable across restarts.
+	pub async fn release_own_leases(&self) -> StoreResult<u64> {
+		let result = scheduler_jobs::Entity::update_many()
+			.col_expr(scheduler_jobs::Column::Status, Expr::value(STATUS_PENDING))
+			.col_expr(scheduler_jobs::Column::LeaseOwner, Expr::value(Option::<String>::None))
+			.col_expr(
+				scheduler_jobs::Column::LeaseExpiresAt,
+				Expr::value(Option::<NaiveDateTime>::None),
+			)
+			.filter(scheduler_jobs::Column::Status.eq(STATUS_LEASED))
+			.filter(scheduler_jobs::Column::LeaseOwner.eq(self.owner.as_str()))
+			.exec(&self.db.conn)
+			.await?;
+		Ok(result.rows_affected)
+	}
+
+	async fn find(&self, key: &str) -> StoreResult<Option<scheduler_jobs::Model>> {
+		Ok(scheduler_jobs::Entity::find()
+			.filter(scheduler_jobs::Column::Key.eq(key))
+			.one(&self.db.conn)
+			.await?)
+	}
+}
+
+fn now() -> NaiveDateTime {
+	Utc::now().naive_utc()
+}
+
+fn to_naive(at: SystemTime) -> NaiveDateTime {
+	DateTime::<Utc>::from(at).naive_utc()
+}
+
+fn to_system_time(at: NaiveDateTime) -> SystemTime {
+	SystemTime::from(DateTime::<Utc>::from_naive_utc_and_offset(at, Utc))
+}
+
+/// Jobs nobody is working on: pending ones, and leased ones whose lease ran out.
+fn available(now: NaiveDateTime) -> Condition {
+	Condition::any().add(scheduler_jobs::Column::Status.eq(STATUS_PENDING)).add(
+		Condition::all()
+			.add(scheduler_jobs::Column::Status.eq(STATUS_LEASED))
+			.add(scheduler_jobs::Column::LeaseExpiresAt.lt(now)),
+	)
+}
+
+impl<J> QueueStore<J> for DatabaseQueueStore<J>
+where
+	J: Serialize + DeserializeOwned + Send + Sync,
+{
+	fn save<'a>(&'a self, job: &'a StoredJob<J>) -> BoxFuture<'a, StoreResult<bool>> {
+		Box::pin(async move {
+			let payload = serde_json::to_string(&job.payload)?;
+			let now = now();
+
+			let Some(existing) = self.find(&job.key).await? else {
+				let model = scheduler_jobs::ActiveModel {
+					key: Set(job.key.clone()),
+					payload: Set(payload),
+					priority: Set(job.priority as i16),
+					status: Set(STATUS_PENDING.to_string()),
+					attempts: Set(job.attempts as i32),
+					retry_at: Set(to_naive(job.retry_at)),
+					lease_owner: Set(None),
+					lease_expires_at: Set(None),
+					last_error: Set(None),
+					created_at: Set(now),
+					updated_at: Set(now),
+					..Default::default()
+				};
+				// Another process may have stored the same job in the meantime, which is just as good.
+				if let Err(e) = scheduler_jobs::Entity::insert(model).exec(&self.db.conn).await {
+					tracing::debug!(key = %job.key, "Job was stored concurrently: {}", e);
+				}
+				return Ok(true);
+			};
+
+			// Dead-lettered, so it waits for an operator to replay or discard it.
+			if existing.status == STATUS_FAILED {
+				return Ok(false);
+			}
+
+			let lease_active = existing.status == STATUS_LEASED && existing.lease_expires_at.is_some_and(|at| at >= now);
+			if lease_active || (job.priority as i16) <= existing.priority {
+				return Ok(true);
+			}
+
+			let mut model: scheduler_jobs::ActiveModel = existing.into();
+			model.payload = Set(payload);
+			model.priority = Set(job.priority as i16);
+			model.status = Set(STATUS_PENDING.to_string());
+			model.lease_owner = Set(None);
+			model.lease_expires_at = Set(None);
+			model.updated_at = Set(now);
+			model.update(&self.db.conn).await?;
+			Ok(true)
+		})
+	}
+
+	fn lease<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<bool>> {
+		Box::pin(async move {
+			let now = now();
+			let expires_at = now + chrono::Duration::from_std(self.lease_duration)?;
+
+			// A single conditional update, so two processes racing for the same job cannot both win.
+			let result = scheduler_jobs::Entity::update_many()
+				.col_expr(scheduler_jobs::Column::Status, Expr::value(STATUS_LEASED))
+				.col_expr(scheduler_jobs::Column::LeaseOwner, Expr::value(self.owner.as_str()))
+				.col_expr(scheduler_jobs::Column::LeaseExpiresAt, Expr::value(expires_at))
+				.col_expr(scheduler_jobs::Column::UpdatedAt, Expr::value(now))
+				.filter(scheduler_jobs::Column::Key.eq(key))
+				.filter(available(now))
+				.exec(&self.db.conn)
+				.await?;
+
+			Ok(result.rows_affected == 1)
+		})
+	}
+
+	fn renew_interval(&self) -> Option<Duration> {
+		// Several chances to renew before the lease runs out, should one of them fail.
+		Some(self.lease_duration / 3)
+	}
+
+	fn renew<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<bool>> {
+		Box::pin(async move {
+			let now = now();
+			let expires_at = now + chrono::Duration::from_std(self.lease_duration)?;
+
+			let result = scheduler_jobs::Entity::update_many()
+				.col_expr(scheduler_jobs::Column::LeaseExpiresAt, Expr::value(expires_at))
+				.col_expr(scheduler_jobs::Column::UpdatedAt, Expr::value(now))
+				.filter(scheduler_jobs::Column::Key.eq(key))
+				.filter(scheduler_jobs::Column::Status.eq(STATUS_LEASED))
+				.filter(scheduler_jobs::Column::LeaseOwner.eq(self.owner.as_str()))
+				.exec(&self.db.conn)
+				.await?;
+
+			Ok(result.rows_affected == 1)
+		})
+	}
+
+	fn complete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async move {
+			scheduler_jobs::Entity::delete_many()
+				.filter(scheduler_jobs::Column::Key.eq(key))
+				.filter(scheduler_jobs::Column::LeaseOwner.eq(self.owner.as_str()))
+				.exec(&self.db.conn)
+				.await?;
+			Ok(())
+		})
+	}
+
+	fn retry<'a>(&'a self, key: &'a str, attempts: u32, retry_at: SystemTime) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async move {
+			scheduler_jobs::Entity::update_many()
+				.col_expr(scheduler_jobs::Column::Status, Expr::value(STATUS_PENDING))
+				.col_expr(scheduler_jobs::Column::Attempts, Expr::value(attempts as i32))
+				.col_expr(scheduler_jobs::Column::RetryAt, Expr::value(to_naive(retry_at)))
+				.col_expr(scheduler_jobs::Column::LeaseOwner, Expr::value(Option::<String>::None))
+				.col_expr(
+					scheduler_jobs::Column::LeaseExpiresAt,
+					Expr::value(Option::<NaiveDateTime>::None),
+				)
+				.col_expr(scheduler_jobs::Column::UpdatedAt, Expr::value(now()))
+				.filter(scheduler_jobs::Column::Key.eq(key))
+				.filter(scheduler_jobs::Column::LeaseOwner.eq(self.owner.as_str()))
+				.exec(&self.db.conn)
+				.await?;
+			Ok(())
+		})
+	}
+
+	fn fail<'a>(&'a self, key: &'a str, attempts: u32, error: &'a str) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async move {
+			scheduler_jobs::Entity::update_many()
+				.col_expr(scheduler_jobs::Column::Status, Expr::value(STATUS_FAILED))
+				.col_expr(scheduler_jobs::Column::Attempts, Expr::value(attempts as i32))
+				.col_expr(scheduler_jobs::Column::LastError, Expr::value(error))
+				.col_expr(scheduler_jobs::Column::LeaseOwner, Expr::value(Option::<String>::None))
+				.col_expr(
+					scheduler_jobs::Column::LeaseExpiresAt,
+					Expr::value(Option::<NaiveDateTime>::None),
+				)
+				.col_expr(scheduler_jobs::Column::UpdatedAt, Expr::value(now()))
+				.filter(scheduler_jobs::Column::Key.eq(key))
+				.filter(scheduler_jobs::Column::LeaseOwner.eq(self.owner.as_str()))
+				.exec(&self.db.conn)
+				.await?;
+			Ok(())
+		})
+	}
+
+	fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, StoreResult<()>> {
+		Box::pin(async move {
+			scheduler_jobs::Entity::delete_many()
+				.filter(scheduler_jobs::Column::Key.eq(key))
+				.filter(scheduler_jobs::Column::Status.eq(STATUS_PENDING))
+				.exec(&self.db.conn)
+				.await?;
+			Ok(())
+		})
+	}
+
+	fn load(&self, limit: usize) -> BoxFuture<'_, StoreResult<Vec<StoredJob<J>>>> {
+		Box::pin(async move {
+			let rows = scheduler_jobs::Entity::find()
+				.filter(available(now()))
+				.order_by_desc(scheduler_jobs::Column::Priority)
+				.order_by_asc(scheduler_jobs::Column::RetryAt)
+				.limit(limit as u64)
+				.all(&self.db.conn)
+				.await?;
+
+			let mut jobs = Vec::with_capacity(rows.len());
+			for row in rows {
+				let payload = match serde_json::from_str(&row.payload) {
+					Ok(payload) => payload,
+					Err(e) => {
+						tracing::warn!(key = %row.key, "Dropping stored job with an unreadable payload: {}", e);
+						scheduler_jobs::Entity::delete_by_id(row.id).exec(&self.db.conn).await?;
+						continue;
+					}
+				};
+				jobs.push(StoredJob {
+					key: row.key,
+					payload,
+					priority: row.priority.clamp(0, u8::MAX as i16) as u8,
+					attempts: row.attempts.max(0) as u32,
+					retry_at: to_system_time(row.retry_at),
+				});
+			}
+			Ok(jobs)
+		})
+	}
+}
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use database_connection::Database;
+use database_entities::{scheduler_locks, scheduler_workers};
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter};
+
+/// Lock held by the one scheduler that scans for due mangas and novels. The others only run the jobs it stores.
+pub(crate) const LEADER_LOCK: &str = "scheduler-leader";
+
+/// How long rows of workers that stopped sending heartbeats are kept around for operators.
+const STOPPED_WORKER_RETENTION: TimeDelta = TimeDelta::days(1);
+
+pub(crate) fn hostname() -> String {
+	std::env::var("HOSTNAME").unwrap_or_else(|_| "scheduler".to_string())
+}
+
+fn expires_at(now: NaiveDateTime, ttl: Duration) -> NaiveDateTime {
+	now + TimeDelta::from_std(ttl).unwrap_or(TimeDelta::MAX)
+}
+
+/// A named lock with an expiring owner in the database. Relies on conditional updates and a unique index only, so
+/// it works the same on every supported backend.
+pub struct DatabaseLock {
+	db: Arc<Database>,
+	name: String,
+	owner: String,
+	ttl: Duration,
+}
+
+impl DatabaseLock {
+	pub fn new(db: Arc<Database>, name: impl Into<String>, owner: impl Into<String>, ttl: Duration) -> Self {
+		Self {
+			db,
+			name: name.into(),
+			owner: owner.into(),
+			ttl,
+		}
+	}
+
+	/// Takes the lock, or extends it when this owner holds it already. Returns false while another owner holds it;
+	/// a holder that stops renewing loses it once `ttl` runs out.
+	pub async fn acquire(&self) -> anyhow::Result<bool> {
+		let now = Utc::now().naive_utc();
+		let result = scheduler_locks::Entity::update_many()
+			.col_expr(scheduler_locks::Column::Owner, Expr::value(self.owner.as_str()))
+			.col_expr(scheduler_locks::Column::ExpiresAt, Expr::value(expires_at(now, self.ttl)))
+			.filter(scheduler_locks::Column::Name.eq(self.name.as_str()))
+			.filter(
+				Condition::any()
+					.add(scheduler_locks::Column::Owner.eq(self.owner.as_str()))
+					.add(scheduler_locks::Column::ExpiresAt.lt(now)),
+			)
+			.exec(&self.db.conn)
+			.await?;
+		if result.rows_affected > 0 {
+			return Ok(true);
+		}
+
+		// The lock was never taken before. The unique name lets only one of the processes racing here insert it.
+		let model = scheduler_locks::ActiveModel {
+			name: Set(self.name.clone()),
+			owner: Set(self.owner.clone()),
+			expires_at: Set(expires_at(now, self.ttl)),
+			..Default::default()
+		};
+		match scheduler_locks::Entity::insert(model).exec(&self.db.conn).await {
+			Ok(_) => Ok(true),
+			Err(e) => {
+				tracing::trace!(lock = %self.name, "Lock is held by another process: {}", e);
+				Ok(false)
+			}
+		}
+	}
+
+	/// Gives the lock up, if this owner holds it, so others need not wait for it to expire.
+	pub async fn release(&self) -> anyhow::Result<()> {
+		scheduler_locks::Entity::delete_many()
+			.filter(scheduler_locks::Column::Name.eq(self.name.as_str()))
+			.filter(scheduler_locks::Column::Owner.eq(self.owner.as_str()))
+			.exec(&self.db.conn)
+			.await?;
+		Ok(())
+	}
+}
+
+/// A scheduler process, as described by its last heartbeat.
+#[derive(Debug, Clone)]
+pub struct WorkerInfo {
+	pub instance_id: String,
+	pub hostname: String,
+	pub pid: u32,
+	/// Whether it was scanning for due mangas and novels.
+	pub leader: bool,
+	pub queued_jobs: u32,
+	pub running_jobs: u32,
+	pub started_at: NaiveDateTime,
+	pub heartbeat_at: NaiveDateTime,
+	/// False once it missed its heartbeats, e.g. because it stopped or lost the database.
+	pub alive: bool,
+}
+
+impl From<scheduler_workers::Model> for WorkerInfo {
+	fn from(row: scheduler_workers::Model) -> Self {
+		Self {
+			alive: row.expires_at >= Utc::now().naive_utc(),
+			instance_id: row.instance_id,
+			hostname: row.hostname,
+			pid: row.pid.max(0) as u32,
+			leader: row.leader,
+			queued_jobs: row.queued_jobs.max(0) as u32,
+			running_jobs: row.running_jobs.max(0) as u32,
+			started_at: row.started_at,
+			heartbeat_at: row.heartbeat_at,
+		}
+	}
+}
+
+/// Keeps the `scheduler_workers` row of this process up to date.
+pub(crate) struct WorkerRegistration {
+	db: Arc<Database>,
+	instance_id: String,
+	started_at: NaiveDateTime,
+	ttl: Duration,
+}
+
+impl WorkerRegistration {
+	pub(crate) fn new(db: Arc<Database>, instance_id: String, ttl: Duration) -> Self {
+		Self {
+			db,
+			instance_id,
+			started_at: Utc::now().naive_utc(),
+			ttl,
+		}
+	}
+
+	/// Records that this process is alive, and forgets workers that stopped long ago.
+	pub(crate) async fn beat(&self, leader: bool, queued_jobs: usize, running_jobs: usize) -> anyhow::Result<()> {
+		let now = Utc::now().naive_utc();
+		let queued_jobs = queued_jobs.min(i32::MAX as usize) as i32;
+		let running_jobs = running_jobs.min(i32::MAX as usize) as i32;
+
+		let result = scheduler_workers::Entity::update_many()
+			.col_expr(scheduler_workers::Column::Hostname, Expr::value(hostname()))
+			.col_expr(scheduler_workers::Column::Pid, Expr::value(std::process::id() as i32))
+			.col_expr(scheduler_workers::Column::Leader, Expr::value(leader))
+			.col_expr(scheduler_workers::Column::QueuedJobs, Expr::value(queued_jobs))
+			.col_expr(scheduler_workers::Column::RunningJobs, Expr::value(running_jobs))
+			.col_expr(scheduler_workers::Column::StartedAt, Expr::value(self.started_at))
+			.col_expr(scheduler_workers::Column::HeartbeatAt, Expr::value(now))
+			.col_expr(scheduler_workers::Column::ExpiresAt, Expr::value(expires_at(now, self.ttl)))
+			.filter(scheduler_workers::Column::InstanceId.eq(self.instance_id.as_str()))
+			.exec(&self.db.conn)
+			.await?;
+
+		if result.rows_affected == 0 {
+			let model = scheduler_workers::ActiveModel {
+				instance_id: Set(self.instance_id.clone()),
+				hostname: Set(hostname()),
+				pid: Set(std::process::id() as i32),
+				leader: Set(leader),
+				queued_jobs: Set(queued_jobs),
+				running_jobs: Set(running_jobs),
+				started_at: Set(self.started_at),
+				heartbeat_at: Set(now),
+				expires_at: Set(expires_at(now, self.ttl)),
+				..Default::default()
+			};
+			scheduler_workers::Entity::insert(model).exec(&self.db.conn).await?;
+		}
+
+		scheduler_workers::Entity::delete_many()
+			.filter(scheduler_workers::Column::ExpiresAt.lt(now - STOPPED_WORKER_RETENTION))
+			.exec(&self.db.conn)
+			.await?;
+
+		Ok(())
+	}
+}
@@ -1,12 +1,9 @@
-use tracing_subscriber::FmtSubscriber;
-
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 const PACKAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-	let subscriber = FmtSubscriber::builder().with_max_level(tracing::Level::INFO).finish();
-	let _ = tracing::subscriber::set_global_default(subscriber);
+	let _telemetry = telemetry::init("manga-vault-website");
 
 	let latest_release = version_check::get_latest_release(PACKAGE_NAME).await;
 
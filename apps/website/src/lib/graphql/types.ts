@@ -15,6 +15,7 @@ export type Scraper = {
 	imageUrl?: string | null;
 	refererUrl?: string | null;
 	type?: ScraperType;
+	transformsPages?: boolean;
 };
 
 export type User = {
@@ -32,6 +33,14 @@ export type Chapter = {
 	url: string;
 };
 
+export type ChapterPage = {
+	index: number;
+	url: string;
+	width?: number | null;
+	height?: number | null;
+	spread: boolean;
+};
+
 export type ScraperInfo = {
 	id: string;
 	name: string;
@@ -18,6 +18,26 @@ export function proxyImage(url: string, referer?: string | null): string {
 	}
 }
 
+/**
+ * Fetches a chapter page through the proxy, which sends the page's own headers and falls back to its mirrors.
+ * Pass `scraper` for scrapers that transform their pages, e.g. to unscramble images.
+ */
+export function proxyChapterPage(
+	chapterId: number,
+	index: number,
+	referer?: string | null,
+	scraper?: string | null,
+): string {
+	let proxied = `${proxyUrl}?chapter=${chapterId}&page=${index}`;
+	if (referer) {
+		proxied += `&referer=${encodeURIComponent(referer)}`;
+	}
+	if (scraper) {
+		proxied += `&scraper=${encodeURIComponent(scraper)}`;
+	}
+	return proxied;
+}
+
 export function getImage(id: number): string {
 	return `${apiUrl}files/${id}`;
 }
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::{env, fs};
 
 use database_migration::MigratorTrait;
-use sea_orm::ConnectOptions;
+use sea_orm::{ConnectOptions, ConnectionTrait, DbBackend};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -30,6 +30,35 @@ pub enum Error {
 	IoError(#[from] std::io::Error),
 }
 
+/// Reports the pool of the last database opened in this process.
+fn register_pool_collector(db: &Arc<Database>) {
+	let db = Arc::downgrade(db);
+	telemetry::metrics::registry().register_collector(
+		"database_pool",
+		Arc::new(move || {
+			if let Some(db) = db.upgrade() {
+				let (size, idle, max) = db.pool_stats();
+				let registry = telemetry::metrics::registry();
+				let help = "Database pool connections, by state.";
+				registry.set("db_pool_connections", help, &[("state", "idle")], idle as f64);
+				registry.set(
+					"db_pool_connections",
+					help,
+					&[("state", "in_use")],
+					size.saturating_sub(idle as u32) as f64,
+				);
+				registry.set(
+					"db_pool_max_connections",
+					"Connections the pool opens at most.",
+					&[],
+					max as f64,
+				);
+			}
+			Box::pin(async { String::new() })
+		}),
+	);
+}
+
 fn current_exe_parent_dir() -> PathBuf {
 	env::current_exe()
 		.expect("Failed to get executable path")
@@ -41,6 +70,8 @@ fn current_exe_parent_dir() -> PathBuf {
 #[derive(Debug, Deserialize, Serialize, config_derive::Config)]
 #[config(name = "database")]
 pub struct Config {
+	/// Hours between SQLite backups, which must divide a day, unless `maintenance.json` gives the `backup` task a
+	/// schedule of its own.
 	#[serde(default)]
 	pub backup_interval: u16,
 	#[serde(default)]
@@ -135,36 +166,35 @@ impl Database {
 			config: config.clone(),
 		});
 
-		if scheme == "sqlite" {
-			let backup_interval = std::time::Duration::from_secs(config.backup_interval as u64 * 3600);
-			let cleanup_interval = std::time::Duration::from_secs(4 * 3600);
-
-			let backup_db = db.clone();
-			tokio::spawn(async move {
-				loop {
-					if let Err(e) = backup_db.backup().await {
-						tracing::error!("Backup failed: {}", e);
-					}
-					tokio::time::sleep(backup_interval).await;
-				}
-			});
+		tracing::info!("Connected to {} database", scheme);
+		register_pool_collector(&db);
+		Ok(db)
+	}
 
-			let cleanup_db = db.clone();
-			tokio::spawn(async move {
-				loop {
-					if let Err(e) = cleanup_db.cleanup_backups().await {
-						tracing::error!("Cleanup failed: {}", e);
-					}
-					tokio::time::sleep(cleanup_interval).await;
-				}
-			});
+	/// Open, idle and maximum connections of the pool.
+	pub fn pool_stats(&self) -> (u32, usize, u32) {
+		match self.conn.get_database_backend() {
+			DbBackend::Sqlite => {
+				let pool = self.conn.get_sqlite_connection_pool();
+				(pool.size(), pool.num_idle(), pool.options().get_max_connections())
+			}
+			DbBackend::Postgres => {
+				let pool = self.conn.get_postgres_connection_pool();
+				(pool.size(), pool.num_idle(), pool.options().get_max_connections())
+			}
+			DbBackend::MySql => {
+				let pool = self.conn.get_mysql_connection_pool();
+				(pool.size(), pool.num_idle(), pool.options().get_max_connections())
+			}
 		}
+	}
 
-		tracing::info!("Connected to {} database", scheme);
-		Ok(db)
+	pub fn config(&self) -> &Config {
+		&self.config
 	}
 
-	async fn backup(&self) -> Result<(), Error> {
+	/// Copies the SQLite database file to the backup folder and returns the name of the copy.
+	pub async fn backup(&self) -> Result<String, Error> {
 		if self.db_type != "sqlite" {
 			return Err(Error::BackupError("Backup is only supported for SQLite".to_string()));
 		}
@@ -181,19 +211,21 @@ impl Database {
 
 		fs::copy(&path, backup_folder.join(&backup_filename))?;
 		tracing::info!("Database backed up to: {}", backup_filename);
-		Ok(())
+		Ok(backup_filename)
 	}
 
-	async fn cleanup_backups(&self) -> Result<(), Error> {
+	/// Removes backups older than the retention period and returns how many were removed.
+	pub async fn cleanup_backups(&self) -> Result<usize, Error> {
 		if self.db_type != "sqlite" {
 			return Err(Error::CleanupError("Cleanup is only supported for SQLite".to_string()));
 		}
 
 		let backup_folder = Path::new(&self.config.database_backup_folder);
 		if !backup_folder.exists() {
-			return Ok(());
+			return Ok(0);
 		}
 
+		let mut removed = 0;
 		let now = chrono::Utc::now();
 		let retention = chrono::Duration::days(self.config.backup_retention_days as i64);
 
@@ -213,6 +245,7 @@ impl Database {
 									Error::CleanupError(format!("Failed to remove `{}`: {}", path.display(), e))
 								})?;
 								tracing::info!("Removed old backup: {}", path.display());
+								removed += 1;
 							}
 						}
 					}
@@ -220,7 +253,7 @@ impl Database {
 			}
 		}
 
-		Ok(())
+		Ok(removed)
 	}
 }
 
@@ -10,6 +10,7 @@ pub struct Model {
 	pub name: String,
 	pub user_id: i32,
 	pub created_at: DateTime,
+	pub auto_update: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
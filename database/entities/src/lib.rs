@@ -7,6 +7,7 @@ pub mod chapters;
 pub mod favorite_mangas;
 pub mod favorite_novels;
 pub mod files;
+pub mod maintenance_tasks;
 pub mod manga_pack_members;
 pub mod manga_packs;
 pub mod mangas;
@@ -14,5 +15,10 @@ pub mod novel_chapters;
 pub mod novels;
 pub mod read_chapters;
 pub mod read_novel_chapters;
+pub mod scheduler_jobs;
+pub mod scheduler_locks;
+pub mod scheduler_pauses;
+pub mod scheduler_workers;
+pub mod scraper_genres;
 pub mod temp;
 pub mod users;
@@ -27,6 +27,7 @@ pub struct Model {
 	#[sea_orm(column_type = "Text", nullable)]
 	pub genres: Option<String>,
 	pub created_at: Option<DateTime>,
+	pub next_check_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -5,6 +5,7 @@ pub use super::chapters::Entity as Chapters;
 pub use super::favorite_mangas::Entity as FavoriteMangas;
 pub use super::favorite_novels::Entity as FavoriteNovels;
 pub use super::files::Entity as Files;
+pub use super::maintenance_tasks::Entity as MaintenanceTasks;
 pub use super::manga_pack_members::Entity as MangaPackMembers;
 pub use super::manga_packs::Entity as MangaPacks;
 pub use super::mangas::Entity as Mangas;
@@ -12,5 +13,10 @@ pub use super::novel_chapters::Entity as NovelChapters;
 pub use super::novels::Entity as Novels;
 pub use super::read_chapters::Entity as ReadChapters;
 pub use super::read_novel_chapters::Entity as ReadNovelChapters;
+pub use super::scheduler_jobs::Entity as SchedulerJobs;
+pub use super::scheduler_locks::Entity as SchedulerLocks;
+pub use super::scheduler_pauses::Entity as SchedulerPauses;
+pub use super::scheduler_workers::Entity as SchedulerWorkers;
+pub use super::scraper_genres::Entity as ScraperGenres;
 pub use super::temp::Entity as Temp;
 pub use super::users::Entity as Users;
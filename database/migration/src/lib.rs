@@ -17,6 +17,13 @@ mod m20260124_030000_add_novels_and_tracking;
 mod m20260125_000000_add_missing_novel_fields;
 mod m20260125_000000_increase_temp_value_size;
 mod m20260125_010000_make_novel_created_at_nullable;
+mod m20261019_000000_create_scraper_genres;
+mod m20261019_010000_create_scheduler_jobs;
+mod m20261019_020000_add_next_check_at;
+mod m20261019_030000_create_scheduler_pauses;
+mod m20261019_040000_create_scheduler_workers;
+mod m20261019_050000_create_maintenance_tasks;
+mod m20261019_060000_add_category_auto_update;
 
 pub struct Migrator;
 
@@ -41,6 +48,13 @@ impl MigratorTrait for Migrator {
 			Box::new(m20260125_000000_add_missing_novel_fields::Migration),
 			Box::new(m20260125_010000_make_novel_created_at_nullable::Migration),
 			Box::new(m20260125_000000_increase_temp_value_size::Migration),
+			Box::new(m20261019_000000_create_scraper_genres::Migration),
+			Box::new(m20261019_010000_create_scheduler_jobs::Migration),
+			Box::new(m20261019_020000_add_next_check_at::Migration),
+			Box::new(m20261019_030000_create_scheduler_pauses::Migration),
+			Box::new(m20261019_040000_create_scheduler_workers::Migration),
+			Box::new(m20261019_050000_create_maintenance_tasks::Migration),
+			Box::new(m20261019_060000_add_category_auto_update::Migration),
 		]
 	}
 }
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(ScraperGenres::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(ScraperGenres::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(ScraperGenres::Scraper).string().not_null())
+					.col(ColumnDef::new(ScraperGenres::Name).string().not_null())
+					.col(ColumnDef::new(ScraperGenres::Url).text().not_null())
+					.col(ColumnDef::new(ScraperGenres::UpdatedAt).date_time().not_null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_scraper_genres_scraper")
+					.table(ScraperGenres::Table)
+					.col(ScraperGenres::Scraper)
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(ScraperGenres::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
+
+#[derive(DeriveIden)]
+enum ScraperGenres {
+	Table,
+	Id,
+	Scraper,
+	Name,
+	Url,
+	UpdatedAt,
+}
@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(SchedulerJobs::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(SchedulerJobs::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(SchedulerJobs::Key).string().not_null())
+					.col(ColumnDef::new(SchedulerJobs::Payload).text().not_null())
+					.col(ColumnDef::new(SchedulerJobs::Priority).small_integer().not_null())
+					.col(ColumnDef::new(SchedulerJobs::Status).string().not_null())
+					.col(ColumnDef::new(SchedulerJobs::Attempts).integer().not_null().default(0))
+					.col(ColumnDef::new(SchedulerJobs::RetryAt).date_time().not_null())
+					.col(ColumnDef::new(SchedulerJobs::LeaseOwner).string().null())
+					.col(ColumnDef::new(SchedulerJobs::LeaseExpiresAt).date_time().null())
+					.col(ColumnDef::new(SchedulerJobs::LastError).text().null())
+					.col(ColumnDef::new(SchedulerJobs::CreatedAt).date_time().not_null())
+					.col(ColumnDef::new(SchedulerJobs::UpdatedAt).date_time().not_null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_scheduler_jobs_key")
+					.table(SchedulerJobs::Table)
+					.col(SchedulerJobs::Key)
+					.unique()
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_scheduler_jobs_status_retry_at")
+					.table(SchedulerJobs::Table)
+					.col(SchedulerJobs::Status)
+					.col(SchedulerJobs::RetryAt)
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(SchedulerJobs::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
+
+#[derive(DeriveIden)]
+enum SchedulerJobs {
+	Table,
+	Id,
+	Key,
+	Payload,
+	Priority,
+	Status,
+	Attempts,
+	RetryAt,
+	LeaseOwner,
+	LeaseExpiresAt,
+	LastError,
+	CreatedAt,
+	UpdatedAt,
+}
@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Mangas::Table)
+					.add_column(ColumnDef::new(Mangas::NextCheckAt).date_time().null())
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Novels::Table)
+					.add_column(ColumnDef::new(Novels::NextCheckAt).date_time().null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_mangas_next_check_at")
+					.table(Mangas::Table)
+					.col(Mangas::NextCheckAt)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_novels_next_check_at")
+					.table(Novels::Table)
+					.col(Novels::NextCheckAt)
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(Index::drop().name("idx_novels_next_check_at").table(Novels::Table).to_owned())
+			.await?;
+		manager
+			.drop_index(Index::drop().name("idx_mangas_next_check_at").table(Mangas::Table).to_owned())
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Novels::Table)
+					.drop_column(Novels::NextCheckAt)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Mangas::Table)
+					.drop_column(Mangas::NextCheckAt)
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+}
+
+#[derive(DeriveIden)]
+enum Mangas {
+	Table,
+	NextCheckAt,
+}
+
+#[derive(DeriveIden)]
+enum Novels {
+	Table,
+	NextCheckAt,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(SchedulerPauses::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(SchedulerPauses::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(SchedulerPauses::Scope).string().not_null())
+					.col(ColumnDef::new(SchedulerPauses::Reason).text().null())
+					.col(ColumnDef::new(SchedulerPauses::PausedBy).string().null())
+					.col(ColumnDef::new(SchedulerPauses::CreatedAt).date_time().not_null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_scheduler_pauses_scope")
+					.table(SchedulerPauses::Table)
+					.col(SchedulerPauses::Scope)
+					.unique()
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(SchedulerPauses::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
+
+#[derive(DeriveIden)]
+enum SchedulerPauses {
+	Table,
+	Id,
+	Scope,
+	Reason,
+	PausedBy,
+	CreatedAt,
+}
@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(SchedulerWorkers::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(SchedulerWorkers::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(SchedulerWorkers::InstanceId).string().not_null())
+					.col(ColumnDef::new(SchedulerWorkers::Hostname).string().not_null())
+					.col(ColumnDef::new(SchedulerWorkers::Pid).integer().not_null())
+					.col(ColumnDef::new(SchedulerWorkers::Leader).boolean().not_null().default(false))
+					.col(ColumnDef::new(SchedulerWorkers::QueuedJobs).integer().not_null().default(0))
+					.col(ColumnDef::new(SchedulerWorkers::RunningJobs).integer().not_null().default(0))
+					.col(ColumnDef::new(SchedulerWorkers::StartedAt).date_time().not_null())
+					.col(ColumnDef::new(SchedulerWorkers::HeartbeatAt).date_time().not_null())
+					.col(ColumnDef::new(SchedulerWorkers::ExpiresAt).date_time().not_null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_scheduler_workers_instance_id")
+					.table(SchedulerWorkers::Table)
+					.col(SchedulerWorkers::InstanceId)
+					.unique()
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(SchedulerLocks::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(SchedulerLocks::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(SchedulerLocks::Name).string().not_null())
+					.col(ColumnDef::new(SchedulerLocks::Owner).string().not_null())
+					.col(ColumnDef::new(SchedulerLocks::ExpiresAt).date_time().not_null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_scheduler_locks_name")
+					.table(SchedulerLocks::Table)
+					.col(SchedulerLocks::Name)
+					.unique()
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(SchedulerLocks::Table).to_owned())
+			.await?;
+		manager
+			.drop_table(Table::drop().table(SchedulerWorkers::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
+
+#[derive(DeriveIden)]
+enum SchedulerWorkers {
+	Table,
+	Id,
+	InstanceId,
+	Hostname,
+	Pid,
+	Leader,
+	QueuedJobs,
+	RunningJobs,
+	StartedAt,
+	HeartbeatAt,
+	ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum SchedulerLocks {
+	Table,
+	Id,
+	Name,
+	Owner,
+	ExpiresAt,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(MaintenanceTasks::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(MaintenanceTasks::Id)
+							.integer()
+							.not_null()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(MaintenanceTasks::Name).string().not_null())
+					.col(ColumnDef::new(MaintenanceTasks::Status).string().not_null())
+					.col(ColumnDef::new(MaintenanceTasks::Trigger).string().not_null())
+					.col(ColumnDef::new(MaintenanceTasks::RunBy).string().not_null())
+					.col(ColumnDef::new(MaintenanceTasks::StartedAt).date_time().not_null())
+					.col(ColumnDef::new(MaintenanceTasks::FinishedAt).date_time().null())
+					.col(ColumnDef::new(MaintenanceTasks::DurationMs).big_integer().null())
+					.col(ColumnDef::new(MaintenanceTasks::Message).text().null())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_maintenance_tasks_name")
+					.table(MaintenanceTasks::Table)
+					.col(MaintenanceTasks::Name)
+					.unique()
+					.to_owned(),
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(MaintenanceTasks::Table).to_owned())
+			.await?;
+
+		Ok(())
+	}
+}
+
+#[derive(DeriveIden)]
+enum MaintenanceTasks {
+	Table,
+	Id,
+	Name,
+	Status,
+	Trigger,
+	RunBy,
+	StartedAt,
+	FinishedAt,
+	DurationMs,
+	Message,
+}
@@ -1,8 +1,11 @@
 wit_bindgen::generate!({
-	path: "scraper.wit"
+	path: "scraper.wit",
+	world: "root",
 });
 
+use exports::scraper::types::scraper::{ErrorKind, ScraperError};
 use serde_json::Value;
+use url::Url;
 
 fn headers() -> Vec<scraper::types::http::Header> {
 	vec![
@@ -27,6 +30,30 @@ fn headers() -> Vec<scraper::types::http::Header> {
 	]
 }
 
+scraper_types::wit_error_helpers!();
+
+/// GETs `url`, going through FlareSolverr when Cloudflare blocks the request.
+fn fetch(url: &str, headers: &[scraper::types::http::Header]) -> Result<scraper::types::http::Response, ScraperError> {
+	let mut res = scraper::types::http::get(url, Some(headers))
+		.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", url), None))?;
+
+	if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
+		res = scraper::types::flare_solverr::get(url, None).ok_or_else(|| {
+			error(
+				ErrorKind::Cloudflare,
+				format!("{} is behind Cloudflare", url),
+				Some(res.status),
+			)
+		})?;
+	}
+
+	if res.status != 200 {
+		return Err(status_error(url, res.status));
+	}
+
+	Ok(res)
+}
+
 fn absolute(url: &str) -> String {
 	if url.starts_with("http") {
 		url.to_string()
@@ -37,24 +64,6 @@ fn absolute(url: &str) -> String {
 	}
 }
 
-fn default_page(url: String) -> exports::scraper::types::scraper::Page {
-	exports::scraper::types::scraper::Page {
-		title: String::new(),
-		url,
-		img_url: String::new(),
-		alternative_names: Vec::new(),
-		authors: Vec::new(),
-		artists: None,
-		status: String::new(),
-		page_type: None,
-		release_date: None,
-		description: String::new(),
-		genres: Vec::new(),
-		chapters: Vec::new(),
-		content_html: None,
-	}
-}
-
 fn get_image_url(element: &::scraper::ElementRef) -> String {
 	if let Some(v) = element.value().attr("data-src") {
 		return absolute(v);
@@ -174,50 +183,51 @@ pub fn parse_page_from_html(html_str: &str, url: &str) -> exports::scraper::type
 	}
 }
 
+/// Novels listed on a sort or genre page.
+fn fetch_novel_list(url: &str) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
+	let res = fetch(url, &headers()[..])?;
+
+	let html = ::scraper::Html::parse_document(&res.body);
+	let row_sel = ::scraper::Selector::parse("div.li-row").unwrap();
+	let mut items = Vec::new();
+
+	for row in html.select(&row_sel) {
+		if let Some(a) = row.select(&::scraper::Selector::parse(".pic a").unwrap()).next() {
+			let href = a.value().attr("href").unwrap_or("");
+			let title = row
+				.select(&::scraper::Selector::parse(".txt h3.tit a").unwrap())
+				.next()
+				.map(|t| t.text().collect::<Vec<_>>().join(" ").trim().to_string())
+				.unwrap_or_default();
+
+			let img = row.select(&::scraper::Selector::parse(".pic img").unwrap()).next();
+			let img_url = img.map(|i| get_image_url(&i)).unwrap_or_default();
+
+			items.push(exports::scraper::types::scraper::Item {
+				title,
+				url: absolute(href),
+				img_url: absolute(&img_url),
+			});
+		}
+	}
+
+	Ok(items)
+}
+
 struct ScraperImpl;
 
 export!(ScraperImpl);
 
 impl exports::scraper::types::scraper::Guest for ScraperImpl {
-	fn scrape_chapter(url: String) -> Vec<String> {
-		let mut res = match scraper::types::http::get(&url, Some(&headers()[..])) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+	fn scrape_chapter(url: String) -> Result<Vec<String>, ScraperError> {
+		let res = fetch(&url, &headers()[..])?;
 
-		parse_chapter_from_html(&res.body)
+		Ok(parse_chapter_from_html(&res.body))
 	}
 
-	fn scrape_latest(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_latest(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://freewebnovel.com/sort/latest-release?p={}", page);
-		let mut res = match scraper::types::http::get(&url, Some(&headers()[..])) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+		let res = fetch(&url, &headers()[..])?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let row_sel = ::scraper::Selector::parse("div.li-row").unwrap();
@@ -243,27 +253,12 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		items
+		Ok(items)
 	}
 
-	fn scrape_trending(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_trending(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://freewebnovel.com/sort/most-popular?p={}", page);
-		let mut res = match scraper::types::http::get(&url, Some(&headers()[..])) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+		let res = fetch(&url, &headers()[..])?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let row_sel = ::scraper::Selector::parse("div.li-row").unwrap();
@@ -289,64 +284,53 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		items
+		Ok(items)
 	}
 
-	fn scrape_search(query: String, _page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_search(
+		query: String,
+		_page: u32,
+		_filters: exports::scraper::types::scraper::SearchFilters,
+	) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let search_url = "https://freewebnovel.com/search".to_string();
 		let body = format!("searchkey={}", urlencoding::encode(&query));
 
-		if let Some(res) = scraper::types::http::post(&search_url, &body, Some(&headers()[..])) {
-			if res.status == 200 {
-				let html = ::scraper::Html::parse_document(&res.body);
-				let row_sel = ::scraper::Selector::parse("div.li-row").unwrap();
-				let mut items = Vec::new();
-
-				for row in html.select(&row_sel) {
-					println!("Row: {:?}", row.inner_html());
-					if let Some(a) = row.select(&::scraper::Selector::parse(".pic a").unwrap()).next() {
-						let href = a.value().attr("href").unwrap_or("");
-						let title = row
-							.select(&::scraper::Selector::parse(".txt h3.tit a").unwrap())
-							.next()
-							.map(|t| t.text().collect::<Vec<_>>().join(" ").trim().to_string())
-							.unwrap_or_default();
-
-						let img = row.select(&::scraper::Selector::parse(".pic img").unwrap()).next();
-						let img_url = img.map(|i| get_image_url(&i)).unwrap_or_default();
-
-						items.push(exports::scraper::types::scraper::Item {
-							title,
-							url: absolute(href),
-							img_url: absolute(&img_url),
-						});
-					}
-				}
-
-				return items;
-			}
+		let res = scraper::types::http::post(&search_url, &body, Some(&headers()[..]))
+			.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", search_url), None))?;
+		if res.status != 200 {
+			return Err(status_error(&search_url, res.status));
 		}
 
-		Vec::new()
-	}
+		let html = ::scraper::Html::parse_document(&res.body);
+		let row_sel = ::scraper::Selector::parse("div.li-row").unwrap();
+		let mut items = Vec::new();
+
+		for row in html.select(&row_sel) {
+			println!("Row: {:?}", row.inner_html());
+			if let Some(a) = row.select(&::scraper::Selector::parse(".pic a").unwrap()).next() {
+				let href = a.value().attr("href").unwrap_or("");
+				let title = row
+					.select(&::scraper::Selector::parse(".txt h3.tit a").unwrap())
+					.next()
+					.map(|t| t.text().collect::<Vec<_>>().join(" ").trim().to_string())
+					.unwrap_or_default();
+
+				let img = row.select(&::scraper::Selector::parse(".pic img").unwrap()).next();
+				let img_url = img.map(|i| get_image_url(&i)).unwrap_or_default();
 
-	fn scrape(url: String) -> exports::scraper::types::scraper::Page {
-		let mut res = match scraper::types::http::get(&url, Some(&headers()[..])) {
-			Some(r) => r,
-			None => return default_page(url),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return default_page(url);
+				items.push(exports::scraper::types::scraper::Item {
+					title,
+					url: absolute(href),
+					img_url: absolute(&img_url),
+				});
 			}
 		}
 
-		if res.status != 200 {
-			return default_page(url);
-		}
+		Ok(items)
+	}
+
+	fn scrape(url: String) -> Result<exports::scraper::types::scraper::Page, ScraperError> {
+		let res = fetch(&url, &headers()[..])?;
 
 		let mut page = parse_page_from_html(&res.body, &url);
 
@@ -418,31 +402,18 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 						use std::collections::HashSet;
 						let mut seen = HashSet::new();
 						page.chapters = all_chapters.into_iter().filter(|c| seen.insert(c.url.clone())).collect();
-						return page;
+						return Ok(page);
 					}
 				}
 			}
 		}
 
-		page
+		Ok(page)
 	}
 
-	fn scrape_genres_list() -> Vec<exports::scraper::types::scraper::Genre> {
+	fn scrape_genres_list() -> Result<Vec<exports::scraper::types::scraper::Genre>, ScraperError> {
 		let url = "https://freewebnovel.com/home".to_string();
-		let mut res = match scraper::types::http::get(&url, Some(&headers()[..])) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+		let res = fetch(&url, &headers()[..])?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let mut genres: Vec<exports::scraper::types::scraper::Genre> = Vec::new();
@@ -457,7 +428,18 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		genres
+		Ok(genres)
+	}
+
+	fn scrape_genre(url: String, page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
+		let mut genre_url = Url::parse(&absolute(&url))
+			.map_err(|e| error(ErrorKind::Validation, format!("invalid genre url '{}': {}", url, e), None))?;
+		genre_url.query_pairs_mut().append_pair("p", &page.to_string());
+		fetch_novel_list(genre_url.as_str())
+	}
+
+	fn scrape_chapter_pages(url: String) -> Result<Vec<exports::scraper::types::scraper::ChapterPage>, ScraperError> {
+		Err(error(ErrorKind::NotFound, format!("novels have no pages: {}", url), None))
 	}
 
 	fn get_info() -> exports::scraper::types::scraper::ScraperInfo {
@@ -470,8 +452,17 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			referer_url: Some("https://freewebnovel.com/home".to_string()),
 			base_url: Some("https://freewebnovel.com".to_string()),
 			legacy_urls: None,
+			search_filters: None,
+			transforms_pages: false,
+			rate_limit: None,
 		}
 	}
+
+	fn transform_page(
+		_page: exports::scraper::types::scraper::PageImage,
+	) -> Result<Option<exports::scraper::types::scraper::ProcessedImage>, ScraperError> {
+		Ok(None)
+	}
 }
 
 #[cfg(test)]
@@ -1,10 +1,12 @@
 wit_bindgen::generate!({
-	path: "scraper.wit"
+	path: "scraper.wit",
+	world: "testable",
 });
 
 use std::collections::HashMap;
 
 use exports::scraper::types::scraper::*;
+use exports::scraper::types::tests::{SearchCall, TestCall, TestCase};
 
 use crate::scraper::types::*;
 
@@ -30,22 +32,28 @@ fn get_image_url(element: &::scraper::ElementRef) -> String {
 	String::new()
 }
 
-fn default_page() -> Page {
-	Page {
-		title: String::new(),
-		img_url: String::new(),
-		alternative_names: Vec::new(),
-		authors: Vec::new(),
-		artists: None,
-		status: String::new(),
-		page_type: None,
-		release_date: None,
-		description: String::new(),
-		genres: Vec::new(),
-		chapters: Vec::new(),
-		url: String::new(),
-		content_html: None,
+scraper_types::wit_error_helpers!();
+
+/// GETs `url`, going through FlareSolverr when Cloudflare blocks the request.
+fn fetch(url: &str) -> Result<http::Response, ScraperError> {
+	let mut response =
+		http::get(url, None).ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", url), None))?;
+
+	if http::has_cloudflare_protection(&response.body, Some(response.status), Some(&response.headers)) {
+		response = flare_solverr::get(url, None).ok_or_else(|| {
+			error(
+				ErrorKind::Cloudflare,
+				format!("failed to bypass Cloudflare for {}", url),
+				Some(response.status),
+			)
+		})?;
 	}
+
+	if response.status != 200 {
+		return Err(status_error(url, response.status));
+	}
+
+	Ok(response)
 }
 
 #[derive(Default)]
@@ -424,28 +432,8 @@ pub fn parse_page_from_html(html_str: &str, url: &str) -> Page {
 	}
 }
 
-fn scrape_manga_page(url: &str) -> Page {
-	let mut response = match http::get(url, None) {
-		Some(res) => res,
-		None => {
-			println!("Error: Failed to get manga list");
-			return default_page();
-		}
-	};
-
-	if http::has_cloudflare_protection(&response.body, Some(response.status), Some(&response.headers)) {
-		if let Some(new_response) = flare_solverr::get(url, None) {
-			response = new_response;
-		} else {
-			println!("Error: Failed to bypass Cloudflare");
-			return default_page();
-		}
-	}
-
-	if response.status != 200 {
-		println!("Error: Non-200 status for manga page");
-		return default_page();
-	}
+fn scrape_manga_page(url: &str) -> Result<Page, ScraperError> {
+	let response = fetch(url)?;
 
 	let mut page = parse_page_from_html(&response.body, url);
 	let chapters_from_api = fetch_chapters_from_api(url);
@@ -453,7 +441,7 @@ fn scrape_manga_page(url: &str) -> Page {
 		page.chapters = chapters_from_api;
 	}
 
-	page
+	Ok(page)
 }
 
 fn parse_manga_list_from_html(html_str: &str) -> Vec<Item> {
@@ -537,86 +525,60 @@ struct ScraperImpl;
 export!(ScraperImpl);
 
 impl exports::scraper::types::scraper::Guest for ScraperImpl {
-	fn scrape_chapter(url: String) -> Vec<String> {
-		let mut response = match http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if http::has_cloudflare_protection(&response.body, Some(response.status), Some(&response.headers)) {
-			if let Some(new_response) = flare_solverr::get(&url, None) {
-				response = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
+	fn scrape_chapter(url: String) -> Result<Vec<String>, ScraperError> {
+		Ok(Self::scrape_chapter_pages(url)?.into_iter().map(|page| page.url).collect())
+	}
 
-		if response.status != 200 {
-			println!("Error: Non-200 status for chapter page");
-			return Vec::new();
-		}
+	fn scrape_chapter_pages(url: String) -> Result<Vec<ChapterPage>, ScraperError> {
+		let response = fetch(&url)?;
 
 		let html = ::scraper::Html::parse_document(&response.body);
-		let img_selector = match ::scraper::Selector::parse("img.wp-manga-chapter-img") {
-			Ok(sel) => sel,
-			Err(_) => return Vec::new(),
-		};
-
-		html.select(&img_selector)
-			.map(|img| get_image_url(&img).trim().to_string())
-			.collect()
+		let img_selector = ::scraper::Selector::parse("img.wp-manga-chapter-img")
+			.map_err(|e| error(ErrorKind::Internal, e.to_string(), None))?;
+
+		Ok(html
+			.select(&img_selector)
+			.map(|img| ChapterPage {
+				url: get_image_url(&img).trim().to_string(),
+				headers: Vec::new(),
+				referer: None,
+				width: img.value().attr("width").and_then(|w| w.trim().parse().ok()),
+				height: img.value().attr("height").and_then(|h| h.trim().parse().ok()),
+				mirrors: Vec::new(),
+			})
+			.collect())
 	}
 
-	fn scrape_latest(page: u32) -> Vec<Item> {
+	fn scrape_latest(page: u32) -> Result<Vec<Item>, ScraperError> {
 		let url = format!("https://www.harimanga.co.uk/home/page/{page}?orderby=latest&post_type=wp-manga");
 		scrape_manga_list(&url)
 	}
 
-	fn scrape_trending(page: u32) -> Vec<Item> {
+	fn scrape_trending(page: u32) -> Result<Vec<Item>, ScraperError> {
 		let url = format!("https://www.harimanga.co.uk/home/page/{page}?orderby=trending&post_type=wp-manga");
 		scrape_manga_list(&url)
 	}
 
-	fn scrape_search(query: String, page: u32) -> Vec<Item> {
+	fn scrape_search(query: String, page: u32, _filters: SearchFilters) -> Result<Vec<Item>, ScraperError> {
 		let url = format!(
 			"https://www.harimanga.co.uk/home/page/{page}?adult=&artist=&author=&op=&post_type=wp-manga&release=&s={query}"
 		);
 		scrape_manga_list(&url)
 	}
 
-	fn scrape(url: String) -> Page {
+	fn scrape(url: String) -> Result<Page, ScraperError> {
 		scrape_manga_page(&url)
 	}
 
-	fn scrape_genres_list() -> Vec<Genre> {
+	fn scrape_genres_list() -> Result<Vec<Genre>, ScraperError> {
 		let url = "https://harimanga.co.uk/home";
-		let mut response = match http::get(url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if http::has_cloudflare_protection(&response.body, Some(response.status), Some(&response.headers)) {
-			if let Some(new_response) = flare_solverr::get(url, None) {
-				response = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
+		let response = fetch(url)?;
 
-		if response.status != 200 {
-			println!("Error: Non-200 status for genres page");
-			return Vec::new();
-		}
+		Ok(parse_genres_from_html(&response.body))
+	}
 
-		parse_genres_from_html(&response.body)
+	fn scrape_genre(url: String, page: u32) -> Result<Vec<Item>, ScraperError> {
+		scrape_manga_list(&format!("{}/page/{}/", url.trim_end_matches('/'), page))
 	}
 
 	fn get_info() -> ScraperInfo {
@@ -632,34 +594,57 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 				"https://harimanga.com/".to_string(),
 				"https://harimanga.me/".to_string(),
 			]),
+			search_filters: None,
+			transforms_pages: false,
+			rate_limit: None,
 		}
 	}
-}
 
-fn scrape_manga_list(url: &str) -> Vec<Item> {
-	let mut response = match http::get(url, None) {
-		Some(res) => res,
-		None => {
-			println!("Error: Failed to get manga list");
-			return Vec::new();
-		}
-	};
-
-	if http::has_cloudflare_protection(&response.body, Some(response.status), Some(&response.headers)) {
-		if let Some(new_response) = flare_solverr::get(url, None) {
-			response = new_response;
-		} else {
-			println!("Error: Failed to bypass Cloudflare");
-			return Vec::new();
-		}
+	fn transform_page(
+		_page: exports::scraper::types::scraper::PageImage,
+	) -> Result<Option<exports::scraper::types::scraper::ProcessedImage>, ScraperError> {
+		Ok(None)
 	}
+}
 
-	if response.status != 200 {
-		println!("Error: Non-200 status for manga list");
-		return Vec::new();
+impl exports::scraper::types::tests::Guest for ScraperImpl {
+	fn list_tests() -> Vec<TestCase> {
+		vec![
+			TestCase {
+				name: "latest".to_string(),
+				call: TestCall::Latest(1),
+				min_results: 1,
+				expect_title: None,
+			},
+			TestCase {
+				name: "search".to_string(),
+				call: TestCall::Search(SearchCall {
+					query: "solo leveling".to_string(),
+					page: 1,
+				}),
+				min_results: 1,
+				expect_title: None,
+			},
+			TestCase {
+				name: "scrape".to_string(),
+				call: TestCall::Scrape("https://www.harimanga.co.uk/manga/solo-leveling".to_string()),
+				min_results: 1,
+				expect_title: Some("Solo Leveling".to_string()),
+			},
+			TestCase {
+				name: "genres".to_string(),
+				call: TestCall::Genres,
+				min_results: 1,
+				expect_title: None,
+			},
+		]
 	}
+}
+
+fn scrape_manga_list(url: &str) -> Result<Vec<Item>, ScraperError> {
+	let response = fetch(url)?;
 
-	parse_manga_list_from_html(&response.body)
+	Ok(parse_manga_list_from_html(&response.body))
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
 wit_bindgen::generate!({
-	path: "scraper.wit"
+	path: "scraper.wit",
+	world: "testable",
 });
 
 use std::collections::{HashMap, VecDeque};
@@ -8,6 +9,7 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime};
 
 use exports::scraper::types::scraper::*;
+use exports::scraper::types::tests::{SearchCall, TestCall, TestCase};
 use once_cell::sync::Lazy;
 use serde_json::Value;
 
@@ -148,7 +150,7 @@ impl RateLimiter {
 static RATE_LIMITER: Lazy<Arc<Mutex<RateLimiter>>> = Lazy::new(|| Arc::new(Mutex::new(RateLimiter::new())));
 
 #[cfg(not(test))]
-fn http_get(url: &str) -> Option<http::Response> {
+fn http_get(url: &str) -> Result<http::Response, ScraperError> {
 	let method = "GET";
 	let path = url.split("api.mangadex.org").nth(1).unwrap_or("");
 
@@ -160,16 +162,13 @@ fn http_get(url: &str) -> Option<http::Response> {
 		value: format!("Manga Vault MangaDex/{}", env!("CARGO_PKG_VERSION")),
 	};
 
-	let response = http::get(url, Some(&[header]));
-	if response.is_none() {
-		return None;
-	}
-	let response = response.unwrap();
+	let response = http::get(url, Some(&[header]))
+		.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", url), None))?;
 
 	match response.status {
 		200 => {
 			limiter.update_from_headers(method, path, &response.headers);
-			Some(response)
+			Ok(response)
 		}
 		429 => {
 			let retry_after = response
@@ -180,14 +179,14 @@ fn http_get(url: &str) -> Option<http::Response> {
 				.unwrap_or(5);
 
 			limiter.handle_rate_limit_exceeded(method, path, retry_after);
-			None
+			Err(status_error(url, response.status))
 		}
-		_ => None,
+		status => Err(status_error(url, status)),
 	}
 }
 
 #[cfg(test)]
-fn http_get(url: &str) -> Option<http::Response> {
+fn http_get(url: &str) -> Result<http::Response, ScraperError> {
 	let method = "GET";
 	let path = url.split("api.mangadex.org").nth(1).unwrap_or("");
 
@@ -199,10 +198,10 @@ fn http_get(url: &str) -> Option<http::Response> {
 		"User-Agent",
 		format!("Manga Vault Testing Suite for MangaDex/{}", env!("CARGO_PKG_VERSION")),
 	);
-	let response = request.send().ok()?;
+	let response = request.send().map_err(|e| error(ErrorKind::Network, e.to_string(), None))?;
 	let headers = response.headers().clone();
 	let status = response.status().as_u16();
-	let body = response.text().ok()?;
+	let body = response.text().map_err(|e| error(ErrorKind::Network, e.to_string(), None))?;
 
 	let response = http::Response {
 		status: status,
@@ -219,7 +218,7 @@ fn http_get(url: &str) -> Option<http::Response> {
 	match response.status {
 		200 => {
 			limiter.update_from_headers(method, path, &response.headers);
-			Some(response)
+			Ok(response)
 		}
 		429 => {
 			let retry_after = response
@@ -230,64 +229,82 @@ fn http_get(url: &str) -> Option<http::Response> {
 				.unwrap_or(5);
 
 			limiter.handle_rate_limit_exceeded(method, path, retry_after);
-			None
+			Err(status_error(url, response.status))
 		}
-		_ => None,
+		status => Err(status_error(url, status)),
 	}
 }
 
-fn parse_json_response(response: &http::Response) -> Option<Value> {
-	if response.status != 200 {
-		return None;
-	}
-	serde_json::from_str(&response.body).ok()
+fn parse_json_response(response: &http::Response) -> Result<Value, ScraperError> {
+	serde_json::from_str(&response.body).map_err(|e| error(ErrorKind::Parse, format!("invalid JSON response: {}", e), None))
+}
+
+fn missing(field: &str) -> ScraperError {
+	error(ErrorKind::Parse, format!("missing '{}' in MangaDex response", field), None)
 }
 
+scraper_types::wit_error_helpers!();
+
 struct ScraperImpl;
 
 export!(ScraperImpl);
 
 impl exports::scraper::types::scraper::Guest for ScraperImpl {
-	fn scrape_chapter(url: String) -> Vec<String> {
+	fn scrape_chapter(url: String) -> Result<Vec<String>, ScraperError> {
+		Ok(Self::scrape_chapter_pages(url)?.into_iter().map(|page| page.url).collect())
+	}
+
+	fn scrape_chapter_pages(url: String) -> Result<Vec<ChapterPage>, ScraperError> {
 		let chapter_id = url.split('/').last().unwrap_or("");
 		if chapter_id.is_empty() {
-			return Vec::new();
+			return Err(error(ErrorKind::Validation, format!("no chapter id in {}", url), None));
 		}
 
 		let api_url = format!("https://api.mangadex.org/at-home/server/{}?forcePort443=false", chapter_id);
 
-		let response = match http_get(&api_url) {
-			Some(res) => res,
-			None => return Vec::new(),
-		};
-
-		let json = match parse_json_response(&response) {
-			Some(data) => data,
-			None => return Vec::new(),
-		};
-
-		let chapter_data = match json.get("chapter") {
-			Some(cd) => cd,
-			None => return Vec::new(),
-		};
-		let hash = match chapter_data.get("hash").and_then(|h| h.as_str()) {
-			Some(h) => h,
-			None => return Vec::new(),
-		};
-		let data = match chapter_data.get("data").and_then(|d| d.as_array()) {
-			Some(d) => d,
-			None => return Vec::new(),
-		};
-
-		data.iter()
-			.filter_map(|page| {
-				page.as_str()
-					.map(|p| format!("https://uploads.mangadex.org/data/{}/{}", hash, p))
+		let response = http_get(&api_url)?;
+		let json = parse_json_response(&response)?;
+
+		let chapter_data = json.get("chapter").ok_or_else(|| missing("chapter"))?;
+		let hash = chapter_data
+			.get("hash")
+			.and_then(|h| h.as_str())
+			.ok_or_else(|| missing("chapter.hash"))?;
+		let data = chapter_data
+			.get("data")
+			.and_then(|d| d.as_array())
+			.ok_or_else(|| missing("chapter.data"))?;
+
+		// Compressed copies of the same pages, used when the full-quality image cannot be fetched.
+		let data_saver = chapter_data
+			.get("dataSaver")
+			.and_then(|d| d.as_array())
+			.map(Vec::as_slice)
+			.unwrap_or_default();
+
+		Ok(data
+			.iter()
+			.enumerate()
+			.filter_map(|(index, page)| {
+				let mirrors = data_saver
+					.get(index)
+					.and_then(|p| p.as_str())
+					.map(|p| vec![format!("https://uploads.mangadex.org/data-saver/{}/{}", hash, p)])
+					.unwrap_or_default();
+
+				page.as_str().map(|p| ChapterPage {
+					url: format!("https://uploads.mangadex.org/data/{}/{}", hash, p),
+					headers: Vec::new(),
+					referer: None,
+					width: None,
+					height: None,
+					mirrors,
+				})
 			})
-			.collect()
+			.collect())
 	}
 
-	fn scrape_latest(page: u32) -> Vec<Item> {
+	fn scrape_latest(page: u32) -> Result<Vec<Item>, ScraperError> {
 		let offset = (page - 1) * 10;
 		let url = format!(
 			"https://api.mangadex.org/manga?limit=10&offset={}&status%5B%5D=ongoing&status%5B%5D=completed&status%5B%5D=hiatus&status%5B%5D=cancelled&order%5BlatestUploadedChapter%5D=desc&includes%5B%5D=cover_art",
@@ -296,7 +313,7 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 		fetch_manga_items(&url)
 	}
 
-	fn scrape_trending(page: u32) -> Vec<Item> {
+	fn scrape_trending(page: u32) -> Result<Vec<Item>, ScraperError> {
 		let offset = (page - 1) * 10;
 		let url = format!(
 			"https://api.mangadex.org/manga?limit=10&offset={}&status%5B%5D=ongoing&status%5B%5D=completed&status%5B%5D=hiatus&status%5B%5D=cancelled&order%5BfollowedCount%5D=desc&includes%5B%5D=cover_art",
@@ -305,20 +322,22 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 		fetch_manga_items(&url)
 	}
 
-	fn scrape_search(query: String, page: u32) -> Vec<Item> {
+	fn scrape_search(query: String, page: u32, filters: SearchFilters) -> Result<Vec<Item>, ScraperError> {
 		let offset = (page - 1) * 10;
 		let encoded_query = query.split_whitespace().collect::<Vec<_>>().join("%20");
 		let url = format!(
-			"https://api.mangadex.org/manga?limit=10&offset={}&title={}&includes%5B%5D=cover_art",
-			offset, encoded_query
+			"https://api.mangadex.org/manga?limit=10&offset={}&title={}&includes%5B%5D=cover_art{}",
+			offset,
+			encoded_query,
+			search_filter_params(&filters)
 		);
 		fetch_manga_items(&url)
 	}
 
-	fn scrape(url: String) -> Page {
+	fn scrape(url: String) -> Result<Page, ScraperError> {
 		let manga_id = url.split('/').last().unwrap_or("");
 		if manga_id.is_empty() {
-			return default_page();
+			return Err(error(ErrorKind::Validation, format!("no manga id in {}", url), None));
 		}
 
 		let manga_url = format!(
@@ -326,22 +345,11 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			manga_id
 		);
 
-		let response = match http_get(&manga_url) {
-			Some(res) => res,
-			None => return default_page(),
-		};
-
-		let json = match parse_json_response(&response) {
-			Some(data) => data,
-			None => return default_page(),
-		};
-
-		let data = match json.get("data") {
-			Some(d) => d,
-			None => return default_page(),
-		};
+		let response = http_get(&manga_url)?;
+		let json = parse_json_response(&response)?;
 
-		let attributes = data.get("attributes").unwrap();
+		let data = json.get("data").ok_or_else(|| missing("data"))?;
+		let attributes = data.get("attributes").ok_or_else(|| missing("data.attributes"))?;
 		let title = attributes["title"]
 			.as_object()
 			.and_then(|titles| titles.values().next())
@@ -410,9 +418,9 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		let chapters = fetch_chapters(manga_id);
+		let chapters = fetch_chapters(manga_id)?;
 
-		Page {
+		Ok(Page {
 			title,
 			url: url.clone(),
 			img_url,
@@ -426,12 +434,16 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			genres,
 			chapters,
 			content_html: None,
-		}
+		})
 	}
 
-	fn scrape_genres_list() -> Vec<Genre> {
+	fn scrape_genres_list() -> Result<Vec<Genre>, ScraperError> {
 		// MangaDex doesn't have a direct genre list endpoint
-		Vec::new()
+		Ok(Vec::new())
+	}
+
+	fn scrape_genre(url: String, _page: u32) -> Result<Vec<Item>, ScraperError> {
+		Err(error(ErrorKind::NotFound, format!("no genre pages to browse: {}", url), None))
 	}
 
 	fn get_info() -> ScraperInfo {
@@ -444,27 +456,116 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			referer_url: None,
 			base_url: None,
 			legacy_urls: None,
+			search_filters: Some(SearchFilterSpec {
+				genres: Vec::new(),
+				exclude_genres: false,
+				statuses: vec![
+					PageStatus::Ongoing,
+					PageStatus::Completed,
+					PageStatus::Hiatus,
+					PageStatus::Cancelled,
+				],
+				types: Vec::new(),
+				sort_orders: vec![
+					SortOrder::Relevance,
+					SortOrder::Latest,
+					SortOrder::Popular,
+					SortOrder::Alphabetical,
+					SortOrder::Rating,
+				],
+				year: true,
+			}),
+			transforms_pages: false,
+			rate_limit: None,
 		}
 	}
+
+	fn transform_page(
+		_page: exports::scraper::types::scraper::PageImage,
+	) -> Result<Option<exports::scraper::types::scraper::ProcessedImage>, ScraperError> {
+		Ok(None)
+	}
 }
 
-fn fetch_manga_items(url: &str) -> Vec<Item> {
-	let response = match http_get(url) {
-		Some(res) => res,
-		None => return Vec::new(),
-	};
+impl exports::scraper::types::tests::Guest for ScraperImpl {
+	fn list_tests() -> Vec<TestCase> {
+		vec![
+			TestCase {
+				name: "latest".to_string(),
+				call: TestCall::Latest(1),
+				min_results: 1,
+				expect_title: None,
+			},
+			TestCase {
+				name: "trending".to_string(),
+				call: TestCall::Trending(1),
+				min_results: 1,
+				expect_title: None,
+			},
+			TestCase {
+				name: "search".to_string(),
+				call: TestCall::Search(SearchCall {
+					query: "Hatsukoi".to_string(),
+					page: 1,
+				}),
+				min_results: 1,
+				expect_title: None,
+			},
+			TestCase {
+				name: "scrape".to_string(),
+				call: TestCall::Scrape("https://mangadex.org/title/aa070232-a668-4c73-8305-a68825db32e4".to_string()),
+				min_results: 1,
+				expect_title: Some("Hatsukoi wa Marude Yaiba no You ni".to_string()),
+			},
+			TestCase {
+				name: "chapter".to_string(),
+				call: TestCall::Chapter("https://mangadex.org/chapter/2b6a4f47-f7d7-4a3e-91a6-73d9bd21f8e9".to_string()),
+				min_results: 1,
+				expect_title: None,
+			},
+		]
+	}
+}
 
-	let json = match parse_json_response(&response) {
-		Some(data) => data,
-		None => return Vec::new(),
-	};
+fn search_filter_params(filters: &SearchFilters) -> String {
+	let mut params = String::new();
 
-	let data = match json.get("data").and_then(|d| d.as_array()) {
-		Some(d) => d,
-		None => return Vec::new(),
-	};
+	if let Some(status) = filters.status {
+		let status = match status {
+			PageStatus::Ongoing => "ongoing",
+			PageStatus::Completed => "completed",
+			PageStatus::Hiatus => "hiatus",
+			PageStatus::Cancelled => "cancelled",
+		};
+		params.push_str(&format!("&status%5B%5D={}", status));
+	}
+
+	if let Some(sort) = filters.sort {
+		let order = match sort {
+			SortOrder::Relevance => "relevance%5D=desc",
+			SortOrder::Latest => "latestUploadedChapter%5D=desc",
+			SortOrder::Popular => "followedCount%5D=desc",
+			SortOrder::Alphabetical => "title%5D=asc",
+			SortOrder::Rating => "rating%5D=desc",
+		};
+		params.push_str(&format!("&order%5B{}", order));
+	}
+
+	if let Some(year) = filters.year {
+		params.push_str(&format!("&year={}", year));
+	}
+
+	params
+}
+
+fn fetch_manga_items(url: &str) -> Result<Vec<Item>, ScraperError> {
+	let response = http_get(url)?;
+	let json = parse_json_response(&response)?;
 
-	data.iter()
+	let data = json.get("data").and_then(|d| d.as_array()).ok_or_else(|| missing("data"))?;
+
+	Ok(data
+		.iter()
 		.filter_map(|item| {
 			let manga_id = item["id"].as_str()?;
 			let title = item["attributes"]["title"].as_object()?.values().next()?.as_str()?;
@@ -482,24 +583,17 @@ fn fetch_manga_items(url: &str) -> Vec<Item> {
 				img_url: cover_url,
 			})
 		})
-		.collect()
+		.collect())
 }
 
-fn fetch_chapters(manga_id: &str) -> Vec<Chapter> {
+fn fetch_chapters(manga_id: &str) -> Result<Vec<Chapter>, ScraperError> {
 	let url = format!(
 		"https://api.mangadex.org/manga/{}/feed?limit=500&translatedLanguage[]=en&order[chapter]=desc&includes[]=scanlation_group",
 		manga_id
 	);
 
-	let response = match http_get(&url) {
-		Some(res) => res,
-		None => return Vec::new(),
-	};
-
-	let json = match parse_json_response(&response) {
-		Some(data) => data,
-		None => return Vec::new(),
-	};
+	let response = http_get(&url)?;
+	let json = parse_json_response(&response)?;
 
 	let group_map: HashMap<String, String> = match json.get("included").and_then(|i| i.as_array()) {
 		Some(included) => included
@@ -517,10 +611,7 @@ fn fetch_chapters(manga_id: &str) -> Vec<Chapter> {
 		None => HashMap::new(),
 	};
 
-	let data = match json.get("data").and_then(|d| d.as_array()) {
-		Some(d) => d,
-		None => return Vec::new(),
-	};
+	let data = json.get("data").and_then(|d| d.as_array()).ok_or_else(|| missing("data"))?;
 
 	let mut chapters = data
 		.iter()
@@ -560,25 +651,7 @@ fn fetch_chapters(manga_id: &str) -> Vec<Chapter> {
 
 	chapters.reverse();
 
-	chapters
-}
-
-fn default_page() -> Page {
-	Page {
-		title: String::new(),
-		url: String::new(),
-		img_url: String::new(),
-		alternative_names: Vec::new(),
-		authors: Vec::new(),
-		artists: None,
-		status: String::new(),
-		page_type: None,
-		release_date: None,
-		description: String::new(),
-		genres: Vec::new(),
-		chapters: Vec::new(),
-		content_html: None,
-	}
+	Ok(chapters)
 }
 
 #[cfg(test)]
@@ -588,13 +661,14 @@ mod tests {
 
 	#[test]
 	fn test_scrape_latest() {
-		let items = ScraperImpl::scrape_latest(1);
+		let items = ScraperImpl::scrape_latest(1).expect("scrape_latest failed");
 		assert!(!items.is_empty());
 	}
 
 	#[test]
 	fn test_scrape_manga() {
-		let manga_page = ScraperImpl::scrape("https://mangadex.org/title/aa070232-a668-4c73-8305-a68825db32e4".to_string());
+		let manga_page = ScraperImpl::scrape("https://mangadex.org/title/aa070232-a668-4c73-8305-a68825db32e4".to_string())
+			.expect("scrape failed");
 		assert_eq!(manga_page.title, "Hatsukoi wa Marude Yaiba no You ni");
 		assert_eq!(
 			manga_page.url,
@@ -609,21 +683,40 @@ mod tests {
 	#[test]
 	fn test_scrape_chapter() {
 		let images =
-			ScraperImpl::scrape_chapter("https://mangadex.org/chapter/2b6a4f47-f7d7-4a3e-91a6-73d9bd21f8e9".to_string());
+			ScraperImpl::scrape_chapter("https://mangadex.org/chapter/2b6a4f47-f7d7-4a3e-91a6-73d9bd21f8e9".to_string())
+				.expect("scrape_chapter failed");
 		assert!(!images.is_empty());
 		assert!(images[0].starts_with("https://uploads.mangadex.org"));
 	}
 
+	#[test]
+	fn test_scrape_chapter_pages() {
+		let pages = ScraperImpl::scrape_chapter_pages(
+			"https://mangadex.org/chapter/2b6a4f47-f7d7-4a3e-91a6-73d9bd21f8e9".to_string(),
+		)
+		.expect("scrape_chapter_pages failed");
+		assert!(!pages.is_empty());
+		assert!(pages[0].mirrors.iter().all(|m| m.contains("/data-saver/")));
+	}
+
 	#[test]
 	fn test_scrape_search() {
-		let items = ScraperImpl::scrape_search("Hatsukoi".to_string(), 1);
+		let filters = SearchFilters {
+			include_genres: Vec::new(),
+			exclude_genres: Vec::new(),
+			status: None,
+			page_type: None,
+			sort: None,
+			year: None,
+		};
+		let items = ScraperImpl::scrape_search("Hatsukoi".to_string(), 1, filters).expect("scrape_search failed");
 		assert!(!items.is_empty());
 		assert!(items.iter().any(|item| item.title.contains("Hatsukoi")));
 	}
 
 	#[test]
 	fn test_scrape_trending() {
-		let items = ScraperImpl::scrape_trending(1);
+		let items = ScraperImpl::scrape_trending(1).expect("scrape_trending failed");
 		assert!(!items.is_empty());
 	}
 
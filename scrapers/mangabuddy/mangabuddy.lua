@@ -737,6 +737,11 @@ function Scrape_genres_list()
 	return genres
 end
 
+function Scrape_genre(url, page)
+	local separator = string.find(url, "?", 1, true) and "&" or "?"
+	return Scrape_manga_list(normalize_url(url) .. separator .. "page=" .. tostring(page))
+end
+
 function Get_info()
 	return {
 		id = "mangabuddy",
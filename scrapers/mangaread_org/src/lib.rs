@@ -1,9 +1,93 @@
 use std::collections::HashMap;
 
+use exports::scraper::types::scraper::{ErrorKind, PageStatus, ScraperError, SearchFilterSpec, SearchFilters, SortOrder};
+
 wit_bindgen::generate!({
-	path: "scraper.wit"
+	path: "scraper.wit",
+	world: "root",
 });
 
+scraper_types::wit_error_helpers!();
+
+/// GETs `url`, going through FlareSolverr when Cloudflare blocks the request.
+fn fetch(url: &str) -> Result<scraper::types::http::Response, ScraperError> {
+	let mut res = scraper::types::http::get(url, None)
+		.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", url), None))?;
+
+	if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
+		res = scraper::types::flare_solverr::get(url, None).ok_or_else(|| {
+			error(
+				ErrorKind::Cloudflare,
+				format!("{} is behind Cloudflare", url),
+				Some(res.status),
+			)
+		})?;
+	}
+
+	if res.status != 200 {
+		return Err(status_error(url, res.status));
+	}
+
+	Ok(res)
+}
+
+/// Genres the site's search form offers. Their slugs are the lowercased names with dashes for spaces.
+const SEARCH_GENRES: [&str; 21] = [
+	"Action",
+	"Adventure",
+	"Comedy",
+	"Drama",
+	"Fantasy",
+	"Harem",
+	"Historical",
+	"Horror",
+	"Isekai",
+	"Martial Arts",
+	"Mystery",
+	"Romance",
+	"School Life",
+	"Sci-fi",
+	"Seinen",
+	"Shoujo",
+	"Shounen",
+	"Slice of Life",
+	"Sports",
+	"Supernatural",
+	"Tragedy",
+];
+
+/// Query parameters of the site's advanced search, except `release` which has its own slot in the URL.
+fn search_filter_params(filters: &SearchFilters) -> String {
+	let mut params = String::new();
+
+	for genre in &filters.include_genres {
+		params.push_str(&format!("&genre%5B%5D={}", genre.to_lowercase().replace(' ', "-")));
+	}
+
+	if let Some(status) = filters.status {
+		let status = match status {
+			PageStatus::Ongoing => "on-going",
+			PageStatus::Completed => "end",
+			PageStatus::Hiatus => "on-hold",
+			PageStatus::Cancelled => "canceled",
+		};
+		params.push_str(&format!("&status%5B%5D={}", status));
+	}
+
+	if let Some(sort) = filters.sort {
+		let order = match sort {
+			SortOrder::Relevance => "",
+			SortOrder::Latest => "latest",
+			SortOrder::Popular => "views",
+			SortOrder::Alphabetical => "alphabet",
+			SortOrder::Rating => "rating",
+		};
+		params.push_str(&format!("&m_orderby={}", order));
+	}
+
+	params
+}
+
 fn get_image_url(&element: &::scraper::ElementRef) -> String {
 	let attrs = element.value().attrs().collect::<HashMap<&str, &str>>();
 
@@ -25,68 +109,38 @@ struct ScraperImpl;
 export!(ScraperImpl);
 
 impl exports::scraper::types::scraper::Guest for ScraperImpl {
-	fn scrape_chapter(url: String) -> Vec<String> {
-		let mut response = match scraper::types::http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&response.body, Some(response.status), Some(&response.headers)) {
-			if let Some(new_response) = scraper::types::flare_solverr::get(&url, None) {
-				response = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
+	fn scrape_chapter(url: String) -> Result<Vec<String>, ScraperError> {
+		Ok(Self::scrape_chapter_pages(url)?.into_iter().map(|page| page.url).collect())
+	}
 
-		if response.status != 200 {
-			println!("Error while getting response on scrape_chapter: {}", response.status);
-			return Vec::new();
-		}
+	fn scrape_chapter_pages(url: String) -> Result<Vec<exports::scraper::types::scraper::ChapterPage>, ScraperError> {
+		let response = fetch(&url)?;
 
 		let html = ::scraper::Html::parse_document(&response.body);
 		let img_selector = ::scraper::Selector::parse("img.wp-manga-chapter-img").unwrap();
-		let mut imgs: Vec<String> = Vec::new();
+		let mut pages = Vec::new();
 
 		for img in html.select(&img_selector) {
-			let img_url = get_image_url(&img).trim().to_string();
-			imgs.push(img_url);
+			pages.push(exports::scraper::types::scraper::ChapterPage {
+				url: get_image_url(&img).trim().to_string(),
+				headers: Vec::new(),
+				referer: None,
+				width: img.value().attr("width").and_then(|w| w.trim().parse().ok()),
+				height: img.value().attr("height").and_then(|h| h.trim().parse().ok()),
+				mirrors: Vec::new(),
+			});
 		}
 
-		imgs
+		Ok(pages)
 	}
 
-	fn scrape_latest(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_latest(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!(
 			"https://www.mangaread.org/?s&post_type=wp-manga&m_orderby=latest&paged={}",
 			page
 		);
 
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(new_response) = scraper::types::flare_solverr::get(&url, None) {
-				res = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			println!("Error while getting response on scrape_latest: {}", res.status);
-			return Vec::new();
-		}
+		let res = fetch(&url)?;
 		let body = res.body;
 
 		let html = ::scraper::Html::parse_document(&body);
@@ -130,33 +184,13 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		manga_items
+		Ok(manga_items)
 	}
 
-	fn scrape_trending(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_trending(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://www.mangaread.org/?s=&post_type=wp-manga&paged={}", page);
 
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(new_response) = scraper::types::flare_solverr::get(&url, None) {
-				res = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			println!("Error while getting response on scrape_trending: {}", res.status);
-			return Vec::new();
-		}
+		let res = fetch(&url)?;
 		let body = res.body;
 
 		let html = ::scraper::Html::parse_document(&body);
@@ -199,36 +233,23 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		manga_items
+		Ok(manga_items)
 	}
 
-	fn scrape_search(query: String, page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_search(
+		query: String,
+		page: u32,
+		filters: SearchFilters,
+	) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!(
-			"https://www.mangaread.org/?s={}&post_type=wp-manga&op=&author=&artist=&release=&adult=&paged={}",
-			query, page
+			"https://www.mangaread.org/?s={}&post_type=wp-manga&op=&author=&artist=&release={}&adult={}&paged={}",
+			query,
+			filters.year.map(|year| year.to_string()).unwrap_or_default(),
+			search_filter_params(&filters),
+			page
 		);
 
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(new_response) = scraper::types::flare_solverr::get(&url, None) {
-				res = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			println!("Error while getting response on scrape_search: {}", res.status);
-			return Vec::new();
-		}
+		let res = fetch(&url)?;
 		let body = res.body;
 
 		let html = ::scraper::Html::parse_document(&body);
@@ -271,45 +292,11 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		manga_items
+		Ok(manga_items)
 	}
 
-	fn scrape(url: String) -> exports::scraper::types::scraper::Page {
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return default_page(url);
-			}
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(new_response) = scraper::types::flare_solverr::get(&url, None) {
-				res = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return default_page(url);
-			}
-		}
-
-		if res.status != 200 {
-			println!("Error while getting response on scrape_manga: {}", res.status);
-			return exports::scraper::types::scraper::Page {
-				title: String::new(),
-				img_url: String::new(),
-				alternative_names: Vec::new(),
-				authors: Vec::new(),
-				artists: None,
-				status: String::new(),
-				page_type: None,
-				release_date: None,
-				description: String::new(),
-				genres: Vec::new(),
-				chapters: Vec::new(),
-				content_html: None,
-				url,
-			};
-		}
+	fn scrape(url: String) -> Result<exports::scraper::types::scraper::Page, ScraperError> {
+		let res = fetch(&url)?;
 		let body = res.body;
 
 		let html = ::scraper::Html::parse_document(&body);
@@ -488,7 +475,7 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			});
 		}
 
-		exports::scraper::types::scraper::Page {
+		Ok(exports::scraper::types::scraper::Page {
 			title,
 			img_url,
 			alternative_names,
@@ -502,32 +489,12 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			chapters: chapters.into_iter().rev().collect(),
 			content_html: None,
 			url: url.to_string(),
-		}
+		})
 	}
 
-	fn scrape_genres_list() -> Vec<exports::scraper::types::scraper::Genre> {
+	fn scrape_genres_list() -> Result<Vec<exports::scraper::types::scraper::Genre>, ScraperError> {
 		let url = "https://www.mangaread.org/";
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(res) => res,
-			None => {
-				println!("Error: Failed to get manga list");
-				return Vec::new();
-			}
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(new_response) = scraper::types::flare_solverr::get(&url, None) {
-				res = new_response;
-			} else {
-				println!("Error: Failed to bypass Cloudflare");
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			println!("Error while getting response on scrape_genres_list: {}", res.status);
-			return Vec::new();
-		}
+		let res = fetch(url)?;
 		let body = res.body;
 
 		let html = ::scraper::Html::parse_document(&body);
@@ -544,7 +511,25 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			})
 			.collect::<Vec<exports::scraper::types::scraper::Genre>>();
 
-		genres
+		Ok(genres)
+	}
+
+	/// Genre archives use a different layout than search results, so genres are browsed through the search form.
+	fn scrape_genre(url: String, page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
+		let slug = url.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+		if slug.is_empty() {
+			return Err(error(ErrorKind::Validation, format!("no genre in {}", url), None));
+		}
+
+		let filters = SearchFilters {
+			include_genres: vec![slug.to_string()],
+			exclude_genres: Vec::new(),
+			status: None,
+			page_type: None,
+			sort: None,
+			year: None,
+		};
+		Self::scrape_search(String::new(), page, filters)
 	}
 
 	fn get_info() -> exports::scraper::types::scraper::ScraperInfo {
@@ -557,24 +542,33 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			referer_url: Some("https://www.mangaread.org/".to_string()),
 			base_url: Some("https://www.mangaread.org/".to_string()),
 			legacy_urls: None,
+			search_filters: Some(SearchFilterSpec {
+				genres: SEARCH_GENRES.iter().map(|genre| genre.to_string()).collect(),
+				exclude_genres: false,
+				statuses: vec![
+					PageStatus::Ongoing,
+					PageStatus::Completed,
+					PageStatus::Hiatus,
+					PageStatus::Cancelled,
+				],
+				types: Vec::new(),
+				sort_orders: vec![
+					SortOrder::Relevance,
+					SortOrder::Latest,
+					SortOrder::Popular,
+					SortOrder::Alphabetical,
+					SortOrder::Rating,
+				],
+				year: true,
+			}),
+			transforms_pages: false,
+			rate_limit: None,
 		}
 	}
-}
 
-fn default_page(url: String) -> exports::scraper::types::scraper::Page {
-	return exports::scraper::types::scraper::Page {
-		title: String::new(),
-		img_url: String::new(),
-		alternative_names: Vec::new(),
-		authors: Vec::new(),
-		artists: None,
-		status: String::new(),
-		page_type: None,
-		release_date: None,
-		description: String::new(),
-		genres: Vec::new(),
-		chapters: Vec::new(),
-		content_html: None,
-		url,
-	};
+	fn transform_page(
+		_page: exports::scraper::types::scraper::PageImage,
+	) -> Result<Option<exports::scraper::types::scraper::ProcessedImage>, ScraperError> {
+		Ok(None)
+	}
 }
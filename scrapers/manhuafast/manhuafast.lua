@@ -412,6 +412,12 @@ function Scrape_genres_list()
 	return out
 end
 
+-- Genre urls point at the search form, which pages like the other listings.
+function Scrape_genre(url, page)
+	local paged = string.gsub(url, "^https://manhuafast%.com/", "https://manhuafast.com/page/" .. tostring(page) .. "/", 1)
+	return scrape_manga_list(paged)
+end
+
 function Get_info()
 	return {
 		id = "manhuafast",
@@ -112,6 +112,35 @@ function Scrape_chapter(url)
 	return imgs
 end
 
+local function scrape_manga_list(url)
+	local resp = http_get(url, { referer = BASE_URL .. "/" })
+	local html = resp.text
+
+	local manga_divs =
+		scraping:select_elements(html, "div.truyen-list .list-truyen-item-wrap, div.comic-list .list-comic-item-wrap")
+	local manga_items = {}
+
+	for _, manga_div_html in ipairs(manga_divs) do
+		local img_elements = scraping:select_elements(manga_div_html, "a img")
+		local img_url = normalize_url(scraping:get_image_url(img_elements[1]) or "")
+
+		local title_elements = scraping:select_elements(manga_div_html, "h3 a")
+		local title = scraping:get_text(title_elements[1]) or ""
+
+		local url_elements = scraping:select_elements(manga_div_html, "h3 a")
+		local manga_url = normalize_url(scraping:get_url(url_elements[1]) or "")
+
+		local manga_item = {
+			title = title,
+			img_url = img_url,
+			url = manga_url,
+		}
+		table.insert(manga_items, manga_item)
+	end
+
+	return manga_items
+end
+
 function Scrape_latest(page)
 	local url = "https://www.natomanga.com/manga-list/latest-manga?page=" .. tostring(page)
 	local resp = http_get(url, { referer = BASE_URL .. "/" })
@@ -322,6 +351,11 @@ function Scrape_genres_list()
 	return genres
 end
 
+function Scrape_genre(url, page)
+	local separator = string.find(url, "?", 1, true) and "&" or "?"
+	return scrape_manga_list(normalize_url(url) .. separator .. "page=" .. tostring(page))
+end
+
 function Get_info()
 	return {
 		id = "natomanga",
@@ -1,9 +1,12 @@
 wit_bindgen::generate!({
-	path: "scraper.wit"
+	path: "scraper.wit",
+	world: "root",
 });
 
 use std::collections::{HashMap, HashSet};
 
+use exports::scraper::types::scraper::{ErrorKind, ScraperError};
+
 fn absolute(url: &str) -> String {
 	let u = url.trim();
 	if u.starts_with("http://") || u.starts_with("https://") {
@@ -17,24 +20,6 @@ fn absolute(url: &str) -> String {
 	}
 }
 
-fn default_page(url: String) -> exports::scraper::types::scraper::Page {
-	exports::scraper::types::scraper::Page {
-		title: String::new(),
-		url,
-		img_url: String::new(),
-		alternative_names: Vec::new(),
-		authors: Vec::new(),
-		artists: None,
-		status: String::new(),
-		page_type: None,
-		release_date: None,
-		description: String::new(),
-		genres: Vec::new(),
-		chapters: Vec::new(),
-		content_html: None,
-	}
-}
-
 fn get_image_url(element: &::scraper::ElementRef) -> String {
 	let attrs = element.value().attrs().collect::<HashMap<&str, &str>>();
 	for key in ["data-src", "data-lazy-src", "data-cfsrc", "src"] {
@@ -79,6 +64,30 @@ fn default_headers() -> Vec<scraper::types::http::Header> {
     ]
 }
 
+scraper_types::wit_error_helpers!();
+
+/// GETs `url`, going through FlareSolverr when Cloudflare blocks the request.
+fn fetch(url: &str, headers: &[scraper::types::http::Header]) -> Result<scraper::types::http::Response, ScraperError> {
+	let mut res = scraper::types::http::get(url, Some(headers))
+		.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", url), None))?;
+
+	if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
+		res = scraper::types::flare_solverr::get(url, None).ok_or_else(|| {
+			error(
+				ErrorKind::Cloudflare,
+				format!("{} is behind Cloudflare", url),
+				Some(res.status),
+			)
+		})?;
+	}
+
+	if res.status != 200 {
+		return Err(status_error(url, res.status));
+	}
+
+	Ok(res)
+}
+
 fn extract_page_number(url: &str) -> Option<u32> {
 	if let Some(idx) = url.find("page=") {
 		let remainder = &url[idx + 5..];
@@ -235,94 +244,47 @@ fn calculate_total_pages(html_str: &str) -> u32 {
 export!(ScraperImpl);
 
 impl exports::scraper::types::scraper::Guest for ScraperImpl {
-	fn scrape_chapter(url: String) -> Vec<String> {
-		let headers = default_headers();
-		let mut res = match scraper::types::http::get(&url, Some(&headers)) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+	fn scrape_chapter(url: String) -> Result<Vec<String>, ScraperError> {
+		let res = fetch(&url, &default_headers())?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let selector = ::scraper::Selector::parse("#content, .chapter-content").unwrap();
 
-		if let Some(elem) = html.select(&selector).next() {
-			return vec![elem.inner_html()];
+		match html.select(&selector).next() {
+			Some(elem) => Ok(vec![elem.inner_html()]),
+			None => Err(error(ErrorKind::Parse, format!("no chapter content found at {}", url), None)),
 		}
-
-		Vec::new()
 	}
 
-	fn scrape_latest(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_latest(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://novelfire.net/latest-release-novels?page={}", page);
-		let headers = default_headers();
-
-		if let Some(res) = scraper::types::http::get(&url, Some(&headers)) {
-			if res.status == 200 {
-				return parse_novel_list_from_html(&res.body);
-			}
-		}
-		Vec::new()
+		let res = fetch(&url, &default_headers())?;
+		Ok(parse_novel_list_from_html(&res.body))
 	}
 
-	fn scrape_trending(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_trending(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!(
 			"https://novelfire.net/genre-all/sort-popular/status-all/all-novel?page={}",
 			page
 		);
-		let headers = default_headers();
-
-		if let Some(res) = scraper::types::http::get(&url, Some(&headers)) {
-			if res.status == 200 {
-				return parse_novel_list_from_html(&res.body);
-			}
-		}
-		Vec::new()
+		let res = fetch(&url, &default_headers())?;
+		Ok(parse_novel_list_from_html(&res.body))
 	}
 
-	fn scrape_search(query: String, page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_search(
+		query: String,
+		page: u32,
+		_filters: exports::scraper::types::scraper::SearchFilters,
+	) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://novelfire.net/search?keyword={}&page={}", query, page);
-		let headers = default_headers();
-
-		if let Some(res) = scraper::types::http::get(&url, Some(&headers)) {
-			if res.status == 200 {
-				return parse_novel_list_from_html(&res.body);
-			}
-		}
-		Vec::new()
+		let res = fetch(&url, &default_headers())?;
+		Ok(parse_novel_list_from_html(&res.body))
 	}
 
-	fn scrape(url: String) -> exports::scraper::types::scraper::Page {
+	fn scrape(url: String) -> Result<exports::scraper::types::scraper::Page, ScraperError> {
 		let headers_vec = default_headers();
 
-		let mut res = match scraper::types::http::get(&url, Some(&headers_vec)) {
-			Some(r) => r,
-			None => return default_page(url),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return default_page(url);
-			}
-		}
-
-		if res.status != 200 {
-			return default_page(url);
-		}
-
+		let res = fetch(&url, &headers_vec)?;
 		let mut page = parse_novel_metadata(&res.body, &url);
 
 		let chapters_url_base = if url.ends_with("/chapters") {
@@ -331,69 +293,53 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			format!("{}/chapters", url.trim_end_matches('/'))
 		};
 
-		let mut chapters_res = match scraper::types::http::get(&chapters_url_base, Some(&headers_vec)) {
-			Some(r) => r,
-			None => return page,
+		// The metadata is still useful when the chapter list cannot be loaded.
+		let chapters_res = match fetch(&chapters_url_base, &headers_vec) {
+			Ok(r) => r,
+			Err(_) => return Ok(page),
 		};
 
-		if scraper::types::http::has_cloudflare_protection(
-			&chapters_res.body,
-			Some(chapters_res.status),
-			Some(&chapters_res.headers),
-		) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&chapters_url_base, None) {
-				chapters_res = nr;
-			} else {
-				return page;
-			}
-		}
-
-		if chapters_res.status == 200 {
-			let mut all_chapters = parse_chapters_from_html(&chapters_res.body);
+		let mut all_chapters = parse_chapters_from_html(&chapters_res.body);
 
-			let max_page = calculate_total_pages(&chapters_res.body);
+		let max_page = calculate_total_pages(&chapters_res.body);
 
-			let safe_max_page = if max_page > 1000 { 1000 } else { max_page };
+		let safe_max_page = if max_page > 1000 { 1000 } else { max_page };
 
-			if safe_max_page > 1 {
-				for p in 2..=safe_max_page {
-					let page_url = format!("{}?page={}", chapters_url_base, p);
+		if safe_max_page > 1 {
+			for p in 2..=safe_max_page {
+				let page_url = format!("{}?page={}", chapters_url_base, p);
 
-					std::thread::sleep(std::time::Duration::from_millis(250));
+				std::thread::sleep(std::time::Duration::from_millis(250));
 
-					let mut p_res = match scraper::types::http::get(&page_url, Some(&headers_vec)) {
-						Some(r) => r,
-						None => break,
-					};
+				let p_res = match fetch(&page_url, &headers_vec) {
+					Ok(r) => r,
+					Err(_) => break,
+				};
 
-					if scraper::types::http::has_cloudflare_protection(&p_res.body, Some(p_res.status), Some(&p_res.headers))
-					{
-						if let Some(nr) = scraper::types::flare_solverr::get(&page_url, None) {
-							p_res = nr;
-						} else {
-							break;
-						}
-					}
-
-					if p_res.status == 200 {
-						let mut page_chapters = parse_chapters_from_html(&p_res.body);
-						if page_chapters.is_empty() {
-							break;
-						}
-						all_chapters.append(&mut page_chapters);
-					}
+				let mut page_chapters = parse_chapters_from_html(&p_res.body);
+				if page_chapters.is_empty() {
+					break;
 				}
+				all_chapters.append(&mut page_chapters);
 			}
-
-			let mut seen = HashSet::new();
-			page.chapters = all_chapters.into_iter().filter(|c| seen.insert(c.url.clone())).collect();
 		}
 
-		page
+		let mut seen = HashSet::new();
+		page.chapters = all_chapters.into_iter().filter(|c| seen.insert(c.url.clone())).collect();
+
+		Ok(page)
 	}
 
-	fn scrape_genres_list() -> Vec<exports::scraper::types::scraper::Genre> {
-		Vec::new()
+	fn scrape_genres_list() -> Result<Vec<exports::scraper::types::scraper::Genre>, ScraperError> {
+		Ok(Vec::new())
+	}
+
+	fn scrape_genre(url: String, _page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
+		Err(error(ErrorKind::NotFound, format!("no genre pages to browse: {}", url), None))
+	}
+
+	fn scrape_chapter_pages(url: String) -> Result<Vec<exports::scraper::types::scraper::ChapterPage>, ScraperError> {
+		Err(error(ErrorKind::NotFound, format!("novels have no pages: {}", url), None))
 	}
 
 	fn get_info() -> exports::scraper::types::scraper::ScraperInfo {
@@ -406,8 +352,17 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			referer_url: Some("https://novelfire.net/".to_string()),
 			base_url: Some("https://novelfire.net".to_string()),
 			legacy_urls: None,
+			search_filters: None,
+			transforms_pages: false,
+			rate_limit: None,
 		}
 	}
+
+	fn transform_page(
+		_page: exports::scraper::types::scraper::PageImage,
+	) -> Result<Option<exports::scraper::types::scraper::ProcessedImage>, ScraperError> {
+		Ok(None)
+	}
 }
 
 #[cfg(test)]
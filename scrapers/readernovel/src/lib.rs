@@ -1,9 +1,12 @@
 wit_bindgen::generate!({
-	path: "scraper.wit"
+	path: "scraper.wit",
+	world: "root",
 });
 
 use std::collections::HashMap;
 
+use exports::scraper::types::scraper::{ErrorKind, ScraperError};
+
 fn absolute(url: &str) -> String {
 	if url.starts_with("http") {
 		url.to_string()
@@ -14,22 +17,28 @@ fn absolute(url: &str) -> String {
 	}
 }
 
-fn default_page(url: String) -> exports::scraper::types::scraper::Page {
-	exports::scraper::types::scraper::Page {
-		title: String::new(),
-		url,
-		img_url: String::new(),
-		alternative_names: Vec::new(),
-		authors: Vec::new(),
-		artists: None,
-		status: String::new(),
-		page_type: None,
-		release_date: None,
-		description: String::new(),
-		genres: Vec::new(),
-		chapters: Vec::new(),
-		content_html: None,
+scraper_types::wit_error_helpers!();
+
+/// GETs `url`, going through FlareSolverr when Cloudflare blocks the request.
+fn fetch(url: &str) -> Result<scraper::types::http::Response, ScraperError> {
+	let mut res = scraper::types::http::get(url, None)
+		.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", url), None))?;
+
+	if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
+		res = scraper::types::flare_solverr::get(url, None).ok_or_else(|| {
+			error(
+				ErrorKind::Cloudflare,
+				format!("{} is behind Cloudflare", url),
+				Some(res.status),
+			)
+		})?;
+	}
+
+	if res.status != 200 {
+		return Err(status_error(url, res.status));
 	}
+
+	Ok(res)
 }
 
 fn get_image_url(element: &::scraper::ElementRef) -> String {
@@ -147,45 +156,15 @@ struct ScraperImpl;
 export!(ScraperImpl);
 
 impl exports::scraper::types::scraper::Guest for ScraperImpl {
-	fn scrape_chapter(url: String) -> Vec<String> {
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+	fn scrape_chapter(url: String) -> Result<Vec<String>, ScraperError> {
+		let res = fetch(&url)?;
 
-		parse_chapter_from_html(&res.body)
+		Ok(parse_chapter_from_html(&res.body))
 	}
 
-	fn scrape_latest(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_latest(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://www.readernovel.net/browse?sort=date&status=0&p={}", page);
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+		let res = fetch(&url)?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let block_sel = ::scraper::Selector::parse("div.manga-block").unwrap();
@@ -211,27 +190,12 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		items
+		Ok(items)
 	}
 
-	fn scrape_trending(page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_trending(page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let url = format!("https://www.readernovel.net/browse?sort=popular&status=0&p={}", page);
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return Vec::new();
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+		let res = fetch(&url)?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let block_sel = ::scraper::Selector::parse("div.manga-block").unwrap();
@@ -257,15 +221,17 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		items
+		Ok(items)
 	}
 
-	fn scrape_search(query: String, _page: u32) -> Vec<exports::scraper::types::scraper::Item> {
+	fn scrape_search(
+		query: String,
+		_page: u32,
+		_filters: exports::scraper::types::scraper::SearchFilters,
+	) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
 		let home_url = "https://www.readernovel.net/".to_string();
-		let mut home_res = match scraper::types::http::get(&home_url, None) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
+		let mut home_res = scraper::types::http::get(&home_url, None)
+			.ok_or_else(|| error(ErrorKind::Network, format!("request to {} failed", home_url), None))?;
 
 		if scraper::types::http::has_cloudflare_protection(&home_res.body, Some(home_res.status), Some(&home_res.headers)) {
 			if let Some(nr) = scraper::types::flare_solverr::get(&home_url, None) {
@@ -300,7 +266,7 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 				if res.status == 200 {
 					if let Ok(json) = serde_json::from_str::<serde_json::Value>(&res.body) {
 						if let Some(arr) = json.get("result").and_then(|r| r.as_array()) {
-							return arr
+							return Ok(arr
 								.iter()
 								.filter_map(|it| {
 									let name = it.get("name").and_then(|v| v.as_str()).unwrap_or("");
@@ -315,7 +281,7 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 										img_url: absolute(image),
 									})
 								})
-								.collect();
+								.collect());
 						}
 					}
 				}
@@ -323,20 +289,7 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 		}
 
 		let url = format!("https://www.readernovel.net/browse?sort=name&status=0&p=1");
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(r) => r,
-			None => return Vec::new(),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			}
-		}
-
-		if res.status != 200 {
-			return Vec::new();
-		}
+		let res = fetch(&url)?;
 
 		let html = ::scraper::Html::parse_document(&res.body);
 		let block_sel = ::scraper::Selector::parse("div.manga-block").unwrap();
@@ -365,32 +318,25 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			}
 		}
 
-		items
+		Ok(items)
 	}
 
-	fn scrape(url: String) -> exports::scraper::types::scraper::Page {
-		let mut res = match scraper::types::http::get(&url, None) {
-			Some(r) => r,
-			None => return default_page(url),
-		};
-
-		if scraper::types::http::has_cloudflare_protection(&res.body, Some(res.status), Some(&res.headers)) {
-			if let Some(nr) = scraper::types::flare_solverr::get(&url, None) {
-				res = nr;
-			} else {
-				return default_page(url);
-			}
-		}
+	fn scrape(url: String) -> Result<exports::scraper::types::scraper::Page, ScraperError> {
+		let res = fetch(&url)?;
 
-		if res.status != 200 {
-			return default_page(url);
-		}
+		Ok(parse_page_from_html(&res.body, &url))
+	}
 
-		parse_page_from_html(&res.body, &url)
+	fn scrape_genres_list() -> Result<Vec<exports::scraper::types::scraper::Genre>, ScraperError> {
+		Ok(Vec::new())
 	}
 
-	fn scrape_genres_list() -> Vec<exports::scraper::types::scraper::Genre> {
-		Vec::new()
+	fn scrape_genre(url: String, _page: u32) -> Result<Vec<exports::scraper::types::scraper::Item>, ScraperError> {
+		Err(error(ErrorKind::NotFound, format!("no genre pages to browse: {}", url), None))
+	}
+
+	fn scrape_chapter_pages(url: String) -> Result<Vec<exports::scraper::types::scraper::ChapterPage>, ScraperError> {
+		Err(error(ErrorKind::NotFound, format!("novels have no pages: {}", url), None))
 	}
 
 	fn get_info() -> exports::scraper::types::scraper::ScraperInfo {
@@ -403,8 +349,17 @@ impl exports::scraper::types::scraper::Guest for ScraperImpl {
 			referer_url: Some("https://www.readernovel.net/".to_string()),
 			base_url: Some("https://www.readernovel.net".to_string()),
 			legacy_urls: None,
+			search_filters: None,
+			transforms_pages: false,
+			rate_limit: None,
 		}
 	}
+
+	fn transform_page(
+		_page: exports::scraper::types::scraper::PageImage,
+	) -> Result<Option<exports::scraper::types::scraper::ProcessedImage>, ScraperError> {
+		Ok(None)
+	}
 }
 
 #[cfg(test)]
@@ -9,6 +9,7 @@ use notify::{Event, EventKind};
 use crate::plugins::lua::LuaPlugin;
 use crate::plugins::wasm::WasmPlugin;
 use crate::plugins::{Plugin, PluginType};
+use crate::rate_limit;
 use crate::{Config, FileModification, ModificationTracker, PLUGIN_FILE_EXTENSIONS, PluginMap};
 
 pub fn read_directory<CB, Fut>(
@@ -64,7 +65,7 @@ pub async fn load_plugin(config: Arc<Config>, file_path: PathBuf, plugin_type: P
 			Ok(Arc::new(Plugin::Lua(plugin)))
 		}
 		PluginType::Wasm => {
-			let plugin = WasmPlugin::new(&file_path).await?;
+			let plugin = WasmPlugin::new(config, &file_path).await?;
 			Ok(Arc::new(Plugin::Wasm(plugin)))
 		}
 	}
@@ -83,11 +84,32 @@ pub async fn load_plugin_file(
 		Plugin::Lua(plugin) => plugin.id.clone(),
 		Plugin::Wasm(plugin) => plugin.name.clone(),
 	};
+	declare_rate_limit(&plugin).await;
 	plugins.write().await.insert(id, plugin);
 
 	Ok(())
 }
 
+/// Applies the request rate the plugin declares to the hosts it scrapes.
+async fn declare_rate_limit(plugin: &Plugin) {
+	let info = match plugin.get_info().await {
+		Ok(info) => info,
+		Err(e) => {
+			tracing::warn!("Failed to read the rate limit of plugin {}: {:#}", plugin.id(), e);
+			return;
+		}
+	};
+
+	if let Some(limit) = info.rate_limit {
+		let urls = info
+			.base_url
+			.iter()
+			.chain(info.referer_url.iter())
+			.chain(info.legacy_urls.iter().flatten());
+		rate_limit::limiter().declare(urls.map(String::as_str), limit);
+	}
+}
+
 pub(crate) async fn handle_single_event(
 	config: Arc<Config>,
 	event: Event,
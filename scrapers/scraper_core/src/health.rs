@@ -0,0 +1,448 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::{LazyLock, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use scraper_types::{ScraperError, ScraperErrorKind};
+use serde::Serialize;
+
+use crate::HealthConfig;
+
+/// Number of recent call latencies kept per scraper for the percentiles.
+const LATENCY_WINDOW: usize = 256;
+/// How long other callers wait for a probe to report back before another probe is let through.
+const PROBE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+static HEALTH: LazyLock<HealthTracker> = LazyLock::new(|| HealthTracker::new(HealthConfig::default()));
+
+/// Tracker shared by every plugin in the process.
+pub fn tracker() -> &'static HealthTracker {
+	&HEALTH
+}
+
+/// Whether a caller should talk to a scraper right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+	Allowed,
+	/// The quarantine ran out; this caller is the single probe that decides whether it is lifted.
+	Probe,
+	Quarantined {
+		remaining: Duration,
+	},
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+	Healthy,
+	Failing,
+	Quarantined,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSnapshot {
+	pub scraper: String,
+	pub state: HealthState,
+	pub successes: u64,
+	pub failures: u64,
+	pub consecutive_failures: u32,
+	pub p50_ms: Option<u64>,
+	pub p90_ms: Option<u64>,
+	pub p99_ms: Option<u64>,
+	pub last_error_kind: Option<String>,
+	pub last_error: Option<String>,
+	pub last_success_at: Option<SystemTime>,
+	pub last_failure_at: Option<SystemTime>,
+	pub quarantined_until: Option<SystemTime>,
+}
+
+#[derive(Debug, Default)]
+struct ScraperStats {
+	successes: u64,
+	failures: u64,
+	consecutive_failures: u32,
+	latencies: VecDeque<Duration>,
+	last_error_kind: Option<ScraperErrorKind>,
+	last_error: Option<String>,
+	last_success_at: Option<SystemTime>,
+	last_failure_at: Option<SystemTime>,
+	quarantine: Option<Quarantine>,
+}
+
+#[derive(Debug)]
+struct Quarantine {
+	until: Instant,
+	/// Number of quarantines in a row, used for the exponential backoff.
+	level: u32,
+	probing: bool,
+}
+
+impl ScraperStats {
+	fn percentile(sorted: &[Duration], percentile: f64) -> Option<u64> {
+		if sorted.is_empty() {
+			return None;
+		}
+		let index = ((sorted.len() - 1) as f64 * percentile).round() as usize;
+		Some(sorted[index].as_millis() as u64)
+	}
+
+	fn snapshot(&self, scraper: &str) -> HealthSnapshot {
+		let mut sorted = self.latencies.iter().copied().collect::<Vec<_>>();
+		sorted.sort();
+
+		let now = Instant::now();
+		let quarantined_until = self
+			.quarantine
+			.as_ref()
+			.map(|q| SystemTime::now() + q.until.saturating_duration_since(now));
+		let state = if self.quarantine.is_some() {
+			HealthState::Quarantined
+		} else if self.consecutive_failures > 0 {
+			HealthState::Failing
+		} else {
+			HealthState::Healthy
+		};
+
+		HealthSnapshot {
+			scraper: scraper.to_string(),
+			state,
+			successes: self.successes,
+			failures: self.failures,
+			consecutive_failures: self.consecutive_failures,
+			p50_ms: Self::percentile(&sorted, 0.5),
+			p90_ms: Self::percentile(&sorted, 0.9),
+			p99_ms: Self::percentile(&sorted, 0.99),
+			last_error_kind: self.last_error_kind.as_ref().map(|kind| kind.to_string()),
+			last_error: self.last_error.clone(),
+			last_success_at: self.last_success_at,
+			last_failure_at: self.last_failure_at,
+			quarantined_until,
+		}
+	}
+}
+
+/// Success, failure and latency statistics per scraper, plus the quarantine of scrapers that keep failing.
+#[derive(Debug)]
+pub struct HealthTracker {
+	config: RwLock<HealthConfig>,
+	scrapers: RwLock<HashMap<String, ScraperStats>>,
+}
+
+impl HealthTracker {
+	pub fn new(config: HealthConfig) -> Self {
+		Self {
+			config: RwLock::new(config),
+			scrapers: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Replaces the thresholds. Running quarantines keep their deadline.
+	pub fn configure(&self, config: HealthConfig) {
+		*self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+	}
+
+	/// Records the outcome of a plugin call. Errors that aren't a [`ScraperError`] count as internal errors.
+	pub fn record(&self, scraper: &str, latency: Duration, error: Option<&anyhow::Error>) {
+		match error {
+			None => self.record_success(scraper, latency),
+			Some(error) => {
+				let error = error
+					.downcast_ref::<ScraperError>()
+					.cloned()
+					.unwrap_or_else(|| ScraperError::new(ScraperErrorKind::Internal, format!("{:#}", error)));
+				self.record_failure(scraper, Some(latency), &error);
+			}
+		}
+	}
+
+	pub fn record_success(&self, scraper: &str, latency: Duration) {
+		let mut scrapers = self.scrapers.write().unwrap_or_else(|e| e.into_inner());
+		let stats = scrapers.entry(scraper.to_string()).or_default();
+		stats.successes += 1;
+		stats.consecutive_failures = 0;
+		stats.last_success_at = Some(SystemTime::now());
+		push_latency(stats, latency);
+
+		if stats.quarantine.take().is_some() {
+			tracing::info!("Scraper {} recovered, lifting quarantine", scraper);
+		}
+	}
+
+	/// Records a failed call. `latency` is `None` when the scraper couldn't be called at all, e.g. it isn't loaded.
+	///
+	/// Only failures that point at the site being unreachable count toward a quarantine, see
+	/// [`counts_toward_quarantine`]. Others, like a missing page or a parse error, are recorded but leave the
+	/// consecutive failures and any quarantine alone, except that such a failure ends a quarantine when it answers
+	/// the probe, since the site was reached.
+	pub fn record_failure(&self, scraper: &str, latency: Option<Duration>, error: &ScraperError) {
+		let mut scrapers = self.scrapers.write().unwrap_or_else(|e| e.into_inner());
+		let stats = scrapers.entry(scraper.to_string()).or_default();
+		stats.failures += 1;
+		stats.last_error_kind = Some(error.kind.clone());
+		stats.last_error = Some(error.message.clone());
+		stats.last_failure_at = Some(SystemTime::now());
+		if let Some(latency) = latency {
+			push_latency(stats, latency);
+		}
+		if !counts_toward_quarantine(error) {
+			// The probe reached the site, so it's back even though this particular call failed.
+			if stats.quarantine.as_ref().is_some_and(|quarantine| quarantine.probing) {
+				tracing::info!("Scraper {} answered its probe, lifting quarantine", scraper);
+				stats.quarantine = None;
+				stats.consecutive_failures = 0;
+			}
+			return;
+		}
+		stats.consecutive_failures = stats.consecutive_failures.saturating_add(1);
+
+		let failure_threshold = self.config.read().unwrap_or_else(|e| e.into_inner()).failure_threshold;
+		let level = match &stats.quarantine {
+			Some(quarantine) if quarantine.probing => quarantine.level + 1,
+			Some(_) => return,
+			None if stats.consecutive_failures >= failure_threshold => 0,
+			None => return,
+		};
+
+		let backoff = self.backoff(level);
+		tracing::warn!(
+			"Quarantining scraper {} for {}s after {} consecutive failures",
+			scraper,
+			backoff.as_secs(),
+			stats.consecutive_failures
+		);
+		stats.quarantine = Some(Quarantine {
+			until: Instant::now() + backoff,
+			level,
+			probing: false,
+		});
+	}
+
+	fn backoff(&self, level: u32) -> Duration {
+		let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+		let secs = config.quarantine_secs.saturating_mul(1u64 << level.min(32));
+		Duration::from_secs(secs.min(config.max_quarantine_secs))
+	}
+
+	/// Decides whether `scraper` may be called. Once a quarantine expires a single probe is let through;
+	/// everyone else keeps waiting until that probe succeeds or fails, or the probe window runs out.
+	pub fn admit(&self, scraper: &str) -> Admission {
+		let mut scrapers = self.scrapers.write().unwrap_or_else(|e| e.into_inner());
+		let Some(quarantine) = scrapers.get_mut(scraper).and_then(|stats| stats.quarantine.as_mut()) else {
+			return Admission::Allowed;
+		};
+
+		let now = Instant::now();
+		if now < quarantine.until {
+			return Admission::Quarantined {
+				remaining: quarantine.until.saturating_duration_since(now),
+			};
+		}
+
+		quarantine.probing = true;
+		quarantine.until = now + PROBE_WINDOW;
+		Admission::Probe
+	}
+
+	pub fn snapshot(&self, scraper: &str) -> Option<HealthSnapshot> {
+		let scrapers = self.scrapers.read().unwrap_or_else(|e| e.into_inner());
+		scrapers.get(scraper).map(|stats| stats.snapshot(scraper))
+	}
+
+	pub fn snapshots(&self) -> Vec<HealthSnapshot> {
+		let scrapers = self.scrapers.read().unwrap_or_else(|e| e.into_inner());
+		let mut snapshots = scrapers
+			.iter()
+			.map(|(scraper, stats)| stats.snapshot(scraper))
+			.collect::<Vec<_>>();
+		snapshots.sort_by(|a, b| a.scraper.cmp(&b.scraper));
+		snapshots
+	}
+
+	/// Statistics in the Prometheus text exposition format.
+	pub fn render_prometheus(&self) -> String {
+		let snapshots = self.snapshots();
+		let mut out = String::new();
+
+		out.push_str("# HELP scraper_calls_total Plugin calls by outcome.\n");
+		out.push_str("# TYPE scraper_calls_total counter\n");
+		for s in &snapshots {
+			let _ = writeln!(
+				out,
+				"scraper_calls_total{{scraper=\"{}\",outcome=\"success\"}} {}",
+				s.scraper, s.successes
+			);
+			let _ = writeln!(
+				out,
+				"scraper_calls_total{{scraper=\"{}\",outcome=\"failure\"}} {}",
+				s.scraper, s.failures
+			);
+		}
+
+		out.push_str("# HELP scraper_latency_milliseconds Latency of recent plugin calls.\n");
+		out.push_str("# TYPE scraper_latency_milliseconds summary\n");
+		for s in &snapshots {
+			for (quantile, value) in [("0.5", s.p50_ms), ("0.9", s.p90_ms), ("0.99", s.p99_ms)] {
+				if let Some(value) = value {
+					let _ = writeln!(
+						out,
+						"scraper_latency_milliseconds{{scraper=\"{}\",quantile=\"{}\"}} {}",
+						s.scraper, quantile, value
+					);
+				}
+			}
+		}
+
+		out.push_str("# HELP scraper_consecutive_failures Failed calls since the last success.\n");
+		out.push_str("# TYPE scraper_consecutive_failures gauge\n");
+		for s in &snapshots {
+			let _ = writeln!(
+				out,
+				"scraper_consecutive_failures{{scraper=\"{}\"}} {}",
+				s.scraper, s.consecutive_failures
+			);
+		}
+
+		out.push_str("# HELP scraper_quarantined Whether the scraper is quarantined.\n");
+		out.push_str("# TYPE scraper_quarantined gauge\n");
+		for s in &snapshots {
+			let quarantined = u8::from(s.state == HealthState::Quarantined);
+			let _ = writeln!(out, "scraper_quarantined{{scraper=\"{}\"}} {}", s.scraper, quarantined);
+		}
+
+		out
+	}
+}
+
+/// Whether `error` means the site is down, blocking or throttling us: network failures, rate limits, Cloudflare
+/// blocks and 5xx answers. A network error carrying another status is an answer the site did give.
+pub fn counts_toward_quarantine(error: &ScraperError) -> bool {
+	match error.kind {
+		ScraperErrorKind::RateLimit | ScraperErrorKind::Cloudflare => true,
+		ScraperErrorKind::Network => error.status_code.is_none_or(|status| status >= 500),
+		_ => false,
+	}
+}
+
+fn push_latency(stats: &mut ScraperStats, latency: Duration) {
+	if stats.latencies.len() == LATENCY_WINDOW {
+		stats.latencies.pop_front();
+	}
+	stats.latencies.push_back(latency);
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	fn tracker(failure_threshold: u32) -> HealthTracker {
+		HealthTracker::new(HealthConfig {
+			failure_threshold,
+			quarantine_secs: 0,
+			max_quarantine_secs: 0,
+		})
+	}
+
+	fn network_error() -> ScraperError {
+		ScraperError::new(ScraperErrorKind::Network, "connection reset")
+	}
+
+	#[test]
+	fn test_records_counts_and_percentiles() {
+		let tracker = tracker(5);
+		for ms in 1..=10 {
+			tracker.record_success("mangadex", Duration::from_millis(ms * 10));
+		}
+		tracker.record(
+			"mangadex",
+			Duration::from_millis(500),
+			Some(&anyhow::Error::from(network_error())),
+		);
+
+		let snapshot = tracker.snapshot("mangadex").unwrap();
+		assert_eq!(snapshot.successes, 10);
+		assert_eq!(snapshot.failures, 1);
+		assert_eq!(snapshot.consecutive_failures, 1);
+		assert_eq!(snapshot.state, HealthState::Failing);
+		assert_eq!(snapshot.last_error_kind.as_deref(), Some("network"));
+		assert_eq!(snapshot.p50_ms, Some(60));
+		assert_eq!(snapshot.p99_ms, Some(500));
+	}
+
+	#[test]
+	fn test_quarantine_and_probe() {
+		let tracker = tracker(2);
+		tracker.record_failure("broken", None, &network_error());
+		assert_eq!(tracker.admit("broken"), Admission::Allowed);
+
+		tracker.record_failure("broken", None, &network_error());
+		assert_eq!(tracker.snapshot("broken").unwrap().state, HealthState::Quarantined);
+
+		assert_eq!(tracker.admit("broken"), Admission::Probe);
+		assert!(matches!(tracker.admit("broken"), Admission::Quarantined { .. }));
+
+		tracker.record_failure("broken", None, &network_error());
+		assert_eq!(tracker.admit("broken"), Admission::Probe);
+
+		tracker.record_success("broken", Duration::from_millis(5));
+		assert_eq!(tracker.admit("broken"), Admission::Allowed);
+		assert_eq!(tracker.snapshot("broken").unwrap().state, HealthState::Healthy);
+	}
+
+	#[test]
+	fn test_only_outages_count_toward_quarantine() {
+		let tracker = tracker(2);
+		for error in [
+			ScraperError::new(ScraperErrorKind::NotFound, "no such manga"),
+			ScraperError::new(ScraperErrorKind::Parse, "missing title"),
+			ScraperError::from_http_status(400, "bad request"),
+		] {
+			tracker.record_failure("picky", None, &error);
+			tracker.record_failure("picky", None, &error);
+		}
+		let snapshot = tracker.snapshot("picky").unwrap();
+		assert_eq!(snapshot.failures, 6);
+		assert_eq!(snapshot.consecutive_failures, 0);
+		assert_eq!(tracker.admit("picky"), Admission::Allowed);
+
+		tracker.record_failure("picky", None, &ScraperError::from_http_status(503, "unavailable"));
+		tracker.record_failure("picky", None, &ScraperError::cloudflare("challenge"));
+		assert_eq!(tracker.snapshot("picky").unwrap().state, HealthState::Quarantined);
+	}
+
+	#[test]
+	fn test_non_outage_probe_failure_lifts_quarantine() {
+		let tracker = tracker(1);
+		tracker.record_failure("flaky", None, &network_error());
+		assert_eq!(tracker.admit("flaky"), Admission::Probe);
+
+		tracker.record_failure("flaky", None, &ScraperError::new(ScraperErrorKind::NotFound, "no such manga"));
+		let snapshot = tracker.snapshot("flaky").unwrap();
+		assert_eq!(snapshot.consecutive_failures, 0);
+		assert_ne!(snapshot.state, HealthState::Quarantined);
+		assert_eq!(tracker.admit("flaky"), Admission::Allowed);
+	}
+
+	#[test]
+	fn test_backoff_doubles_up_to_the_limit() {
+		let tracker = HealthTracker::new(HealthConfig {
+			failure_threshold: 1,
+			quarantine_secs: 60,
+			max_quarantine_secs: 300,
+		});
+		assert_eq!(tracker.backoff(0), Duration::from_secs(60));
+		assert_eq!(tracker.backoff(2), Duration::from_secs(240));
+		assert_eq!(tracker.backoff(10), Duration::from_secs(300));
+	}
+
+	#[test]
+	fn test_render_prometheus() {
+		let tracker = tracker(5);
+		tracker.record_success("mangadex", Duration::from_millis(42));
+
+		let metrics = tracker.render_prometheus();
+		assert!(metrics.contains("scraper_calls_total{scraper=\"mangadex\",outcome=\"success\"} 1"));
+		assert!(metrics.contains("scraper_latency_milliseconds{scraper=\"mangadex\",quantile=\"0.5\"} 42"));
+		assert!(metrics.contains("scraper_quarantined{scraper=\"mangadex\"} 0"));
+	}
+}
@@ -1,5 +1,5 @@
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, LazyLock};
@@ -8,12 +8,17 @@ use std::time::Instant;
 use anyhow::{Context, Result};
 use notify::{RecommendedWatcher, Watcher};
 use plugins::{Plugin, PluginType};
+use scraper_types::RateLimit;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 mod files;
 pub use files::load_plugin;
+pub mod health;
+pub mod limits;
+pub mod metrics;
 pub mod plugins;
+pub mod rate_limit;
 mod repository;
 
 fn current_exe_parent_dir() -> PathBuf {
@@ -47,6 +52,23 @@ pub struct Config {
 	pub headless: Option<String>,
 	#[serde(default)]
 	pub flaresolverr_url: Option<String>,
+	/// Folder where per-plugin cookie jars are persisted. `None` keeps cookies in memory only.
+	#[serde(default = "default_cookie_jar_folder")]
+	pub cookie_jar_folder: Option<String>,
+	#[serde(default)]
+	pub wasm: WasmConfig,
+	#[serde(default)]
+	pub lua: LuaConfig,
+	/// Record plugin traffic to, or replay it from, fixture files. Used for offline regression tests.
+	#[serde(default)]
+	pub fixtures: Option<FixtureConfig>,
+	#[serde(default)]
+	pub health: HealthConfig,
+	#[serde(default)]
+	pub rate_limits: RateLimitConfig,
+	/// Per-scraper concurrency and cooldown, shared by the scheduler and the API.
+	#[serde(default)]
+	pub limits: limits::LimitsConfig,
 }
 
 impl Default for Config {
@@ -56,10 +78,161 @@ impl Default for Config {
 			repositories: Vec::new(),
 			headless: None,
 			flaresolverr_url: None,
+			cookie_jar_folder: default_cookie_jar_folder(),
+			wasm: WasmConfig::default(),
+			lua: LuaConfig::default(),
+			fixtures: None,
+			health: HealthConfig::default(),
+			rate_limits: RateLimitConfig::default(),
+			limits: limits::LimitsConfig::default(),
+		}
+	}
+}
+
+fn default_cookie_jar_folder() -> Option<String> {
+	Some(format!("{}/cache/cookies", current_exe_parent_dir().display()))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WasmConfig {
+	/// Use wasmtime's pooling instance allocator for the shared engine.
+	#[serde(default = "default_pooling_allocator")]
+	pub pooling_allocator: bool,
+	/// Keep instances alive between calls instead of instantiating a fresh one every time. A reused instance keeps the
+	/// guest's memory and globals from its previous calls; turn this off for plugins that rely on starting fresh.
+	#[serde(default = "default_reuse_instances")]
+	pub reuse_instances: bool,
+	/// Maximum number of instances per plugin, busy or idle. Calls beyond it wait for an instance to free up.
+	#[serde(default = "default_instance_pool_size")]
+	pub instance_pool_size: usize,
+	/// Folder where precompiled components are stored. `None` disables the cache.
+	#[serde(default = "default_component_cache_folder")]
+	pub component_cache_folder: Option<String>,
+}
+
+impl Default for WasmConfig {
+	fn default() -> Self {
+		Self {
+			pooling_allocator: default_pooling_allocator(),
+			reuse_instances: default_reuse_instances(),
+			instance_pool_size: default_instance_pool_size(),
+			component_cache_folder: default_component_cache_folder(),
+		}
+	}
+}
+
+fn default_pooling_allocator() -> bool {
+	true
+}
+fn default_reuse_instances() -> bool {
+	true
+}
+fn default_instance_pool_size() -> usize {
+	4
+}
+fn default_component_cache_folder() -> Option<String> {
+	Some(format!("{}/cache/wasm", current_exe_parent_dir().display()))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LuaConfig {
+	/// Maximum number of Lua states per plugin. States are created lazily as concurrent calls need them.
+	#[serde(default = "default_runtime_pool_size")]
+	pub runtime_pool_size: usize,
+}
+
+impl Default for LuaConfig {
+	fn default() -> Self {
+		Self {
+			runtime_pool_size: default_runtime_pool_size(),
+		}
+	}
+}
+
+fn default_runtime_pool_size() -> usize {
+	4
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureMode {
+	Record,
+	Replay,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FixtureConfig {
+	pub mode: FixtureMode,
+	/// Folder holding one `<plugin>.json` fixture file per plugin.
+	pub folder: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HealthConfig {
+	/// Consecutive failed calls after which a scraper is quarantined.
+	#[serde(default = "default_failure_threshold")]
+	pub failure_threshold: u32,
+	/// How long the first quarantine lasts. Every failed probe doubles it.
+	#[serde(default = "default_quarantine_secs")]
+	pub quarantine_secs: u64,
+	#[serde(default = "default_max_quarantine_secs")]
+	pub max_quarantine_secs: u64,
+}
+
+impl Default for HealthConfig {
+	fn default() -> Self {
+		Self {
+			failure_threshold: default_failure_threshold(),
+			quarantine_secs: default_quarantine_secs(),
+			max_quarantine_secs: default_max_quarantine_secs(),
 		}
 	}
 }
 
+fn default_failure_threshold() -> u32 {
+	5
+}
+fn default_quarantine_secs() -> u64 {
+	60
+}
+fn default_max_quarantine_secs() -> u64 {
+	6 * 60 * 60
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RateLimitConfig {
+	/// Limit for hosts neither `hosts` nor a plugin sets one for. `None` leaves them unlimited.
+	#[serde(default = "default_host_rate_limit")]
+	pub default: Option<RateLimit>,
+	/// Limits by host name, e.g. `uploads.mangadex.org`. They win over what plugins declare; a
+	/// `requests_per_minute` of 0 lifts the limit.
+	#[serde(default)]
+	pub hosts: BTreeMap<String, RateLimit>,
+	/// Longest a `Retry-After` header may hold back requests to a host.
+	#[serde(default = "default_max_retry_after_secs")]
+	pub max_retry_after_secs: u64,
+}
+
+impl Default for RateLimitConfig {
+	fn default() -> Self {
+		Self {
+			default: default_host_rate_limit(),
+			hosts: BTreeMap::new(),
+			max_retry_after_secs: default_max_retry_after_secs(),
+		}
+	}
+}
+
+fn default_host_rate_limit() -> Option<RateLimit> {
+	Some(RateLimit {
+		requests_per_minute: 240,
+		burst: 30,
+	})
+}
+fn default_max_retry_after_secs() -> u64 {
+	15 * 60
+}
+
 pub(crate) const PLUGIN_FILE_EXTENSIONS: [&str; 2] = ["wasm", "lua"];
 
 #[derive(Debug)]
@@ -77,15 +250,27 @@ pub(crate) const CONFIG: LazyLock<Arc<Config>> = LazyLock::new(|| Arc::new(Confi
 pub struct ScraperManager {
 	plugins: PluginMap,
 	modification_tracker: ModificationTracker,
+	/// Off while a newer app version is available, since new plugin versions may need it.
+	updates_enabled: bool,
 }
 
 impl ScraperManager {
 	pub async fn new(update: bool) -> Result<Arc<Self>> {
+		let config = Config::load();
+		health::tracker().configure(config.health.clone());
+		limits::limiter().configure_from(config.limits.clone());
+		rate_limit::limiter().configure(config.rate_limits.clone());
+		telemetry::metrics::registry().register_collector(
+			"scraper_health",
+			Arc::new(|| Box::pin(async { health::tracker().render_prometheus() })),
+		);
+
 		if update {
 			tracing::info!("Creating plugin manager");
 			let manager = Self {
 				plugins: Arc::new(RwLock::new(HashMap::new())),
 				modification_tracker: Arc::new(RwLock::new(HashMap::new())),
+				updates_enabled: true,
 			};
 
 			manager.initialize(true).await?;
@@ -96,6 +281,7 @@ impl ScraperManager {
 		let manager = Self {
 			plugins: Arc::new(RwLock::new(HashMap::new())),
 			modification_tracker: Arc::new(RwLock::new(HashMap::new())),
+			updates_enabled: false,
 		};
 
 		manager.initialize(false).await?;
@@ -115,6 +301,17 @@ impl ScraperManager {
 		self.start_file_watcher()
 	}
 
+	/// Downloads new plugin versions from the configured repositories. The file watcher loads them once written.
+	/// Returns false when updates are held back.
+	pub async fn update_plugins(&self) -> Result<bool> {
+		if !self.updates_enabled {
+			return Ok(false);
+		}
+
+		repository::load_repos(&CONFIG).await?;
+		Ok(true)
+	}
+
 	fn setup_plugins_directory(&self) -> Result<()> {
 		let plugins_dir = PathBuf::from(&CONFIG.plugins_folder);
 		if !plugins_dir.exists() {
@@ -244,4 +441,9 @@ impl ScraperManager {
 	pub async fn get_plugin(&self, name: &str) -> Option<Arc<Plugin>> {
 		self.plugins.read().await.get(name).cloned()
 	}
+
+	/// Writes the cookie changes still waiting for their batched write, so sessions survive the exit.
+	pub fn shutdown(&self) {
+		plugins::common::cookies::flush_all();
+	}
 }
@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const DEFAULT_PER_SCRAPER_CONCURRENCY: usize = 2;
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(10);
+
+static LIMITER: LazyLock<ScraperLimiter> =
+	LazyLock::new(|| ScraperLimiter::new(DEFAULT_PER_SCRAPER_CONCURRENCY, DEFAULT_COOLDOWN, HashMap::new()));
+
+/// Process-wide limiter shared by the scheduler and anything else that fans out over scrapers.
+pub fn limiter() -> &'static ScraperLimiter {
+	&LIMITER
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LimitsConfig {
+	/// Concurrent calls allowed per scraper. 0 uses the default of 2.
+	#[serde(default)]
+	pub default_per_scraper_concurrency: usize,
+	/// Concurrent calls by scraper id, overriding the default.
+	#[serde(default)]
+	pub per_scraper_limits: BTreeMap<String, usize>,
+	/// Seconds between the starts of calls to the same scraper. 0 uses the default of 10.
+	#[serde(default)]
+	pub cooldown_seconds: u64,
+}
+
+impl Default for LimitsConfig {
+	fn default() -> Self {
+		Self {
+			default_per_scraper_concurrency: DEFAULT_PER_SCRAPER_CONCURRENCY,
+			per_scraper_limits: BTreeMap::new(),
+			cooldown_seconds: DEFAULT_COOLDOWN.as_secs(),
+		}
+	}
+}
+
+/// Concurrency slots of one scraper.
+struct Slots {
+	semaphore: Arc<Semaphore>,
+	cap: usize,
+	/// Permits still to be taken back after the cap was lowered while they were in use.
+	owed: Arc<AtomicUsize>,
+}
+
+impl Slots {
+	fn new(cap: usize) -> Self {
+		Self {
+			semaphore: Arc::new(Semaphore::new(cap)),
+			cap,
+			owed: Arc::new(AtomicUsize::new(0)),
+		}
+	}
+
+	fn in_use(&self) -> usize {
+		(self.cap + self.owed.load(Ordering::Acquire)).saturating_sub(self.semaphore.available_permits())
+	}
+
+	/// Changes the cap in place, so calls holding a permit still return it to the same semaphore. Permits beyond a
+	/// lowered cap are taken back as those calls finish.
+	fn resize(&mut self, cap: usize) {
+		if cap > self.cap {
+			let mut grow = cap - self.cap;
+			let cancelled = self
+				.owed
+				.fetch_update(Ordering::AcqRel, Ordering::Acquire, |owed| Some(owed.saturating_sub(grow)))
+				.unwrap_or_default()
+				.min(grow);
+			grow -= cancelled;
+			self.semaphore.add_permits(grow);
+		} else if cap < self.cap {
+			let excess = self.cap - cap;
+			let owed = excess - self.semaphore.forget_permits(excess);
+			if owed > 0 && self.owed.fetch_add(owed, Ordering::AcqRel) == 0 {
+				self.reclaim();
+			}
+		}
+		self.cap = cap;
+	}
+
+	fn reclaim(&self) {
+		let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+			return;
+		};
+		let semaphore = Arc::clone(&self.semaphore);
+		let owed = Arc::clone(&self.owed);
+		runtime.spawn(async move {
+			while owed.load(Ordering::Acquire) > 0 {
+				let Ok(permit) = Arc::clone(&semaphore).acquire_owned().await else {
+					return;
+				};
+				// The cap may have been raised again while waiting; hand the permit back then.
+				if owed
+					.fetch_update(Ordering::AcqRel, Ordering::Acquire, |owed| owed.checked_sub(1))
+					.is_ok()
+				{
+					permit.forget();
+				}
+			}
+		});
+	}
+}
+
+struct LimiterState {
+	slots: HashMap<String, Slots>,
+	last_used: HashMap<String, Instant>,
+	cooldown: Duration,
+	per_scraper_concurrency: usize,
+	overrides: HashMap<String, usize>,
+}
+
+/// Limits of one scraper and how much of them is in use.
+#[derive(Debug, Clone)]
+pub struct LimiterSnapshot {
+	pub scraper: String,
+	pub max_concurrency: usize,
+	pub in_use: usize,
+	/// Time left before the next call to the scraper may start.
+	pub cooldown_remaining: Duration,
+}
+
+/// Caps concurrent calls per scraper and spaces calls to the same scraper by a cooldown.
+pub struct ScraperLimiter {
+	state: Mutex<LimiterState>,
+}
+
+impl LimiterState {
+	fn cap(&self, scraper: &str) -> usize {
+		self.overrides
+			.get(scraper)
+			.copied()
+			.unwrap_or(self.per_scraper_concurrency)
+			.max(1)
+	}
+}
+
+impl ScraperLimiter {
+	pub fn new(per_scraper_concurrency: usize, cooldown: Duration, overrides: HashMap<String, usize>) -> Self {
+		Self {
+			state: Mutex::new(LimiterState {
+				slots: HashMap::new(),
+				last_used: HashMap::new(),
+				cooldown,
+				per_scraper_concurrency: per_scraper_concurrency.max(1),
+				overrides,
+			}),
+		}
+	}
+
+	/// Changes the limits. Scrapers already seen are resized in place, so calls holding a permit still count
+	/// against the new limit until they finish.
+	pub fn configure(&self, per_scraper_concurrency: usize, cooldown: Duration, overrides: HashMap<String, usize>) {
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		state.cooldown = cooldown;
+		state.per_scraper_concurrency = per_scraper_concurrency.max(1);
+		state.overrides = overrides;
+
+		let caps: Vec<(String, usize)> = state
+			.slots
+			.keys()
+			.map(|scraper| (scraper.clone(), state.cap(scraper)))
+			.collect();
+		for (scraper, cap) in caps {
+			if let Some(slots) = state.slots.get_mut(&scraper) {
+				slots.resize(cap);
+			}
+		}
+	}
+
+	/// Applies the limits of `config`, using the defaults for the ones left at zero.
+	pub fn configure_from(&self, config: LimitsConfig) {
+		let per_scraper_concurrency = match config.default_per_scraper_concurrency {
+			0 => DEFAULT_PER_SCRAPER_CONCURRENCY,
+			concurrency => concurrency,
+		};
+		let cooldown = match config.cooldown_seconds {
+			0 => DEFAULT_COOLDOWN,
+			secs => Duration::from_secs(secs),
+		};
+		self.configure(
+			per_scraper_concurrency,
+			cooldown,
+			config.per_scraper_limits.into_iter().collect(),
+		);
+	}
+
+	/// Every scraper the limiter has seen, sorted by name.
+	pub fn snapshots(&self) -> Vec<LimiterSnapshot> {
+		let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let mut snapshots: Vec<LimiterSnapshot> = state
+			.slots
+			.iter()
+			.map(|(scraper, slots)| {
+				let max_concurrency = slots.cap;
+				let cooldown_remaining = state
+					.last_used
+					.get(scraper)
+					.and_then(|at| state.cooldown.checked_sub(at.elapsed()))
+					.unwrap_or_default();
+
+				LimiterSnapshot {
+					scraper: scraper.clone(),
+					max_concurrency,
+					in_use: slots.in_use(),
+					cooldown_remaining,
+				}
+			})
+			.collect();
+
+		snapshots.sort_by(|a, b| a.scraper.cmp(&b.scraper));
+		snapshots
+	}
+
+	fn needs_cooldown(&self, scraper: &str) -> Option<Duration> {
+		let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let elapsed = state.last_used.get(scraper)?.elapsed();
+		state.cooldown.checked_sub(elapsed).filter(|d| !d.is_zero())
+	}
+
+	fn semaphore(&self, scraper: &str) -> Arc<Semaphore> {
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let cap = state.cap(scraper);
+		state.last_used.insert(scraper.to_string(), Instant::now());
+		Arc::clone(
+			&state
+				.slots
+				.entry(scraper.to_string())
+				.or_insert_with(|| Slots::new(cap))
+				.semaphore,
+		)
+	}
+
+	/// Waits out the scraper's cooldown, then for a free slot. Hold the permit for the duration of the call.
+	pub async fn acquire(&self, scraper: &str) -> Option<OwnedSemaphorePermit> {
+		if let Some(delay) = self.needs_cooldown(scraper) {
+			tokio::time::sleep(delay).await;
+		}
+
+		self.semaphore(scraper).acquire_owned().await.ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_acquire_respects_limits() {
+		let overrides = HashMap::from([("slow".to_string(), 1)]);
+		let limiter = ScraperLimiter::new(2, Duration::ZERO, overrides);
+
+		let first = limiter.acquire("fast").await.expect("permit");
+		let _second = limiter.acquire("fast").await.expect("permit");
+		let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("fast")).await;
+		assert!(third.is_err(), "a third concurrent call should wait");
+
+		drop(first);
+		let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("fast")).await;
+		assert!(third.is_ok(), "a released slot should be reused");
+
+		let _slow = limiter.acquire("slow").await.expect("permit");
+		let second_slow = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("slow")).await;
+		assert!(second_slow.is_err(), "overrides should cap the scraper");
+
+		let snapshots = limiter.snapshots();
+		assert_eq!(snapshots.len(), 2);
+		assert_eq!((snapshots[0].scraper.as_str(), snapshots[0].in_use), ("fast", 2));
+		assert_eq!((snapshots[1].scraper.as_str(), snapshots[1].max_concurrency), ("slow", 1));
+	}
+
+	#[tokio::test]
+	async fn test_configure_resizes_in_place() {
+		let limiter = ScraperLimiter::new(2, Duration::ZERO, HashMap::new());
+		let first = limiter.acquire("site").await.expect("permit");
+		let second = limiter.acquire("site").await.expect("permit");
+
+		limiter.configure(1, Duration::ZERO, HashMap::new());
+		// Let the reclaiming task queue up for the next returned permit.
+		tokio::task::yield_now().await;
+		assert_eq!(limiter.snapshots()[0].in_use, 2);
+		drop(first);
+		let waiting = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("site")).await;
+		assert!(waiting.is_err(), "the lowered cap should hold while the old call runs");
+
+		drop(second);
+		let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("site")).await;
+		assert!(third.is_ok(), "a slot should free up once in-flight calls fit the new cap");
+		let fourth = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("site")).await;
+		assert!(fourth.is_err());
+
+		limiter.configure(3, Duration::ZERO, HashMap::new());
+		let snapshot = &limiter.snapshots()[0];
+		assert_eq!((snapshot.max_concurrency, snapshot.in_use), (3, 1));
+	}
+
+	#[tokio::test]
+	async fn test_acquire_waits_for_cooldown() {
+		let limiter = ScraperLimiter::new(2, Duration::from_millis(100), HashMap::new());
+
+		let _first = limiter.acquire("site").await.expect("permit");
+		let start = Instant::now();
+		let _second = limiter.acquire("site").await.expect("permit");
+		assert!(start.elapsed() >= Duration::from_millis(50));
+	}
+}
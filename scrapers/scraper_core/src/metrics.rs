@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use telemetry::metrics::registry;
+
+use crate::rate_limit::host_of;
+
+/// Counts a response of a scraped site by the client that sent the request. `status` is `None` when the request
+/// got no response at all.
+pub fn record_upstream_response(client: &str, url: &str, status: Option<u16>) {
+	record_response(client, &host_of(url).unwrap_or_default(), status);
+}
+
+/// Like [`record_upstream_response`] for urls a client was handed, such as images proxied for readers. Those can
+/// point anywhere, so they are counted under the scraper they belong to, or `other`, instead of their host.
+pub fn record_proxied_response(client: &str, scraper: Option<&str>, status: Option<u16>) {
+	record_response(client, scraper.unwrap_or("other"), status);
+}
+
+fn record_response(client: &str, host: &str, status: Option<u16>) {
+	let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+	registry().increment(
+		"upstream_requests_total",
+		"Requests sent to scraped sites, by client, host and response status.",
+		&[("client", client), ("host", host), ("status", &status)],
+	);
+}
+
+pub(crate) fn record_plugin_call(scraper: &str, call: &str, latency: Duration) {
+	registry().observe(
+		"scraper_call_duration_seconds",
+		"Duration of plugin calls, failed ones included.",
+		&[("scraper", scraper), ("call", call)],
+		latency.as_secs_f64(),
+	);
+}
@@ -0,0 +1,377 @@
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+
+use reqwest::Url;
+use reqwest::header::HeaderValue;
+
+use crate::Config;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCookie {
+	pub name: String,
+	pub value: String,
+	pub domain: Option<String>,
+	pub path: Option<String>,
+	/// Seconds until the cookie expires. `None` keeps it for the lifetime of the jar.
+	pub max_age: Option<i64>,
+}
+
+impl SessionCookie {
+	fn to_set_cookie(&self) -> String {
+		let mut header = format!("{}={}", self.name, self.value);
+		if let Some(domain) = &self.domain {
+			header.push_str(&format!("; Domain={}", domain));
+		}
+		header.push_str(&format!("; Path={}", self.path.as_deref().unwrap_or("/")));
+		if let Some(max_age) = self.max_age {
+			header.push_str(&format!("; Max-Age={}", max_age));
+		}
+		header
+	}
+}
+
+/// How long changes are batched before the jar is written to disk.
+const PERSIST_DELAY: Duration = Duration::from_secs(2);
+
+/// Keeps the temporary files of concurrent writes apart.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Every jar created by [`CookieJar::for_plugin`], so pending writes can be flushed on shutdown.
+static PLUGIN_JARS: Mutex<Vec<Weak<CookieJar>>> = Mutex::new(Vec::new());
+
+/// Writes every plugin jar with unsaved changes to disk.
+pub fn flush_all() {
+	let jars = {
+		let mut jars = PLUGIN_JARS.lock().unwrap_or_else(|e| e.into_inner());
+		jars.retain(|jar| jar.strong_count() > 0);
+		jars.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+	};
+	for jar in jars {
+		jar.flush();
+	}
+}
+
+/// Cookie store for a single plugin, optionally persisted to disk so sessions survive restarts.
+///
+/// Session cookies (without an expiry) are persisted too, since login flows usually rely on them.
+#[derive(Debug)]
+pub struct CookieJar {
+	store: RwLock<cookie_store::CookieStore>,
+	path: Option<PathBuf>,
+	dirty: AtomicBool,
+	/// Set while a write is waiting out `persist_delay`.
+	scheduled: AtomicBool,
+	/// Serializes writes so an older snapshot never replaces a newer one.
+	writing: Mutex<()>,
+	persist_delay: Duration,
+}
+
+impl Default for CookieJar {
+	fn default() -> Self {
+		Self::in_memory()
+	}
+}
+
+impl CookieJar {
+	pub fn in_memory() -> Self {
+		Self {
+			store: RwLock::new(cookie_store::CookieStore::default()),
+			path: None,
+			dirty: AtomicBool::new(false),
+			scheduled: AtomicBool::new(false),
+			writing: Mutex::new(()),
+			persist_delay: PERSIST_DELAY,
+		}
+	}
+
+	pub fn load(path: PathBuf) -> Self {
+		let store = match std::fs::File::open(&path) {
+			Ok(file) => cookie_store::serde::json::load_all(BufReader::new(file)).unwrap_or_else(|e| {
+				tracing::warn!("Failed to read cookie jar {}: {}", path.display(), e);
+				cookie_store::CookieStore::default()
+			}),
+			Err(_) => cookie_store::CookieStore::default(),
+		};
+
+		Self {
+			store: RwLock::new(store),
+			path: Some(path),
+			dirty: AtomicBool::new(false),
+			scheduled: AtomicBool::new(false),
+			writing: Mutex::new(()),
+			persist_delay: PERSIST_DELAY,
+		}
+	}
+
+	/// Jar for `plugin`, persisted under the configured cookie folder when there is one and flushed by [`flush_all`].
+	pub fn for_plugin(config: &Config, plugin: &str) -> Arc<Self> {
+		let Some(folder) = &config.cookie_jar_folder else {
+			return Arc::new(Self::in_memory());
+		};
+		let jar = Arc::new(Self::load(PathBuf::from(folder).join(format!("{}.json", plugin))));
+		PLUGIN_JARS
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.push(Arc::downgrade(&jar));
+		jar
+	}
+
+	pub fn get(&self, url: &Url) -> Vec<SessionCookie> {
+		let store = self.store.read().unwrap_or_else(|e| e.into_inner());
+		store
+			.matches(url)
+			.into_iter()
+			.map(|cookie| SessionCookie {
+				name: cookie.name().to_string(),
+				value: cookie.value().to_string(),
+				domain: cookie.domain().map(|d| d.to_string()),
+				path: cookie.path().map(|p| p.to_string()),
+				max_age: None,
+			})
+			.collect()
+	}
+
+	pub fn set(&self, url: &Url, cookie: &SessionCookie) -> Result<(), String> {
+		let mut store = self.store.write().unwrap_or_else(|e| e.into_inner());
+		store.parse(&cookie.to_set_cookie(), url).map_err(|e| e.to_string())?;
+		self.dirty.store(true, Ordering::Relaxed);
+		Ok(())
+	}
+
+	pub fn clear(&self) {
+		self.store.write().unwrap_or_else(|e| e.into_inner()).clear();
+		self.dirty.store(true, Ordering::Relaxed);
+	}
+
+	/// Schedules a write of the jar if it changed, off the calling task and batched with the changes that follow
+	/// within a short delay. Writes right away when called outside a Tokio runtime.
+	pub fn persist(self: &Arc<Self>) {
+		if self.path.is_none() || !self.dirty.load(Ordering::Relaxed) {
+			return;
+		}
+		let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+			self.flush();
+			return;
+		};
+		if self.scheduled.swap(true, Ordering::AcqRel) {
+			return;
+		}
+
+		let jar = Arc::clone(self);
+		runtime.spawn(async move {
+			tokio::time::sleep(jar.persist_delay).await;
+			jar.scheduled.store(false, Ordering::Release);
+			let _ = tokio::task::spawn_blocking(move || jar.flush()).await;
+		});
+	}
+
+	/// Writes the jar to disk now if it changed since it was last written.
+	pub fn flush(&self) {
+		let Some(path) = &self.path else { return };
+		let _writing = self.writing.lock().unwrap_or_else(|e| e.into_inner());
+		if !self.dirty.swap(false, Ordering::Relaxed) {
+			return;
+		}
+
+		if let Err(e) = self.write(path) {
+			tracing::warn!("Failed to save cookie jar {}: {}", path.display(), e);
+		}
+	}
+
+	fn write(&self, path: &Path) -> anyhow::Result<()> {
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+
+		let mut buf = Vec::new();
+		{
+			let store = self.store.read().unwrap_or_else(|e| e.into_inner());
+			cookie_store::serde::json::save_incl_expired_and_nonpersistent(&store, &mut buf)
+				.map_err(|e| anyhow::anyhow!(e))?;
+		}
+
+		let tmp = path.with_extension(format!(
+			"json.{}.{}.tmp",
+			std::process::id(),
+			TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+		));
+		if let Err(e) = write_private(&tmp, &buf).and_then(|_| std::fs::rename(&tmp, path)) {
+			let _ = std::fs::remove_file(&tmp);
+			return Err(e.into());
+		}
+		Ok(())
+	}
+}
+
+/// Writes `buf` to a new file only its owner can read, since the jar holds session cookies.
+fn write_private(path: &Path, buf: &[u8]) -> std::io::Result<()> {
+	let mut options = std::fs::OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+	#[cfg(unix)]
+	std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+	options.open(path)?.write_all(buf)
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+	fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+		let cookies = cookie_headers
+			.filter_map(|value| value.to_str().ok())
+			.filter_map(|value| cookie_store::RawCookie::parse(value.to_string()).ok())
+			.collect::<Vec<_>>();
+
+		if cookies.is_empty() {
+			return;
+		}
+
+		let mut store = self.store.write().unwrap_or_else(|e| e.into_inner());
+		store.store_response_cookies(cookies.into_iter(), url);
+		self.dirty.store(true, Ordering::Relaxed);
+	}
+
+	fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+		let store = self.store.read().unwrap_or_else(|e| e.into_inner());
+		let header = store
+			.get_request_values(url)
+			.map(|(name, value)| format!("{}={}", name, value))
+			.collect::<Vec<_>>()
+			.join("; ");
+
+		if header.is_empty() {
+			None
+		} else {
+			HeaderValue::from_str(&header).ok()
+		}
+	}
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use reqwest::cookie::CookieStore;
+
+	use super::*;
+
+	fn url(s: &str) -> Url {
+		Url::parse(s).unwrap()
+	}
+
+	#[test]
+	fn test_set_and_get() {
+		let jar = CookieJar::in_memory();
+		jar.set(
+			&url("https://example.com/login"),
+			&SessionCookie {
+				name: "session".to_string(),
+				value: "abc".to_string(),
+				domain: None,
+				path: None,
+				max_age: None,
+			},
+		)
+		.unwrap();
+
+		let cookies = jar.get(&url("https://example.com/manga/1"));
+		assert_eq!(cookies.len(), 1);
+		assert_eq!(cookies[0].value, "abc");
+		assert!(jar.get(&url("https://other.com/")).is_empty());
+
+		assert_eq!(
+			jar.cookies(&url("https://example.com/")).unwrap().to_str().unwrap(),
+			"session=abc"
+		);
+
+		jar.clear();
+		assert!(jar.get(&url("https://example.com/")).is_empty());
+	}
+
+	#[test]
+	fn test_response_cookies_are_stored() {
+		let jar = CookieJar::in_memory();
+		let header = HeaderValue::from_static("cf_clearance=token; Path=/; Max-Age=3600");
+		jar.set_cookies(&mut std::iter::once(&header), &url("https://example.com/"));
+
+		assert_eq!(jar.get(&url("https://example.com/chapter"))[0].name, "cf_clearance");
+	}
+
+	#[test]
+	fn test_persist_round_trip() {
+		let dir = std::env::temp_dir().join(format!("cookie-jar-test-{}", std::process::id()));
+		let path = dir.join("plugin.json");
+
+		let jar = Arc::new(CookieJar::load(path.clone()));
+		jar.set(
+			&url("https://example.com/"),
+			&SessionCookie {
+				name: "login".to_string(),
+				value: "1".to_string(),
+				domain: None,
+				path: None,
+				max_age: None,
+			},
+		)
+		.unwrap();
+		jar.persist();
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+		}
+		let reloaded = CookieJar::load(path);
+		assert_eq!(reloaded.get(&url("https://example.com/"))[0].value, "1");
+
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	#[tokio::test]
+	async fn test_flush_all_writes_pending_jars() {
+		let dir = std::env::temp_dir().join(format!("cookie-jar-flush-test-{}", std::process::id()));
+		let config = Config {
+			cookie_jar_folder: Some(dir.to_string_lossy().into_owned()),
+			..Config::default()
+		};
+
+		let jar = CookieJar::for_plugin(&config, "plugin");
+		let header = HeaderValue::from_static("login=1; Path=/; Max-Age=3600");
+		jar.set_cookies(&mut std::iter::once(&header), &url("https://example.com/"));
+		jar.persist();
+		assert!(!dir.join("plugin.json").exists());
+
+		flush_all();
+		let reloaded = CookieJar::load(dir.join("plugin.json"));
+		assert_eq!(reloaded.get(&url("https://example.com/"))[0].value, "1");
+
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	#[tokio::test]
+	async fn test_persist_is_batched() {
+		let dir = std::env::temp_dir().join(format!("cookie-jar-batch-test-{}", std::process::id()));
+		let path = dir.join("plugin.json");
+
+		let mut jar = CookieJar::load(path.clone());
+		jar.persist_delay = Duration::from_millis(50);
+		let jar = Arc::new(jar);
+		for value in ["1", "2"] {
+			let header = HeaderValue::from_str(&format!("login={}; Path=/; Max-Age=3600", value)).unwrap();
+			jar.set_cookies(&mut std::iter::once(&header), &url("https://example.com/"));
+			jar.persist();
+		}
+		assert!(!path.exists());
+
+		for _ in 0..100 {
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			if !jar.scheduled.load(Ordering::Acquire) && !jar.dirty.load(Ordering::Relaxed) && path.exists() {
+				break;
+			}
+		}
+		let reloaded = CookieJar::load(path);
+		assert_eq!(reloaded.get(&url("https://example.com/"))[0].value, "2");
+		assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+		let _ = std::fs::remove_dir_all(dir);
+	}
+}
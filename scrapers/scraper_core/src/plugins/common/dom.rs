@@ -0,0 +1,312 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use ego_tree::NodeId;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::plugins::common::html::HtmlError;
+use crate::plugins::common::xpath::{self, XPathItem};
+
+/// A parsed HTML document shared by all nodes selected from it, so the page is only parsed once.
+#[derive(Clone)]
+pub struct DomDocument {
+	tree: Arc<Mutex<Html>>,
+}
+
+/// A handle to an element inside a [`DomDocument`].
+#[derive(Clone)]
+pub struct DomNode {
+	tree: Arc<Mutex<Html>>,
+	id: NodeId,
+}
+
+impl DomDocument {
+	pub fn parse(html: &str) -> Self {
+		Self {
+			tree: Arc::new(Mutex::new(Html::parse_document(html))),
+		}
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Html> {
+		self.tree.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	pub fn root(&self) -> DomNode {
+		let id = self.lock().root_element().id();
+		DomNode {
+			tree: self.tree.clone(),
+			id,
+		}
+	}
+
+	pub fn select(&self, selector: &str) -> Result<Vec<DomNode>, HtmlError> {
+		let sel = parse_selector(selector)?;
+		let ids: Vec<NodeId> = self.lock().select(&sel).map(|el| el.id()).collect();
+		Ok(ids.into_iter().map(|id| self.node(id)).collect())
+	}
+
+	pub fn select_one(&self, selector: &str) -> Result<Option<DomNode>, HtmlError> {
+		let sel = parse_selector(selector)?;
+		let id = self.lock().select(&sel).next().map(|el| el.id());
+		Ok(id.map(|id| self.node(id)))
+	}
+
+	pub fn xpath(&self, expr: &str) -> Result<Vec<DomNode>, HtmlError> {
+		let root = self.lock().tree.root().id();
+		self.node(root).xpath(expr)
+	}
+
+	pub fn xpath_text(&self, expr: &str) -> Result<Vec<String>, HtmlError> {
+		let root = self.lock().tree.root().id();
+		self.node(root).xpath_text(expr)
+	}
+
+	fn node(&self, id: NodeId) -> DomNode {
+		DomNode {
+			tree: self.tree.clone(),
+			id,
+		}
+	}
+}
+
+impl DomNode {
+	fn with_element<T>(&self, f: impl FnOnce(ElementRef<'_>) -> T) -> Option<T> {
+		let html = self.tree.lock().unwrap_or_else(|e| e.into_inner());
+		html.tree.get(self.id).and_then(ElementRef::wrap).map(f)
+	}
+
+	fn sibling(&self, id: NodeId) -> DomNode {
+		DomNode {
+			tree: self.tree.clone(),
+			id,
+		}
+	}
+
+	fn siblings_of(&self, ids: Vec<NodeId>) -> Vec<DomNode> {
+		ids.into_iter().map(|id| self.sibling(id)).collect()
+	}
+
+	pub fn name(&self) -> String {
+		self.with_element(|el| el.value().name().to_string()).unwrap_or_default()
+	}
+
+	pub fn select(&self, selector: &str) -> Result<Vec<DomNode>, HtmlError> {
+		let sel = parse_selector(selector)?;
+		let ids = self
+			.with_element(|el| el.select(&sel).map(|e| e.id()).collect())
+			.unwrap_or_default();
+		Ok(self.siblings_of(ids))
+	}
+
+	pub fn select_one(&self, selector: &str) -> Result<Option<DomNode>, HtmlError> {
+		let sel = parse_selector(selector)?;
+		let id = self.with_element(|el| el.select(&sel).next().map(|e| e.id())).flatten();
+		Ok(id.map(|id| self.sibling(id)))
+	}
+
+	pub fn children(&self) -> Vec<DomNode> {
+		let ids = self
+			.with_element(|el| el.children().filter_map(ElementRef::wrap).map(|e| e.id()).collect())
+			.unwrap_or_default();
+		self.siblings_of(ids)
+	}
+
+	pub fn parent(&self) -> Option<DomNode> {
+		self.with_element(|el| el.parent().and_then(ElementRef::wrap).map(|e| e.id()))
+			.flatten()
+			.map(|id| self.sibling(id))
+	}
+
+	pub fn next_sibling(&self) -> Option<DomNode> {
+		self.with_element(|el| el.next_siblings().find_map(ElementRef::wrap).map(|e| e.id()))
+			.flatten()
+			.map(|id| self.sibling(id))
+	}
+
+	pub fn prev_sibling(&self) -> Option<DomNode> {
+		self.with_element(|el| el.prev_siblings().find_map(ElementRef::wrap).map(|e| e.id()))
+			.flatten()
+			.map(|id| self.sibling(id))
+	}
+
+	/// Element siblings in document order, excluding the node itself.
+	pub fn siblings(&self) -> Vec<DomNode> {
+		let ids = self
+			.with_element(|el| match el.parent() {
+				Some(parent) => parent
+					.children()
+					.filter_map(ElementRef::wrap)
+					.map(|e| e.id())
+					.filter(|id| *id != self.id)
+					.collect(),
+				None => Vec::new(),
+			})
+			.unwrap_or_default();
+		self.siblings_of(ids)
+	}
+
+	/// All descendant text, unmodified.
+	pub fn text(&self) -> String {
+		self.with_element(|el| el.text().collect::<String>()).unwrap_or_default()
+	}
+
+	/// Descendant text with whitespace runs collapsed and trimmed, close to what a browser renders.
+	pub fn inner_text(&self) -> String {
+		self.text().split_whitespace().collect::<Vec<_>>().join(" ")
+	}
+
+	pub fn inner_html(&self) -> String {
+		self.with_element(|el| el.inner_html()).unwrap_or_default()
+	}
+
+	pub fn outer_html(&self) -> String {
+		self.with_element(|el| el.html()).unwrap_or_default()
+	}
+
+	pub fn attr(&self, name: &str) -> Option<String> {
+		self.with_element(|el| el.value().attr(name).map(|v| v.to_string())).flatten()
+	}
+
+	pub fn attrs(&self) -> Vec<(String, String)> {
+		self.with_element(|el| {
+			el.value()
+				.attrs()
+				.map(|(name, value)| (name.to_string(), value.to_string()))
+				.collect()
+		})
+		.unwrap_or_default()
+	}
+
+	pub fn xpath(&self, expr: &str) -> Result<Vec<DomNode>, HtmlError> {
+		let items = self.evaluate(expr)?;
+		let ids = items
+			.into_iter()
+			.filter_map(|item| match item {
+				XPathItem::Node(id) => Some(id),
+				_ => None,
+			})
+			.collect();
+		Ok(self.siblings_of(ids))
+	}
+
+	/// Evaluates `expr` and returns its string values: text of nodes, attribute values and text nodes.
+	pub fn xpath_text(&self, expr: &str) -> Result<Vec<String>, HtmlError> {
+		let items = self.evaluate(expr)?;
+		let html = self.tree.lock().unwrap_or_else(|e| e.into_inner());
+		Ok(items
+			.into_iter()
+			.map(|item| match item {
+				XPathItem::Node(id) => html
+					.tree
+					.get(id)
+					.and_then(ElementRef::wrap)
+					.map(|el| el.text().collect::<String>())
+					.unwrap_or_default(),
+				XPathItem::Text(text) | XPathItem::Attr(text) => text,
+			})
+			.collect())
+	}
+
+	fn evaluate(&self, expr: &str) -> Result<Vec<XPathItem>, HtmlError> {
+		let path = xpath::parse(expr)?;
+		let html = self.tree.lock().unwrap_or_else(|e| e.into_inner());
+		Ok(xpath::evaluate(&html, self.id, &path))
+	}
+
+	/// First match of `pattern` in the node's inner text. Returns the first capture group when the pattern has one.
+	pub fn regex_match(&self, pattern: &str) -> Result<Option<String>, HtmlError> {
+		let re = parse_regex(pattern)?;
+		let text = self.inner_text();
+		Ok(re.captures(&text).and_then(|caps| capture_value(&caps)))
+	}
+
+	pub fn regex_match_all(&self, pattern: &str) -> Result<Vec<String>, HtmlError> {
+		let re = parse_regex(pattern)?;
+		let text = self.inner_text();
+		Ok(re.captures_iter(&text).filter_map(|caps| capture_value(&caps)).collect())
+	}
+}
+
+fn capture_value(caps: &regex::Captures<'_>) -> Option<String> {
+	caps.get(1).or_else(|| caps.get(0)).map(|m| m.as_str().to_string())
+}
+
+fn parse_selector(selector: &str) -> Result<Selector, HtmlError> {
+	Selector::parse(selector).map_err(|_| HtmlError::InvalidSelector(selector.to_string()))
+}
+
+fn parse_regex(pattern: &str) -> Result<Regex, HtmlError> {
+	Regex::new(pattern).map_err(|e| HtmlError::Regex(e.to_string()))
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	const PAGE: &str = r#"
+		<html><body>
+			<div id="info">
+				<h1 class="title">  The   Title </h1>
+				<span class="status">Status: Ongoing</span>
+				<ul class="genres"><li>Action</li><li>Drama</li><li>Fantasy</li></ul>
+			</div>
+			<a href="/chapter-2">Chapter 2</a>
+			<a href="/chapter-1">Chapter 1</a>
+		</body></html>
+	"#;
+
+	#[test]
+	fn test_select_and_navigate() {
+		let doc = DomDocument::parse(PAGE);
+		let title = doc.select_one("h1.title").unwrap().expect("title");
+		assert_eq!(title.inner_text(), "The Title");
+		assert_eq!(title.name(), "h1");
+		assert_eq!(title.parent().unwrap().attr("id").as_deref(), Some("info"));
+		assert_eq!(title.next_sibling().unwrap().attr("class").as_deref(), Some("status"));
+		assert_eq!(title.siblings().len(), 2);
+
+		let genres = doc.select_one("ul.genres").unwrap().unwrap();
+		let names: Vec<String> = genres.children().iter().map(|li| li.inner_text()).collect();
+		assert_eq!(names, vec!["Action", "Drama", "Fantasy"]);
+		assert_eq!(genres.children()[1].prev_sibling().unwrap().inner_text(), "Action");
+	}
+
+	#[test]
+	fn test_attrs_and_html() {
+		let doc = DomDocument::parse(PAGE);
+		let links = doc.select("a").unwrap();
+		assert_eq!(links.len(), 2);
+		assert_eq!(links[0].attrs(), vec![("href".to_string(), "/chapter-2".to_string())]);
+		assert_eq!(links[0].outer_html(), r#"<a href="/chapter-2">Chapter 2</a>"#);
+		assert_eq!(links[0].inner_html(), "Chapter 2");
+	}
+
+	#[test]
+	fn test_invalid_selector() {
+		let doc = DomDocument::parse(PAGE);
+		assert!(matches!(doc.select("<<"), Err(HtmlError::InvalidSelector(_))));
+	}
+
+	#[test]
+	fn test_regex_helpers() {
+		let doc = DomDocument::parse(PAGE);
+		let status = doc.select_one(".status").unwrap().unwrap();
+		assert_eq!(status.regex_match(r"Status:\s*(\w+)").unwrap().as_deref(), Some("Ongoing"));
+
+		let body = doc.select_one("body").unwrap().unwrap();
+		assert_eq!(body.regex_match_all(r"Chapter \d+").unwrap(), vec!["Chapter 2", "Chapter 1"]);
+		assert!(matches!(body.regex_match("("), Err(HtmlError::Regex(_))));
+	}
+
+	#[test]
+	fn test_xpath() {
+		let doc = DomDocument::parse(PAGE);
+		assert_eq!(doc.xpath_text("//ul[@class='genres']/li[2]/text()").unwrap(), vec!["Drama"]);
+		assert_eq!(doc.xpath_text("//a/@href").unwrap(), vec!["/chapter-2", "/chapter-1"]);
+
+		let info = doc.xpath("//div[@id='info']").unwrap();
+		assert_eq!(info.len(), 1);
+		assert_eq!(info[0].xpath("./ul/li").unwrap().len(), 3);
+	}
+}
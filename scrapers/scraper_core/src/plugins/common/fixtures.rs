@@ -0,0 +1,499 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::Url;
+use scraper_types::{ScraperError, ScraperErrorKind};
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::common::headless::HeadlessError;
+use crate::plugins::common::headless::traits::{HeadlessBackend, HeadlessElement};
+use crate::plugins::common::http::{RequestBody, RequestOptions, Response};
+use crate::{Config, FixtureMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractionKind {
+	Http,
+	FlareSolverr,
+	Headless,
+}
+
+/// What identifies a request when looking up its recorded answer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FixtureRequest {
+	pub kind: InteractionKind,
+	/// HTTP method, or the headless operation (`goto`, `find`, `find_all`).
+	pub method: String,
+	/// Full URL including the query string, or the selector for headless lookups.
+	pub target: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub body: Option<String>,
+}
+
+impl FixtureRequest {
+	pub fn http(url: &str, options: &RequestOptions) -> Self {
+		let target = match Url::parse(url) {
+			Ok(mut parsed) if !options.query.is_empty() => {
+				parsed.query_pairs_mut().extend_pairs(&options.query);
+				parsed.to_string()
+			}
+			_ => url.to_string(),
+		};
+
+		let body = options.body.as_ref().map(|body| match body {
+			RequestBody::Text(text) => text.clone(),
+			RequestBody::Bytes(bytes) => BASE64.encode(bytes),
+			RequestBody::Form(fields) => fields
+				.iter()
+				.map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+				.collect::<Vec<_>>()
+				.join("&"),
+			RequestBody::Multipart(parts) => parts
+				.iter()
+				.map(|part| format!("{}:{}", part.name, BASE64.encode(&part.data)))
+				.collect::<Vec<_>>()
+				.join(";"),
+		});
+
+		Self {
+			kind: InteractionKind::Http,
+			method: format!("{:?}", options.method).to_uppercase(),
+			target,
+			body,
+		}
+	}
+
+	pub fn flaresolverr(url: &str) -> Self {
+		Self {
+			kind: InteractionKind::FlareSolverr,
+			method: "GET".to_string(),
+			target: url.to_string(),
+			body: None,
+		}
+	}
+
+	fn headless(operation: &str, target: &str) -> Self {
+		Self {
+			kind: InteractionKind::Headless,
+			method: operation.to_string(),
+			target: target.to_string(),
+			body: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedElement {
+	pub selector: String,
+	pub html: String,
+	pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+	pub request: FixtureRequest,
+	#[serde(default)]
+	pub status: u16,
+	#[serde(default)]
+	pub headers: HashMap<String, String>,
+	#[serde(default)]
+	pub body: String,
+	/// Base64 encoded body of binary responses.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub bytes: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<ScraperError>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub elements: Vec<RecordedElement>,
+}
+
+impl Interaction {
+	fn from_response(request: FixtureRequest, response: &Response) -> Self {
+		Self {
+			request,
+			status: response.status,
+			headers: response.headers.clone(),
+			body: response.text.clone(),
+			bytes: response.bytes.as_ref().map(|bytes| BASE64.encode(bytes)),
+			error: response.error.clone(),
+			elements: Vec::new(),
+		}
+	}
+
+	pub fn into_response(self) -> Response {
+		let bytes = self.bytes.and_then(|encoded| BASE64.decode(encoded).ok());
+		match (self.error, bytes) {
+			(Some(error), _) if self.status == 0 => Response::from_error(error),
+			(_, Some(bytes)) => Response::from_bytes(bytes, self.status, self.headers),
+			_ => Response::from_parts(self.body, self.status, self.headers),
+		}
+	}
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FixtureFile {
+	interactions: Vec<Interaction>,
+}
+
+/// Network traffic of a single plugin, either being captured from the live sites or served back from disk.
+///
+/// Replayed requests are answered in the order they were recorded. Once a request runs out of answers the
+/// last one keeps being served, so retries and repeated calls still work offline.
+///
+/// Recorded traffic is kept in memory and written out by [`Fixtures::flush`], which also runs on drop.
+#[derive(Debug)]
+pub struct Fixtures {
+	mode: FixtureMode,
+	path: PathBuf,
+	recorded: Mutex<Vec<Interaction>>,
+	replay: Mutex<HashMap<FixtureRequest, VecDeque<Interaction>>>,
+}
+
+impl Fixtures {
+	/// Fixtures for `plugin` when the config enables recording or replaying, stored as `<folder>/<plugin>.json`.
+	pub fn for_plugin(config: &Config, plugin: &str) -> Option<Arc<Self>> {
+		let fixtures = config.fixtures.as_ref()?;
+		let path = PathBuf::from(&fixtures.folder).join(format!("{}.json", plugin));
+		Some(Arc::new(match fixtures.mode {
+			FixtureMode::Record => Self::record(path),
+			FixtureMode::Replay => Self::replay(path),
+		}))
+	}
+
+	pub fn record(path: PathBuf) -> Self {
+		Self {
+			mode: FixtureMode::Record,
+			path,
+			recorded: Mutex::new(Vec::new()),
+			replay: Mutex::new(HashMap::new()),
+		}
+	}
+
+	pub fn replay(path: PathBuf) -> Self {
+		let file = match std::fs::read(&path) {
+			Ok(data) => serde_json::from_slice::<FixtureFile>(&data).unwrap_or_else(|e| {
+				tracing::warn!("Failed to parse fixtures {}: {}", path.display(), e);
+				FixtureFile::default()
+			}),
+			Err(e) => {
+				tracing::warn!("Failed to read fixtures {}: {}", path.display(), e);
+				FixtureFile::default()
+			}
+		};
+
+		let mut replay: HashMap<FixtureRequest, VecDeque<Interaction>> = HashMap::new();
+		for interaction in file.interactions {
+			replay.entry(interaction.request.clone()).or_default().push_back(interaction);
+		}
+
+		Self {
+			mode: FixtureMode::Replay,
+			path,
+			recorded: Mutex::new(Vec::new()),
+			replay: Mutex::new(replay),
+		}
+	}
+
+	pub fn is_replaying(&self) -> bool {
+		self.mode == FixtureMode::Replay
+	}
+
+	/// Whether the recording contains any traffic of `kind`.
+	pub fn has_kind(&self, kind: InteractionKind) -> bool {
+		let replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+		replay.keys().any(|request| request.kind == kind)
+	}
+
+	pub fn next(&self, request: &FixtureRequest) -> Option<Interaction> {
+		let mut replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+		let answers = replay.get_mut(request)?;
+		if answers.len() > 1 {
+			answers.pop_front()
+		} else {
+			answers.front().cloned()
+		}
+	}
+
+	/// Recorded answer to `request` as a response, or a network error when nothing was recorded for it.
+	pub fn replay_response(&self, request: &FixtureRequest) -> Response {
+		match self.next(request) {
+			Some(interaction) => interaction.into_response(),
+			None => Response::from_error(missing_fixture(request)),
+		}
+	}
+
+	pub fn record_response(&self, request: FixtureRequest, response: &Response) {
+		self.push(Interaction::from_response(request, response));
+	}
+
+	pub fn push(&self, interaction: Interaction) {
+		self.recorded.lock().unwrap_or_else(|e| e.into_inner()).push(interaction);
+	}
+
+	/// Writes the recording to the fixture file. Does nothing when replaying or when nothing was recorded.
+	pub fn flush(&self) {
+		let recorded = self.recorded.lock().unwrap_or_else(|e| e.into_inner());
+		if self.mode != FixtureMode::Record || recorded.is_empty() {
+			return;
+		}
+
+		if let Err(e) = self.write(&recorded) {
+			tracing::warn!("Failed to save fixtures {}: {}", self.path.display(), e);
+		}
+	}
+
+	fn write(&self, interactions: &[Interaction]) -> anyhow::Result<()> {
+		if let Some(parent) = self.path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+
+		let file = FixtureFile {
+			interactions: interactions.to_vec(),
+		};
+		let tmp = self.path.with_extension("json.tmp");
+		std::fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+		std::fs::rename(&tmp, &self.path)?;
+		Ok(())
+	}
+}
+
+impl Drop for Fixtures {
+	fn drop(&mut self) {
+		self.flush();
+	}
+}
+
+fn missing_fixture(request: &FixtureRequest) -> ScraperError {
+	ScraperError::with_retryable(
+		ScraperErrorKind::Network,
+		format!("no recorded fixture for {} {}", request.method, request.target),
+		false,
+	)
+}
+
+/// Headless backend that records what `inner` sees, or answers from the recording without a browser.
+pub struct FixtureHeadless {
+	inner: Option<Arc<dyn HeadlessBackend>>,
+	fixtures: Arc<Fixtures>,
+}
+
+impl FixtureHeadless {
+	pub fn record(inner: Arc<dyn HeadlessBackend>, fixtures: Arc<Fixtures>) -> Self {
+		Self {
+			inner: Some(inner),
+			fixtures,
+		}
+	}
+
+	pub fn replay(fixtures: Arc<Fixtures>) -> Self {
+		Self { inner: None, fixtures }
+	}
+
+	fn answer(&self, request: FixtureRequest) -> Result<Interaction, HeadlessError> {
+		let interaction = self
+			.fixtures
+			.next(&request)
+			.ok_or_else(|| HeadlessError::BrowserError(missing_fixture(&request).message))?;
+		match &interaction.error {
+			Some(error) => Err(HeadlessError::BrowserError(error.message.clone())),
+			None => Ok(interaction),
+		}
+	}
+
+	fn save(&self, request: FixtureRequest, elements: Vec<RecordedElement>, error: Option<&HeadlessError>) {
+		self.fixtures.push(Interaction {
+			request,
+			status: 0,
+			headers: HashMap::new(),
+			body: String::new(),
+			bytes: None,
+			error: error.map(|e| ScraperError::new(ScraperErrorKind::Internal, e.to_string())),
+			elements,
+		});
+	}
+
+	async fn find_elements(
+		&self,
+		inner: &Arc<dyn HeadlessBackend>,
+		operation: &str,
+		selector: String,
+	) -> Result<Vec<Box<dyn HeadlessElement>>, HeadlessError> {
+		let request = FixtureRequest::headless(operation, &selector);
+		let result = if operation == "find" {
+			inner
+				.find(selector)
+				.await
+				.map(|element| element.into_iter().collect::<Vec<_>>())
+		} else {
+			inner.find_all(selector).await
+		};
+
+		match result {
+			Ok(elements) => {
+				let mut recorded = Vec::with_capacity(elements.len());
+				for element in &elements {
+					recorded.push(RecordedElement {
+						selector: element._selector().unwrap_or_default(),
+						html: element._html().await.unwrap_or_default(),
+						text: element.text().await.unwrap_or_default(),
+					});
+				}
+				self.save(request, recorded, None);
+				Ok(elements)
+			}
+			Err(e) => {
+				self.save(request, Vec::new(), Some(&e));
+				Err(e)
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl HeadlessBackend for FixtureHeadless {
+	async fn goto(&self, url: String) -> Result<(), HeadlessError> {
+		let request = FixtureRequest::headless("goto", &url);
+		let Some(inner) = &self.inner else {
+			return self.answer(request).map(|_| ());
+		};
+
+		let result = inner.goto(url).await;
+		self.save(request, Vec::new(), result.as_ref().err());
+		result
+	}
+
+	async fn find(&self, selector: String) -> Result<Option<Box<dyn HeadlessElement>>, HeadlessError> {
+		let Some(inner) = &self.inner else {
+			let interaction = self.answer(FixtureRequest::headless("find", &selector))?;
+			return Ok(interaction
+				.elements
+				.into_iter()
+				.next()
+				.map(|element| Box::new(element) as Box<dyn HeadlessElement>));
+		};
+
+		let mut elements = self.find_elements(inner, "find", selector).await?;
+		Ok(elements.pop())
+	}
+
+	async fn find_all(&self, selector: String) -> Result<Vec<Box<dyn HeadlessElement>>, HeadlessError> {
+		let Some(inner) = &self.inner else {
+			let interaction = self.answer(FixtureRequest::headless("find_all", &selector))?;
+			return Ok(interaction
+				.elements
+				.into_iter()
+				.map(|element| Box::new(element) as Box<dyn HeadlessElement>)
+				.collect());
+		};
+
+		self.find_elements(inner, "find_all", selector).await
+	}
+
+	async fn close(&self) -> Result<(), HeadlessError> {
+		match &self.inner {
+			Some(inner) => inner.close().await,
+			None => Ok(()),
+		}
+	}
+}
+
+#[async_trait]
+impl HeadlessElement for RecordedElement {
+	async fn _html(&self) -> Result<String, HeadlessError> {
+		Ok(self.html.clone())
+	}
+
+	async fn text(&self) -> Result<String, HeadlessError> {
+		Ok(self.text.clone())
+	}
+
+	async fn click(&self) -> Result<(), HeadlessError> {
+		Ok(())
+	}
+
+	async fn _attr(&self, _name: &str) -> Result<Option<String>, HeadlessError> {
+		Ok(None)
+	}
+
+	fn _selector(&self) -> Option<String> {
+		Some(self.selector.clone())
+	}
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use mockito::Server;
+
+	use super::*;
+	use crate::plugins::common::http::CommonHttp;
+
+	fn fixture_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("scraper-fixtures-{}-{}.json", name, uuid::Uuid::new_v4()))
+	}
+
+	#[tokio::test]
+	async fn test_record_then_replay() {
+		let mut server = Server::new_async().await;
+		let mock = server
+			.mock("GET", "/manga?page=2")
+			.with_status(200)
+			.with_header("content-type", "text/html")
+			.with_body("<h1>One Piece</h1>")
+			.expect(1)
+			.create_async()
+			.await;
+
+		let path = fixture_path("roundtrip");
+		let url = format!("{}/manga", server.url());
+		let options = || RequestOptions {
+			query: vec![("page".to_string(), "2".to_string())],
+			..Default::default()
+		};
+
+		let recording = CommonHttp::new().with_fixtures(Arc::new(Fixtures::record(path.clone())));
+		let live = recording.request(url.clone(), options()).await;
+		assert_eq!(live.text, "<h1>One Piece</h1>");
+		mock.assert_async().await;
+		assert!(!path.exists());
+		drop(recording);
+
+		let replaying = CommonHttp::new().with_fixtures(Arc::new(Fixtures::replay(path.clone())));
+		for _ in 0..2 {
+			let replayed = replaying.request(url.clone(), options()).await;
+			assert!(replayed.ok);
+			assert_eq!(replayed.text, "<h1>One Piece</h1>");
+			assert_eq!(replayed.headers.get("content-type").map(String::as_str), Some("text/html"));
+		}
+		mock.assert_async().await;
+
+		let missing = replaying.get(format!("{}/other", server.url()), None).await;
+		assert!(!missing.ok);
+		assert!(missing.error.unwrap().message.contains("no recorded fixture"));
+
+		std::fs::remove_file(path).ok();
+	}
+
+	#[tokio::test]
+	async fn test_replayed_answers_keep_their_order() {
+		let path = fixture_path("order");
+		let fixtures = Fixtures::record(path.clone());
+		let request = FixtureRequest::flaresolverr("https://example.com/");
+		for body in ["first", "second"] {
+			fixtures.record_response(request.clone(), &Response::from_parts(body.to_string(), 200, HashMap::new()));
+		}
+		fixtures.flush();
+
+		let replay = Fixtures::replay(path.clone());
+		assert!(replay.has_kind(InteractionKind::FlareSolverr));
+		assert_eq!(replay.replay_response(&request).text, "first");
+		assert_eq!(replay.replay_response(&request).text, "second");
+		assert_eq!(replay.replay_response(&request).text, "second");
+
+		std::fs::remove_file(path).ok();
+	}
+}
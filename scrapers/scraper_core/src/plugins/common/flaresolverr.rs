@@ -9,7 +9,10 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::Config;
+use crate::plugins::common::cookies::SessionCookie;
+use crate::plugins::common::fixtures::{FixtureRequest, InteractionKind};
 use crate::plugins::common::http::{CommonHttp, Response};
+use crate::{metrics, rate_limit};
 
 #[derive(Clone)]
 struct FlareSession {
@@ -37,7 +40,9 @@ pub struct FlareSolverrManager {
 }
 
 impl FlareSolverrManager {
-	pub fn new(config: &Config) -> Self {
+	/// Creates a manager that shares cookies with `http`, so clearance cookies obtained through
+	/// FlareSolverr are reused by plain HTTP calls and the other way around.
+	pub fn with_http(config: &Config, http: CommonHttp) -> Self {
 		let url = if let Some(ref base_url) = config.flaresolverr_url {
 			if base_url.ends_with("/v1") {
 				base_url.clone()
@@ -53,7 +58,7 @@ impl FlareSolverrManager {
 			url,
 			client: reqwest::Client::new(),
 			global_session: Arc::new(RwLock::new(None)),
-			fallback: CommonHttp::new(),
+			fallback: http,
 		}
 	}
 
@@ -90,7 +95,10 @@ impl FlareSolverrManager {
 	}
 
 	pub fn using_flaresolverr(&self) -> bool {
-		!self.url.is_empty()
+		match self.fallback.fixtures() {
+			Some(fixtures) if fixtures.is_replaying() => fixtures.has_kind(InteractionKind::FlareSolverr),
+			_ => !self.url.is_empty(),
+		}
 	}
 
 	async fn create_session_internal(&self) -> Result<FlareSession, ScraperError> {
@@ -234,6 +242,21 @@ impl FlareSolverrManager {
 	}
 
 	pub async fn get(&self, target_url: String) -> Response {
+		let Some(fixtures) = self.fallback.fixtures().cloned() else {
+			return self.get_live(target_url).await;
+		};
+
+		let request = FixtureRequest::flaresolverr(&target_url);
+		if fixtures.is_replaying() {
+			return fixtures.replay_response(&request);
+		}
+
+		let response = self.get_live(target_url).await;
+		fixtures.record_response(request, &response);
+		response
+	}
+
+	async fn get_live(&self, target_url: String) -> Response {
 		match self.get_internal(target_url).await {
 			Ok(response) => response,
 			Err(error) => Response::from_error(error),
@@ -254,6 +277,7 @@ impl FlareSolverrManager {
 		}
 
 		self.determine_manager_type().await?;
+		rate_limit::limiter().acquire(parsed_target_url.as_str()).await;
 
 		let session_id_opt = self.get_or_refresh_session().await?;
 
@@ -272,11 +296,27 @@ impl FlareSolverrManager {
 				.insert("session".to_string(), serde_json::Value::String(session_id.to_string()));
 		}
 
+		let known_cookies = self.fallback.cookies().get(&parsed_target_url);
+		if !known_cookies.is_empty() {
+			let cookies = known_cookies
+				.iter()
+				.map(|c| serde_json::json!({ "name": c.name, "value": c.value }))
+				.collect::<Vec<_>>();
+			payload
+				.as_object_mut()
+				.unwrap()
+				.insert("cookies".to_string(), Value::Array(cookies));
+		}
+
 		let api_res = self.client.post(&self.url).json(&payload).send().await;
+		if api_res.is_err() {
+			metrics::record_upstream_response("flaresolverr", parsed_target_url.as_str(), None);
+		}
 		if let Ok(r) = api_res {
 			let status_from_http = r.status().as_u16();
 
 			if status_from_http != 200 {
+				metrics::record_upstream_response("flaresolverr", parsed_target_url.as_str(), Some(status_from_http));
 				tracing::error!("FlareSolverr returned a non-200 status: {}", status_from_http);
 				if let Ok(text) = r.text().await {
 					tracing::error!("FlareSolverr error response body: {}", text);
@@ -292,6 +332,8 @@ impl FlareSolverrManager {
 
 			if let Ok(text) = r.text().await {
 				if let Ok(v) = serde_json::from_str::<Value>(&text) {
+					self.import_solution_cookies(&v, &parsed_target_url);
+
 					let body_opt = v
 						.pointer("/solution/response/body")
 						.and_then(Value::as_str)
@@ -326,6 +368,13 @@ impl FlareSolverrManager {
 						}
 					}
 
+					let retry_after = headers_map
+						.iter()
+						.find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+						.map(|(_, value)| value.as_str());
+					rate_limit::limiter().record_response(parsed_target_url.as_str(), status, retry_after);
+					metrics::record_upstream_response("flaresolverr", parsed_target_url.as_str(), Some(status));
+
 					if let Some(body) = body_opt {
 						return Ok(Response::from_parts(body, status, headers_map));
 					}
@@ -343,4 +392,48 @@ impl FlareSolverrManager {
 
 		Ok(self.fallback.get(parsed_target_url.to_string(), None).await)
 	}
+
+	fn import_solution_cookies(&self, solution: &Value, url: &Url) {
+		if let Some(user_agent) = solution.pointer("/solution/userAgent").and_then(Value::as_str) {
+			self.fallback.set_user_agent(Some(user_agent.to_string()));
+		}
+
+		let Some(cookies) = solution.pointer("/solution/cookies").and_then(Value::as_array) else {
+			return;
+		};
+
+		let now = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.map(|d| d.as_secs_f64())
+			.unwrap_or_default();
+
+		for cookie in cookies {
+			let (Some(name), Some(value)) = (
+				cookie.get("name").and_then(Value::as_str),
+				cookie.get("value").and_then(Value::as_str),
+			) else {
+				continue;
+			};
+
+			let max_age = cookie
+				.get("expires")
+				.and_then(Value::as_f64)
+				.filter(|expires| *expires > 0.0)
+				.map(|expires| (expires - now).max(0.0) as i64);
+
+			let cookie = SessionCookie {
+				name: name.to_string(),
+				value: value.to_string(),
+				domain: cookie.get("domain").and_then(Value::as_str).map(|d| d.to_string()),
+				path: cookie.get("path").and_then(Value::as_str).map(|p| p.to_string()),
+				max_age,
+			};
+
+			if let Err(e) = self.fallback.cookies().set(url, &cookie) {
+				tracing::debug!("Ignoring FlareSolverr cookie {}: {}", cookie.name, e);
+			}
+		}
+
+		self.fallback.cookies().persist();
+	}
 }
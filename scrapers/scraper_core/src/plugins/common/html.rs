@@ -5,6 +5,10 @@ use scraper::{Html, Selector};
 pub enum HtmlError {
 	#[error("Invalid CSS selector: {0}")]
 	InvalidSelector(String),
+	#[error("Invalid XPath expression: {0}")]
+	XPath(String),
+	#[error("Invalid regex: {0}")]
+	Regex(String),
 }
 
 pub struct HtmlDocument {
@@ -1,12 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use reqwest::Url;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER, USER_AGENT};
+use reqwest::{Url, redirect};
 use scraper_types::{ScraperError, ScraperErrorKind};
 
+use crate::Config;
+use crate::plugins::common::cookies::CookieJar;
+use crate::plugins::common::fixtures::{FixtureRequest, Fixtures};
+use crate::plugins::plugin_key;
+use crate::{metrics, rate_limit};
+
 #[derive(Clone)]
 pub struct Response {
 	pub text: String,
+	/// Raw body, only set for requests made with [`RequestOptions::binary`]. `text` is empty in that case.
+	pub bytes: Option<Vec<u8>>,
 	pub status: u16,
 	pub headers: HashMap<String, String>,
 	pub ok: bool,
@@ -17,6 +28,7 @@ impl Response {
 	pub fn success(text: String, status: u16, headers: HashMap<String, String>) -> Self {
 		Self {
 			text,
+			bytes: None,
 			status,
 			headers,
 			ok: (200..300).contains(&status),
@@ -28,6 +40,7 @@ impl Response {
 		let error = ScraperError::from_http_status(status, format!("HTTP {}", status));
 		Self {
 			text,
+			bytes: None,
 			status,
 			headers,
 			ok: false,
@@ -39,6 +52,7 @@ impl Response {
 		let status = error.status_code.unwrap_or(0);
 		Self {
 			text: String::new(),
+			bytes: None,
 			status,
 			headers: HashMap::new(),
 			ok: false,
@@ -53,11 +67,151 @@ impl Response {
 			Self::http_error(status, headers, text)
 		}
 	}
+
+	pub fn from_bytes(bytes: Vec<u8>, status: u16, headers: HashMap<String, String>) -> Self {
+		Self {
+			bytes: Some(bytes),
+			..Self::from_parts(String::new(), status, headers)
+		}
+	}
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Method {
+	#[default]
+	Get,
+	Post,
+	Put,
+	Patch,
+	Delete,
+	Head,
+	Options,
+}
+
+impl Method {
+	pub fn parse(method: &str) -> Option<Self> {
+		match method.to_ascii_uppercase().as_str() {
+			"GET" => Some(Self::Get),
+			"POST" => Some(Self::Post),
+			"PUT" => Some(Self::Put),
+			"PATCH" => Some(Self::Patch),
+			"DELETE" => Some(Self::Delete),
+			"HEAD" => Some(Self::Head),
+			"OPTIONS" => Some(Self::Options),
+			_ => None,
+		}
+	}
+}
+
+impl From<Method> for reqwest::Method {
+	fn from(method: Method) -> Self {
+		match method {
+			Method::Get => reqwest::Method::GET,
+			Method::Post => reqwest::Method::POST,
+			Method::Put => reqwest::Method::PUT,
+			Method::Patch => reqwest::Method::PATCH,
+			Method::Delete => reqwest::Method::DELETE,
+			Method::Head => reqwest::Method::HEAD,
+			Method::Options => reqwest::Method::OPTIONS,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartPart {
+	pub name: String,
+	pub filename: Option<String>,
+	pub content_type: Option<String>,
+	pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestBody {
+	Text(String),
+	Bytes(Vec<u8>),
+	Form(Vec<(String, String)>),
+	Multipart(Vec<MultipartPart>),
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestOptions {
+	pub method: Method,
+	pub headers: HashMap<String, String>,
+	pub query: Vec<(String, String)>,
+	pub body: Option<RequestBody>,
+	/// Overrides the client's default 30 second timeout.
+	pub timeout: Option<Duration>,
+	pub follow_redirects: bool,
+	/// Keep the body as raw bytes instead of decoding it as text.
+	pub binary: bool,
+}
+
+impl Default for RequestOptions {
+	fn default() -> Self {
+		Self {
+			method: Method::Get,
+			headers: HashMap::new(),
+			query: Vec::new(),
+			body: None,
+			timeout: None,
+			follow_redirects: true,
+			binary: false,
+		}
+	}
+}
+
+/// A response whose body is read incrementally, for payloads too large to buffer.
+#[derive(Debug)]
+pub struct HttpStream {
+	pub status: u16,
+	pub headers: HashMap<String, String>,
+	body: tokio::sync::Mutex<StreamBody>,
+}
+
+#[derive(Debug)]
+enum StreamBody {
+	Live(reqwest::Response),
+	/// Body already read into memory, as happens when recording or replaying fixtures.
+	Buffered(VecDeque<Vec<u8>>),
+}
+
+impl HttpStream {
+	fn buffered(response: Response) -> Self {
+		let body = response.bytes.unwrap_or_else(|| response.text.into_bytes());
+		let chunks = if body.is_empty() {
+			VecDeque::new()
+		} else {
+			VecDeque::from([body])
+		};
+		Self {
+			status: response.status,
+			headers: response.headers,
+			body: tokio::sync::Mutex::new(StreamBody::Buffered(chunks)),
+		}
+	}
+
+	/// Next chunk of the body, or `None` once it has been fully read.
+	pub async fn next_chunk(&self) -> Result<Option<Vec<u8>>, ScraperError> {
+		let mut body = self.body.lock().await;
+		match &mut *body {
+			StreamBody::Live(response) => {
+				let chunk = response.chunk().await.map_err(|e| {
+					ScraperError::new(ScraperErrorKind::Network, format!("Failed to read response body: {}", e))
+				})?;
+				Ok(chunk.map(|bytes| bytes.to_vec()))
+			}
+			StreamBody::Buffered(chunks) => Ok(chunks.pop_front()),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
 pub struct CommonHttp {
 	client: reqwest::Client,
+	no_redirect_client: reqwest::Client,
+	cookies: Arc<CookieJar>,
+	user_agent: Arc<RwLock<Option<String>>>,
+	fixtures: Option<Arc<Fixtures>>,
 }
 
 impl Default for CommonHttp {
@@ -68,15 +222,57 @@ impl Default for CommonHttp {
 
 impl CommonHttp {
 	pub fn new() -> Self {
-		let client = reqwest::Client::builder()
-			.timeout(std::time::Duration::from_secs(30))
-			.user_agent(
-				"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/58.0.3029.110 Safari/537.36",
-			)
-			.build()
-			.expect("Failed to build HTTP client");
+		Self::with_cookies(Arc::new(CookieJar::in_memory()))
+	}
+
+	pub fn with_cookies(cookies: Arc<CookieJar>) -> Self {
+		Self {
+			client: build_client(&cookies, redirect::Policy::default()),
+			no_redirect_client: build_client(&cookies, redirect::Policy::none()),
+			cookies,
+			user_agent: Arc::new(RwLock::new(None)),
+			fixtures: None,
+		}
+	}
+
+	/// Client for the plugin loaded from `file`, with its persisted cookie jar and any configured fixtures.
+	pub fn for_plugin(config: &Config, file: &Path) -> Self {
+		let key = plugin_key(file);
+		let http = Self::with_cookies(CookieJar::for_plugin(config, &key));
+		match Fixtures::for_plugin(config, &key) {
+			Some(fixtures) => http.with_fixtures(fixtures),
+			None => http,
+		}
+	}
+
+	/// Records every request to `fixtures`, or answers from them instead of the network when replaying.
+	pub fn with_fixtures(mut self, fixtures: Arc<Fixtures>) -> Self {
+		self.fixtures = Some(fixtures);
+		self
+	}
+
+	pub fn fixtures(&self) -> Option<&Arc<Fixtures>> {
+		self.fixtures.as_ref()
+	}
+
+	pub fn cookies(&self) -> &Arc<CookieJar> {
+		&self.cookies
+	}
 
-		Self { client }
+	/// Overrides the default user agent, e.g. to match the one a Cloudflare clearance cookie was issued for.
+	pub fn set_user_agent(&self, user_agent: Option<String>) {
+		*self.user_agent.write().unwrap_or_else(|e| e.into_inner()) = user_agent;
+	}
+
+	fn request_headers(&self, headers_map: &HashMap<String, String>) -> Result<HeaderMap, ScraperError> {
+		let mut headers = Self::build_header_map(headers_map)?;
+		if !headers.contains_key(USER_AGENT) {
+			let user_agent = self.user_agent.read().unwrap_or_else(|e| e.into_inner()).clone();
+			if let Some(value) = user_agent.and_then(|ua| HeaderValue::from_str(&ua).ok()) {
+				headers.insert(USER_AGENT, value);
+			}
+		}
+		Ok(headers)
 	}
 
 	fn build_header_map(headers_map: &HashMap<String, String>) -> Result<HeaderMap, ScraperError> {
@@ -102,66 +298,142 @@ impl CommonHttp {
 	}
 
 	pub async fn get(&self, url: String, headers_map: Option<HashMap<String, String>>) -> Response {
-		match self.get_internal(url, headers_map).await {
-			Ok(response) => response,
-			Err(error) => Response::from_error(error),
-		}
+		let options = RequestOptions {
+			headers: headers_map.unwrap_or_default(),
+			..Default::default()
+		};
+		self.request(url, options).await
 	}
 
-	async fn get_internal(
-		&self,
-		url: String,
-		headers_map: Option<HashMap<String, String>>,
-	) -> Result<Response, ScraperError> {
-		let parsed = Url::parse(&url).map_err(|e| {
-			ScraperError::with_retryable(ScraperErrorKind::Validation, format!("invalid url '{}': {}", url, e), false)
-		})?;
+	pub async fn post(&self, url: String, body: String, headers_map: Option<HashMap<String, String>>) -> Response {
+		let options = RequestOptions {
+			method: Method::Post,
+			headers: headers_map.unwrap_or_default(),
+			body: Some(RequestBody::Text(body)),
+			..Default::default()
+		};
+		self.request(url, options).await
+	}
 
-		let headers_map = headers_map.unwrap_or_default();
-		let headers = Self::build_header_map(&headers_map)?;
+	pub async fn request(&self, url: String, options: RequestOptions) -> Response {
+		let Some(fixtures) = &self.fixtures else {
+			return self.request_live(url, options).await;
+		};
 
-		let response = self
-			.client
-			.get(parsed)
-			.headers(headers)
-			.send()
-			.await
-			.map_err(|e| classify_reqwest_error(&url, e))?;
+		let request = FixtureRequest::http(&url, &options);
+		if fixtures.is_replaying() {
+			return fixtures.replay_response(&request);
+		}
 
-		extract_response(response).await
+		let response = self.request_live(url, options).await;
+		fixtures.record_response(request, &response);
+		response
 	}
 
-	pub async fn post(&self, url: String, body: String, headers_map: Option<HashMap<String, String>>) -> Response {
-		match self.post_internal(url, body, headers_map).await {
+	async fn request_live(&self, url: String, options: RequestOptions) -> Response {
+		let binary = options.binary;
+		let result = match self.send(&url, options).await {
+			Ok(response) if binary => extract_bytes_response(response).await,
+			Ok(response) => extract_response(response).await,
+			Err(error) => Err(error),
+		};
+
+		match result {
 			Ok(response) => response,
 			Err(error) => Response::from_error(error),
 		}
 	}
 
-	async fn post_internal(
-		&self,
-		url: String,
-		body: String,
-		headers_map: Option<HashMap<String, String>>,
-	) -> Result<Response, ScraperError> {
-		let parsed = Url::parse(&url).map_err(|e| {
+	/// Sends the request and hands the body back unread, so it can be consumed chunk by chunk.
+	///
+	/// With fixtures the body is read in full instead, so it can be recorded or served from the recording.
+	pub async fn stream(&self, url: String, options: RequestOptions) -> Result<HttpStream, ScraperError> {
+		if self.fixtures.is_some() {
+			let response = self.request(url, RequestOptions { binary: true, ..options }).await;
+			if response.status == 0 {
+				return Err(response.error.unwrap_or_else(|| ScraperError::network("request failed")));
+			}
+			return Ok(HttpStream::buffered(response));
+		}
+
+		let response = self.send(&url, options).await?;
+		Ok(HttpStream {
+			status: response.status().as_u16(),
+			headers: response_headers(&response),
+			body: tokio::sync::Mutex::new(StreamBody::Live(response)),
+		})
+	}
+
+	async fn send(&self, url: &str, options: RequestOptions) -> Result<reqwest::Response, ScraperError> {
+		let parsed = Url::parse(url).map_err(|e| {
 			ScraperError::with_retryable(ScraperErrorKind::Validation, format!("invalid url '{}': {}", url, e), false)
 		})?;
 
-		let headers_map = headers_map.unwrap_or_default();
-		let headers = Self::build_header_map(&headers_map)?;
+		let headers = self.request_headers(&options.headers)?;
+		let client = if options.follow_redirects {
+			&self.client
+		} else {
+			&self.no_redirect_client
+		};
+
+		let mut request = client.request(options.method.into(), parsed).headers(headers);
+		if !options.query.is_empty() {
+			request = request.query(&options.query);
+		}
+		if let Some(timeout) = options.timeout {
+			request = request.timeout(timeout);
+		}
+		request = match options.body {
+			Some(RequestBody::Text(text)) => request.body(text),
+			Some(RequestBody::Bytes(bytes)) => request.body(bytes),
+			Some(RequestBody::Form(fields)) => request.form(&fields),
+			Some(RequestBody::Multipart(parts)) => request.multipart(build_multipart(parts)?),
+			None => request,
+		};
 
-		let response = self
-			.client
-			.post(parsed)
-			.headers(headers)
-			.body(body)
-			.send()
-			.await
-			.map_err(|e| classify_reqwest_error(&url, e))?;
+		rate_limit::limiter().acquire(url).await;
+		let response = request.send().await;
+		metrics::record_upstream_response("plugin", url, response.as_ref().ok().map(|r| r.status().as_u16()));
+		let response = response.map_err(|e| classify_reqwest_error(url, e))?;
+		self.cookies.persist();
+
+		let retry_after = response.headers().get(RETRY_AFTER).and_then(|value| value.to_str().ok());
+		rate_limit::limiter().record_response(url, response.status().as_u16(), retry_after);
+		Ok(response)
+	}
+}
+
+fn build_client(cookies: &Arc<CookieJar>, redirects: redirect::Policy) -> reqwest::Client {
+	reqwest::Client::builder()
+		.cookie_provider(cookies.clone())
+		.redirect(redirects)
+		.timeout(Duration::from_secs(30))
+		.user_agent(
+			"Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/58.0.3029.110 Safari/537.36",
+		)
+		.build()
+		.expect("Failed to build HTTP client")
+}
 
-		extract_response(response).await
+fn build_multipart(parts: Vec<MultipartPart>) -> Result<reqwest::multipart::Form, ScraperError> {
+	let mut form = reqwest::multipart::Form::new();
+	for part in parts {
+		let mut field = reqwest::multipart::Part::bytes(part.data);
+		if let Some(filename) = part.filename {
+			field = field.file_name(filename);
+		}
+		if let Some(content_type) = part.content_type {
+			field = field.mime_str(&content_type).map_err(|e| {
+				ScraperError::with_retryable(
+					ScraperErrorKind::Validation,
+					format!("invalid content type '{}': {}", content_type, e),
+					false,
+				)
+			})?;
+		}
+		form = form.part(part.name, field);
 	}
+	Ok(form)
 }
 
 fn classify_reqwest_error(url: &str, error: reqwest::Error) -> ScraperError {
@@ -182,13 +454,29 @@ fn classify_reqwest_error(url: &str, error: reqwest::Error) -> ScraperError {
 	ScraperError::new(ScraperErrorKind::Network, message)
 }
 
-async fn extract_response(response: reqwest::Response) -> Result<Response, ScraperError> {
-	let status = response.status().as_u16();
-	let headers: HashMap<String, String> = response
+fn response_headers(response: &reqwest::Response) -> HashMap<String, String> {
+	response
 		.headers()
 		.iter()
 		.map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-		.collect();
+		.collect()
+}
+
+async fn extract_bytes_response(response: reqwest::Response) -> Result<Response, ScraperError> {
+	let status = response.status().as_u16();
+	let headers = response_headers(&response);
+
+	let bytes = response
+		.bytes()
+		.await
+		.map_err(|e| ScraperError::new(ScraperErrorKind::Network, format!("Failed to read response body: {}", e)))?;
+
+	Ok(Response::from_bytes(bytes.to_vec(), status, headers))
+}
+
+async fn extract_response(response: reqwest::Response) -> Result<Response, ScraperError> {
+	let status = response.status().as_u16();
+	let headers = response_headers(&response);
 
 	let text = response
 		.text()
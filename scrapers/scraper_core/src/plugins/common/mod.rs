@@ -1,4 +1,8 @@
+pub mod cookies;
+pub mod dom;
+pub mod fixtures;
 pub mod flaresolverr;
 pub mod headless;
 pub mod html;
 pub mod http;
+pub mod xpath;
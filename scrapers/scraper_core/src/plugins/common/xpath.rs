@@ -0,0 +1,351 @@
+//! A small XPath subset evaluated directly on the `scraper` tree.
+//!
+//! Supported: absolute and relative location paths with `/` and `//`, name tests, `*`, `.`, `..`,
+//! `text()`, `@name` / `@*`, and the predicates `[n]`, `[last()]`, `[@name]`, `[@name='v']`,
+//! `[text()='v']`, `[.='v']` and `[contains(@name|text()|., 'v')]`.
+
+use std::collections::HashSet;
+
+use ego_tree::{NodeId, NodeRef};
+use scraper::{ElementRef, Html, Node};
+
+use crate::plugins::common::html::HtmlError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XPathItem {
+	Node(NodeId),
+	Text(String),
+	Attr(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct XPath {
+	absolute: bool,
+	steps: Vec<Step>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Step {
+	descendant: bool,
+	test: NodeTest,
+	predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, PartialEq)]
+enum NodeTest {
+	Name(String),
+	AnyElement,
+	SelfNode,
+	Parent,
+	Text,
+	Attr(Option<String>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Operand {
+	Attr(String),
+	Text,
+	StringValue,
+}
+
+#[derive(Debug, PartialEq)]
+enum Predicate {
+	Position(usize),
+	Last,
+	HasAttr(String),
+	Equals(Operand, String),
+	Contains(Operand, String),
+}
+
+pub fn parse(expr: &str) -> Result<XPath, HtmlError> {
+	let invalid = |reason: &str| HtmlError::XPath(format!("{}: {}", reason, expr));
+
+	let expr = expr.trim();
+	if expr.is_empty() {
+		return Err(invalid("empty expression"));
+	}
+
+	let mut rest = expr;
+	let absolute = rest.starts_with('/');
+	let mut steps = Vec::new();
+	let mut descendant = false;
+
+	loop {
+		if let Some(r) = rest.strip_prefix("//") {
+			descendant = true;
+			rest = r;
+		} else if let Some(r) = rest.strip_prefix('/') {
+			rest = r;
+		}
+
+		let end = split_point(rest, '/').unwrap_or(rest.len());
+		let (raw_step, tail) = rest.split_at(end);
+		if raw_step.is_empty() {
+			return Err(invalid("empty step"));
+		}
+
+		steps.push(parse_step(raw_step, descendant).map_err(|reason| invalid(&reason))?);
+		descendant = false;
+		rest = tail;
+
+		if rest.is_empty() {
+			break;
+		}
+	}
+
+	Ok(XPath { absolute, steps })
+}
+
+/// Index of the first `delim` outside of quotes and brackets.
+fn split_point(s: &str, delim: char) -> Option<usize> {
+	let mut quote = None;
+	let mut depth = 0usize;
+	for (i, c) in s.char_indices() {
+		match (quote, c) {
+			(Some(q), c) if c == q => quote = None,
+			(Some(_), _) => {}
+			(None, c) if c == delim && depth == 0 => return Some(i),
+			(None, '\'' | '"') => quote = Some(c),
+			(None, '[' | '(') => depth += 1,
+			(None, ']' | ')') => depth = depth.saturating_sub(1),
+			_ => {}
+		}
+	}
+	None
+}
+
+fn parse_step(raw: &str, descendant: bool) -> Result<Step, String> {
+	let (test, mut rest) = match raw.find('[') {
+		Some(i) => raw.split_at(i),
+		None => (raw, ""),
+	};
+
+	let test = match test.trim() {
+		"." => NodeTest::SelfNode,
+		".." => NodeTest::Parent,
+		"*" => NodeTest::AnyElement,
+		"text()" => NodeTest::Text,
+		"@*" => NodeTest::Attr(None),
+		t if t.starts_with('@') => NodeTest::Attr(Some(t[1..].to_string())),
+		t if !t.is_empty() && t.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') => {
+			NodeTest::Name(t.to_ascii_lowercase())
+		}
+		t => return Err(format!("unsupported step '{}'", t)),
+	};
+
+	let mut predicates = Vec::new();
+	while !rest.is_empty() {
+		let inner = rest.strip_prefix('[').ok_or_else(|| format!("unexpected '{}'", rest))?;
+		let end = split_point(inner, ']').ok_or("unclosed predicate")?;
+		predicates.push(parse_predicate(inner[..end].trim())?);
+		rest = &inner[end + 1..];
+	}
+
+	Ok(Step {
+		descendant,
+		test,
+		predicates,
+	})
+}
+
+fn parse_predicate(raw: &str) -> Result<Predicate, String> {
+	if let Ok(n) = raw.parse::<usize>() {
+		return if n == 0 {
+			Err("positions start at 1".to_string())
+		} else {
+			Ok(Predicate::Position(n))
+		};
+	}
+
+	if raw == "last()" {
+		return Ok(Predicate::Last);
+	}
+
+	if let Some(args) = raw.strip_prefix("contains(").and_then(|r| r.strip_suffix(')')) {
+		let comma = split_point(args, ',').ok_or("contains() takes two arguments")?;
+		return Ok(Predicate::Contains(
+			parse_operand(args[..comma].trim())?,
+			parse_literal(args[comma + 1..].trim())?,
+		));
+	}
+
+	if let Some(eq) = split_point(raw, '=') {
+		return Ok(Predicate::Equals(
+			parse_operand(raw[..eq].trim())?,
+			parse_literal(raw[eq + 1..].trim())?,
+		));
+	}
+
+	match raw.strip_prefix('@') {
+		Some(name) => Ok(Predicate::HasAttr(name.to_string())),
+		None => Err(format!("unsupported predicate '{}'", raw)),
+	}
+}
+
+fn parse_operand(raw: &str) -> Result<Operand, String> {
+	match raw {
+		"text()" => Ok(Operand::Text),
+		"." => Ok(Operand::StringValue),
+		r if r.starts_with('@') => Ok(Operand::Attr(r[1..].to_string())),
+		r => Err(format!("unsupported operand '{}'", r)),
+	}
+}
+
+fn parse_literal(raw: &str) -> Result<String, String> {
+	let quoted = (raw.starts_with('\'') && raw.ends_with('\'')) || (raw.starts_with('"') && raw.ends_with('"'));
+	if raw.len() >= 2 && quoted {
+		Ok(raw[1..raw.len() - 1].to_string())
+	} else {
+		Err(format!("expected a quoted string, got '{}'", raw))
+	}
+}
+
+pub fn evaluate(html: &Html, context: NodeId, path: &XPath) -> Vec<XPathItem> {
+	let start = if path.absolute { html.tree.root().id() } else { context };
+	let mut current = vec![XPathItem::Node(start)];
+
+	for step in &path.steps {
+		let mut next = Vec::new();
+		let mut seen = HashSet::new();
+
+		for item in &current {
+			let XPathItem::Node(id) = item else { continue };
+			let Some(node) = html.tree.get(*id) else { continue };
+
+			let bases: Vec<NodeRef<'_, Node>> = if step.descendant {
+				node.descendants().collect()
+			} else {
+				vec![node]
+			};
+
+			for base in bases {
+				let candidates = apply_test(base, &step.test);
+				for candidate in apply_predicates(html, candidates, &step.predicates) {
+					if let XPathItem::Node(id) = candidate
+						&& !seen.insert(id)
+					{
+						continue;
+					}
+					next.push(candidate);
+				}
+			}
+		}
+
+		current = next;
+	}
+
+	current
+}
+
+fn apply_test(node: NodeRef<'_, Node>, test: &NodeTest) -> Vec<XPathItem> {
+	match test {
+		NodeTest::SelfNode => vec![XPathItem::Node(node.id())],
+		NodeTest::Parent => node.parent().map(|p| XPathItem::Node(p.id())).into_iter().collect(),
+		NodeTest::AnyElement => node
+			.children()
+			.filter_map(ElementRef::wrap)
+			.map(|el| XPathItem::Node(el.id()))
+			.collect(),
+		NodeTest::Name(name) => node
+			.children()
+			.filter_map(ElementRef::wrap)
+			.filter(|el| el.value().name().eq_ignore_ascii_case(name))
+			.map(|el| XPathItem::Node(el.id()))
+			.collect(),
+		NodeTest::Text => node
+			.children()
+			.filter_map(|child| child.value().as_text().map(|t| XPathItem::Text(t.to_string())))
+			.collect(),
+		NodeTest::Attr(name) => match ElementRef::wrap(node) {
+			Some(el) => el
+				.value()
+				.attrs()
+				.filter(|(attr, _)| name.as_deref().is_none_or(|n| n == *attr))
+				.map(|(_, value)| XPathItem::Attr(value.to_string()))
+				.collect(),
+			None => Vec::new(),
+		},
+	}
+}
+
+fn apply_predicates(html: &Html, mut items: Vec<XPathItem>, predicates: &[Predicate]) -> Vec<XPathItem> {
+	for predicate in predicates {
+		items = match predicate {
+			Predicate::Position(n) => items.into_iter().nth(n - 1).into_iter().collect(),
+			Predicate::Last => items.pop().into_iter().collect(),
+			_ => items.into_iter().filter(|item| matches(html, item, predicate)).collect(),
+		};
+	}
+	items
+}
+
+fn matches(html: &Html, item: &XPathItem, predicate: &Predicate) -> bool {
+	let value = |operand: &Operand| -> Option<String> {
+		match item {
+			XPathItem::Node(id) => {
+				let el = html.tree.get(*id).and_then(ElementRef::wrap)?;
+				match operand {
+					Operand::Attr(name) => el.value().attr(name).map(|v| v.to_string()),
+					Operand::Text => Some(
+						el.children()
+							.filter_map(|c| c.value().as_text().map(|t| t.to_string()))
+							.collect(),
+					),
+					Operand::StringValue => Some(el.text().collect()),
+				}
+			}
+			XPathItem::Text(value) | XPathItem::Attr(value) => match operand {
+				Operand::StringValue | Operand::Text => Some(value.clone()),
+				Operand::Attr(_) => None,
+			},
+		}
+	};
+
+	match predicate {
+		Predicate::HasAttr(name) => value(&Operand::Attr(name.clone())).is_some(),
+		Predicate::Equals(operand, expected) => value(operand).is_some_and(|v| v.trim() == expected),
+		Predicate::Contains(operand, needle) => value(operand).is_some_and(|v| v.contains(needle.as_str())),
+		Predicate::Position(_) | Predicate::Last => true,
+	}
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	fn texts(html: &Html, expr: &str) -> Vec<String> {
+		let path = parse(expr).expect("valid xpath");
+		evaluate(html, html.tree.root().id(), &path)
+			.into_iter()
+			.map(|item| match item {
+				XPathItem::Node(id) => ElementRef::wrap(html.tree.get(id).unwrap()).unwrap().text().collect(),
+				XPathItem::Text(t) | XPathItem::Attr(t) => t,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_paths_and_predicates() {
+		let html = Html::parse_document(r#"<div class="list"><p>a</p><p class="x">b</p><p>c</p></div><div><p>d</p></div>"#);
+
+		assert_eq!(texts(&html, "//p"), vec!["a", "b", "c", "d"]);
+		assert_eq!(texts(&html, "//div[@class='list']/p[last()]"), vec!["c"]);
+		assert_eq!(texts(&html, "//p[1]"), vec!["a", "d"]);
+		assert_eq!(texts(&html, "//p[@class]/text()"), vec!["b"]);
+		assert_eq!(texts(&html, "//p[.='d']/../@class"), Vec::<String>::new());
+		assert_eq!(texts(&html, "//p[contains(., 'b')]/../@class"), vec!["list"]);
+		assert_eq!(texts(&html, "/html/body/div[2]/*"), vec!["d"]);
+	}
+
+	#[test]
+	fn test_invalid_expressions() {
+		for expr in ["", "//p[", "//p[0]", "//p[@a=b]", "//p/following::a", "//"] {
+			assert!(
+				matches!(parse(expr), Err(HtmlError::XPath(_))),
+				"expected '{}' to be rejected",
+				expr
+			);
+		}
+	}
+}
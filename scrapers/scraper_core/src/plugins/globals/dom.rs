@@ -0,0 +1,140 @@
+use mlua::{UserData, UserDataMethods};
+use scraper_types::{ScraperError, ScraperErrorKind};
+
+use crate::plugins::common::dom::{DomDocument, DomNode};
+use crate::plugins::common::html::HtmlError;
+
+pub struct LuaDocument(pub DomDocument);
+
+pub struct LuaNode(pub DomNode);
+
+fn parse_error(e: HtmlError) -> mlua::Error {
+	mlua::Error::external(ScraperError::new(ScraperErrorKind::Parse, e.to_string()))
+}
+
+fn wrap(nodes: Vec<DomNode>) -> Vec<LuaNode> {
+	nodes.into_iter().map(LuaNode).collect()
+}
+
+impl UserData for LuaDocument {
+	fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+		methods.add_method("root", |_, this, ()| Ok(LuaNode(this.0.root())));
+
+		methods.add_method("select", |_, this, selector: String| {
+			this.0.select(&selector).map(wrap).map_err(parse_error)
+		});
+
+		methods.add_method("select_one", |_, this, selector: String| {
+			this.0.select_one(&selector).map(|n| n.map(LuaNode)).map_err(parse_error)
+		});
+
+		methods.add_method("xpath", |_, this, expr: String| {
+			this.0.xpath(&expr).map(wrap).map_err(parse_error)
+		});
+
+		methods.add_method("xpath_text", |_, this, expr: String| {
+			this.0.xpath_text(&expr).map_err(parse_error)
+		});
+	}
+}
+
+impl UserData for LuaNode {
+	fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+		methods.add_method("name", |_, this, ()| Ok(this.0.name()));
+
+		methods.add_method("select", |_, this, selector: String| {
+			this.0.select(&selector).map(wrap).map_err(parse_error)
+		});
+
+		methods.add_method("select_one", |_, this, selector: String| {
+			this.0.select_one(&selector).map(|n| n.map(LuaNode)).map_err(parse_error)
+		});
+
+		methods.add_method("children", |_, this, ()| Ok(wrap(this.0.children())));
+		methods.add_method("parent", |_, this, ()| Ok(this.0.parent().map(LuaNode)));
+		methods.add_method("next_sibling", |_, this, ()| Ok(this.0.next_sibling().map(LuaNode)));
+		methods.add_method("prev_sibling", |_, this, ()| Ok(this.0.prev_sibling().map(LuaNode)));
+		methods.add_method("siblings", |_, this, ()| Ok(wrap(this.0.siblings())));
+
+		methods.add_method("text", |_, this, ()| Ok(this.0.text()));
+		methods.add_method("inner_text", |_, this, ()| Ok(this.0.inner_text()));
+		methods.add_method("inner_html", |_, this, ()| Ok(this.0.inner_html()));
+		methods.add_method("outer_html", |_, this, ()| Ok(this.0.outer_html()));
+
+		methods.add_method("attr", |_, this, name: String| Ok(this.0.attr(&name)));
+
+		methods.add_method("attrs", |lua, this, ()| {
+			let table = lua.create_table()?;
+			for (name, value) in this.0.attrs() {
+				table.set(name, value)?;
+			}
+			Ok(table)
+		});
+
+		methods.add_method("xpath", |_, this, expr: String| {
+			this.0.xpath(&expr).map(wrap).map_err(parse_error)
+		});
+
+		methods.add_method("xpath_text", |_, this, expr: String| {
+			this.0.xpath_text(&expr).map_err(parse_error)
+		});
+
+		methods.add_method("regex_match", |_, this, pattern: String| {
+			this.0.regex_match(&pattern).map_err(parse_error)
+		});
+
+		methods.add_method("regex_match_all", |_, this, pattern: String| {
+			this.0.regex_match_all(&pattern).map_err(parse_error)
+		});
+	}
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use mlua::Lua;
+
+	fn lua() -> Lua {
+		let lua = Lua::new();
+		crate::plugins::globals::scraping::load(&lua).unwrap();
+		lua
+	}
+
+	#[test]
+	fn test_parse_and_navigate() {
+		let lua = lua();
+		let script = r#"
+			local doc = scraping:parse('<ul><li class="a">One</li><li data-id="2">Two</li></ul>')
+			local first = doc:select_one("li.a")
+			local second = first:next_sibling()
+			return first:inner_text() .. "|" .. second:attrs()["data-id"] .. "|" .. #first:parent():children()
+				.. "|" .. second:prev_sibling():name()
+		"#;
+		let result: String = lua.load(script).eval().unwrap();
+		assert_eq!(result, "One|2|2|li");
+	}
+
+	#[test]
+	fn test_xpath_and_regex() {
+		let lua = lua();
+		let script = r#"
+			local doc = scraping:parse('<div><span>Chapter 12</span><a href="/x">x</a></div>')
+			local href = doc:xpath_text("//a/@href")[1]
+			local number = doc:xpath("//span")[1]:regex_match("Chapter (\\d+)")
+			return href .. "|" .. number
+		"#;
+		let result: String = lua.load(script).eval().unwrap();
+		assert_eq!(result, "/x|12");
+	}
+
+	#[test]
+	fn test_invalid_selector_raises() {
+		let lua = lua();
+		let script = r#"
+			local doc = scraping:parse('<div></div>')
+			return doc:select("<<")
+		"#;
+		let result: mlua::Result<mlua::Value> = lua.load(script).eval();
+		assert!(result.is_err());
+	}
+}
@@ -4,6 +4,7 @@ use mlua::{Lua, UserData, UserDataMethods};
 
 use crate::Config;
 use crate::plugins::common::flaresolverr::FlareSolverrManager;
+use crate::plugins::common::http::CommonHttp;
 use crate::plugins::globals::utils::create_response_table;
 
 pub struct SharedFlareSolverrManager(pub Arc<FlareSolverrManager>);
@@ -23,8 +24,8 @@ impl UserData for SharedFlareSolverrManager {
 	}
 }
 
-pub fn load(config: &Config, lua: &Lua) -> anyhow::Result<()> {
-	let mgr = SharedFlareSolverrManager(Arc::new(FlareSolverrManager::new(config)));
+pub fn load(config: &Config, lua: &Lua, http: CommonHttp) -> anyhow::Result<()> {
+	let mgr = SharedFlareSolverrManager(Arc::new(FlareSolverrManager::with_http(config, http)));
 	lua.globals().set("flaresolverr", mgr)?;
 	Ok(())
 }
@@ -36,6 +37,8 @@ mod tests {
 	use mockito::Server;
 	use serde_json::json;
 
+	use crate::plugins::common::http::CommonHttp;
+
 	#[tokio::test]
 	async fn test_flaresolverr_get() {
 		let mut server = Server::new_async().await;
@@ -69,7 +72,7 @@ mod tests {
 			flaresolverr_url: Some(server.url()),
 			..Default::default()
 		};
-		super::load(&config, &lua).unwrap();
+		super::load(&config, &lua, CommonHttp::new()).unwrap();
 		let script = r#"
 	  local response = flaresolverr:get("https://jsonplaceholder.typicode.com/posts/1", nil)
 	  return response.text, response.json(), response.ok"#;
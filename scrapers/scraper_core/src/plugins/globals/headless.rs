@@ -3,9 +3,11 @@ use std::sync::Arc;
 use mlua::{Lua, UserData, UserDataMethods};
 
 use crate::Config;
+use crate::plugins::common::fixtures::FixtureHeadless;
 use crate::plugins::common::headless::fallback::FallbackBackend;
 use crate::plugins::common::headless::fantoccini::FantocciniBackend;
 use crate::plugins::common::headless::traits::{HeadlessBackend, HeadlessElement};
+use crate::plugins::common::http::CommonHttp;
 
 struct HeadlessClient {
 	inner: Arc<dyn HeadlessBackend>,
@@ -63,7 +65,29 @@ impl UserData for LuaHeadlessElement {
 	}
 }
 
-pub(crate) async fn load(config: &Config, lua: &Lua) -> anyhow::Result<()> {
+pub(crate) async fn load(config: &Config, lua: &Lua, http: &CommonHttp) -> anyhow::Result<()> {
+	match http.fixtures() {
+		Some(fixtures) if fixtures.is_replaying() => {
+			let headless_client = HeadlessClient {
+				inner: Arc::new(FixtureHeadless::replay(fixtures.clone())),
+			};
+			lua.globals().set("headless_client", headless_client)?;
+			return Ok(());
+		}
+		Some(fixtures) => {
+			let inner: Arc<dyn HeadlessBackend> = match config.headless {
+				Some(_) => Arc::new(FantocciniBackend::new(config).await?),
+				None => Arc::new(FallbackBackend::new()),
+			};
+			let headless_client = HeadlessClient {
+				inner: Arc::new(FixtureHeadless::record(inner, fixtures.clone())),
+			};
+			lua.globals().set("headless_client", headless_client)?;
+			return Ok(());
+		}
+		None => {}
+	}
+
 	if config.headless.is_none() {
 		tracing::debug!("Headless mode is not enabled in the config — registering DummyHeadless fallback");
 		let dummy: Arc<dyn HeadlessBackend> = Arc::new(FallbackBackend::new());
@@ -1,9 +1,13 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use mlua::{Lua, LuaSerdeExt, UserData, UserDataMethods};
+use mlua::{Lua, LuaSerdeExt, UserData, UserDataFields, UserDataMethods};
+use reqwest::Url;
+use scraper_types::{ScraperError, ScraperErrorKind};
 
-use crate::plugins::common::http::CommonHttp;
+use crate::plugins::common::cookies::SessionCookie;
+use crate::plugins::common::http::{CommonHttp, HttpStream, Method, MultipartPart, RequestBody, RequestOptions};
 use crate::plugins::globals::utils::create_response_table;
 
 pub struct SharedHttp(pub Arc<CommonHttp>);
@@ -34,6 +38,26 @@ impl UserData for SharedHttp {
 			},
 		);
 
+		methods.add_async_method("request", |lua, this, (url, options): (String, Option<mlua::Table>)| {
+			let http_client = this.0.clone();
+
+			async move {
+				let options = parse_options(options)?;
+				let response = http_client.request(url, options).await;
+				create_response_table(&lua, response)
+			}
+		});
+
+		methods.add_async_method("stream", |_lua, this, (url, options): (String, Option<mlua::Table>)| {
+			let http_client = this.0.clone();
+
+			async move {
+				let options = parse_options(options)?;
+				let stream = http_client.stream(url, options).await.map_err(mlua::Error::external)?;
+				Ok(LuaHttpStream(stream))
+			}
+		});
+
 		methods.add_method(
 			"has_cloudflare_protection",
 			|_lua, _this, (text, status_code, headers): (String, Option<u16>, Option<HashMap<String, String>>)| {
@@ -70,12 +94,114 @@ impl UserData for SharedHttp {
 			let encoded = urlencoding::encode(&string);
 			lua.to_value(&encoded)
 		});
+
+		methods.add_method("get_cookies", |lua, this, url: String| {
+			let url = parse_url(&url)?;
+			let cookies = lua.create_table()?;
+			for cookie in this.0.cookies().get(&url) {
+				cookies.set(cookie.name, cookie.value)?;
+			}
+			Ok(cookies)
+		});
+
+		methods.add_method("set_cookie", |_, this, (url, cookie): (String, mlua::Table)| {
+			let url = parse_url(&url)?;
+			let cookie = SessionCookie {
+				name: cookie.get("name")?,
+				value: cookie.get("value")?,
+				domain: cookie.get("domain")?,
+				path: cookie.get("path")?,
+				max_age: cookie.get("max_age")?,
+			};
+			this.0.cookies().set(&url, &cookie).map_err(|e| {
+				mlua::Error::external(ScraperError::new(
+					ScraperErrorKind::Validation,
+					format!("invalid cookie: {}", e),
+				))
+			})?;
+			this.0.cookies().persist();
+			Ok(())
+		});
+
+		methods.add_method("clear_cookies", |_, this, ()| {
+			this.0.cookies().clear();
+			this.0.cookies().persist();
+			Ok(())
+		});
+	}
+}
+
+pub struct LuaHttpStream(HttpStream);
+
+impl UserData for LuaHttpStream {
+	fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+		fields.add_field_method_get("status", |_, this| Ok(this.0.status));
+		fields.add_field_method_get("headers", |_, this| Ok(this.0.headers.clone()));
+	}
+
+	fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+		methods.add_async_method("read", |lua, this, ()| async move {
+			match this.0.next_chunk().await.map_err(mlua::Error::external)? {
+				Some(chunk) => Ok(mlua::Value::String(lua.create_string(&chunk)?)),
+				None => Ok(mlua::Value::Nil),
+			}
+		});
+	}
+}
+
+fn validation_error(message: String) -> mlua::Error {
+	mlua::Error::external(ScraperError::with_retryable(ScraperErrorKind::Validation, message, false))
+}
+
+/// Reads the options table accepted by `http:request` and `http:stream`.
+fn parse_options(options: Option<mlua::Table>) -> mlua::Result<RequestOptions> {
+	let mut parsed = RequestOptions::default();
+	let Some(options) = options else {
+		return Ok(parsed);
+	};
+
+	if let Some(method) = options.get::<Option<String>>("method")? {
+		parsed.method = Method::parse(&method).ok_or_else(|| validation_error(format!("unknown method '{}'", method)))?;
+	}
+	parsed.headers = options.get::<Option<HashMap<String, String>>>("headers")?.unwrap_or_default();
+	parsed.query = options
+		.get::<Option<HashMap<String, String>>>("query")?
+		.unwrap_or_default()
+		.into_iter()
+		.collect();
+	parsed.timeout = options.get::<Option<u64>>("timeout")?.map(Duration::from_millis);
+	parsed.follow_redirects = options.get::<Option<bool>>("follow_redirects")?.unwrap_or(true);
+	parsed.binary = options.get::<Option<bool>>("binary")?.unwrap_or(false);
+
+	if let Some(body) = options.get::<Option<mlua::String>>("body")? {
+		parsed.body = Some(RequestBody::Bytes(body.as_bytes().to_vec()));
+	} else if let Some(form) = options.get::<Option<HashMap<String, String>>>("form")? {
+		parsed.body = Some(RequestBody::Form(form.into_iter().collect()));
+	} else if let Some(parts) = options.get::<Option<Vec<mlua::Table>>>("multipart")? {
+		let parts = parts
+			.into_iter()
+			.map(|part| {
+				Ok(MultipartPart {
+					name: part.get("name")?,
+					filename: part.get("filename")?,
+					content_type: part.get("content_type")?,
+					data: part.get::<mlua::String>("data")?.as_bytes().to_vec(),
+				})
+			})
+			.collect::<mlua::Result<Vec<_>>>()?;
+		parsed.body = Some(RequestBody::Multipart(parts));
 	}
+
+	Ok(parsed)
+}
+
+fn parse_url(url: &str) -> mlua::Result<Url> {
+	Url::parse(url).map_err(|e| validation_error(format!("invalid url '{}': {}", url, e)))
 }
 
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
-pub(crate) fn load(lua: &Lua) -> anyhow::Result<()> {
-	lua.globals().set("http", SharedHttp(Arc::new(CommonHttp::new())))?;
+pub(crate) fn load(lua: &Lua, http: CommonHttp) -> anyhow::Result<()> {
+	lua.globals().set("http", SharedHttp(Arc::new(http)))?;
 	Ok(())
 }
 
@@ -86,6 +212,8 @@ mod tests {
 	use mockito::Server;
 	use serde_json::json;
 
+	use crate::plugins::common::http::CommonHttp;
+
 	#[tokio::test]
 	async fn test_get() {
 		let mut server = Server::new_async().await;
@@ -106,7 +234,7 @@ mod tests {
 			.await;
 
 		let lua = Lua::new();
-		super::load(&lua).unwrap();
+		super::load(&lua, CommonHttp::new()).unwrap();
 
 		let script = format!(
 			r#"
@@ -138,7 +266,7 @@ mod tests {
 			.await;
 
 		let lua = Lua::new();
-		super::load(&lua).unwrap();
+		super::load(&lua, CommonHttp::new()).unwrap();
 
 		let script = format!(
 			r#"
@@ -177,7 +305,7 @@ mod tests {
 			.await;
 
 		let lua = Lua::new();
-		super::load(&lua).unwrap();
+		super::load(&lua, CommonHttp::new()).unwrap();
 
 		let script = format!(
 			r#"
@@ -205,7 +333,7 @@ mod tests {
 	#[tokio::test]
 	async fn test_url_encode() {
 		let lua = Lua::new();
-		super::load(&lua).unwrap();
+		super::load(&lua, CommonHttp::new()).unwrap();
 
 		let script = r#"
             return http:url_encode("https://example.com/?query=rust&lang=en")
@@ -214,4 +342,143 @@ mod tests {
 		let result: String = lua.load(script).eval_async().await.unwrap();
 		assert_eq!(result, "https%3A%2F%2Fexample.com%2F%3Fquery%3Drust%26lang%3Den");
 	}
+
+	#[tokio::test]
+	async fn test_cookies_are_kept_between_requests() {
+		let mut server = Server::new_async().await;
+		let login = server
+			.mock("POST", "/login")
+			.with_status(200)
+			.with_header("set-cookie", "session=abc; Path=/")
+			.create_async()
+			.await;
+		let me = server
+			.mock("GET", "/me")
+			.match_header("cookie", "session=abc")
+			.with_status(200)
+			.with_body("ok")
+			.create_async()
+			.await;
+
+		let lua = Lua::new();
+		super::load(&lua, CommonHttp::new()).unwrap();
+
+		let script = format!(
+			r#"
+                http:post("{url}/login", "user=a", nil)
+                local response = http:get("{url}/me", nil)
+                local session = http:get_cookies("{url}/")["session"]
+                http:clear_cookies()
+                return response.ok, session, next(http:get_cookies("{url}/")) == nil
+            "#,
+			url = server.url()
+		);
+
+		let result: (bool, String, bool) = lua.load(&script).eval_async().await.unwrap();
+
+		login.assert_async().await;
+		me.assert_async().await;
+		assert!(result.0, "request with session cookie should succeed");
+		assert_eq!(result.1, "abc");
+		assert!(result.2, "cookies should be cleared");
+	}
+
+	#[tokio::test]
+	async fn test_request_binary_with_options() {
+		let mut server = Server::new_async().await;
+		let mock_server = server
+			.mock("PUT", "/image")
+			.match_query(mockito::Matcher::UrlEncoded("page".into(), "2".into()))
+			.match_body("name=cover")
+			.with_status(200)
+			.with_body([0xff, 0xd8, 0x00, 0x10])
+			.create_async()
+			.await;
+
+		let lua = Lua::new();
+		super::load(&lua, CommonHttp::new()).unwrap();
+
+		let script = format!(
+			r#"
+                local response = http:request("{}/image", {{
+                    method = "put",
+                    query = {{ page = "2" }},
+                    form = {{ name = "cover" }},
+                    timeout = 5000,
+                    binary = true,
+                }})
+                return response.text, response.ok
+            "#,
+			server.url()
+		);
+
+		let result: (mlua::String, bool) = lua.load(&script).eval_async().await.unwrap();
+
+		mock_server.assert_async().await;
+		assert!(result.1, "response.ok should be true");
+		assert_eq!(result.0.as_bytes().to_vec(), vec![0xff, 0xd8, 0x00, 0x10]);
+	}
+
+	#[tokio::test]
+	async fn test_request_without_redirects() {
+		let mut server = Server::new_async().await;
+		let mock_server = server
+			.mock("GET", "/old")
+			.with_status(302)
+			.with_header("location", "/new")
+			.create_async()
+			.await;
+
+		let lua = Lua::new();
+		super::load(&lua, CommonHttp::new()).unwrap();
+
+		let script = format!(
+			r#"
+                local response = http:request("{}/old", {{ follow_redirects = false }})
+                return response.status, response.headers["location"]
+            "#,
+			server.url()
+		);
+
+		let result: (u16, String) = lua.load(&script).eval_async().await.unwrap();
+
+		mock_server.assert_async().await;
+		assert_eq!(result.0, 302);
+		assert_eq!(result.1, "/new");
+	}
+
+	#[tokio::test]
+	async fn test_stream() {
+		let mut server = Server::new_async().await;
+		let body = "x".repeat(64 * 1024);
+		let mock_server = server
+			.mock("GET", "/large")
+			.with_status(200)
+			.with_body(&body)
+			.create_async()
+			.await;
+
+		let lua = Lua::new();
+		super::load(&lua, CommonHttp::new()).unwrap();
+
+		let script = format!(
+			r#"
+                local stream = http:stream("{}/large")
+                local size = 0
+                while true do
+                    local chunk = stream:read()
+                    if chunk == nil then break end
+                    size = size + #chunk
+                end
+                return stream.status, size
+            "#,
+			server.url()
+		);
+
+		let result: (u16, usize) = lua.load(&script).eval_async().await.unwrap();
+
+		mock_server.assert_async().await;
+		assert_eq!(result.0, 200);
+		assert_eq!(result.1, body.len());
+	}
 }
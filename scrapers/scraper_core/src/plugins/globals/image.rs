@@ -0,0 +1,153 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, GenericImageView, ImageFormat, RgbaImage, imageops};
+use mlua::{Lua, Table};
+
+/// Moves the `w`x`h` block at (`sx`, `sy`) of the source image to (`dx`, `dy`).
+struct Tile {
+	sx: u32,
+	sy: u32,
+	w: u32,
+	h: u32,
+	dx: u32,
+	dy: u32,
+}
+
+impl Tile {
+	fn from_table(table: &Table) -> mlua::Result<Self> {
+		Ok(Tile {
+			sx: table.get("sx")?,
+			sy: table.get("sy")?,
+			w: table.get("w")?,
+			h: table.get("h")?,
+			dx: table.get("dx")?,
+			dy: table.get("dy")?,
+		})
+	}
+}
+
+fn mime(format: ImageFormat) -> &'static str {
+	match format {
+		ImageFormat::Jpeg => "image/jpeg",
+		_ => "image/png",
+	}
+}
+
+/// Copies every tile onto a canvas of the source's size. JPEGs stay JPEGs, anything else is encoded as PNG.
+fn reassemble(data: &[u8], tiles: &[Tile]) -> anyhow::Result<(Vec<u8>, &'static str)> {
+	let format = image::guess_format(data)?;
+	let source = image::load_from_memory_with_format(data, format)?;
+	let (width, height) = source.dimensions();
+	let mut canvas = RgbaImage::new(width, height);
+
+	for tile in tiles {
+		let fits_source = tile.sx.checked_add(tile.w).is_some_and(|x| x <= width)
+			&& tile.sy.checked_add(tile.h).is_some_and(|y| y <= height);
+		if !fits_source {
+			anyhow::bail!(
+				"tile {}x{} at ({}, {}) is outside the {}x{} image",
+				tile.w,
+				tile.h,
+				tile.sx,
+				tile.sy,
+				width,
+				height
+			);
+		}
+		let piece = source.view(tile.sx, tile.sy, tile.w, tile.h).to_image();
+		imageops::replace(&mut canvas, &piece, tile.dx as i64, tile.dy as i64);
+	}
+
+	let output_format = if format == ImageFormat::Jpeg {
+		ImageFormat::Jpeg
+	} else {
+		ImageFormat::Png
+	};
+	let mut out = Cursor::new(Vec::new());
+	match output_format {
+		ImageFormat::Jpeg => DynamicImage::ImageRgba8(canvas).to_rgb8().write_to(&mut out, output_format)?,
+		_ => canvas.write_to(&mut out, output_format)?,
+	}
+
+	Ok((out.into_inner(), mime(output_format)))
+}
+
+pub fn load(lua: &Lua) -> anyhow::Result<()> {
+	let images_table = lua.create_table()?;
+
+	images_table.set(
+		"size",
+		lua.create_function(|_, data: mlua::String| {
+			let image = image::load_from_memory(&data.as_bytes()).map_err(mlua::Error::external)?;
+			Ok(image.dimensions())
+		})?,
+	)?;
+
+	images_table.set(
+		"reassemble",
+		lua.create_function(|lua, (data, tiles): (mlua::String, Vec<Table>)| {
+			let tiles = tiles.iter().map(Tile::from_table).collect::<mlua::Result<Vec<_>>>()?;
+			let (bytes, content_type) = reassemble(&data.as_bytes(), &tiles).map_err(mlua::Error::external)?;
+			Ok((lua.create_string(&bytes)?, content_type))
+		})?,
+	)?;
+
+	lua.globals().set("images", images_table)?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use image::Rgba;
+	use mlua::Lua;
+
+	use super::*;
+
+	const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+	const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+	fn halves_png() -> Vec<u8> {
+		let image = RgbaImage::from_fn(4, 2, |x, _| if x < 2 { RED } else { BLUE });
+		let mut out = Cursor::new(Vec::new());
+		image.write_to(&mut out, ImageFormat::Png).unwrap();
+		out.into_inner()
+	}
+
+	#[test]
+	fn test_reassemble_swaps_tiles() {
+		let lua = Lua::new();
+		super::load(&lua).unwrap();
+		lua.globals().set("source", lua.create_string(halves_png()).unwrap()).unwrap();
+
+		let script = r#"
+            local width, height = images.size(source)
+            local data, content_type = images.reassemble(source, {
+                { sx = 0, sy = 0, w = 2, h = 2, dx = 2, dy = 0 },
+                { sx = 2, sy = 0, w = 2, h = 2, dx = 0, dy = 0 },
+            })
+            return width, height, data, content_type
+        "#;
+		let (width, height, data, content_type): (u32, u32, mlua::String, String) = lua.load(script).eval().unwrap();
+
+		assert_eq!((width, height), (4, 2));
+		assert_eq!(content_type, "image/png");
+		let result = image::load_from_memory(&data.as_bytes()).unwrap().to_rgba8();
+		assert_eq!(*result.get_pixel(0, 0), BLUE);
+		assert_eq!(*result.get_pixel(3, 1), RED);
+	}
+
+	#[test]
+	fn test_reassemble_rejects_tiles_outside_image() {
+		let tiles = [Tile {
+			sx: 3,
+			sy: 0,
+			w: 2,
+			h: 2,
+			dx: 0,
+			dy: 0,
+		}];
+		assert!(reassemble(&halves_png(), &tiles).is_err());
+	}
+}
@@ -1,5 +1,5 @@
 use mlua::{Lua, Value, Variadic};
-use tracing::{debug, error, info, warn};
+use tracing::Level;
 
 fn format_message(lua: &Lua, args: Variadic<Value>) -> mlua::Result<String> {
 	let mut parts = Vec::new();
@@ -13,44 +13,26 @@ fn format_message(lua: &Lua, args: Variadic<Value>) -> mlua::Result<String> {
 	Ok(parts.join(" "))
 }
 
-pub fn load(lua: &Lua) -> anyhow::Result<()> {
+/// Exposes `log.debug`, `log.info`, `log.warn` and `log.error`, logging under the plugin's `target`.
+pub fn load(lua: &Lua, target: &str) -> anyhow::Result<()> {
 	let log_table = lua.create_table()?;
 
-	log_table.set(
-		"debug",
-		lua.create_function(|lua, args: Variadic<Value>| {
-			let msg = format_message(lua, args)?;
-			debug!("(Lua) {}", msg);
-			Ok(())
-		})?,
-	)?;
-
-	log_table.set(
-		"info",
-		lua.create_function(|lua, args: Variadic<Value>| {
-			let msg = format_message(lua, args)?;
-			info!("(Lua) {}", msg);
-			Ok(())
-		})?,
-	)?;
-
-	log_table.set(
-		"warn",
-		lua.create_function(|lua, args: Variadic<Value>| {
-			let msg = format_message(lua, args)?;
-			warn!("(Lua) {}", msg);
-			Ok(())
-		})?,
-	)?;
-
-	log_table.set(
-		"error",
-		lua.create_function(|lua, args: Variadic<Value>| {
-			let msg = format_message(lua, args)?;
-			error!("(Lua) {}", msg);
-			Ok(())
-		})?,
-	)?;
+	for (name, level) in [
+		("debug", Level::DEBUG),
+		("info", Level::INFO),
+		("warn", Level::WARN),
+		("error", Level::ERROR),
+	] {
+		let target = target.to_string();
+		log_table.set(
+			name,
+			lua.create_function(move |lua, args: Variadic<Value>| {
+				let msg = format_message(lua, args)?;
+				telemetry::log_event(&target, level, &msg);
+				Ok(())
+			})?,
+		)?;
+	}
 
 	lua.globals().set("log", log_table)?;
 	Ok(())
@@ -59,34 +41,58 @@ pub fn load(lua: &Lua) -> anyhow::Result<()> {
 #[cfg(test)]
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
 mod tests {
+	use std::sync::{Arc, Mutex};
+
 	use mlua::Lua;
-	use tracing_test::traced_test;
 
-	#[test]
-	#[traced_test]
-	fn test_log_info() {
-		let lua = Lua::new();
-		super::load(&lua).unwrap();
+	/// Runs `script` with the `log` global of a plugin logging under `plugin::test`, returning what was logged.
+	fn logged(script: &str, filter: &str) -> String {
+		let buffer = Arc::new(Mutex::new(Vec::new()));
+		let writer = Arc::clone(&buffer);
+		let subscriber = tracing_subscriber::fmt()
+			.with_env_filter(filter)
+			.with_writer(move || WriteTo(Arc::clone(&writer)))
+			.with_ansi(false)
+			.finish();
+
+		tracing::subscriber::with_default(subscriber, || {
+			let lua = Lua::new();
+			super::load(&lua, "plugin::test").unwrap();
+			lua.load(script).exec().unwrap();
+		});
+
+		String::from_utf8(buffer.lock().unwrap().clone()).unwrap()
+	}
 
-		let script = r#"
-			log.info("Hello", "from", "Lua", 123, {a=1})
-		"#;
-		lua.load(script).exec().unwrap();
+	struct WriteTo(Arc<Mutex<Vec<u8>>>);
 
-		assert!(logs_contain("(Lua) Hello from Lua 123 table"));
+	impl std::io::Write for WriteTo {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
 	}
 
 	#[test]
-	#[traced_test]
-	fn test_log_error() {
-		let lua = Lua::new();
-		super::load(&lua).unwrap();
+	fn test_log_info() {
+		let logs = logged(r#"log.info("Hello", "from", "Lua", 123, {a=1})"#, "info");
+		assert!(logs.contains("INFO plugin::test: Hello from Lua 123 table"));
+	}
 
-		let script = r#"
-			log.error("Something went wrong")
-		"#;
-		lua.load(script).exec().unwrap();
+	#[test]
+	fn test_log_error() {
+		let logs = logged(r#"log.error("Something went wrong")"#, "info");
+		assert!(logs.contains("ERROR plugin::test: Something went wrong"));
+	}
 
-		assert!(logs_contain("(Lua) Something went wrong"));
+	#[test]
+	fn test_log_filtered_by_plugin() {
+		let script = r#"log.debug("page 2 has 20 items")"#;
+		assert!(!logged(script, "info").contains("page 2 has 20 items"));
+		assert!(logged(script, "info,plugin::test=debug").contains("DEBUG plugin::test: page 2 has 20 items"));
 	}
 }
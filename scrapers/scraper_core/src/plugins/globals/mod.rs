@@ -1,10 +1,13 @@
 use mlua::Lua;
 
 use crate::Config;
+use crate::plugins::common::http::CommonHttp;
 
+mod dom;
 mod flaresolverr;
 mod headless;
 mod http;
+mod image;
 mod log;
 mod scraping;
 mod string;
@@ -12,15 +15,16 @@ mod table;
 mod utils;
 
 #[allow(unused_variables)]
-pub async fn load(config: &Config, lua: &Lua) -> anyhow::Result<()> {
-	http::load(lua)?;
+pub async fn load(config: &Config, lua: &Lua, http: &CommonHttp, log_target: &str) -> anyhow::Result<()> {
+	http::load(lua, http.clone())?;
 	scraping::load(lua)?;
-	headless::load(config, lua).await?;
-	flaresolverr::load(config, lua)?;
+	image::load(lua)?;
+	headless::load(config, lua, http).await?;
+	flaresolverr::load(config, lua, http.clone())?;
 	string::load(lua)?;
 	table::load(lua)?;
 	utils::load(lua)?;
-	log::load(lua)?;
+	log::load(lua, log_target)?;
 
 	Ok(())
 }
@@ -4,7 +4,9 @@ use mlua::{IntoLua, Lua, UserData, UserDataMethods};
 use scraper::{Html, Selector};
 use scraper_types::{ScraperError, ScraperErrorKind};
 
+use crate::plugins::common::dom::DomDocument;
 use crate::plugins::common::html;
+use crate::plugins::globals::dom::LuaDocument;
 
 struct CustomScraper;
 impl UserData for CustomScraper {
@@ -40,6 +42,8 @@ impl UserData for CustomScraper {
 			Ok(url)
 		});
 
+		methods.add_method("parse", |_, _, html: String| Ok(LuaDocument(DomDocument::parse(&html))));
+
 		methods.add_method("get_text", |_, _, html: String| {
 			let html = html::HtmlElement::new(html, "".to_string());
 			let text = html.text();
@@ -15,8 +15,9 @@ type Aes256CbcDec = cbc::Decryptor<Aes256>;
 pub fn create_response_table(lua: &Lua, response: Response) -> mlua::Result<Table> {
 	let response_table = lua.create_table()?;
 
-	let text = response.text.clone();
-	response_table.set("text", response.text)?;
+	// Binary responses expose the raw body through `text`; Lua strings are byte strings.
+	let body = response.bytes.unwrap_or_else(|| response.text.into_bytes());
+	response_table.set("text", lua.create_string(&body)?)?;
 	response_table.set("status", response.status)?;
 	response_table.set("ok", response.ok)?;
 
@@ -35,7 +36,7 @@ pub fn create_response_table(lua: &Lua, response: Response) -> mlua::Result<Tabl
 	response_table.set(
 		"json",
 		lua.create_function(move |lua, ()| {
-			let json: serde_json::Value = match serde_json::from_str(&text) {
+			let json: serde_json::Value = match serde_json::from_slice(&body) {
 				Ok(value) => value,
 				Err(_) => serde_json::Value::Null,
 			};
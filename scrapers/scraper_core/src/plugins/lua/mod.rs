@@ -2,21 +2,28 @@ use std::fs;
 use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::Context;
-use mlua::Lua;
-use scraper_types::{Genre, Item, Page, ScraperError, ScraperErrorKind, ScraperInfo};
+use scraper_types::{
+	ChapterPage, Genre, Item, Page, PageImage, ProcessedImage, ScraperError, ScraperErrorKind, ScraperInfo, SearchFilters,
+};
 use serde_json::Value as JsonValue;
 
-use super::globals;
 use crate::Config;
+use crate::plugins::TestOutcome;
+use crate::plugins::common::http::CommonHttp;
+
+mod pool;
 
 #[derive(Debug, Clone)]
 pub struct LuaPlugin {
 	pub id: String,
 	pub version: String,
 	pub file: std::path::PathBuf,
-	pub(crate) runtime: Lua,
+	/// Whether pages go through `Transform_page`, read once when the plugin loads.
+	pub transforms_pages: bool,
+	pub(crate) pool: Arc<pool::RuntimePool>,
 }
 
 fn classify_lua_error(e: mlua::Error) -> anyhow::Error {
@@ -35,42 +42,57 @@ fn classify_lua_error(e: mlua::Error) -> anyhow::Error {
 
 impl LuaPlugin {
 	pub async fn new(config: Arc<Config>, file: &Path) -> anyhow::Result<Self> {
-		let runtime = Lua::new();
-		globals::load(&config, &runtime).await?;
-
-		let mut lua_file = fs::File::open(&file)?;
+		let mut lua_file = fs::File::open(file)?;
 		let mut script_content = String::new();
 		lua_file.read_to_string(&mut script_content)?;
 
-		runtime
-			.load(&script_content)
-			.set_name(&format!("@{}", file.display()))
-			.exec()
-			.with_context(|| format!("Failed to load Lua plugin: {}", file.display()))?;
+		let http = CommonHttp::for_plugin(&config, file);
+		let pool_size = config.lua.runtime_pool_size;
+		let pool = Arc::new(pool::RuntimePool::new(config, file, script_content, http, pool_size));
 
+		let runtime = pool.acquire().await?;
 		let globals = runtime.globals();
 		let info: mlua::Function = globals.get("Get_info").context("Missing PLUGIN_NAME in Lua plugin")?;
 		let info_table: mlua::Table = info
 			.call_async(())
 			.await
 			.context("Get_info() did not return valid plugin info")?;
+		let id = info_table.get("id").context("Missing 'id' in plugin info")?;
+		let version = info_table.get("version").context("Missing 'version' in plugin info")?;
+		// Defining the hook is enough to opt in.
+		let transforms_pages = info_table
+			.get::<Option<bool>>("transforms_pages")
+			.ok()
+			.flatten()
+			.unwrap_or_default()
+			|| globals.get::<Option<mlua::Function>>("Transform_page")?.is_some();
+		drop(runtime);
 
 		Ok(Self {
-			id: info_table.get("id").context("Missing 'id' in plugin info")?,
-			version: info_table.get("version").context("Missing 'version' in plugin info")?,
+			id,
+			version,
 			file: file.into(),
-			runtime,
+			transforms_pages,
+			pool,
 		})
 	}
 
 	pub async fn scrape_chapter(&self, url: String) -> anyhow::Result<Vec<String>> {
-		let scrape_chapter: mlua::Function = self.runtime.globals().get("Scrape_chapter")?;
-		let pages: Vec<String> = scrape_chapter.call_async(url).await.map_err(classify_lua_error)?;
+		let pages = self.scrape_chapter_pages(url).await?;
+		Ok(pages.into_iter().map(|page| page.url).collect())
+	}
+
+	/// `Scrape_chapter` may return bare strings or page tables, so both entry points share it.
+	pub async fn scrape_chapter_pages(&self, url: String) -> anyhow::Result<Vec<ChapterPage>> {
+		let runtime = self.pool.acquire().await?;
+		let scrape_chapter: mlua::Function = runtime.globals().get("Scrape_chapter")?;
+		let pages: Vec<ChapterPage> = scrape_chapter.call_async(url).await.map_err(classify_lua_error)?;
 		Ok(pages)
 	}
 
 	pub async fn scrape_latest(&self, page: u32) -> anyhow::Result<Vec<Item>> {
-		let scrape_latest: mlua::Function = self.runtime.globals().get("Scrape_latest")?;
+		let runtime = self.pool.acquire().await?;
+		let scrape_latest: mlua::Function = runtime.globals().get("Scrape_latest")?;
 		let raw_value: mlua::Value = scrape_latest.call_async(page).await.map_err(classify_lua_error)?;
 		let json: JsonValue = scraper_types::conversion::mlua_value_to_json(raw_value)?;
 		let items = scraper_types::conversion::value_to_items(&json)?;
@@ -78,23 +100,29 @@ impl LuaPlugin {
 	}
 
 	pub async fn scrape_trending(&self, page: u32) -> anyhow::Result<Vec<Item>> {
-		let scrape_trending: mlua::Function = self.runtime.globals().get("Scrape_trending")?;
+		let runtime = self.pool.acquire().await?;
+		let scrape_trending: mlua::Function = runtime.globals().get("Scrape_trending")?;
 		let raw_value: mlua::Value = scrape_trending.call_async(page).await.map_err(classify_lua_error)?;
 		let json: JsonValue = scraper_types::conversion::mlua_value_to_json(raw_value)?;
 		let items = scraper_types::conversion::value_to_items(&json)?;
 		Ok(items)
 	}
 
-	pub async fn scrape_search(&self, query: String, page: u32) -> anyhow::Result<Vec<Item>> {
-		let scrape_search: mlua::Function = self.runtime.globals().get("Scrape_search")?;
-		let raw_value: mlua::Value = scrape_search.call_async((query, page)).await.map_err(classify_lua_error)?;
+	pub async fn scrape_search(&self, query: String, page: u32, filters: SearchFilters) -> anyhow::Result<Vec<Item>> {
+		let runtime = self.pool.acquire().await?;
+		let scrape_search: mlua::Function = runtime.globals().get("Scrape_search")?;
+		let raw_value: mlua::Value = scrape_search
+			.call_async((query, page, filters))
+			.await
+			.map_err(classify_lua_error)?;
 		let json: JsonValue = scraper_types::conversion::mlua_value_to_json(raw_value)?;
 		let items = scraper_types::conversion::value_to_items(&json)?;
 		Ok(items)
 	}
 
 	pub async fn scrape(&self, url: String) -> anyhow::Result<Page> {
-		let scrape_manga: mlua::Function = self.runtime.globals().get("Scrape")?;
+		let runtime = self.pool.acquire().await?;
+		let scrape_manga: mlua::Function = runtime.globals().get("Scrape")?;
 		let raw_value: mlua::Value = scrape_manga.call_async(url).await.map_err(classify_lua_error)?;
 		let json: JsonValue = scraper_types::conversion::mlua_value_to_json(raw_value)?;
 		let page = scraper_types::conversion::value_to_page(&json)?;
@@ -102,32 +130,61 @@ impl LuaPlugin {
 	}
 
 	pub async fn scrape_genres_list(&self) -> anyhow::Result<Vec<Genre>> {
-		let scrape_genres_list: mlua::Function = self.runtime.globals().get("Scrape_genres_list")?;
+		let runtime = self.pool.acquire().await?;
+		let scrape_genres_list: mlua::Function = runtime.globals().get("Scrape_genres_list")?;
 		let genres: Vec<Genre> = scrape_genres_list.call_async(()).await.map_err(classify_lua_error)?;
 		Ok(genres)
 	}
 
+	pub async fn scrape_genre(&self, url: String, page: u32) -> anyhow::Result<Vec<Item>> {
+		let runtime = self.pool.acquire().await?;
+		let scrape_genre: mlua::Function = runtime.globals().get("Scrape_genre")?;
+		let raw_value: mlua::Value = scrape_genre.call_async((url, page)).await.map_err(classify_lua_error)?;
+		let json: JsonValue = scraper_types::conversion::mlua_value_to_json(raw_value)?;
+		let items = scraper_types::conversion::value_to_items(&json)?;
+		Ok(items)
+	}
+
 	pub async fn get_info(&self) -> anyhow::Result<ScraperInfo> {
-		let get_info: mlua::Function = self.runtime.globals().get("Get_info")?;
-		let info: ScraperInfo = get_info.call_async(()).await.map_err(classify_lua_error)?;
+		let runtime = self.pool.acquire().await?;
+		let get_info: mlua::Function = runtime.globals().get("Get_info")?;
+		let mut info: ScraperInfo = get_info.call_async(()).await.map_err(classify_lua_error)?;
+		// Defining the hook is enough to opt in.
+		info.transforms_pages |= runtime.globals().get::<Option<mlua::Function>>("Transform_page")?.is_some();
 		Ok(info)
 	}
 
-	pub async fn run_declared_tests(&self) -> anyhow::Result<Vec<String>> {
-		let globals = self.runtime.globals();
+	/// Runs the optional `Transform_page` hook. Plugins without it, or returning nil, leave the page unchanged.
+	pub async fn transform_page(&self, page: PageImage) -> anyhow::Result<Option<ProcessedImage>> {
+		let runtime = self.pool.acquire().await?;
+		let Some(transform_page) = runtime.globals().get::<Option<mlua::Function>>("Transform_page")? else {
+			return Ok(None);
+		};
+		let image: Option<ProcessedImage> = transform_page.call_async(page).await.map_err(classify_lua_error)?;
+		Ok(image)
+	}
+
+	pub async fn run_declared_tests(&self) -> anyhow::Result<Vec<TestOutcome>> {
+		let runtime = self.pool.acquire().await?;
+		let globals = runtime.globals();
 		let tests_table: mlua::Table = match globals.get("Tests") {
 			Ok(t) => t,
 			Err(_) => return Ok(Vec::new()),
 		};
 
-		let mut executed = Vec::new();
-		for pair in tests_table.pairs::<String, mlua::Function>() {
-			let (test_name, test_fn) = pair.map_err(classify_lua_error)?;
-			test_fn.call_async::<()>(()).await.map_err(classify_lua_error)?;
-			executed.push(test_name);
+		let tests = tests_table
+			.pairs::<String, mlua::Function>()
+			.collect::<mlua::Result<Vec<_>>>()
+			.map_err(classify_lua_error)?;
+
+		let mut outcomes = Vec::with_capacity(tests.len());
+		for (test_name, test_fn) in tests {
+			let started = Instant::now();
+			let result = test_fn.call_async::<()>(()).await.map_err(classify_lua_error);
+			outcomes.push(TestOutcome::new(test_name, started.elapsed(), result));
 		}
 
-		Ok(executed)
+		Ok(outcomes)
 	}
 }
 
@@ -229,7 +286,8 @@ mod tests {
 					}
 				};
 
-				let globals = plugin.runtime.globals();
+				let runtime = plugin.pool.acquire().await.expect("Failed to acquire Lua runtime");
+				let globals = runtime.globals();
 				let tests_table: mlua::Table = match globals.get("Tests") {
 					Ok(t) => t,
 					Err(_) => {
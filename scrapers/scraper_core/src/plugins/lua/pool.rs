@@ -0,0 +1,174 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use mlua::Lua;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::Config;
+use crate::plugins::common::http::CommonHttp;
+use crate::plugins::{globals, log_target};
+
+/// Independently initialized Lua states for a single plugin source.
+///
+/// States are created on demand up to `size`; callers wait for a free one once the pool is exhausted.
+#[derive(Debug)]
+pub(crate) struct RuntimePool {
+	config: Arc<Config>,
+	file: PathBuf,
+	source: String,
+	http: CommonHttp,
+	idle: Mutex<Vec<Lua>>,
+	permits: Semaphore,
+}
+
+impl RuntimePool {
+	pub fn new(config: Arc<Config>, file: &Path, source: String, http: CommonHttp, size: usize) -> Self {
+		Self {
+			config,
+			file: file.into(),
+			source,
+			http,
+			idle: Mutex::new(Vec::new()),
+			permits: Semaphore::new(size.max(1)),
+		}
+	}
+
+	pub async fn acquire(&self) -> anyhow::Result<PooledRuntime<'_>> {
+		let permit = self.permits.acquire().await.context("Lua runtime pool closed")?;
+
+		let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+		let runtime = match idle {
+			Some(runtime) => runtime,
+			None => create_runtime(&self.config, &self.file, &self.source, &self.http).await?,
+		};
+
+		Ok(PooledRuntime {
+			runtime: Some(runtime),
+			pool: self,
+			_permit: permit,
+		})
+	}
+
+	#[cfg(test)]
+	pub fn idle_len(&self) -> usize {
+		self.idle.lock().unwrap_or_else(|e| e.into_inner()).len()
+	}
+}
+
+pub(crate) struct PooledRuntime<'a> {
+	runtime: Option<Lua>,
+	pool: &'a RuntimePool,
+	_permit: SemaphorePermit<'a>,
+}
+
+impl Deref for PooledRuntime<'_> {
+	type Target = Lua;
+
+	fn deref(&self) -> &Lua {
+		self.runtime.as_ref().expect("runtime is only taken on drop")
+	}
+}
+
+impl Drop for PooledRuntime<'_> {
+	fn drop(&mut self) {
+		if let Some(runtime) = self.runtime.take() {
+			self.pool.idle.lock().unwrap_or_else(|e| e.into_inner()).push(runtime);
+		}
+	}
+}
+
+async fn create_runtime(config: &Config, file: &Path, source: &str, http: &CommonHttp) -> anyhow::Result<Lua> {
+	let runtime = Lua::new();
+	globals::load(config, &runtime, http, &log_target(file)).await?;
+
+	runtime
+		.load(source)
+		.set_name(format!("@{}", file.display()))
+		.exec()
+		.with_context(|| format!("Failed to load Lua plugin: {}", file.display()))?;
+
+	Ok(runtime)
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	const SOURCE: &str = r#"
+		Counter = 0
+		function Increment()
+			Counter = Counter + 1
+			return Counter
+		end
+	"#;
+
+	fn pool(size: usize) -> RuntimePool {
+		RuntimePool::new(
+			Arc::new(Config::default()),
+			Path::new("test.lua"),
+			SOURCE.to_string(),
+			CommonHttp::new(),
+			size,
+		)
+	}
+
+	#[tokio::test]
+	async fn test_runtimes_are_reused() {
+		let pool = pool(2);
+
+		{
+			let runtime = pool.acquire().await.expect("acquire");
+			let increment: mlua::Function = runtime.globals().get("Increment").unwrap();
+			assert_eq!(increment.call_async::<i64>(()).await.unwrap(), 1);
+		}
+		assert_eq!(pool.idle_len(), 1);
+
+		let runtime = pool.acquire().await.expect("acquire");
+		let increment: mlua::Function = runtime.globals().get("Increment").unwrap();
+		assert_eq!(increment.call_async::<i64>(()).await.unwrap(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_concurrent_runtimes_are_isolated() {
+		let pool = pool(2);
+
+		let a = pool.acquire().await.expect("acquire");
+		let b = pool.acquire().await.expect("acquire");
+
+		a.globals().set("Counter", 10).unwrap();
+		let counter: i64 = b.globals().get("Counter").unwrap();
+		assert_eq!(counter, 0);
+
+		drop(a);
+		drop(b);
+		assert_eq!(pool.idle_len(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_acquire_waits_when_exhausted() {
+		let pool = pool(1);
+
+		let runtime = pool.acquire().await.expect("acquire");
+		let waiting = tokio::time::timeout(std::time::Duration::from_millis(50), pool.acquire()).await;
+		assert!(waiting.is_err(), "second acquire should wait for the first runtime");
+
+		drop(runtime);
+		pool.acquire().await.expect("acquire after release");
+	}
+
+	#[tokio::test]
+	async fn test_invalid_source() {
+		let pool = RuntimePool::new(
+			Arc::new(Config::default()),
+			Path::new("broken.lua"),
+			"this is not lua".to_string(),
+			CommonHttp::new(),
+			1,
+		);
+		let err = pool.acquire().await.err().expect("expected load error");
+		assert!(format!("{:#}", err).contains("Failed to load Lua plugin"));
+	}
+}
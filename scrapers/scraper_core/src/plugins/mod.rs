@@ -1,10 +1,14 @@
 use std::future::Future;
+use std::path::Path;
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use scraper_types::{Genre, Item, Page, ScraperInfo};
+use scraper_types::{ChapterPage, Genre, Item, Page, PageImage, ProcessedImage, ScraperError, ScraperInfo, SearchFilters};
+use serde::Serialize;
+use tracing::Instrument;
 
-mod common;
+pub(crate) mod common;
 mod globals;
 pub mod lua;
 pub mod wasm;
@@ -20,46 +24,198 @@ pub enum Plugin {
 	Wasm(wasm::WasmPlugin),
 }
 
+/// Result of one of the self-tests a plugin declares.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestOutcome {
+	pub name: String,
+	pub duration_ms: u64,
+	/// Why the test failed, `None` when it passed.
+	pub failure: Option<String>,
+}
+
+impl TestOutcome {
+	pub fn new(name: String, duration: Duration, result: Result<()>) -> Self {
+		Self {
+			name,
+			duration_ms: duration.as_millis() as u64,
+			failure: result.err().map(|e| format!("{:#}", e)),
+		}
+	}
+
+	pub fn passed(&self) -> bool {
+		self.failure.is_none()
+	}
+}
+
+/// Awaits a plugin call in a span of its own and records its outcome with the health tracker and on `/metrics`.
+async fn tracked<T>(call: &'static str, id: &str, future: impl Future<Output = Result<T>>) -> Result<T> {
+	let span = tracing::info_span!("plugin_call", scraper = %id, call);
+	let start = Instant::now();
+	let result = future.instrument(span).await;
+	let latency = start.elapsed();
+	crate::health::tracker().record(id, latency, result.as_ref().err());
+	crate::metrics::record_plugin_call(id, call, latency);
+	result
+}
+
+/// Key for per-plugin state that has to exist before the plugin reports its id, such as its cookie jar.
+pub(crate) fn plugin_key(file: &Path) -> String {
+	file.file_stem()
+		.map(|stem| stem.to_string_lossy().into_owned())
+		.unwrap_or_else(|| "plugin".to_string())
+}
+
+/// Target the logs of a plugin are written under, e.g. `plugin::mangadex`, so filters can pick out one plugin.
+pub(crate) fn log_target(file: &Path) -> String {
+	format!("plugin::{}", plugin_key(file))
+}
+
 impl Plugin {
+	pub fn id(&self) -> &str {
+		match self {
+			Plugin::Lua(lua_plugin) => &lua_plugin.id,
+			Plugin::Wasm(wasm_plugin) => &wasm_plugin.name,
+		}
+	}
+
 	pub fn scrape_latest(&self, page: u32) -> Pin<Box<dyn Future<Output = Result<Vec<Item>>> + Send + '_>> {
 		match self {
-			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.scrape_latest(page)),
-			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.scrape_latest(page)),
+			Plugin::Lua(lua_plugin) => Box::pin(tracked("scrape_latest", &lua_plugin.id, lua_plugin.scrape_latest(page))),
+			Plugin::Wasm(wasm_plugin) => {
+				Box::pin(tracked("scrape_latest", &wasm_plugin.name, wasm_plugin.scrape_latest(page)))
+			}
 		}
 	}
 
 	pub fn scrape_chapter(&self, url: String) -> Pin<Box<dyn Future<Output = Result<Vec<String>>> + Send + '_>> {
 		match self {
-			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.scrape_chapter(url)),
-			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.scrape_chapter(url)),
+			Plugin::Lua(lua_plugin) => Box::pin(tracked("scrape_chapter", &lua_plugin.id, lua_plugin.scrape_chapter(url))),
+			Plugin::Wasm(wasm_plugin) => {
+				Box::pin(tracked("scrape_chapter", &wasm_plugin.name, wasm_plugin.scrape_chapter(url)))
+			}
 		}
 	}
 
-	pub fn scrape_trending(&self, page: u32) -> Pin<Box<dyn Future<Output = Result<Vec<Item>>> + Send + '_>> {
+	/// Manga chapter pages with the headers and mirrors needed to download them.
+	pub fn scrape_chapter_pages(&self, url: String) -> Pin<Box<dyn Future<Output = Result<Vec<ChapterPage>>> + Send + '_>> {
 		match self {
-			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.scrape_trending(page)),
-			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.scrape_trending(page)),
+			Plugin::Lua(lua_plugin) => Box::pin(tracked(
+				"scrape_chapter_pages",
+				&lua_plugin.id,
+				lua_plugin.scrape_chapter_pages(url),
+			)),
+			Plugin::Wasm(wasm_plugin) => Box::pin(tracked(
+				"scrape_chapter_pages",
+				&wasm_plugin.name,
+				wasm_plugin.scrape_chapter_pages(url),
+			)),
 		}
 	}
 
-	pub fn scrape_search(&self, query: String, page: u32) -> Pin<Box<dyn Future<Output = Result<Vec<Item>>> + Send + '_>> {
+	pub fn scrape_trending(&self, page: u32) -> Pin<Box<dyn Future<Output = Result<Vec<Item>>> + Send + '_>> {
 		match self {
-			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.scrape_search(query, page)),
-			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.scrape_search(query, page)),
+			Plugin::Lua(lua_plugin) => {
+				Box::pin(tracked("scrape_trending", &lua_plugin.id, lua_plugin.scrape_trending(page)))
+			}
+			Plugin::Wasm(wasm_plugin) => Box::pin(tracked(
+				"scrape_trending",
+				&wasm_plugin.name,
+				wasm_plugin.scrape_trending(page),
+			)),
+		}
+	}
+
+	pub fn scrape_search(
+		&self,
+		query: String,
+		page: u32,
+		filters: SearchFilters,
+	) -> Pin<Box<dyn Future<Output = Result<Vec<Item>>> + Send + '_>> {
+		Box::pin(async move {
+			let filters = self.resolve_search_filters(filters).await?;
+			match self {
+				Plugin::Lua(lua_plugin) => {
+					tracked(
+						"scrape_search",
+						&lua_plugin.id,
+						lua_plugin.scrape_search(query, page, filters),
+					)
+					.await
+				}
+				Plugin::Wasm(wasm_plugin) => {
+					tracked(
+						"scrape_search",
+						&wasm_plugin.name,
+						wasm_plugin.scrape_search(query, page, filters),
+					)
+					.await
+				}
+			}
+		})
+	}
+
+	/// Rejects filters the plugin did not declare before they reach it.
+	async fn resolve_search_filters(&self, filters: SearchFilters) -> Result<SearchFilters> {
+		if filters.is_empty() {
+			return Ok(filters);
+		}
+
+		match self.get_info().await?.search_filters {
+			Some(spec) => Ok(spec.resolve(filters)?),
+			None => Err(ScraperError::validation(format!("scraper {} does not support search filters", self.id())).into()),
 		}
 	}
 
 	pub fn scrape(&self, url: String) -> Pin<Box<dyn Future<Output = Result<Page>> + Send + '_>> {
 		match self {
-			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.scrape(url)),
-			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.scrape(url)),
+			Plugin::Lua(lua_plugin) => Box::pin(tracked("scrape", &lua_plugin.id, lua_plugin.scrape(url))),
+			Plugin::Wasm(wasm_plugin) => Box::pin(tracked("scrape", &wasm_plugin.name, wasm_plugin.scrape(url))),
+		}
+	}
+
+	/// Scrapes a page and normalizes it for storage, resolving relative URLs against the plugin's base URL.
+	/// Anything the normalization could not fix is logged as a validation warning.
+	pub async fn scrape_normalized(&self, url: String) -> Result<Page> {
+		let base_url = self.get_info().await?.base_url;
+		let mut page = self.scrape(url.clone()).await?;
+
+		for warning in page.normalize(base_url.as_deref().or(Some(&url))) {
+			tracing::warn!("Scraper {} returned an invalid page for {}: {}", self.id(), url, warning);
 		}
+
+		Ok(page)
 	}
 
 	pub fn scrape_genres_list(&self) -> Pin<Box<dyn Future<Output = Result<Vec<Genre>>> + Send + '_>> {
 		match self {
-			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.scrape_genres_list()),
-			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.scrape_genres_list()),
+			Plugin::Lua(lua_plugin) => {
+				Box::pin(tracked("scrape_genres_list", &lua_plugin.id, lua_plugin.scrape_genres_list()))
+			}
+			Plugin::Wasm(wasm_plugin) => Box::pin(tracked(
+				"scrape_genres_list",
+				&wasm_plugin.name,
+				wasm_plugin.scrape_genres_list(),
+			)),
+		}
+	}
+
+	/// Items listed under a genre url returned by `scrape_genres_list`.
+	pub fn scrape_genre(&self, url: String, page: u32) -> Pin<Box<dyn Future<Output = Result<Vec<Item>>> + Send + '_>> {
+		match self {
+			Plugin::Lua(lua_plugin) => Box::pin(tracked("scrape_genre", &lua_plugin.id, lua_plugin.scrape_genre(url, page))),
+			Plugin::Wasm(wasm_plugin) => Box::pin(tracked(
+				"scrape_genre",
+				&wasm_plugin.name,
+				wasm_plugin.scrape_genre(url, page),
+			)),
+		}
+	}
+
+	/// Whether the plugin post-processes chapter pages, as reported when it was loaded.
+	pub fn transforms_pages(&self) -> bool {
+		match self {
+			Plugin::Lua(lua_plugin) => lua_plugin.transforms_pages,
+			Plugin::Wasm(wasm_plugin) => wasm_plugin.transforms_pages,
 		}
 	}
 
@@ -69,4 +225,25 @@ impl Plugin {
 			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.get_info()),
 		}
 	}
+
+	/// Post-processes a downloaded chapter page, e.g. to reassemble a scrambled image. `None` keeps the page as is.
+	pub fn transform_page(
+		&self,
+		page: PageImage,
+	) -> Pin<Box<dyn Future<Output = Result<Option<ProcessedImage>>> + Send + '_>> {
+		match self {
+			Plugin::Lua(lua_plugin) => Box::pin(tracked("transform_page", &lua_plugin.id, lua_plugin.transform_page(page))),
+			Plugin::Wasm(wasm_plugin) => {
+				Box::pin(tracked("transform_page", &wasm_plugin.name, wasm_plugin.transform_page(page)))
+			}
+		}
+	}
+
+	/// Runs the plugin's declared self-tests. Plugins without tests return an empty list.
+	pub fn run_declared_tests(&self) -> Pin<Box<dyn Future<Output = Result<Vec<TestOutcome>>> + Send + '_>> {
+		match self {
+			Plugin::Lua(lua_plugin) => Box::pin(lua_plugin.run_declared_tests()),
+			Plugin::Wasm(wasm_plugin) => Box::pin(wasm_plugin.run_declared_tests()),
+		}
+	}
 }
@@ -1,9 +1,52 @@
 wasmtime::component::bindgen!({
 	path: "scraper.wit",
+	world: "root",
 	imports: { default: async | trappable },
 	exports: { default: async },
+	with: {
+		"scraper:types/html.document": crate::plugins::common::dom::DomDocument,
+		"scraper:types/html.node": crate::plugins::common::dom::DomNode,
+		"scraper:types/http.response-stream": crate::plugins::common::http::HttpStream,
+	},
 });
 
+/// Bindings for plugins that also export declared tests. Imports are shared with the `root` bindings above.
+pub mod testable {
+	wasmtime::component::bindgen!({
+		path: "scraper.wit",
+		world: "testable",
+		exports: { default: async },
+		with: {
+			"scraper:types/http": super::scraper::types::http,
+			"scraper:types/html": super::scraper::types::html,
+			"scraper:types/headless": super::scraper::types::headless,
+			"scraper:types/flare-solverr": super::scraper::types::flare_solverr,
+		},
+	});
+}
+
+impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::ScraperError> for scraper_types::ScraperError {
+	fn from(error: crate::plugins::wasm::bindings::exports::scraper::types::scraper::ScraperError) -> Self {
+		use crate::plugins::wasm::bindings::exports::scraper::types::scraper::ErrorKind;
+
+		let kind = match error.kind {
+			ErrorKind::Network => scraper_types::ScraperErrorKind::Network,
+			ErrorKind::Cloudflare => scraper_types::ScraperErrorKind::Cloudflare,
+			ErrorKind::RateLimit => scraper_types::ScraperErrorKind::RateLimit,
+			ErrorKind::NotFound => scraper_types::ScraperErrorKind::NotFound,
+			ErrorKind::Parse => scraper_types::ScraperErrorKind::Parse,
+			ErrorKind::Validation => scraper_types::ScraperErrorKind::Validation,
+			ErrorKind::Internal => scraper_types::ScraperErrorKind::Internal,
+		};
+		Self {
+			kind,
+			message: error.message,
+			retryable: error.retryable,
+			status_code: error.status_code,
+		}
+	}
+}
+
 impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::Item> for scraper_types::Item {
 	fn from(item: crate::plugins::wasm::bindings::exports::scraper::types::scraper::Item) -> Self {
 		Self {
@@ -47,6 +90,19 @@ impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::Genr
 	}
 }
 
+impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::ChapterPage> for scraper_types::ChapterPage {
+	fn from(page: crate::plugins::wasm::bindings::exports::scraper::types::scraper::ChapterPage) -> Self {
+		Self {
+			url: page.url,
+			headers: page.headers.into_iter().map(|header| (header.name, header.value)).collect(),
+			referer: page.referer,
+			width: page.width,
+			height: page.height,
+			mirrors: page.mirrors,
+		}
+	}
+}
+
 impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::Chapter> for scraper_types::Chapter {
 	fn from(chapter: crate::plugins::wasm::bindings::exports::scraper::types::scraper::Chapter) -> Self {
 		Self {
@@ -82,6 +138,112 @@ impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::Scra
 			referer_url: info.referer_url,
 			base_url: info.base_url,
 			legacy_urls: info.legacy_urls,
+			search_filters: info.search_filters.map(Into::into),
+			transforms_pages: info.transforms_pages,
+			rate_limit: info.rate_limit.map(|limit| scraper_types::RateLimit {
+				requests_per_minute: limit.requests_per_minute,
+				burst: limit.burst,
+			}),
+		}
+	}
+}
+
+impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::PageStatus> for scraper_types::PageStatus {
+	fn from(status: crate::plugins::wasm::bindings::exports::scraper::types::scraper::PageStatus) -> Self {
+		use crate::plugins::wasm::bindings::exports::scraper::types::scraper::PageStatus;
+
+		match status {
+			PageStatus::Ongoing => scraper_types::PageStatus::Ongoing,
+			PageStatus::Completed => scraper_types::PageStatus::Completed,
+			PageStatus::Hiatus => scraper_types::PageStatus::Hiatus,
+			PageStatus::Cancelled => scraper_types::PageStatus::Cancelled,
+		}
+	}
+}
+
+impl From<scraper_types::PageStatus> for crate::plugins::wasm::bindings::exports::scraper::types::scraper::PageStatus {
+	fn from(status: scraper_types::PageStatus) -> Self {
+		match status {
+			scraper_types::PageStatus::Ongoing => Self::Ongoing,
+			scraper_types::PageStatus::Completed => Self::Completed,
+			scraper_types::PageStatus::Hiatus => Self::Hiatus,
+			scraper_types::PageStatus::Cancelled => Self::Cancelled,
+		}
+	}
+}
+
+impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::SortOrder> for scraper_types::SortOrder {
+	fn from(sort: crate::plugins::wasm::bindings::exports::scraper::types::scraper::SortOrder) -> Self {
+		use crate::plugins::wasm::bindings::exports::scraper::types::scraper::SortOrder;
+
+		match sort {
+			SortOrder::Relevance => scraper_types::SortOrder::Relevance,
+			SortOrder::Latest => scraper_types::SortOrder::Latest,
+			SortOrder::Popular => scraper_types::SortOrder::Popular,
+			SortOrder::Alphabetical => scraper_types::SortOrder::Alphabetical,
+			SortOrder::Rating => scraper_types::SortOrder::Rating,
+		}
+	}
+}
+
+impl From<scraper_types::SortOrder> for crate::plugins::wasm::bindings::exports::scraper::types::scraper::SortOrder {
+	fn from(sort: scraper_types::SortOrder) -> Self {
+		match sort {
+			scraper_types::SortOrder::Relevance => Self::Relevance,
+			scraper_types::SortOrder::Latest => Self::Latest,
+			scraper_types::SortOrder::Popular => Self::Popular,
+			scraper_types::SortOrder::Alphabetical => Self::Alphabetical,
+			scraper_types::SortOrder::Rating => Self::Rating,
+		}
+	}
+}
+
+impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::SearchFilterSpec>
+	for scraper_types::SearchFilterSpec
+{
+	fn from(spec: crate::plugins::wasm::bindings::exports::scraper::types::scraper::SearchFilterSpec) -> Self {
+		Self {
+			genres: spec.genres,
+			exclude_genres: spec.exclude_genres,
+			statuses: spec.statuses.into_iter().map(Into::into).collect(),
+			types: spec.types,
+			sort_orders: spec.sort_orders.into_iter().map(Into::into).collect(),
+			year: spec.year,
+		}
+	}
+}
+
+impl From<scraper_types::SearchFilters> for crate::plugins::wasm::bindings::exports::scraper::types::scraper::SearchFilters {
+	fn from(filters: scraper_types::SearchFilters) -> Self {
+		Self {
+			include_genres: filters.include_genres,
+			exclude_genres: filters.exclude_genres,
+			status: filters.status.map(Into::into),
+			page_type: filters.page_type,
+			sort: filters.sort.map(Into::into),
+			year: filters.year,
+		}
+	}
+}
+
+impl From<scraper_types::PageImage> for crate::plugins::wasm::bindings::exports::scraper::types::scraper::PageImage {
+	fn from(page: scraper_types::PageImage) -> Self {
+		Self {
+			url: page.url,
+			referer: page.referer,
+			content_type: page.content_type,
+			data: page.data,
+		}
+	}
+}
+
+impl From<crate::plugins::wasm::bindings::exports::scraper::types::scraper::ProcessedImage>
+	for scraper_types::ProcessedImage
+{
+	fn from(image: crate::plugins::wasm::bindings::exports::scraper::types::scraper::ProcessedImage) -> Self {
+		Self {
+			content_type: image.content_type,
+			data: image.data,
 		}
 	}
 }
@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use wasmtime::Engine;
+use wasmtime::component::Component;
+
+/// Loads a component, reusing a precompiled artifact from `cache_folder` when one exists for the same file contents.
+pub(crate) fn load_component(engine: &Engine, file: &Path, cache_folder: Option<&Path>) -> Result<Component> {
+	let bytes = std::fs::read(file).map_err(|e| anyhow!("Failed to load WASM component {}: {}", file.display(), e))?;
+
+	let Some(cache_folder) = cache_folder else {
+		return compile(engine, file, &bytes);
+	};
+
+	let cached = cache_path(cache_folder, &bytes);
+	if cached.exists() {
+		// SAFETY: the cache folder is only written by `store`, with artifacts produced by `Component::serialize`.
+		// wasmtime still validates that the artifact was compiled for a compatible engine configuration.
+		match unsafe { Component::deserialize_file(engine, &cached) } {
+			Ok(component) => return Ok(component),
+			Err(e) => {
+				tracing::warn!("Discarding unusable WASM cache entry {}: {}", cached.display(), e);
+				let _ = std::fs::remove_file(&cached);
+			}
+		}
+	}
+
+	let component = compile(engine, file, &bytes)?;
+	if let Err(e) = store(&component, &cached) {
+		tracing::warn!("Failed to cache WASM component {}: {}", file.display(), e);
+	}
+
+	Ok(component)
+}
+
+fn compile(engine: &Engine, file: &Path, bytes: &[u8]) -> Result<Component> {
+	Component::new(engine, bytes).map_err(|e| anyhow!("Failed to load WASM component {}: {}", file.display(), e))
+}
+
+fn cache_path(cache_folder: &Path, bytes: &[u8]) -> PathBuf {
+	cache_folder.join(format!("{:x}.cwasm", md5::compute(bytes)))
+}
+
+fn store(component: &Component, path: &Path) -> Result<()> {
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+
+	let serialized = component.serialize()?;
+	let tmp = path.with_extension(format!("cwasm.{}.tmp", std::process::id()));
+	std::fs::write(&tmp, serialized)?;
+	std::fs::rename(&tmp, path)?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_cache_path_depends_on_contents() {
+		let folder = Path::new("/tmp/cache");
+		let a = cache_path(folder, b"component-a");
+		let b = cache_path(folder, b"component-b");
+
+		assert_eq!(a, cache_path(folder, b"component-a"));
+		assert_ne!(a, b);
+		assert_eq!(a.extension().and_then(|e| e.to_str()), Some("cwasm"));
+	}
+}
@@ -0,0 +1,59 @@
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use wasmtime::{Engine, InstanceAllocationStrategy, PoolingAllocationConfig};
+
+use crate::WasmConfig;
+
+static ENGINE: OnceLock<Engine> = OnceLock::new();
+
+/// Returns the engine shared by every WASM plugin, creating it on first use.
+///
+/// The pooling allocator is used when enabled in the config. If the host can't
+/// reserve the pool (e.g. restricted virtual memory) we fall back to on-demand
+/// allocation instead of failing to load plugins.
+pub(crate) fn shared_engine(config: &WasmConfig) -> Result<Engine> {
+	if let Some(engine) = ENGINE.get() {
+		return Ok(engine.clone());
+	}
+
+	let engine = if config.pooling_allocator {
+		match Engine::new(&engine_config(true)) {
+			Ok(engine) => engine,
+			Err(e) => {
+				tracing::warn!("Failed to create pooling WASM engine, using on-demand allocation: {}", e);
+				Engine::new(&engine_config(false))?
+			}
+		}
+	} else {
+		Engine::new(&engine_config(false))?
+	};
+
+	let _ = ENGINE.set(engine);
+	Ok(ENGINE.get().expect("engine was just initialized").clone())
+}
+
+fn engine_config(pooling: bool) -> wasmtime::Config {
+	let mut config = wasmtime::Config::new();
+	config.consume_fuel(true);
+
+	if pooling {
+		config.allocation_strategy(InstanceAllocationStrategy::Pooling(PoolingAllocationConfig::default()));
+	}
+
+	config
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_shared_engine_is_reused() {
+		let config = WasmConfig::default();
+		let a = shared_engine(&config).expect("engine");
+		let b = shared_engine(&config).expect("engine");
+		assert!(Engine::same(&a, &b));
+	}
+}
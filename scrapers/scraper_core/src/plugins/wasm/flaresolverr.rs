@@ -1,17 +1,10 @@
-use std::sync::{Arc, LazyLock};
-
-use crate::CONFIG;
-use crate::plugins::common::flaresolverr::FlareSolverrManager;
 use crate::plugins::wasm::bindings::scraper::types::flare_solverr::Response;
 use crate::plugins::wasm::bindings::{self};
 use crate::plugins::wasm::state::States;
 
-const _FLARE_SOLVERR_MANAGER: LazyLock<Arc<FlareSolverrManager>> =
-	LazyLock::new(|| Arc::new(FlareSolverrManager::new(&CONFIG)));
-
 impl bindings::scraper::types::flare_solverr::Host for States {
 	async fn get(&mut self, url: String, _session_id: Option<String>) -> Result<Option<Response>, wasmtime::Error> {
-		let manager = _FLARE_SOLVERR_MANAGER.clone();
+		let manager = self.flaresolverr.clone();
 		let response = manager.get(url).await;
 
 		if response.ok {
@@ -1,16 +1,26 @@
 use std::sync::{Arc, OnceLock};
 
 use crate::CONFIG;
+use crate::plugins::common::fixtures::FixtureHeadless;
 use crate::plugins::common::headless::fallback::FallbackBackend;
 use crate::plugins::common::headless::fantoccini::FantocciniBackend;
 use crate::plugins::common::headless::traits::{HeadlessBackend, HeadlessElement};
+use crate::plugins::common::http::CommonHttp;
 use crate::plugins::wasm::bindings::scraper::types::headless::Element;
 use crate::plugins::wasm::bindings::{self};
 use crate::plugins::wasm::state::States;
 
 static _HEADLESS: OnceLock<Arc<dyn HeadlessBackend>> = OnceLock::new();
 
-async fn _get_headless() -> Arc<dyn HeadlessBackend> {
+async fn _get_headless(http: &CommonHttp) -> Arc<dyn HeadlessBackend> {
+	match http.fixtures() {
+		Some(fixtures) if fixtures.is_replaying() => Arc::new(FixtureHeadless::replay(fixtures.clone())),
+		Some(fixtures) => Arc::new(FixtureHeadless::record(_shared_headless().await, fixtures.clone())),
+		None => _shared_headless().await,
+	}
+}
+
+async fn _shared_headless() -> Arc<dyn HeadlessBackend> {
 	if let Some(h) = _HEADLESS.get() {
 		return h.clone();
 	}
@@ -36,7 +46,7 @@ async fn _convert_to_binding(
 
 impl bindings::scraper::types::headless::Host for States {
 	async fn goto(&mut self, url: String) -> Result<Result<(), String>, wasmtime::Error> {
-		let headless = _get_headless().await;
+		let headless = _get_headless(&self.http).await;
 		let result = headless.goto(url).await;
 		let inner_result = match result {
 			Ok(_) => Ok(()),
@@ -49,7 +59,7 @@ impl bindings::scraper::types::headless::Host for States {
 	}
 
 	async fn find_one(&mut self, selector: String) -> Result<Option<Element>, wasmtime::Error> {
-		let headless = _get_headless().await;
+		let headless = _get_headless(&self.http).await;
 		let result = headless.find(selector).await;
 		match result {
 			Ok(opt_element) => {
@@ -68,7 +78,7 @@ impl bindings::scraper::types::headless::Host for States {
 	}
 
 	async fn find_all(&mut self, selector: String) -> Result<Vec<Element>, wasmtime::Error> {
-		let headless = _get_headless().await;
+		let headless = _get_headless(&self.http).await;
 		let result = headless.find_all(selector).await;
 		match result {
 			Ok(elements) => {
@@ -87,7 +97,7 @@ impl bindings::scraper::types::headless::Host for States {
 	}
 
 	async fn close(&mut self) -> Result<bool, wasmtime::Error> {
-		let headless = _get_headless().await;
+		let headless = _get_headless(&self.http).await;
 		let result = headless.close().await;
 		match result {
 			Ok(_) => Ok(true),
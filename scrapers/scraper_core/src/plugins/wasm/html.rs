@@ -1,6 +1,9 @@
+use wasmtime::component::Resource;
+
+use crate::plugins::common::dom::{DomDocument, DomNode};
 use crate::plugins::common::html;
 use crate::plugins::wasm::bindings;
-use crate::plugins::wasm::bindings::scraper::types::html::Element;
+use crate::plugins::wasm::bindings::scraper::types::html::{Attribute, Element};
 use crate::plugins::wasm::state::States;
 
 impl From<html::HtmlElement> for Element {
@@ -52,3 +55,200 @@ impl bindings::scraper::types::html::Host for States {
 		Ok(elem.attr(name))
 	}
 }
+
+impl States {
+	fn node(&mut self, node: &Resource<DomNode>) -> wasmtime::Result<DomNode> {
+		Ok(self.table().get(node)?.clone())
+	}
+
+	fn document(&mut self, doc: &Resource<DomDocument>) -> wasmtime::Result<DomDocument> {
+		Ok(self.table().get(doc)?.clone())
+	}
+
+	fn push_node(&mut self, node: DomNode) -> wasmtime::Result<Resource<DomNode>> {
+		Ok(self.table().push(node)?)
+	}
+
+	fn push_nodes(&mut self, nodes: Vec<DomNode>) -> wasmtime::Result<Vec<Resource<DomNode>>> {
+		nodes.into_iter().map(|node| self.push_node(node)).collect()
+	}
+
+	fn push_optional_node(&mut self, node: Option<DomNode>) -> wasmtime::Result<Option<Resource<DomNode>>> {
+		node.map(|node| self.push_node(node)).transpose()
+	}
+}
+
+impl bindings::scraper::types::html::HostDocument for States {
+	async fn new(&mut self, html: String) -> wasmtime::Result<Resource<DomDocument>> {
+		Ok(self.table().push(DomDocument::parse(&html))?)
+	}
+
+	async fn root(&mut self, doc: Resource<DomDocument>) -> wasmtime::Result<Resource<DomNode>> {
+		let root = self.document(&doc)?.root();
+		self.push_node(root)
+	}
+
+	async fn select(
+		&mut self,
+		doc: Resource<DomDocument>,
+		selector: String,
+	) -> wasmtime::Result<Result<Vec<Resource<DomNode>>, String>> {
+		match self.document(&doc)?.select(&selector) {
+			Ok(nodes) => Ok(Ok(self.push_nodes(nodes)?)),
+			Err(e) => Ok(Err(e.to_string())),
+		}
+	}
+
+	async fn select_one(
+		&mut self,
+		doc: Resource<DomDocument>,
+		selector: String,
+	) -> wasmtime::Result<Result<Option<Resource<DomNode>>, String>> {
+		match self.document(&doc)?.select_one(&selector) {
+			Ok(node) => Ok(Ok(self.push_optional_node(node)?)),
+			Err(e) => Ok(Err(e.to_string())),
+		}
+	}
+
+	async fn xpath(
+		&mut self,
+		doc: Resource<DomDocument>,
+		expr: String,
+	) -> wasmtime::Result<Result<Vec<Resource<DomNode>>, String>> {
+		match self.document(&doc)?.xpath(&expr) {
+			Ok(nodes) => Ok(Ok(self.push_nodes(nodes)?)),
+			Err(e) => Ok(Err(e.to_string())),
+		}
+	}
+
+	async fn xpath_text(
+		&mut self,
+		doc: Resource<DomDocument>,
+		expr: String,
+	) -> wasmtime::Result<Result<Vec<String>, String>> {
+		Ok(self.document(&doc)?.xpath_text(&expr).map_err(|e| e.to_string()))
+	}
+
+	async fn drop(&mut self, doc: Resource<DomDocument>) -> wasmtime::Result<()> {
+		self.table().delete(doc)?;
+		Ok(())
+	}
+}
+
+impl bindings::scraper::types::html::HostNode for States {
+	async fn name(&mut self, node: Resource<DomNode>) -> wasmtime::Result<String> {
+		Ok(self.node(&node)?.name())
+	}
+
+	async fn select(
+		&mut self,
+		node: Resource<DomNode>,
+		selector: String,
+	) -> wasmtime::Result<Result<Vec<Resource<DomNode>>, String>> {
+		match self.node(&node)?.select(&selector) {
+			Ok(nodes) => Ok(Ok(self.push_nodes(nodes)?)),
+			Err(e) => Ok(Err(e.to_string())),
+		}
+	}
+
+	async fn select_one(
+		&mut self,
+		node: Resource<DomNode>,
+		selector: String,
+	) -> wasmtime::Result<Result<Option<Resource<DomNode>>, String>> {
+		match self.node(&node)?.select_one(&selector) {
+			Ok(found) => Ok(Ok(self.push_optional_node(found)?)),
+			Err(e) => Ok(Err(e.to_string())),
+		}
+	}
+
+	async fn children(&mut self, node: Resource<DomNode>) -> wasmtime::Result<Vec<Resource<DomNode>>> {
+		let children = self.node(&node)?.children();
+		self.push_nodes(children)
+	}
+
+	async fn parent(&mut self, node: Resource<DomNode>) -> wasmtime::Result<Option<Resource<DomNode>>> {
+		let parent = self.node(&node)?.parent();
+		self.push_optional_node(parent)
+	}
+
+	async fn next_sibling(&mut self, node: Resource<DomNode>) -> wasmtime::Result<Option<Resource<DomNode>>> {
+		let sibling = self.node(&node)?.next_sibling();
+		self.push_optional_node(sibling)
+	}
+
+	async fn prev_sibling(&mut self, node: Resource<DomNode>) -> wasmtime::Result<Option<Resource<DomNode>>> {
+		let sibling = self.node(&node)?.prev_sibling();
+		self.push_optional_node(sibling)
+	}
+
+	async fn siblings(&mut self, node: Resource<DomNode>) -> wasmtime::Result<Vec<Resource<DomNode>>> {
+		let siblings = self.node(&node)?.siblings();
+		self.push_nodes(siblings)
+	}
+
+	async fn text(&mut self, node: Resource<DomNode>) -> wasmtime::Result<String> {
+		Ok(self.node(&node)?.text())
+	}
+
+	async fn inner_text(&mut self, node: Resource<DomNode>) -> wasmtime::Result<String> {
+		Ok(self.node(&node)?.inner_text())
+	}
+
+	async fn inner_html(&mut self, node: Resource<DomNode>) -> wasmtime::Result<String> {
+		Ok(self.node(&node)?.inner_html())
+	}
+
+	async fn outer_html(&mut self, node: Resource<DomNode>) -> wasmtime::Result<String> {
+		Ok(self.node(&node)?.outer_html())
+	}
+
+	async fn attr(&mut self, node: Resource<DomNode>, name: String) -> wasmtime::Result<Option<String>> {
+		Ok(self.node(&node)?.attr(&name))
+	}
+
+	async fn attrs(&mut self, node: Resource<DomNode>) -> wasmtime::Result<Vec<Attribute>> {
+		Ok(self
+			.node(&node)?
+			.attrs()
+			.into_iter()
+			.map(|(name, value)| Attribute { name, value })
+			.collect())
+	}
+
+	async fn xpath(
+		&mut self,
+		node: Resource<DomNode>,
+		expr: String,
+	) -> wasmtime::Result<Result<Vec<Resource<DomNode>>, String>> {
+		match self.node(&node)?.xpath(&expr) {
+			Ok(nodes) => Ok(Ok(self.push_nodes(nodes)?)),
+			Err(e) => Ok(Err(e.to_string())),
+		}
+	}
+
+	async fn xpath_text(&mut self, node: Resource<DomNode>, expr: String) -> wasmtime::Result<Result<Vec<String>, String>> {
+		Ok(self.node(&node)?.xpath_text(&expr).map_err(|e| e.to_string()))
+	}
+
+	async fn regex_match(
+		&mut self,
+		node: Resource<DomNode>,
+		pattern: String,
+	) -> wasmtime::Result<Result<Option<String>, String>> {
+		Ok(self.node(&node)?.regex_match(&pattern).map_err(|e| e.to_string()))
+	}
+
+	async fn regex_match_all(
+		&mut self,
+		node: Resource<DomNode>,
+		pattern: String,
+	) -> wasmtime::Result<Result<Vec<String>, String>> {
+		Ok(self.node(&node)?.regex_match_all(&pattern).map_err(|e| e.to_string()))
+	}
+
+	async fn drop(&mut self, node: Resource<DomNode>) -> wasmtime::Result<()> {
+		self.table().delete(node)?;
+		Ok(())
+	}
+}
@@ -1,79 +1,200 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::Url;
+use wasmtime::component::Resource;
+
+use crate::plugins::common::cookies::SessionCookie;
+use crate::plugins::common::http::{self, HttpStream};
 use crate::plugins::wasm::bindings;
+use crate::plugins::wasm::bindings::scraper::types::http::{
+	Cookie, FullResponse, Header, Method, MultipartPart, Param, RequestBody, RequestOptions, Response, ResponseBody,
+};
 use crate::plugins::wasm::state::States;
 
-impl bindings::scraper::types::http::Host for States {
-	async fn get(
-		&mut self,
-		url: String,
-		headers: Option<Vec<bindings::scraper::types::http::Header>>,
-	) -> Result<Option<bindings::scraper::types::http::Response>, wasmtime::Error> {
-		let headers = headers.unwrap_or_default();
-		let client = reqwest::Client::new();
-		let mut request = client.get(&url);
-		for header in headers {
-			request = request.header(header.name, header.value);
+fn header_map(headers: Option<Vec<Header>>) -> HashMap<String, String> {
+	headers
+		.unwrap_or_default()
+		.into_iter()
+		.map(|header| (header.name, header.value))
+		.collect()
+}
+
+fn headers_list(headers: HashMap<String, String>) -> Vec<Header> {
+	headers.into_iter().map(|(name, value)| Header { name, value }).collect()
+}
+
+fn params(params: Vec<Param>) -> Vec<(String, String)> {
+	params.into_iter().map(|param| (param.name, param.value)).collect()
+}
+
+impl From<Method> for http::Method {
+	fn from(method: Method) -> Self {
+		match method {
+			Method::Get => Self::Get,
+			Method::Post => Self::Post,
+			Method::Put => Self::Put,
+			Method::Patch => Self::Patch,
+			Method::Delete => Self::Delete,
+			Method::Head => Self::Head,
+			Method::Options => Self::Options,
 		}
+	}
+}
 
-		match request.send().await {
-			Ok(res) => {
-				let status = res.status().as_u16();
-				let headers = res
-					.headers()
-					.iter()
-					.map(|(name, value)| bindings::scraper::types::http::Header {
-						name: name.to_string(),
-						value: value.to_str().unwrap_or("").to_string(),
+impl From<RequestBody> for http::RequestBody {
+	fn from(body: RequestBody) -> Self {
+		match body {
+			RequestBody::Text(text) => Self::Text(text),
+			RequestBody::Bytes(bytes) => Self::Bytes(bytes),
+			RequestBody::Form(fields) => Self::Form(params(fields)),
+			RequestBody::Multipart(parts) => Self::Multipart(
+				parts
+					.into_iter()
+					.map(|part: MultipartPart| http::MultipartPart {
+						name: part.name,
+						filename: part.filename,
+						content_type: part.content_type,
+						data: part.data,
 					})
-					.collect();
-				let body = res.text().await.unwrap_or_default();
-				Ok(Some(bindings::scraper::types::http::Response { status, headers, body }))
-			}
-			Err(e) => {
-				tracing::error!("Error fetching URL {}: {}", url, e);
-				Ok(None)
-			}
+					.collect(),
+			),
 		}
 	}
+}
+
+impl From<RequestOptions> for http::RequestOptions {
+	fn from(options: RequestOptions) -> Self {
+		Self {
+			method: options.method.into(),
+			headers: header_map(Some(options.headers)),
+			query: params(options.query),
+			body: options.body.map(Into::into),
+			timeout: options.timeout_ms.map(|ms| Duration::from_millis(ms.into())),
+			follow_redirects: options.follow_redirects,
+			binary: options.binary,
+		}
+	}
+}
+
+/// Requests that never reached the server surface as `none`; HTTP error statuses are still returned.
+fn into_response(url: &str, response: http::Response) -> Option<Response> {
+	if response.status == 0 {
+		if let Some(error) = response.error {
+			tracing::error!("Error fetching URL {}: {}", url, error.message);
+		}
+		return None;
+	}
+
+	Some(Response {
+		status: response.status,
+		headers: headers_list(response.headers),
+		body: response.text,
+	})
+}
+
+impl bindings::scraper::types::http::Host for States {
+	async fn get(&mut self, url: String, headers: Option<Vec<Header>>) -> Result<Option<Response>, wasmtime::Error> {
+		let response = self.http.get(url.clone(), Some(header_map(headers))).await;
+		Ok(into_response(&url, response))
+	}
 
 	async fn post(
 		&mut self,
 		url: String,
 		body: String,
-		headers: Option<Vec<bindings::scraper::types::http::Header>>,
-	) -> Result<Option<bindings::scraper::types::http::Response>, wasmtime::Error> {
-		let headers = headers.unwrap_or_default();
-		let client = reqwest::Client::new();
-		let mut request = client.post(&url).body(body);
-		for header in headers {
-			request = request.header(header.name, header.value);
+		headers: Option<Vec<Header>>,
+	) -> Result<Option<Response>, wasmtime::Error> {
+		let response = self.http.post(url.clone(), body, Some(header_map(headers))).await;
+		Ok(into_response(&url, response))
+	}
+
+	async fn request(
+		&mut self,
+		url: String,
+		options: RequestOptions,
+	) -> Result<Result<FullResponse, String>, wasmtime::Error> {
+		let response = self.http.request(url, options.into()).await;
+		if response.status == 0 {
+			let message = response.error.map(|e| e.message).unwrap_or_default();
+			return Ok(Err(message));
 		}
 
-		match request.send().await {
-			Ok(res) => {
-				let status = res.status().as_u16();
-				let headers = res
-					.headers()
-					.iter()
-					.map(|(name, value)| bindings::scraper::types::http::Header {
-						name: name.to_string(),
-						value: value.to_str().unwrap_or("").to_string(),
-					})
-					.collect();
-				let body = res.text().await.unwrap_or_default();
-				Ok(Some(bindings::scraper::types::http::Response { status, headers, body }))
-			}
-			Err(e) => {
-				tracing::error!("Error posting to URL {}: {}", url, e);
-				Ok(None)
-			}
+		let body = match response.bytes {
+			Some(bytes) => ResponseBody::Bytes(bytes),
+			None => ResponseBody::Text(response.text),
+		};
+		Ok(Ok(FullResponse {
+			status: response.status,
+			headers: headers_list(response.headers),
+			body,
+		}))
+	}
+
+	async fn open_stream(
+		&mut self,
+		url: String,
+		options: RequestOptions,
+	) -> Result<Result<Resource<HttpStream>, String>, wasmtime::Error> {
+		match self.http.stream(url, options.into()).await {
+			Ok(stream) => Ok(Ok(self.table().push(stream)?)),
+			Err(e) => Ok(Err(e.message)),
 		}
 	}
 
+	async fn get_cookies(&mut self, url: String) -> Result<Vec<Cookie>, wasmtime::Error> {
+		let Ok(url) = Url::parse(&url) else {
+			return Ok(Vec::new());
+		};
+
+		Ok(self
+			.http
+			.cookies()
+			.get(&url)
+			.into_iter()
+			.map(|cookie| Cookie {
+				name: cookie.name,
+				value: cookie.value,
+				domain: cookie.domain,
+				path: cookie.path,
+				max_age: cookie.max_age,
+			})
+			.collect())
+	}
+
+	async fn set_cookie(&mut self, url: String, cookie: Cookie) -> Result<Result<(), String>, wasmtime::Error> {
+		let url = match Url::parse(&url) {
+			Ok(url) => url,
+			Err(e) => return Ok(Err(format!("invalid url '{}': {}", url, e))),
+		};
+
+		let jar = self.http.cookies();
+		let result = jar.set(
+			&url,
+			&SessionCookie {
+				name: cookie.name,
+				value: cookie.value,
+				domain: cookie.domain,
+				path: cookie.path,
+				max_age: cookie.max_age,
+			},
+		);
+		jar.persist();
+		Ok(result)
+	}
+
+	async fn clear_cookies(&mut self) -> Result<(), wasmtime::Error> {
+		let jar = self.http.cookies();
+		jar.clear();
+		jar.persist();
+		Ok(())
+	}
+
 	async fn has_cloudflare_protection(
 		&mut self,
 		text: String,
 		status_code: Option<u16>,
-		headers: Option<Vec<bindings::scraper::types::http::Header>>,
+		headers: Option<Vec<Header>>,
 	) -> Result<bool, wasmtime::Error> {
 		let is_protected = text.contains("Attention Required! | Cloudflare")
 			|| text.contains("Just a moment...")
@@ -108,3 +229,23 @@ impl bindings::scraper::types::http::Host for States {
 		Ok(false)
 	}
 }
+
+impl bindings::scraper::types::http::HostResponseStream for States {
+	async fn status(&mut self, stream: Resource<HttpStream>) -> wasmtime::Result<u16> {
+		Ok(self.table().get(&stream)?.status)
+	}
+
+	async fn headers(&mut self, stream: Resource<HttpStream>) -> wasmtime::Result<Vec<Header>> {
+		Ok(headers_list(self.table().get(&stream)?.headers.clone()))
+	}
+
+	async fn read(&mut self, stream: Resource<HttpStream>) -> wasmtime::Result<Result<Option<Vec<u8>>, String>> {
+		let stream = self.table().get(&stream)?;
+		Ok(stream.next_chunk().await.map_err(|e| e.message))
+	}
+
+	async fn drop(&mut self, stream: Resource<HttpStream>) -> wasmtime::Result<()> {
+		self.table().delete(stream)?;
+		Ok(())
+	}
+}
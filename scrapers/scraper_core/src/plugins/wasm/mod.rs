@@ -1,40 +1,48 @@
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{Result, anyhow};
-use wasmtime::component::{Component, HasSelf, Linker};
-use wasmtime::{Engine, Store};
+use scraper_types::ScraperError;
+use wasmtime::component::{HasSelf, Linker};
 
-use crate::plugins::wasm::state::States;
+use crate::Config;
+use crate::plugins::common::flaresolverr::FlareSolverrManager;
+use crate::plugins::common::http::CommonHttp;
+use crate::plugins::wasm::bindings::exports::scraper::types::scraper::ScraperError as WitScraperError;
+use crate::plugins::wasm::bindings::testable::exports::scraper::types::tests::GuestIndices as TestsIndices;
+use crate::plugins::wasm::pool::{InstancePool, PooledInstance};
+use crate::plugins::{TestOutcome, log_target};
 
 mod bindings;
+mod cache;
+mod engine;
 mod flaresolverr;
 mod headless;
 mod html;
 mod http;
+mod output;
+mod pool;
+mod self_test;
 mod state;
 
 pub struct WasmPlugin {
 	pub name: String,
 	pub version: String,
 	pub file: PathBuf,
-	engine: Engine,
-	component: Component,
-	linker: Linker<States>,
+	/// Whether pages go through `transform-page`, read once when the plugin loads.
+	pub transforms_pages: bool,
+	pool: InstancePool,
+	/// Set when the component targets the `testable` world and exports declared tests.
+	tests: Option<TestsIndices>,
 }
 
 impl WasmPlugin {
-	pub async fn new(file: &Path) -> Result<Self> {
-		let mut config = wasmtime::Config::new();
-		config.consume_fuel(true);
+	pub async fn new(config: Arc<Config>, file: &Path) -> Result<Self> {
+		let engine = engine::shared_engine(&config.wasm)?;
 
-		let engine = Engine::new(&config)?;
-
-		let component = Component::from_file(&engine, &file)
-			.map_err(|e| anyhow!("Failed to load WASM component {}: {}", file.display(), e))?;
-
-		let mut store = Store::new(&engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
+		let cache_folder = config.wasm.component_cache_folder.as_ref().map(PathBuf::from);
+		let component = cache::load_component(&engine, file, cache_folder.as_deref())?;
 
 		let mut linker = Linker::new(&engine);
 		wasmtime_wasi::p2::add_to_linker_async(&mut linker).expect("Could not add wasi to linker");
@@ -43,145 +51,220 @@ impl WasmPlugin {
 		bindings::scraper::types::headless::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
 		bindings::scraper::types::flare_solverr::add_to_linker::<_, HasSelf<_>>(&mut linker, |state| state)?;
 
-		let instance = bindings::Root::instantiate_async(&mut store, &component, &linker).await?;
-		let scraper = instance.scraper_types_scraper();
-		let info = scraper.call_get_info(&mut store).await?;
+		let http = CommonHttp::for_plugin(&config, file);
+		let flaresolverr = Arc::new(FlareSolverrManager::with_http(&config, http.clone()));
+
+		let instance_pre = linker.instantiate_pre(&component)?;
+		let tests = TestsIndices::new(&instance_pre).ok();
+		let pre = bindings::RootPre::new(instance_pre)?;
+		let pool = InstancePool::new(
+			engine,
+			pre,
+			http,
+			flaresolverr,
+			Arc::from(log_target(file)),
+			config.wasm.instance_pool_size,
+			config.wasm.reuse_instances,
+		);
+
+		let mut instance = pool.acquire().await?;
+		let result = instance.root.scraper_types_scraper().call_get_info(&mut instance.store).await;
+		pool.release(instance, result.is_ok());
+		let info = result?;
 
 		Ok(Self {
 			name: info.id,
 			version: info.version,
 			file: file.into(),
-			engine,
-			component,
-			linker,
+			transforms_pages: info.transforms_pages,
+			pool,
+			tests,
 		})
 	}
 
-	pub async fn scrape_chapter(&self, url: String) -> Result<Vec<String>> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
+	async fn acquire(&self) -> Result<PooledInstance> {
+		self.pool
+			.acquire()
 			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
+			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))
+	}
 
-		let pages = instance
+	/// Traps keep the old opaque error; errors returned by the plugin become a typed [`ScraperError`].
+	fn plugin_result<T>(
+		&self,
+		result: wasmtime::Result<Result<T, WitScraperError>>,
+		action: impl FnOnce() -> String,
+	) -> Result<T> {
+		match result {
+			Ok(Ok(value)) => Ok(value),
+			Ok(Err(error)) => Err(ScraperError::from(error).into()),
+			Err(e) => Err(anyhow!("Failed to {} for plugin {}: {}", action(), self.name, e)),
+		}
+	}
+
+	pub async fn scrape_chapter(&self, url: String) -> Result<Vec<String>> {
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
 			.scraper_types_scraper()
-			.call_scrape_chapter(&mut store, &url)
-			.await
-			.map_err(|e| anyhow!("Failed to scrape chapter for plugin {}: {}", name, e))?;
+			.call_scrape_chapter(&mut instance.store, &url)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let pages = self.plugin_result(result, || "scrape chapter".to_string())?;
 
 		Ok(pages.into_iter().map(Into::into).collect())
 	}
 
-	pub async fn scrape_latest(&self, page: u32) -> Result<Vec<scraper_types::Item>> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
-			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
+	pub async fn scrape_chapter_pages(&self, url: String) -> Result<Vec<scraper_types::ChapterPage>> {
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
+			.scraper_types_scraper()
+			.call_scrape_chapter_pages(&mut instance.store, &url)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let pages = self.plugin_result(result, || "scrape chapter pages".to_string())?;
+
+		Ok(pages.into_iter().map(Into::into).collect())
+	}
 
-		let items = instance
+	pub async fn scrape_latest(&self, page: u32) -> Result<Vec<scraper_types::Item>> {
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
 			.scraper_types_scraper()
-			.call_scrape_latest(&mut store, page)
-			.await
-			.map_err(|e| anyhow!("Failed to scrape latest for plugin {}: {}", name, e))?;
+			.call_scrape_latest(&mut instance.store, page)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let items = self.plugin_result(result, || "scrape latest".to_string())?;
 
 		Ok(items.into_iter().map(Into::into).collect())
 	}
 
 	pub async fn scrape_trending(&self, page: u32) -> Result<Vec<scraper_types::Item>> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
-			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
-
-		let items = instance
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
 			.scraper_types_scraper()
-			.call_scrape_trending(&mut store, page)
-			.await
-			.map_err(|e| anyhow!("Failed to scrape trending for plugin {}: {}", name, e))?;
+			.call_scrape_trending(&mut instance.store, page)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let items = self.plugin_result(result, || "scrape trending".to_string())?;
 
 		Ok(items.into_iter().map(Into::into).collect())
 	}
 
-	pub async fn scrape_search(&self, query: String, page: u32) -> Result<Vec<scraper_types::Item>> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
-			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
-
-		let items = instance
+	pub async fn scrape_search(
+		&self,
+		query: String,
+		page: u32,
+		filters: scraper_types::SearchFilters,
+	) -> Result<Vec<scraper_types::Item>> {
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
 			.scraper_types_scraper()
-			.call_scrape_search(&mut store, &query, page)
-			.await
-			.map_err(|e| anyhow!("Failed to scrape search for plugin {}: {}", name, e))?;
+			.call_scrape_search(&mut instance.store, &query, page, &filters.into())
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let items = self.plugin_result(result, || "scrape search".to_string())?;
 
 		Ok(items.into_iter().map(Into::into).collect())
 	}
 
 	pub async fn scrape(&self, url: String) -> Result<scraper_types::Page> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
-			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
-
-		let page = instance
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
 			.scraper_types_scraper()
-			.call_scrape(&mut store, &url)
-			.await
-			.map_err(|e| anyhow!("Failed to scrape {} for plugin {}: {}", url, name, e))?;
+			.call_scrape(&mut instance.store, &url)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let page = self.plugin_result(result, || format!("scrape {}", url))?;
 
 		Ok(page.into())
 	}
 
 	pub async fn scrape_genres_list(&self) -> Result<Vec<scraper_types::Genre>> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
-			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
-
-		let genres = instance
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
 			.scraper_types_scraper()
-			.call_scrape_genres_list(&mut store)
-			.await
-			.map_err(|e| anyhow!("Failed to scrape genres list for plugin {}: {}", name, e))?;
+			.call_scrape_genres_list(&mut instance.store)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let genres = self.plugin_result(result, || "scrape genres list".to_string())?;
 
 		Ok(genres.into_iter().map(Into::into).collect())
 	}
 
+	pub async fn scrape_genre(&self, url: String, page: u32) -> Result<Vec<scraper_types::Item>> {
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
+			.scraper_types_scraper()
+			.call_scrape_genre(&mut instance.store, &url, page)
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let items = self.plugin_result(result, || format!("scrape genre {}", url))?;
+
+		Ok(items.into_iter().map(Into::into).collect())
+	}
+
 	pub async fn get_info(&self) -> Result<scraper_types::ScraperInfo> {
-		let mut store = Store::new(&self.engine, States::new());
-		store.set_fuel(u64::MAX)?;
-		store.fuel_async_yield_interval(Some(10000))?;
-		let instance = bindings::Root::instantiate_async(&mut store, &self.component, &self.linker)
-			.await
-			.map_err(|e| anyhow!("Failed to instantiate WASM component {}: {}", self.file.display(), e))?;
-		let name = self.name.clone();
+		let mut instance = self.acquire().await?;
+		let result = instance.root.scraper_types_scraper().call_get_info(&mut instance.store).await;
+		self.pool.release(instance, result.is_ok());
 
-		let info = instance
-			.scraper_types_scraper()
-			.call_get_info(&mut store)
-			.await
-			.map_err(|e| anyhow!("Failed to get info for plugin {}: {}", name, e))?;
+		let info = result.map_err(|e| anyhow!("Failed to get info for plugin {}: {}", self.name, e))?;
 
 		Ok(info.into())
 	}
+
+	pub async fn transform_page(&self, page: scraper_types::PageImage) -> Result<Option<scraper_types::ProcessedImage>> {
+		let url = page.url.clone();
+		let mut instance = self.acquire().await?;
+		let result = instance
+			.root
+			.scraper_types_scraper()
+			.call_transform_page(&mut instance.store, &page.into())
+			.await;
+		self.pool.release(instance, result.is_ok());
+
+		let image = self.plugin_result(result, || format!("transform page {}", url))?;
+
+		Ok(image.map(Into::into))
+	}
+
+	pub async fn run_declared_tests(&self) -> Result<Vec<TestOutcome>> {
+		let Some(indices) = &self.tests else {
+			return Ok(Vec::new());
+		};
+
+		let mut instance = self.acquire().await?;
+		let result = match indices.load(&mut instance.store, &instance.instance) {
+			Ok(guest) => guest.call_list_tests(&mut instance.store).await,
+			Err(e) => Err(e),
+		};
+		self.pool.release(instance, result.is_ok());
+		let cases = result.map_err(|e| anyhow!("Failed to list tests for plugin {}: {}", self.name, e))?;
+
+		let mut outcomes = Vec::with_capacity(cases.len());
+		for case in cases {
+			let started = Instant::now();
+			let result = self_test::run_case(self, &case).await;
+			outcomes.push(TestOutcome::new(case.name, started.elapsed(), result));
+		}
+		Ok(outcomes)
+	}
 }
 
 #[cfg(test)]
@@ -217,7 +300,7 @@ mod tests {
 		}
 
 		Some(
-			WasmPlugin::new(&p)
+			WasmPlugin::new(Arc::new(Config::default()), &p)
 				.await
 				.expect("WasmPlugin::new failed for real plugin file"),
 		)
@@ -226,7 +309,7 @@ mod tests {
 	#[tokio::test]
 	async fn test_new_missing_file() {
 		let path = Path::new("this-file-should-not-exist-12345.wasm");
-		let res = WasmPlugin::new(path).await;
+		let res = WasmPlugin::new(Arc::new(Config::default()), path).await;
 		assert!(res.is_err(), "expected error when loading non-existent file");
 		let err_str = format!("{:?}", res.err());
 
@@ -237,11 +320,29 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_plugin_error_is_typed() {
+		use crate::plugins::wasm::bindings::exports::scraper::types::scraper::ErrorKind;
+
+		let error: ScraperError = WitScraperError {
+			kind: ErrorKind::RateLimit,
+			message: "slow down".to_string(),
+			retryable: true,
+			status_code: Some(429),
+		}
+		.into();
+
+		assert_eq!(error.kind, scraper_types::ScraperErrorKind::RateLimit);
+		assert!(error.retryable);
+		assert_eq!(error.status_code, Some(429));
+		assert_eq!(error.message, "slow down");
+	}
+
 	#[tokio::test]
 	async fn test_new_invalid_file() {
 		let p = unique_temp_path("wasm");
 		let _f = File::create(&p).expect("create temp file");
-		let res = WasmPlugin::new(&p).await;
+		let res = WasmPlugin::new(Arc::new(Config::default()), &p).await;
 		assert!(res.is_err(), "expected error for invalid/empty wasm file");
 		let err_str = format!("{:?}", res.err());
 		assert!(
@@ -300,7 +401,7 @@ mod tests {
 			None => panic!("get_scraper returned None, set VAULT_TEST_WASM_PLUGIN to run this test"),
 		};
 		let items = scraper
-			.scrape_search("test".to_string(), 1)
+			.scrape_search("test".to_string(), 1, Default::default())
 			.await
 			.expect("scrape_search failed");
 		println!("scrape_search returned {} items", items.len());
@@ -0,0 +1,83 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::AsyncWrite;
+use tracing::Level;
+use wasmtime_wasi::cli::{IsTerminal, StdoutStream};
+
+/// Longest line kept back waiting for its newline, so a component printing without one cannot grow it unbounded.
+const MAX_LINE: usize = 8 * 1024;
+
+/// Forwards what a component prints to stdout or stderr to the plugin's log target, one event per line.
+#[derive(Debug, Clone)]
+pub(crate) struct PluginOutput {
+	target: Arc<str>,
+	level: Level,
+}
+
+impl PluginOutput {
+	pub fn new(target: Arc<str>, level: Level) -> Self {
+		Self { target, level }
+	}
+
+	fn log(&self, line: &[u8]) {
+		let line = String::from_utf8_lossy(line);
+		let line = line.trim_end();
+		if !line.is_empty() {
+			telemetry::log_event(&self.target, self.level, line);
+		}
+	}
+}
+
+impl IsTerminal for PluginOutput {
+	fn is_terminal(&self) -> bool {
+		false
+	}
+}
+
+impl StdoutStream for PluginOutput {
+	fn async_stream(&self) -> Box<dyn AsyncWrite + Send + Sync> {
+		Box::new(LineWriter {
+			output: self.clone(),
+			line: Vec::new(),
+		})
+	}
+}
+
+struct LineWriter {
+	output: PluginOutput,
+	line: Vec<u8>,
+}
+
+impl AsyncWrite for LineWriter {
+	fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		let this = self.get_mut();
+		this.line.extend_from_slice(buf);
+		while let Some(end) = this.line.iter().position(|b| *b == b'\n') {
+			let line: Vec<u8> = this.line.drain(..=end).collect();
+			this.output.log(&line);
+		}
+		if this.line.len() >= MAX_LINE {
+			this.output.log(&std::mem::take(&mut this.line));
+		}
+		Poll::Ready(Ok(buf.len()))
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+		let this = self.get_mut();
+		this.output.log(&std::mem::take(&mut this.line));
+		Poll::Ready(Ok(()))
+	}
+}
+
+impl Drop for LineWriter {
+	fn drop(&mut self) {
+		self.output.log(&self.line);
+	}
+}
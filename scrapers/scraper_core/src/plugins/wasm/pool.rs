@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use wasmtime::component::Instance;
+use wasmtime::{Engine, Store};
+
+use crate::plugins::common::flaresolverr::FlareSolverrManager;
+use crate::plugins::common::http::CommonHttp;
+use crate::plugins::wasm::bindings::{Root, RootPre};
+use crate::plugins::wasm::state::States;
+
+pub(crate) struct PooledInstance {
+	pub store: Store<States>,
+	pub root: Root,
+	pub instance: Instance,
+	/// Counts the instance against the pool's cap until it is released or dropped.
+	_permit: OwnedSemaphorePermit,
+}
+
+/// Keeps idle instances of a single component around so calls don't pay for instantiation every time.
+///
+/// At most `size` instances exist at once, idle or busy; callers wait for one once they are all in use.
+///
+/// Instances are only returned to the pool after a successful call; a trapped or failed
+/// instance may be left in an inconsistent state, so it is dropped instead. A reused instance keeps whatever the
+/// guest left in its memory and globals, so plugins must not carry state from one call to the next; components that
+/// do have to run with `reuse_instances` off, which gives every call a fresh instance.
+pub(crate) struct InstancePool {
+	engine: Engine,
+	pre: RootPre<States>,
+	http: CommonHttp,
+	flaresolverr: Arc<FlareSolverrManager>,
+	log_target: Arc<str>,
+	idle: Mutex<Vec<(Store<States>, Root, Instance)>>,
+	permits: Arc<Semaphore>,
+	reuse: bool,
+}
+
+impl InstancePool {
+	pub fn new(
+		engine: Engine,
+		pre: RootPre<States>,
+		http: CommonHttp,
+		flaresolverr: Arc<FlareSolverrManager>,
+		log_target: Arc<str>,
+		size: usize,
+		reuse: bool,
+	) -> Self {
+		Self {
+			engine,
+			pre,
+			http,
+			flaresolverr,
+			log_target,
+			idle: Mutex::new(Vec::new()),
+			permits: Arc::new(Semaphore::new(size.max(1))),
+			reuse,
+		}
+	}
+
+	pub async fn acquire(&self) -> Result<PooledInstance> {
+		let permit = Arc::clone(&self.permits)
+			.acquire_owned()
+			.await
+			.context("WASM instance pool closed")?;
+
+		// Idle instances hold no permit of their own; every one of them was counted by a permit now dropped.
+		let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+		if let Some((mut store, root, instance)) = idle {
+			store.set_fuel(u64::MAX)?;
+			return Ok(PooledInstance {
+				store,
+				root,
+				instance,
+				_permit: permit,
+			});
+		}
+
+		let mut store = Store::new(
+			&self.engine,
+			States::new(self.http.clone(), self.flaresolverr.clone(), self.log_target.clone()),
+		);
+		store.set_fuel(u64::MAX)?;
+		store.fuel_async_yield_interval(Some(10000))?;
+		let instance = self.pre.instance_pre().instantiate_async(&mut store).await?;
+		let root = Root::new(&mut store, &instance)?;
+
+		Ok(PooledInstance {
+			store,
+			root,
+			instance,
+			_permit: permit,
+		})
+	}
+
+	/// Keeps a healthy instance for the next call. Its permit is given back either way.
+	pub fn release(&self, instance: PooledInstance, healthy: bool) {
+		if !self.reuse || !healthy {
+			return;
+		}
+
+		let PooledInstance {
+			store, root, instance, ..
+		} = instance;
+		self.idle
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.push((store, root, instance));
+	}
+}
@@ -0,0 +1,98 @@
+use anyhow::{Result, bail};
+use scraper_types::Item;
+
+use crate::plugins::wasm::WasmPlugin;
+use crate::plugins::wasm::bindings::testable::exports::scraper::types::tests::{TestCall, TestCase};
+
+/// Runs the call described by `case` and checks its result against the case's expectations.
+pub(super) async fn run_case(plugin: &WasmPlugin, case: &TestCase) -> Result<()> {
+	let min_results = case.min_results as usize;
+
+	match &case.call {
+		TestCall::Latest(page) => check_items(&plugin.scrape_latest(*page).await?, min_results),
+		TestCall::Trending(page) => check_items(&plugin.scrape_trending(*page).await?, min_results),
+		TestCall::Search(search) => check_items(
+			&plugin
+				.scrape_search(search.query.clone(), search.page, Default::default())
+				.await?,
+			min_results,
+		),
+		TestCall::Scrape(url) => {
+			let page = plugin.scrape(url.clone()).await?;
+			if page.title.trim().is_empty() {
+				bail!("scraped page has no title");
+			}
+			if let Some(expected) = &case.expect_title
+				&& !page.title.contains(expected.as_str())
+			{
+				bail!("expected title to contain '{}', got '{}'", expected, page.title);
+			}
+			check_count("chapters", page.chapters.len(), min_results)?;
+			if let Some(chapter) = page.chapters.iter().find(|chapter| chapter.url.trim().is_empty()) {
+				bail!("chapter '{}' has no url", chapter.title);
+			}
+			Ok(())
+		}
+		TestCall::Chapter(url) => {
+			let pages = plugin.scrape_chapter(url.clone()).await?;
+			check_count("pages", pages.len(), min_results)?;
+			if pages.iter().any(|page| page.trim().is_empty()) {
+				bail!("chapter returned an empty page");
+			}
+			Ok(())
+		}
+		TestCall::Genres => {
+			let genres = plugin.scrape_genres_list().await?;
+			check_count("genres", genres.len(), min_results)?;
+			if let Some(genre) = genres.iter().find(|genre| genre.name.trim().is_empty()) {
+				bail!("genre with url '{}' has no name", genre.url);
+			}
+			Ok(())
+		}
+	}
+}
+
+fn check_items(items: &[Item], min_results: usize) -> Result<()> {
+	check_count("items", items.len(), min_results)?;
+	for item in items {
+		if item.title.trim().is_empty() {
+			bail!("item with url '{}' has no title", item.url);
+		}
+		if item.url.trim().is_empty() {
+			bail!("item '{}' has no url", item.title);
+		}
+	}
+	Ok(())
+}
+
+fn check_count(what: &str, count: usize, min_results: usize) -> Result<()> {
+	if count < min_results {
+		bail!("expected at least {} {}, got {}", min_results, what, count);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	fn item(title: &str, url: &str) -> Item {
+		Item {
+			title: title.to_string(),
+			url: url.to_string(),
+			img_url: None,
+		}
+	}
+
+	#[test]
+	fn test_check_items() {
+		assert!(check_items(&[item("One Piece", "https://example.com/1")], 1).is_ok());
+
+		let error = check_items(&[], 1).unwrap_err();
+		assert_eq!(error.to_string(), "expected at least 1 items, got 0");
+
+		let error = check_items(&[item("", "https://example.com/1")], 0).unwrap_err();
+		assert_eq!(error.to_string(), "item with url 'https://example.com/1' has no title");
+	}
+}
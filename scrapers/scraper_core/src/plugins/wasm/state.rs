@@ -1,16 +1,38 @@
+use std::sync::Arc;
+
+use tracing::Level;
 use wasmtime::component::ResourceTable;
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
 
+use crate::plugins::common::flaresolverr::FlareSolverrManager;
+use crate::plugins::common::http::CommonHttp;
+use crate::plugins::wasm::output::PluginOutput;
+
 pub struct States {
 	table: ResourceTable,
 	ctx: WasiCtx,
+	pub http: CommonHttp,
+	pub flaresolverr: Arc<FlareSolverrManager>,
 }
 
 impl States {
-	pub fn new() -> Self {
+	/// What the component prints goes to `log_target`, stdout at debug and stderr at warn.
+	pub fn new(http: CommonHttp, flaresolverr: Arc<FlareSolverrManager>, log_target: Arc<str>) -> Self {
 		let table = ResourceTable::new();
-		let ctx = WasiCtxBuilder::new().build();
-		Self { table, ctx }
+		let ctx = WasiCtxBuilder::new()
+			.stdout(PluginOutput::new(log_target.clone(), Level::DEBUG))
+			.stderr(PluginOutput::new(log_target, Level::WARN))
+			.build();
+		Self {
+			table,
+			ctx,
+			http,
+			flaresolverr,
+		}
+	}
+
+	pub fn table(&mut self) -> &mut ResourceTable {
+		&mut self.table
 	}
 }
 
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use reqwest::Url;
+use scraper_types::RateLimit;
+
+use crate::RateLimitConfig;
+
+static LIMITER: LazyLock<HostRateLimiter> = LazyLock::new(|| HostRateLimiter::new(RateLimitConfig::default()));
+
+/// Limiter shared by every request the process sends to scraped sites: plugin HTTP calls, FlareSolverr and the
+/// image proxy.
+pub fn limiter() -> &'static HostRateLimiter {
+	&LIMITER
+}
+
+/// Rate limit of one host and how much of it is left.
+#[derive(Debug, Clone)]
+pub struct HostLimitSnapshot {
+	pub host: String,
+	pub limit: RateLimit,
+	/// Requests that may be sent right away.
+	pub available: u32,
+	/// Time left before a `Retry-After` from the host lets requests through again.
+	pub blocked_for: Duration,
+}
+
+#[derive(Debug)]
+struct Bucket {
+	limit: RateLimit,
+	tokens: f64,
+	refilled_at: Instant,
+	blocked_until: Option<Instant>,
+}
+
+impl Bucket {
+	fn new(limit: RateLimit) -> Self {
+		Self {
+			limit,
+			tokens: limit.burst.max(1) as f64,
+			refilled_at: Instant::now(),
+			blocked_until: None,
+		}
+	}
+
+	fn refill(&mut self, now: Instant) {
+		let per_second = self.limit.requests_per_minute as f64 / 60.0;
+		let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * per_second).min(self.limit.burst.max(1) as f64);
+		self.refilled_at = now;
+	}
+
+	/// Takes a token, or returns how long to wait before trying again.
+	fn take(&mut self, now: Instant) -> Option<Duration> {
+		if let Some(until) = self.blocked_until.filter(|until| *until > now) {
+			return Some(until - now);
+		}
+		self.blocked_until = None;
+
+		self.refill(now);
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			return None;
+		}
+
+		let per_second = self.limit.requests_per_minute as f64 / 60.0;
+		Some(Duration::from_secs_f64((1.0 - self.tokens) / per_second))
+	}
+}
+
+#[derive(Default)]
+struct LimiterState {
+	/// Limits plugins declared for their hosts.
+	declared: HashMap<String, RateLimit>,
+	buckets: HashMap<String, Bucket>,
+}
+
+/// Token buckets keyed by upstream host, so every caller hitting the same site shares one budget.
+pub struct HostRateLimiter {
+	config: RwLock<RateLimitConfig>,
+	state: Mutex<LimiterState>,
+}
+
+impl HostRateLimiter {
+	pub fn new(config: RateLimitConfig) -> Self {
+		Self {
+			config: RwLock::new(config),
+			state: Mutex::new(LimiterState::default()),
+		}
+	}
+
+	/// Replaces the configured limits. Hosts already seen switch to their new limit, keeping any `Retry-After` hold.
+	pub fn configure(&self, config: RateLimitConfig) {
+		*self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let hosts: Vec<String> = state.buckets.keys().cloned().collect();
+		for host in hosts {
+			self.relimit(&mut state, &host);
+		}
+	}
+
+	/// Applies the limit a plugin declared to the hosts of `urls`. Limits configured for a host win. Hosts already seen
+	/// switch to the new limit, keeping any `Retry-After` hold.
+	pub fn declare<'a>(&self, urls: impl IntoIterator<Item = &'a str>, limit: RateLimit) {
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		for host in urls.into_iter().filter_map(host_of) {
+			state.declared.insert(host.clone(), limit);
+			self.relimit(&mut state, &host);
+		}
+	}
+
+	/// Switches the bucket of `host` to its current limit, keeping any `Retry-After` hold, or drops it when the host
+	/// is no longer limited.
+	fn relimit(&self, state: &mut LimiterState, host: &str) {
+		match self.limit_for(state, host) {
+			Some(limit) => {
+				if let Some(bucket) = state.buckets.get_mut(host) {
+					bucket.limit = limit;
+					bucket.tokens = bucket.tokens.min(limit.burst.max(1) as f64);
+				}
+			}
+			None => {
+				state.buckets.remove(host);
+			}
+		}
+	}
+
+	fn limit_for(&self, state: &LimiterState, host: &str) -> Option<RateLimit> {
+		let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+		config
+			.hosts
+			.get(host)
+			.or_else(|| state.declared.get(host))
+			.or(config.default.as_ref())
+			.copied()
+			.filter(|limit| limit.requests_per_minute > 0)
+	}
+
+	/// Waits until a request to the host of `url` may be sent. Urls without a host are not limited.
+	pub async fn acquire(&self, url: &str) {
+		let Some(host) = host_of(url) else {
+			return;
+		};
+
+		loop {
+			let wait = {
+				let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+				let Some(limit) = self.limit_for(&state, &host) else {
+					return;
+				};
+				match state
+					.buckets
+					.entry(host.clone())
+					.or_insert_with(|| Bucket::new(limit))
+					.take(Instant::now())
+				{
+					Some(wait) => wait,
+					None => return,
+				}
+			};
+
+			tracing::debug!("Waiting {}ms for the rate limit of {}", wait.as_millis(), host);
+			tokio::time::sleep(wait).await;
+		}
+	}
+
+	/// Holds back requests to the host of `url` after it answered with `status`, for as long as its `Retry-After`
+	/// header asks. A 429 without the header uses up the burst instead.
+	pub fn record_response(&self, url: &str, status: u16, retry_after: Option<&str>) {
+		if status != 429 && status != 503 {
+			return;
+		}
+		let Some(host) = host_of(url) else {
+			return;
+		};
+
+		let max_retry_after = self.config.read().unwrap_or_else(|e| e.into_inner()).max_retry_after_secs;
+		let delay = retry_after
+			.and_then(parse_retry_after)
+			.map(|delay| delay.min(Duration::from_secs(max_retry_after)));
+		if delay.is_none() && status != 429 {
+			return;
+		}
+
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let Some(limit) = self.limit_for(&state, &host) else {
+			return;
+		};
+		let bucket = state.buckets.entry(host.clone()).or_insert_with(|| Bucket::new(limit));
+		bucket.tokens = 0.0;
+		bucket.refilled_at = Instant::now();
+		if let Some(delay) = delay {
+			tracing::warn!(
+				"{} asked to retry after {}s, holding back its requests",
+				host,
+				delay.as_secs()
+			);
+			let until = Instant::now() + delay;
+			bucket.blocked_until = Some(bucket.blocked_until.map_or(until, |current| current.max(until)));
+		}
+	}
+
+	/// Every host the limiter has seen, sorted by name.
+	pub fn snapshots(&self) -> Vec<HostLimitSnapshot> {
+		let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+		let now = Instant::now();
+		let mut snapshots: Vec<HostLimitSnapshot> = state
+			.buckets
+			.iter_mut()
+			.map(|(host, bucket)| {
+				bucket.refill(now);
+				HostLimitSnapshot {
+					host: host.clone(),
+					limit: bucket.limit,
+					available: bucket.tokens as u32,
+					blocked_for: bucket
+						.blocked_until
+						.map(|until| until.saturating_duration_since(now))
+						.unwrap_or_default(),
+				}
+			})
+			.collect();
+
+		snapshots.sort_by(|a, b| a.host.cmp(&b.host));
+		snapshots
+	}
+}
+
+pub(crate) fn host_of(url: &str) -> Option<String> {
+	Url::parse(url).ok()?.host_str().map(|host| host.to_ascii_lowercase())
+}
+
+/// Reads a `Retry-After` value, either a number of seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+	let value = value.trim();
+	if let Ok(seconds) = value.parse::<u64>() {
+		return Some(Duration::from_secs(seconds));
+	}
+
+	let at = httpdate::parse_http_date(value).ok()?;
+	Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+
+	use super::*;
+
+	fn limiter(default: Option<RateLimit>) -> HostRateLimiter {
+		HostRateLimiter::new(RateLimitConfig {
+			default,
+			hosts: BTreeMap::from([(
+				"configured.test".to_string(),
+				RateLimit {
+					requests_per_minute: 0,
+					burst: 1,
+				},
+			)]),
+			max_retry_after_secs: 60,
+		})
+	}
+
+	#[tokio::test]
+	async fn test_acquire_waits_for_tokens() {
+		let limiter = limiter(Some(RateLimit {
+			requests_per_minute: 600,
+			burst: 2,
+		}));
+
+		let start = Instant::now();
+		limiter.acquire("https://site.test/a").await;
+		limiter.acquire("https://site.test/b").await;
+		assert!(start.elapsed() < Duration::from_millis(50), "the burst is sent right away");
+
+		limiter.acquire("https://site.test/c").await;
+		assert!(
+			start.elapsed() >= Duration::from_millis(90),
+			"then requests are spaced by the rate"
+		);
+
+		// Other hosts and hosts configured without a limit are not held back.
+		let start = Instant::now();
+		limiter.acquire("https://other.test/").await;
+		for _ in 0..5 {
+			limiter.acquire("https://configured.test/").await;
+		}
+		assert!(start.elapsed() < Duration::from_millis(50));
+	}
+
+	#[tokio::test]
+	async fn test_declared_limits_and_retry_after() {
+		let limiter = limiter(None);
+		limiter.acquire("https://plugin.test/").await;
+		assert!(limiter.snapshots().is_empty(), "hosts without a limit are not tracked");
+
+		let declared = RateLimit {
+			requests_per_minute: 60,
+			burst: 5,
+		};
+		limiter.declare(["https://plugin.test/", "https://configured.test/"], declared);
+		limiter.acquire("https://plugin.test/manga/1").await;
+		limiter.acquire("https://configured.test/").await;
+
+		limiter.record_response("https://plugin.test/manga/2", 429, Some("30"));
+		let snapshots = limiter.snapshots();
+		assert_eq!(snapshots.len(), 1);
+		assert_eq!(snapshots[0].host, "plugin.test");
+		assert_eq!(snapshots[0].limit, declared);
+		assert_eq!(snapshots[0].available, 0);
+		assert!(snapshots[0].blocked_for > Duration::from_secs(29));
+
+		let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire("https://plugin.test/")).await;
+		assert!(blocked.is_err(), "requests wait out the Retry-After");
+
+		let redeclared = RateLimit {
+			requests_per_minute: 120,
+			burst: 2,
+		};
+		limiter.declare(["https://plugin.test/"], redeclared);
+		let snapshots = limiter.snapshots();
+		assert_eq!(snapshots[0].limit, redeclared);
+		assert!(
+			snapshots[0].blocked_for > Duration::from_secs(29),
+			"declaring again keeps the Retry-After"
+		);
+	}
+
+	#[tokio::test]
+	async fn test_configure_updates_seen_hosts() {
+		let limiter = limiter(Some(RateLimit {
+			requests_per_minute: 60,
+			burst: 5,
+		}));
+		limiter.acquire("https://site.test/").await;
+		limiter.acquire("https://other.test/").await;
+		limiter.record_response("https://other.test/", 429, Some("30"));
+
+		limiter.configure(RateLimitConfig {
+			default: Some(RateLimit {
+				requests_per_minute: 60,
+				burst: 2,
+			}),
+			hosts: BTreeMap::from([(
+				"site.test".to_string(),
+				RateLimit {
+					requests_per_minute: 0,
+					burst: 1,
+				},
+			)]),
+			max_retry_after_secs: 60,
+		});
+
+		let snapshots = limiter.snapshots();
+		assert_eq!(snapshots.len(), 1, "hosts configured without a limit are dropped");
+		assert_eq!((snapshots[0].host.as_str(), snapshots[0].limit.burst), ("other.test", 2));
+		assert!(
+			snapshots[0].blocked_for > Duration::from_secs(29),
+			"the Retry-After hold is kept"
+		);
+	}
+
+	#[test]
+	fn test_parse_retry_after() {
+		assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+		assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+
+		let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(90));
+		let parsed = parse_retry_after(&later).expect("http date");
+		assert!(parsed > Duration::from_secs(85) && parsed <= Duration::from_secs(90));
+		assert_eq!(parse_retry_after("soon"), None);
+	}
+}
@@ -13,12 +13,105 @@
 ---@field value T?
 ---@field error ScraperError?
 
+-- Search filters
+---@alias PageStatus "ongoing" | "completed" | "hiatus" | "cancelled"
+---@alias SortOrder "relevance" | "latest" | "popular" | "alphabetical" | "rating"
+
+--- Declared as `search_filters` in `Get_info()`. Empty lists and `false` mean the filter is not supported.
+---@class SearchFilterSpec
+---@field genres? string[]
+---@field exclude_genres? boolean
+---@field statuses? PageStatus[]
+---@field types? string[]
+---@field sort_orders? SortOrder[]
+---@field year? boolean
+
+--- Third argument of `Scrape_search`. Only contains filters the plugin declared.
+---@class SearchFilters
+---@field include_genres string[]
+---@field exclude_genres string[]
+---@field status PageStatus?
+---@field page_type string?
+---@field sort SortOrder?
+---@field year integer?
+
+--- Declared as `rate_limit` in `Get_info()`. Applies to the hosts of `base_url`, `referer_url` and `legacy_urls`.
+---@class RateLimit
+---@field requests_per_minute integer
+---@field burst? integer Requests allowed at once, 1 by default.
+
+-- Chapter pages
+--- `Scrape_chapter` of manga plugins may return these instead of bare url strings.
+---@class ChapterPage
+---@field url string
+---@field headers? table<string, string> Extra request headers the image proxy sends for this page
+---@field referer? string Overrides the scraper's `referer_url`
+---@field width? integer
+---@field height? integer
+---@field mirrors? string[] Tried in order when `url` cannot be fetched
+
+-- Page transforms
+--- Argument of the optional `Transform_page` hook, called by the image proxy for every chapter page.
+--- Data the plugin needs, such as a descrambling key, can be put in the page url's fragment.
+---@class PageImage
+---@field url string
+---@field referer string?
+---@field content_type string
+---@field data string Raw image bytes
+
+--- Returned by `Transform_page`; return nil to serve the page unchanged.
+---@class ProcessedImage
+---@field content_type string
+---@field data string
+
+---@class ImageTile
+---@field sx integer
+---@field sy integer
+---@field w integer
+---@field h integer
+---@field dx integer
+---@field dy integer
+
 -- HTTP and response types
 ---@class CommonHttp
 ---@field get fun(self: CommonHttp, url: string, headers?: table<string, string>): HttpResponse
 ---@field post fun(self: CommonHttp, url: string, body: string, headers?: table<string, string>): HttpResponse
 ---@field has_cloudflare_protection fun(self: CommonHttp, text: string, status_code?: integer, headers?: table<string, string>): boolean
 ---@field url_encode fun(self: CommonHttp, s: string): string
+---@field request fun(self: CommonHttp, url: string, options?: HttpRequestOptions): HttpResponse
+---@field stream fun(self: CommonHttp, url: string, options?: HttpRequestOptions): HttpStream
+---@field get_cookies fun(self: CommonHttp, url: string): table<string, string>
+---@field set_cookie fun(self: CommonHttp, url: string, cookie: HttpCookie)
+---@field clear_cookies fun(self: CommonHttp)
+
+---@class HttpRequestOptions
+---@field method? "GET"|"POST"|"PUT"|"PATCH"|"DELETE"|"HEAD"|"OPTIONS"
+---@field headers? table<string, string>
+---@field query? table<string, string>
+---@field body? string
+---@field form? table<string, string>
+---@field multipart? HttpMultipartPart[]
+---@field timeout? integer Milliseconds
+---@field follow_redirects? boolean Defaults to true
+---@field binary? boolean Keep the body as raw bytes in `text`
+
+---@class HttpMultipartPart
+---@field name string
+---@field filename? string
+---@field content_type? string
+---@field data string
+
+---@class HttpStream
+---@field status integer
+---@field headers table<string, string>
+---@field read fun(self: HttpStream): string? Next chunk of the body, nil once fully read
+
+---@class HttpCookie
+---@field name string
+---@field value string
+---@field domain? string
+---@field path? string
+---@field max_age? integer
 
 ---@class HttpResponse
 ---@field ok boolean
@@ -53,6 +146,39 @@
 ---@field select_element fun(self: CustomScraper, html: string, selector: string): string?
 ---@field try_select_elements fun(self: CustomScraper, html: string, selector: string): ScraperResult<string[]>
 ---@field try_select_element fun(self: CustomScraper, html: string, selector: string): ScraperResult<string>
+---@field parse fun(self: CustomScraper, html: string): HtmlDocument
+
+---@class HtmlDocument
+---@field root fun(self: HtmlDocument): HtmlNode
+---@field select fun(self: HtmlDocument, selector: string): HtmlNode[]
+---@field select_one fun(self: HtmlDocument, selector: string): HtmlNode?
+---@field xpath fun(self: HtmlDocument, expr: string): HtmlNode[]
+---@field xpath_text fun(self: HtmlDocument, expr: string): string[]
+
+---@class HtmlNode
+---@field name fun(self: HtmlNode): string
+---@field select fun(self: HtmlNode, selector: string): HtmlNode[]
+---@field select_one fun(self: HtmlNode, selector: string): HtmlNode?
+---@field children fun(self: HtmlNode): HtmlNode[]
+---@field parent fun(self: HtmlNode): HtmlNode?
+---@field next_sibling fun(self: HtmlNode): HtmlNode?
+---@field prev_sibling fun(self: HtmlNode): HtmlNode?
+---@field siblings fun(self: HtmlNode): HtmlNode[]
+---@field text fun(self: HtmlNode): string
+---@field inner_text fun(self: HtmlNode): string
+---@field inner_html fun(self: HtmlNode): string
+---@field outer_html fun(self: HtmlNode): string
+---@field attr fun(self: HtmlNode, name: string): string?
+---@field attrs fun(self: HtmlNode): table<string, string>
+---@field xpath fun(self: HtmlNode, expr: string): HtmlNode[]
+---@field xpath_text fun(self: HtmlNode, expr: string): string[]
+---@field regex_match fun(self: HtmlNode, pattern: string): string?
+---@field regex_match_all fun(self: HtmlNode, pattern: string): string[]
+
+-- Image helpers
+---@class Images
+---@field size fun(data: string): integer, integer
+---@field reassemble fun(data: string, tiles: ImageTile[]): string, string Image bytes and their content type
 
 -- Utility functions
 ---@class Utils
@@ -90,6 +216,9 @@ utils = nil
 ---@type Log
 log = nil
 
+---@type Images
+images = nil
+
 -- Extend the builtin string/table libraries so the language server won't mark our runtime-added helpers as undefined.
 ---@class stringlib
 ---@field split fun(s: string, delimiter: string): string[]
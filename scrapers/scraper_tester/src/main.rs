@@ -2,21 +2,113 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{Context, Result, bail};
-use scraper_core::plugins::{Plugin, PluginType};
-use scraper_core::{Config, load_plugin};
-use scraper_types::Item;
+use scraper_core::plugins::{Plugin, PluginType, TestOutcome};
+use scraper_core::{Config, FixtureConfig, FixtureMode, load_plugin};
+use scraper_types::{ChapterPage, Genre, Item, Page, ScraperInfo, SearchFilters};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 fn print_usage() {
 	eprintln!("Usage: cargo run -p lua_scraper_tester -- <command> <plugin-file> [args]");
 	eprintln!("Commands:");
 	eprintln!("  info <plugin-file>");
-	eprintln!("  search <plugin-file> <query> [page]");
+	eprintln!("  search <plugin-file> <query> [page] [filters-json]");
 	eprintln!("  latest <plugin-file> [page]");
 	eprintln!("  trending <plugin-file> [page]");
 	eprintln!("  scrape <plugin-file> <url>");
 	eprintln!("  chapter <plugin-file> <url>");
+	eprintln!("  pages <plugin-file> <url>");
 	eprintln!("  genres <plugin-file>");
-	eprintln!("  tests <plugin-file>   # Lua only");
+	eprintln!("  genre <plugin-file> <genre-url> [page]");
+	eprintln!("  tests <plugin-file> [--format text|json|junit] [--output <file>]");
+	eprintln!("  record <plugin-file> <case-dir> <command> [args]   # capture traffic and a golden snapshot");
+	eprintln!("  replay <plugin-file> <case-dir> [--update]         # rerun offline and compare with the snapshot");
+}
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// Command and output captured by `record`, checked again by `replay`.
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+	command: String,
+	args: Vec<String>,
+	output: Value,
+}
+
+enum CommandOutput {
+	Info(ScraperInfo),
+	Items(&'static str, Vec<Item>),
+	Page(Page),
+	Chapter(Vec<String>),
+	Pages(Vec<ChapterPage>),
+	Genres(Vec<Genre>),
+}
+
+impl CommandOutput {
+	fn print(&self) {
+		match self {
+			CommandOutput::Info(info) => {
+				println!("Loaded scraper: {}", info.name);
+				println!("  id: {}", info.id);
+				println!("  version: {}", info.version);
+				println!("  type: {}", info.r#type);
+				println!("  image: {}", info.img_url);
+				if let Some(filters) = &info.search_filters {
+					println!("  search filters: {:?}", filters);
+				}
+			}
+			CommandOutput::Items(label, items) => {
+				println!("{} {} items", label, items.len());
+				print_items(items);
+			}
+			CommandOutput::Page(page) => {
+				println!("Title: {}", page.title);
+				println!("URL: {}", page.url);
+				println!("Description: {}", page.description.clone().unwrap_or_default());
+				println!("Chapters: {}", page.chapters.len());
+			}
+			CommandOutput::Chapter(pages) => {
+				println!("Chapter returned {} pages", pages.len());
+				for page in pages {
+					println!("- {}", page);
+				}
+			}
+			CommandOutput::Pages(pages) => {
+				println!("Chapter returned {} pages", pages.len());
+				for page in pages {
+					let size = match (page.width, page.height) {
+						(Some(width), Some(height)) => format!(" ({}x{})", width, height),
+						_ => String::new(),
+					};
+					println!(
+						"- {}{} | {} headers | {} mirrors",
+						page.url,
+						size,
+						page.headers.len(),
+						page.mirrors.len()
+					);
+				}
+			}
+			CommandOutput::Genres(genres) => {
+				println!("Genres: {}", genres.len());
+				for genre in genres {
+					println!("- {}", genre.name);
+				}
+			}
+		}
+	}
+
+	fn to_json(&self) -> Result<Value> {
+		let value = match self {
+			CommandOutput::Info(info) => serde_json::to_value(info)?,
+			CommandOutput::Items(_, items) => serde_json::to_value(items)?,
+			CommandOutput::Page(page) => serde_json::to_value(page)?,
+			CommandOutput::Chapter(pages) => serde_json::to_value(pages)?,
+			CommandOutput::Pages(pages) => serde_json::to_value(pages)?,
+			CommandOutput::Genres(genres) => serde_json::to_value(genres)?,
+		};
+		Ok(value)
+	}
 }
 
 fn parse_page_arg(arg: Option<&String>) -> Result<u32> {
@@ -33,9 +125,23 @@ fn detect_plugin_type(path: &Path) -> Result<PluginType> {
 }
 
 async fn load_plugin_for_path(path: &Path) -> Result<Arc<Plugin>> {
+	load_plugin_with_config(path, Config::load()).await
+}
+
+async fn load_plugin_with_config(path: &Path, config: Config) -> Result<Arc<Plugin>> {
 	let plugin_type = detect_plugin_type(path)?;
-	let config = Arc::new(Config::load());
-	load_plugin(config, path.to_path_buf(), plugin_type).await
+	load_plugin(Arc::new(config), path.to_path_buf(), plugin_type).await
+}
+
+/// Config that records to or replays from `case_dir`. Cookies stay in memory so every run starts from the same state.
+fn fixture_config(mode: FixtureMode, case_dir: &Path) -> Config {
+	let mut config = Config::load();
+	config.cookie_jar_folder = None;
+	config.fixtures = Some(FixtureConfig {
+		mode,
+		folder: case_dir.display().to_string(),
+	});
+	config
 }
 
 fn print_items(items: &[Item]) {
@@ -44,117 +150,281 @@ fn print_items(items: &[Item]) {
 	}
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-	let mut args = std::env::args().skip(1).collect::<Vec<_>>();
-	if args.is_empty() {
-		print_usage();
-		bail!("Missing command");
-	}
-
-	let command = args.remove(0);
-	if args.is_empty() {
-		print_usage();
-		bail!("Missing plugin file path");
-	}
-
-	let plugin_path = PathBuf::from(&args.remove(0));
-	if !plugin_path.exists() {
-		bail!("Plugin file does not exist: {}", plugin_path.display());
-	}
-	if !plugin_path.is_file() {
-		bail!("Path is not a file: {}", plugin_path.display());
-	}
-
-	match command.as_str() {
-		"info" => {
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let info = plugin.get_info().await?;
-			println!("Loaded scraper: {}", info.name);
-			println!("  id: {}", info.id);
-			println!("  version: {}", info.version);
-			println!("  type: {}", info.r#type);
-			println!("  image: {}", info.img_url);
-		}
+async fn run_command(plugin: &Plugin, command: &str, args: &[String]) -> Result<CommandOutput> {
+	let output = match command {
+		"info" => CommandOutput::Info(plugin.get_info().await?),
 		"search" => {
-			let query = args.get(0).cloned().unwrap_or_default();
+			let query = args.first().cloned().unwrap_or_default();
 			if query.is_empty() {
 				bail!("Missing search query");
 			}
 			let page = parse_page_arg(args.get(1))?;
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let items = plugin.scrape_search(query, page).await?;
-			println!("Found {} items", items.len());
-			print_items(&items);
+			let filters: SearchFilters = match args.get(2) {
+				Some(json) => serde_json::from_str(json).context("Invalid search filters JSON")?,
+				None => SearchFilters::default(),
+			};
+			CommandOutput::Items("Found", plugin.scrape_search(query, page, filters).await?)
 		}
 		"latest" => {
-			let page = parse_page_arg(args.get(0))?;
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let items = plugin.scrape_latest(page).await?;
-			println!("Latest {} items", items.len());
-			print_items(&items);
+			let page = parse_page_arg(args.first())?;
+			CommandOutput::Items("Latest", plugin.scrape_latest(page).await?)
 		}
 		"trending" => {
-			let page = parse_page_arg(args.get(0))?;
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let items = plugin.scrape_trending(page).await?;
-			println!("Trending {} items", items.len());
-			print_items(&items);
+			let page = parse_page_arg(args.first())?;
+			CommandOutput::Items("Trending", plugin.scrape_trending(page).await?)
 		}
 		"scrape" => {
-			let url = args.get(0).cloned().unwrap_or_default();
+			let url = args.first().cloned().unwrap_or_default();
 			if url.is_empty() {
 				bail!("Missing target URL");
 			}
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let page = plugin.scrape(url).await?;
-			println!("Title: {}", page.title);
-			println!("URL: {}", page.url);
-			println!("Description: {}", page.description.unwrap_or_default());
-			println!("Chapters: {}", page.chapters.len());
+			CommandOutput::Page(plugin.scrape(url).await?)
 		}
 		"chapter" => {
-			let url = args.get(0).cloned().unwrap_or_default();
+			let url = args.first().cloned().unwrap_or_default();
 			if url.is_empty() {
 				bail!("Missing chapter URL");
 			}
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let pages = plugin.scrape_chapter(url).await?;
-			println!("Chapter returned {} pages", pages.len());
-			for page in pages {
-				println!("- {}", page);
-			}
+			CommandOutput::Chapter(plugin.scrape_chapter(url).await?)
 		}
-		"genres" => {
-			let plugin = load_plugin_for_path(&plugin_path).await?;
-			let genres = plugin.scrape_genres_list().await?;
-			println!("Genres: {}", genres.len());
-			for genre in genres {
-				println!("- {}", genre.name);
+		"pages" => {
+			let url = args.first().cloned().unwrap_or_default();
+			if url.is_empty() {
+				bail!("Missing chapter URL");
 			}
+			CommandOutput::Pages(plugin.scrape_chapter_pages(url).await?)
 		}
-		"tests" => {
-			let plugin_type = detect_plugin_type(&plugin_path)?;
-			if plugin_type != PluginType::Lua {
-				bail!("Tests are only supported for Lua plugins");
-			}
-			let config = Arc::new(Config::load());
-			let lua = scraper_core::plugins::lua::LuaPlugin::new(config, &plugin_path).await?;
-			let tests = lua.run_declared_tests().await.context("Lua tests failed")?;
-			if tests.is_empty() {
-				println!("No Tests table found. Scraper loaded successfully.");
-			} else {
-				println!("Executed {} tests:", tests.len());
-				for name in tests {
-					println!("- {}", name);
-				}
-				println!("All tests passed.");
+		"genres" => CommandOutput::Genres(plugin.scrape_genres_list().await?),
+		"genre" => {
+			let url = args.first().cloned().unwrap_or_default();
+			if url.is_empty() {
+				bail!("Missing genre URL");
 			}
+			let page = parse_page_arg(args.get(1))?;
+			CommandOutput::Items("Genre", plugin.scrape_genre(url, page).await?)
 		}
 		_ => {
 			print_usage();
 			bail!("Unknown command: {}", command);
 		}
+	};
+	Ok(output)
+}
+
+async fn record(plugin_path: &Path, mut args: Vec<String>) -> Result<()> {
+	if args.len() < 2 {
+		print_usage();
+		bail!("Missing case directory or command to record");
+	}
+	let case_dir = PathBuf::from(args.remove(0));
+	let command = args.remove(0);
+
+	std::fs::create_dir_all(&case_dir)
+		.with_context(|| format!("Failed to create case directory: {}", case_dir.display()))?;
+
+	let plugin = load_plugin_with_config(plugin_path, fixture_config(FixtureMode::Record, &case_dir)).await?;
+	let output = run_command(&plugin, &command, &args).await?;
+	output.print();
+	// Writes the recorded fixtures.
+	drop(plugin);
+
+	let snapshot = Snapshot {
+		command,
+		args,
+		output: output.to_json()?,
+	};
+	write_snapshot(&case_dir, &snapshot)?;
+	println!("Recorded fixtures and snapshot in {}", case_dir.display());
+	Ok(())
+}
+
+async fn replay(plugin_path: &Path, args: Vec<String>) -> Result<()> {
+	let Some(case_dir) = args.first().map(PathBuf::from) else {
+		print_usage();
+		bail!("Missing case directory");
+	};
+	let update = args.iter().any(|arg| arg == "--update");
+
+	let snapshot_path = case_dir.join(SNAPSHOT_FILE);
+	let data =
+		std::fs::read(&snapshot_path).with_context(|| format!("Failed to read snapshot: {}", snapshot_path.display()))?;
+	let mut snapshot: Snapshot =
+		serde_json::from_slice(&data).with_context(|| format!("Invalid snapshot: {}", snapshot_path.display()))?;
+
+	let plugin = load_plugin_with_config(plugin_path, fixture_config(FixtureMode::Replay, &case_dir)).await?;
+	let output = run_command(&plugin, &snapshot.command, &snapshot.args).await?.to_json()?;
+
+	if output == snapshot.output {
+		println!("Snapshot matches: {} {}", snapshot.command, snapshot.args.join(" "));
+		return Ok(());
+	}
+
+	if update {
+		snapshot.output = output;
+		write_snapshot(&case_dir, &snapshot)?;
+		println!("Updated snapshot in {}", snapshot_path.display());
+		return Ok(());
+	}
+
+	eprintln!("Expected:\n{}", serde_json::to_string_pretty(&snapshot.output)?);
+	eprintln!("Actual:\n{}", serde_json::to_string_pretty(&output)?);
+	bail!("Snapshot mismatch for {}", snapshot_path.display());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportFormat {
+	Text,
+	Json,
+	Junit,
+}
+
+fn parse_report_args(args: &[String]) -> Result<(ReportFormat, Option<PathBuf>)> {
+	let mut format = ReportFormat::Text;
+	let mut output = None;
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--format" => {
+				format = match args.next().map(String::as_str) {
+					Some("text") => ReportFormat::Text,
+					Some("json") => ReportFormat::Json,
+					Some("junit") => ReportFormat::Junit,
+					other => bail!("Unknown report format: {}", other.unwrap_or_default()),
+				}
+			}
+			"--output" => {
+				let Some(path) = args.next() else {
+					bail!("Missing file after --output");
+				};
+				output = Some(PathBuf::from(path));
+			}
+			other => bail!("Unknown argument: {}", other),
+		}
+	}
+	Ok((format, output))
+}
+
+async fn run_tests(plugin_path: &Path, args: &[String]) -> Result<()> {
+	let (format, output) = parse_report_args(args)?;
+	let plugin = load_plugin_for_path(plugin_path).await?;
+	let suite = plugin.get_info().await?.id;
+	let outcomes = plugin.run_declared_tests().await.context("Failed to run declared tests")?;
+
+	let report = match format {
+		ReportFormat::Text => text_report(&outcomes),
+		ReportFormat::Json => serde_json::to_string_pretty(&outcomes)?,
+		ReportFormat::Junit => junit_report(&suite, &outcomes),
+	};
+	match output {
+		Some(path) => {
+			std::fs::write(&path, report).with_context(|| format!("Failed to write report: {}", path.display()))?
+		}
+		None => print!("{}", report),
+	}
+
+	let failed = outcomes.iter().filter(|outcome| !outcome.passed()).count();
+	if failed > 0 {
+		bail!("{} of {} tests failed", failed, outcomes.len());
+	}
+	Ok(())
+}
+
+fn text_report(outcomes: &[TestOutcome]) -> String {
+	if outcomes.is_empty() {
+		return "No declared tests found. Scraper loaded successfully.\n".to_string();
+	}
+
+	let mut report = format!("Executed {} tests:\n", outcomes.len());
+	for outcome in outcomes {
+		match &outcome.failure {
+			None => report.push_str(&format!("- {} ({} ms)\n", outcome.name, outcome.duration_ms)),
+			Some(failure) => report.push_str(&format!("- {} FAILED: {}\n", outcome.name, failure)),
+		}
+	}
+	if outcomes.iter().all(TestOutcome::passed) {
+		report.push_str("All tests passed.\n");
+	}
+	report
+}
+
+fn junit_report(suite: &str, outcomes: &[TestOutcome]) -> String {
+	let failures = outcomes.iter().filter(|outcome| !outcome.passed()).count();
+	let total_ms: u64 = outcomes.iter().map(|outcome| outcome.duration_ms).sum();
+
+	let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	report.push_str(&format!(
+		"<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+		xml_escape(suite),
+		outcomes.len(),
+		failures,
+		total_ms as f64 / 1000.0
+	));
+	for outcome in outcomes {
+		let attributes = format!(
+			"name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+			xml_escape(&outcome.name),
+			xml_escape(suite),
+			outcome.duration_ms as f64 / 1000.0
+		);
+		match &outcome.failure {
+			None => report.push_str(&format!("  <testcase {} />\n", attributes)),
+			Some(failure) => report.push_str(&format!(
+				"  <testcase {}>\n    <failure message=\"{}\">{}</failure>\n  </testcase>\n",
+				attributes,
+				xml_escape(failure.lines().next().unwrap_or_default()),
+				xml_escape(failure)
+			)),
+		}
+	}
+	report.push_str("</testsuite>\n");
+	report
+}
+
+fn xml_escape(value: &str) -> String {
+	value
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+		.replace('\'', "&apos;")
+}
+
+fn write_snapshot(case_dir: &Path, snapshot: &Snapshot) -> Result<()> {
+	let path = case_dir.join(SNAPSHOT_FILE);
+	std::fs::write(&path, serde_json::to_string_pretty(snapshot)?)
+		.with_context(|| format!("Failed to write snapshot: {}", path.display()))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+	let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+	if args.is_empty() {
+		print_usage();
+		bail!("Missing command");
+	}
+
+	let command = args.remove(0);
+	if args.is_empty() {
+		print_usage();
+		bail!("Missing plugin file path");
+	}
+
+	let plugin_path = PathBuf::from(&args.remove(0));
+	if !plugin_path.exists() {
+		bail!("Plugin file does not exist: {}", plugin_path.display());
+	}
+	if !plugin_path.is_file() {
+		bail!("Path is not a file: {}", plugin_path.display());
+	}
+
+	match command.as_str() {
+		"record" => record(&plugin_path, args).await?,
+		"replay" => replay(&plugin_path, args).await?,
+		"tests" => run_tests(&plugin_path, &args).await?,
+		_ => {
+			let plugin = load_plugin_for_path(&plugin_path).await?;
+			run_command(&plugin, &command, &args).await?.print();
+		}
 	}
 
 	Ok(())
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "lua")]
+use mlua::{FromLua, IntoLua, Lua, Value};
+use serde::{Deserialize, Serialize};
+
+/// One image of a manga chapter and what the image proxy needs to download it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ChapterPage {
+	pub url: String,
+	/// Extra request headers, such as a token or cookie the source checks per image.
+	#[serde(default)]
+	pub headers: BTreeMap<String, String>,
+	/// Overrides the scraper's `referer_url` for this page.
+	#[serde(default)]
+	pub referer: Option<String>,
+	#[serde(default)]
+	pub width: Option<u32>,
+	#[serde(default)]
+	pub height: Option<u32>,
+	/// Copies of the same image, tried in order when `url` cannot be fetched.
+	#[serde(default)]
+	pub mirrors: Vec<String>,
+}
+
+impl ChapterPage {
+	/// Whether the size hints describe a landscape page, which usually is a double-page spread.
+	pub fn is_spread(&self) -> bool {
+		matches!((self.width, self.height), (Some(width), Some(height)) if width > height)
+	}
+
+	/// `url` followed by the mirrors.
+	pub fn sources(&self) -> impl Iterator<Item = &str> {
+		std::iter::once(self.url.as_str()).chain(self.mirrors.iter().map(String::as_str))
+	}
+}
+
+impl From<String> for ChapterPage {
+	fn from(url: String) -> Self {
+		ChapterPage {
+			url,
+			..Default::default()
+		}
+	}
+}
+
+#[cfg(feature = "lua")]
+impl IntoLua for ChapterPage {
+	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+		let table = lua.create_table()?;
+		table.set("url", self.url)?;
+		table.set("headers", self.headers)?;
+		table.set("referer", self.referer)?;
+		table.set("width", self.width)?;
+		table.set("height", self.height)?;
+		table.set("mirrors", self.mirrors)?;
+		Ok(Value::Table(table))
+	}
+}
+
+/// Plugins may return a bare URL string instead of a table, which is what `Scrape_chapter` always returned.
+#[cfg(feature = "lua")]
+impl FromLua for ChapterPage {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		if let Value::String(url) = value {
+			return Ok(ChapterPage::from(url.to_str()?.to_string()));
+		}
+
+		let table: mlua::Table = FromLua::from_lua(value, lua)?;
+		Ok(ChapterPage {
+			url: table.get("url")?,
+			headers: table.get("headers").ok().unwrap_or_default(),
+			referer: table.get("referer").ok(),
+			width: table.get("width").ok(),
+			height: table.get("height").ok(),
+			mirrors: table.get("mirrors").ok().unwrap_or_default(),
+		})
+	}
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_chapter_page_from_lua() {
+		let lua = Lua::new();
+		let pages: Vec<ChapterPage> = lua
+			.load(
+				r#"
+				return {
+					"https://example.com/1.jpg",
+					{
+						url = "https://example.com/2.jpg",
+						headers = { ["X-Token"] = "abc" },
+						width = 1600,
+						height = 1200,
+						mirrors = { "https://mirror.example.com/2.jpg" },
+					},
+				}
+				"#,
+			)
+			.eval()
+			.expect("failed to load pages");
+
+		assert_eq!(pages[0], ChapterPage::from("https://example.com/1.jpg".to_string()));
+		assert_eq!(pages[1].headers.get("X-Token").map(String::as_str), Some("abc"));
+		assert_eq!(pages[1].referer, None);
+		assert!(pages[1].is_spread());
+		assert_eq!(
+			pages[1].sources().collect::<Vec<_>>(),
+			vec!["https://example.com/2.jpg", "https://mirror.example.com/2.jpg"]
+		);
+	}
+}
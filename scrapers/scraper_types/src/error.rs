@@ -1,5 +1,6 @@
 use std::fmt;
 
+#[cfg(feature = "lua")]
 use mlua::{FromLua, IntoLua, Lua, Value};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -151,6 +152,49 @@ impl ScraperError {
 	}
 }
 
+/// Defines `error` and `status_error` in a WASM plugin, for the `ScraperError` and `ErrorKind` it generates from
+/// `scraper.wit`. Both follow the host's retry rules, see [`ScraperErrorKind::default_retryable`] and
+/// [`ScraperError::from_http_status`].
+#[macro_export]
+macro_rules! wit_error_helpers {
+	() => {
+		fn error(kind: ErrorKind, message: String, status_code: Option<u16>) -> ScraperError {
+			let retryable = match kind {
+				ErrorKind::Network => $crate::ScraperErrorKind::Network,
+				ErrorKind::Cloudflare => $crate::ScraperErrorKind::Cloudflare,
+				ErrorKind::RateLimit => $crate::ScraperErrorKind::RateLimit,
+				ErrorKind::NotFound => $crate::ScraperErrorKind::NotFound,
+				ErrorKind::Parse => $crate::ScraperErrorKind::Parse,
+				ErrorKind::Validation => $crate::ScraperErrorKind::Validation,
+				ErrorKind::Internal => $crate::ScraperErrorKind::Internal,
+			}
+			.default_retryable();
+			ScraperError {
+				kind,
+				message,
+				retryable,
+				status_code,
+			}
+		}
+
+		fn status_error(url: &str, status: u16) -> ScraperError {
+			let err = $crate::ScraperError::from_http_status(status, format!("HTTP {} for {}", status, url));
+			let kind = match err.kind {
+				$crate::ScraperErrorKind::NotFound => ErrorKind::NotFound,
+				$crate::ScraperErrorKind::RateLimit => ErrorKind::RateLimit,
+				$crate::ScraperErrorKind::Cloudflare => ErrorKind::Cloudflare,
+				_ => ErrorKind::Network,
+			};
+			ScraperError {
+				kind,
+				message: err.message,
+				retryable: err.retryable,
+				status_code: err.status_code,
+			}
+		}
+	};
+}
+
 impl fmt::Display for ScraperError {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(f, "[{}] {}", self.kind, self.message)
@@ -159,6 +203,7 @@ impl fmt::Display for ScraperError {
 
 impl std::error::Error for ScraperError {}
 
+#[cfg(feature = "lua")]
 impl IntoLua for ScraperError {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let table = lua.create_table()?;
@@ -172,6 +217,7 @@ impl IntoLua for ScraperError {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for ScraperError {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		let table: mlua::Table = FromLua::from_lua(value, lua)?;
@@ -240,6 +286,7 @@ mod tests {
 	}
 
 	#[test]
+	#[cfg(feature = "lua")]
 	fn test_lua_roundtrip() {
 		let lua = mlua::Lua::new();
 		let error = ScraperError::with_status(ScraperErrorKind::Network, "Connection timeout", 503);
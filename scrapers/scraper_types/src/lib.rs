@@ -1,11 +1,20 @@
 use std::sync::LazyLock;
 
+#[cfg(feature = "lua")]
 use mlua::{FromLua, IntoLua, Lua, Value};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+mod chapter_page;
 mod error;
+mod normalize;
+mod search;
+mod transform;
+pub use chapter_page::ChapterPage;
 pub use error::{ScraperError, ScraperErrorKind, ScraperResult};
+pub use normalize::{normalize_genre, resolve_url};
+pub use search::{SearchFilterSpec, SearchFilters, SortOrder};
+pub use transform::{PageImage, ProcessedImage};
 
 const CHAP_NUMBER_REGEX: LazyLock<Regex> =
 	LazyLock::new(|| Regex::new(r"(\d+)").expect("Failed to compile chapter number regex"));
@@ -18,6 +27,7 @@ pub struct Item {
 	pub img_url: Option<String>,
 }
 
+#[cfg(feature = "lua")]
 impl IntoLua for Item {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let table = lua.create_table()?;
@@ -28,6 +38,7 @@ impl IntoLua for Item {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for Item {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		let table: mlua::Table = FromLua::from_lua(value, lua)?;
@@ -102,6 +113,7 @@ impl Page {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl IntoLua for Page {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let table = lua.create_table()?;
@@ -122,6 +134,7 @@ impl IntoLua for Page {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for Page {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		let table: mlua::Table = FromLua::from_lua(value, lua)?;
@@ -173,6 +186,7 @@ impl Chapter {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl IntoLua for Chapter {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let table = lua.create_table()?;
@@ -183,6 +197,7 @@ impl IntoLua for Chapter {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for Chapter {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		let table: mlua::Table = FromLua::from_lua(value, lua)?;
@@ -201,6 +216,7 @@ pub struct Genre {
 	pub url: String,
 }
 
+#[cfg(feature = "lua")]
 impl IntoLua for Genre {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let table = lua.create_table()?;
@@ -210,6 +226,7 @@ impl IntoLua for Genre {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for Genre {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		let table: mlua::Table = FromLua::from_lua(value, lua)?;
@@ -231,6 +248,48 @@ pub struct ScraperInfo {
 	pub base_url: Option<String>,
 	pub legacy_urls: Option<Vec<String>>,
 	pub r#type: ScraperType,
+	/// Filters `scrape_search` accepts. `None` when the scraper only supports a text query.
+	#[serde(default)]
+	pub search_filters: Option<SearchFilterSpec>,
+	/// Whether the image proxy passes this scraper's chapter pages through `transform_page`.
+	#[serde(default)]
+	pub transforms_pages: bool,
+	/// How fast the hosts of `base_url`, `referer_url` and `legacy_urls` may be called. `None` uses the default.
+	#[serde(default)]
+	pub rate_limit: Option<RateLimit>,
+}
+
+/// A request rate as a token bucket: up to `burst` requests at once, refilled at `requests_per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+	pub requests_per_minute: u32,
+	#[serde(default = "default_burst")]
+	pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+	1
+}
+
+#[cfg(feature = "lua")]
+impl IntoLua for RateLimit {
+	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+		let table = lua.create_table()?;
+		table.set("requests_per_minute", self.requests_per_minute)?;
+		table.set("burst", self.burst)?;
+		Ok(Value::Table(table))
+	}
+}
+
+#[cfg(feature = "lua")]
+impl FromLua for RateLimit {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		let table: mlua::Table = FromLua::from_lua(value, lua)?;
+		Ok(RateLimit {
+			requests_per_minute: table.get("requests_per_minute")?,
+			burst: table.get::<Option<u32>>("burst")?.unwrap_or_else(default_burst),
+		})
+	}
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -269,6 +328,68 @@ impl std::fmt::Display for ScraperType {
 	}
 }
 
+/// Canonical publication status every scraper's free-form status is mapped to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PageStatus {
+	Ongoing,
+	Completed,
+	Hiatus,
+	Cancelled,
+}
+
+impl PageStatus {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			PageStatus::Ongoing => "ongoing",
+			PageStatus::Completed => "completed",
+			PageStatus::Hiatus => "hiatus",
+			PageStatus::Cancelled => "cancelled",
+		}
+	}
+
+	/// Recognizes the spellings sources use for each status, e.g. "Publishing", "Finished" or "On Hold". Keywords
+	/// match whole words by their start, so negations like "Incomplete" or "Not finished" are not taken for them.
+	pub fn parse(s: &str) -> Option<Self> {
+		let s = s.trim().to_lowercase();
+		let words: Vec<&str> = s.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+		let keywords: [(&[&str], PageStatus); 4] = [
+			(&["cancel", "drop", "discontinu", "abandon"], PageStatus::Cancelled),
+			(&["hiatus", "on hold", "paused"], PageStatus::Hiatus),
+			(&["complet", "finish", "ended", "concluded"], PageStatus::Completed),
+			(
+				&["ongoing", "on going", "publishing", "releasing", "updating", "serializ"],
+				PageStatus::Ongoing,
+			),
+		];
+
+		keywords
+			.iter()
+			.find(|(keywords, _)| keywords.iter().any(|keyword| contains_keyword(&words, keyword)))
+			.map(|(_, status)| *status)
+	}
+}
+
+/// Whether `words` contain the words of `keyword`, the last one as a prefix, without a "not" or "no" before them.
+fn contains_keyword(words: &[&str], keyword: &str) -> bool {
+	let parts: Vec<&str> = keyword.split(' ').collect();
+	let Some((last, leading)) = parts.split_last() else {
+		return false;
+	};
+
+	words.windows(parts.len()).enumerate().any(|(start, window)| {
+		let negated = start > 0 && matches!(words[start - 1], "not" | "no");
+		!negated && window[..leading.len()] == *leading && window[leading.len()].starts_with(last)
+	})
+}
+
+impl std::fmt::Display for PageStatus {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.as_str())
+	}
+}
+
+#[cfg(feature = "lua")]
 impl IntoLua for ScraperType {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let s = self.to_string();
@@ -276,6 +397,7 @@ impl IntoLua for ScraperType {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for ScraperType {
 	fn from_lua(value: Value, _: &Lua) -> mlua::Result<Self> {
 		match value {
@@ -316,6 +438,7 @@ impl FromLua for ScraperType {
 	}
 }
 
+#[cfg(feature = "lua")]
 impl IntoLua for ScraperInfo {
 	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
 		let table = lua.create_table()?;
@@ -327,10 +450,14 @@ impl IntoLua for ScraperInfo {
 		table.set("base_url", self.base_url)?;
 		table.set("legacy_urls", self.legacy_urls)?;
 		table.set("type", self.r#type)?;
+		table.set("search_filters", self.search_filters)?;
+		table.set("transforms_pages", self.transforms_pages)?;
+		table.set("rate_limit", self.rate_limit)?;
 		Ok(Value::Table(table))
 	}
 }
 
+#[cfg(feature = "lua")]
 impl FromLua for ScraperInfo {
 	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
 		let table: mlua::Table = FromLua::from_lua(value, lua)?;
@@ -343,6 +470,9 @@ impl FromLua for ScraperInfo {
 			base_url: table.get("base_url").ok(),
 			legacy_urls: table.get("legacy_urls").ok(),
 			r#type: table.get("type").ok().unwrap_or(ScraperType::Manga),
+			search_filters: table.get("search_filters").ok().flatten(),
+			transforms_pages: table.get("transforms_pages").ok().unwrap_or_default(),
+			rate_limit: table.get("rate_limit").ok().flatten(),
 		})
 	}
 }
@@ -407,6 +537,7 @@ pub mod conversion {
 		}
 	}
 
+	#[cfg(feature = "lua")]
 	pub fn mlua_value_to_json(val: mlua::Value) -> Result<JsonValue, ScraperError> {
 		use mlua::Value as LuaValue;
 
@@ -462,6 +593,22 @@ pub mod conversion {
 mod tests {
 	use super::*;
 
+	#[test]
+	fn test_parse_page_status() {
+		assert_eq!(PageStatus::parse(" Ongoing "), Some(PageStatus::Ongoing));
+		assert_eq!(PageStatus::parse("Publishing"), Some(PageStatus::Ongoing));
+		assert_eq!(PageStatus::parse("Completed"), Some(PageStatus::Completed));
+		assert_eq!(PageStatus::parse("On Hold"), Some(PageStatus::Hiatus));
+		assert_eq!(PageStatus::parse("Canceled"), Some(PageStatus::Cancelled));
+		assert_eq!(PageStatus::parse("Dropped"), Some(PageStatus::Cancelled));
+		assert_eq!(PageStatus::parse("???"), None);
+		assert_eq!(PageStatus::parse("on-hold"), Some(PageStatus::Hiatus));
+		assert_eq!(PageStatus::parse("Status: Finished"), Some(PageStatus::Completed));
+		for negated in ["Incomplete", "Not completed", "Unfinished", "not finished"] {
+			assert_eq!(PageStatus::parse(negated), None, "{}", negated);
+		}
+	}
+
 	#[test]
 	fn test_extract() {
 		let chapter = Chapter {
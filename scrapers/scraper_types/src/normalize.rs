@@ -0,0 +1,301 @@
+use std::collections::HashSet;
+
+use url::Url;
+
+use crate::{Page, PageStatus, ScraperError};
+
+/// Spellings sources use for the same genre, keyed by `genre_key`, and the name they are stored as.
+const GENRE_ALIASES: &[(&[&str], &str)] = &[
+	(&["sci fi", "scifi", "science fiction"], "Sci-Fi"),
+	(&["shonen", "shounen"], "Shounen"),
+	(&["shojo", "shoujo"], "Shoujo"),
+	(&["shonen ai", "shounen ai"], "Shounen Ai"),
+	(&["shojo ai", "shoujo ai"], "Shoujo Ai"),
+	(&["slice of life", "sliceoflife"], "Slice of Life"),
+	(&["martial art", "martial arts", "martialarts"], "Martial Arts"),
+	(
+		&["gender bender", "genderbender", "gender swap", "genderswap"],
+		"Gender Bender",
+	),
+	(&["super power", "superpower", "super powers", "superpowers"], "Superpower"),
+	(&["school", "school life", "schoollife"], "School Life"),
+	(&["boys love", "bl"], "Boys' Love"),
+	(&["girls love", "gl"], "Girls' Love"),
+	(&["4 koma", "4koma", "yonkoma"], "4-Koma"),
+	(&["post apocalyptic", "postapocalyptic"], "Post-Apocalyptic"),
+	(&["sport", "sports"], "Sports"),
+	(&["history", "historical"], "Historical"),
+	(&["rom com", "romcom", "romantic comedy"], "Romantic Comedy"),
+];
+
+/// Lowercases a genre and folds separators so "Sci_Fi", "sci-fi" and "Sci Fi" compare equal.
+fn genre_key(name: &str) -> String {
+	name.to_lowercase()
+		.replace(['-', '_', '.'], " ")
+		.replace('\'', "")
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Maps a genre to its canonical name, falling back to title case for genres without an alias.
+pub fn normalize_genre(name: &str) -> Option<String> {
+	let key = genre_key(name);
+	if key.is_empty() {
+		return None;
+	}
+
+	if let Some((_, canonical)) = GENRE_ALIASES.iter().find(|(aliases, _)| aliases.contains(&key.as_str())) {
+		return Some(canonical.to_string());
+	}
+
+	let words: Vec<String> = key
+		.split(' ')
+		.map(|word| {
+			let mut chars = word.chars();
+			match chars.next() {
+				Some(first) => first.to_uppercase().chain(chars).collect(),
+				None => String::new(),
+			}
+		})
+		.collect();
+	Some(words.join(" "))
+}
+
+/// Resolves protocol-less and relative URLs against `base`.
+pub fn resolve_url(raw: &str, base: Option<&Url>) -> Result<String, String> {
+	let raw = raw.trim();
+	if raw.is_empty() {
+		return Err("URL is empty".to_string());
+	}
+
+	let url = if let Some(rest) = raw.strip_prefix("//") {
+		Url::parse(&format!("https://{}", rest))
+	} else {
+		match Url::parse(raw) {
+			Err(url::ParseError::RelativeUrlWithoutBase) => match base {
+				Some(base) => base.join(raw),
+				None => return Err(format!("relative URL '{}' without a base URL", raw)),
+			},
+			other => other,
+		}
+	};
+
+	match url {
+		Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(url.into()),
+		Ok(url) => Err(format!("unsupported URL scheme '{}' in '{}'", url.scheme(), raw)),
+		Err(e) => Err(format!("invalid URL '{}': {}", raw, e)),
+	}
+}
+
+fn clean_list(values: &mut Vec<String>) {
+	let mut seen = HashSet::new();
+	values.retain_mut(|value| {
+		*value = value.trim().to_string();
+		!value.is_empty() && seen.insert(value.to_lowercase())
+	});
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+	value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl Page {
+	/// Normalizes a scraped page before it is stored.
+	///
+	/// URLs are resolved against `base_url`, the status is mapped to a [`PageStatus`], genres go through the
+	/// alias table and chapters are deduplicated by URL. Anything that could not be fixed is returned as a
+	/// validation warning; the page is still usable.
+	pub fn normalize(&mut self, base_url: Option<&str>) -> Vec<ScraperError> {
+		let mut warnings = Vec::new();
+		let base = base_url.and_then(|b| Url::parse(b).ok());
+
+		if self.title.trim().is_empty() {
+			warnings.push(ScraperError::validation("page has an empty title"));
+		}
+		self.title = self.title.trim().to_string();
+
+		match resolve_url(&self.url, base.as_ref()) {
+			Ok(url) => self.url = url,
+			Err(e) => warnings.push(ScraperError::validation(format!("page url: {}", e))),
+		}
+
+		let page_base = Url::parse(&self.url).ok().or(base);
+
+		if let Some(img_url) = non_empty(self.img_url.take()) {
+			match resolve_url(&img_url, page_base.as_ref()) {
+				Ok(url) => self.img_url = Some(url),
+				Err(e) => warnings.push(ScraperError::validation(format!("img_url: {}", e))),
+			}
+		}
+
+		self.status = match non_empty(self.status.take()) {
+			Some(status) => match PageStatus::parse(&status) {
+				Some(parsed) => Some(parsed.as_str().to_string()),
+				None => {
+					warnings.push(ScraperError::validation(format!("unknown status '{}'", status)));
+					None
+				}
+			},
+			None => None,
+		};
+
+		self.page_type = non_empty(self.page_type.take());
+		self.release_date = non_empty(self.release_date.take());
+		if let Some(ref date) = self.release_date
+			&& self.parse_release_date().is_none()
+		{
+			warnings.push(ScraperError::validation(format!("unparseable release date '{}'", date)));
+		}
+
+		clean_list(&mut self.alternative_names);
+		clean_list(&mut self.authors);
+		if let Some(ref mut artists) = self.artists {
+			clean_list(artists);
+		}
+
+		let mut seen_genres = HashSet::new();
+		self.genres = self
+			.genres
+			.iter()
+			.filter_map(|genre| normalize_genre(genre))
+			.filter(|genre| seen_genres.insert(genre.clone()))
+			.collect();
+
+		let mut seen_urls = HashSet::new();
+		let mut duplicates = 0;
+		let chapters = std::mem::take(&mut self.chapters);
+		for mut chapter in chapters {
+			match resolve_url(&chapter.url, page_base.as_ref()) {
+				Ok(url) => chapter.url = url,
+				Err(e) => {
+					warnings.push(ScraperError::validation(format!(
+						"dropped chapter '{}': {}",
+						chapter.title, e
+					)));
+					continue;
+				}
+			}
+
+			if seen_urls.insert(chapter.url.clone()) {
+				self.chapters.push(chapter);
+			} else {
+				duplicates += 1;
+			}
+		}
+
+		if duplicates > 0 {
+			warnings.push(ScraperError::validation(format!(
+				"dropped {} chapters with duplicate URLs",
+				duplicates
+			)));
+		}
+
+		warnings
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Chapter, ScraperErrorKind};
+
+	fn page() -> Page {
+		Page {
+			title: " Solo Leveling ".to_string(),
+			url: "/manga/solo-leveling".to_string(),
+			img_url: Some("//cdn.example.com/cover.jpg".to_string()),
+			alternative_names: vec!["Na Honjaman Level Up".to_string(), " ".to_string()],
+			authors: vec!["Chugong".to_string(), "chugong ".to_string()],
+			artists: None,
+			status: Some("Finished".to_string()),
+			page_type: Some(" Manhwa ".to_string()),
+			release_date: Some("2018".to_string()),
+			description: None,
+			genres: vec![
+				"Sci-Fi".to_string(),
+				"science fiction".to_string(),
+				"shonen".to_string(),
+				"action".to_string(),
+			],
+			chapters: vec![
+				Chapter {
+					title: "Chapter 2".to_string(),
+					url: "chapter-2".to_string(),
+					..Default::default()
+				},
+				Chapter {
+					title: "Chapter 1".to_string(),
+					url: "https://example.com/manga/chapter-1".to_string(),
+					..Default::default()
+				},
+				Chapter {
+					title: "Chapter 1".to_string(),
+					url: "/manga/chapter-1".to_string(),
+					..Default::default()
+				},
+			],
+			content_html: None,
+		}
+	}
+
+	#[test]
+	fn test_normalize_page() {
+		let mut page = page();
+		let warnings = page.normalize(Some("https://example.com"));
+
+		assert_eq!(page.title, "Solo Leveling");
+		assert_eq!(page.url, "https://example.com/manga/solo-leveling");
+		assert_eq!(page.img_url.as_deref(), Some("https://cdn.example.com/cover.jpg"));
+		assert_eq!(page.alternative_names, vec!["Na Honjaman Level Up"]);
+		assert_eq!(page.authors, vec!["Chugong"]);
+		assert_eq!(page.status.as_deref(), Some("completed"));
+		assert_eq!(page.page_type.as_deref(), Some("Manhwa"));
+		assert_eq!(page.genres, vec!["Sci-Fi", "Shounen", "Action"]);
+
+		let urls: Vec<&str> = page.chapters.iter().map(|c| c.url.as_str()).collect();
+		assert_eq!(
+			urls,
+			vec!["https://example.com/manga/chapter-2", "https://example.com/manga/chapter-1"]
+		);
+
+		assert_eq!(warnings.len(), 1);
+		assert_eq!(warnings[0].kind, ScraperErrorKind::Validation);
+	}
+
+	#[test]
+	fn test_normalize_reports_violations() {
+		let mut page = page();
+		page.status = Some("Licensed".to_string());
+		page.release_date = Some("sometime".to_string());
+		let warnings = page.normalize(None);
+
+		assert_eq!(page.url, "/manga/solo-leveling");
+		assert_eq!(page.status, None);
+		assert_eq!(page.chapters.len(), 1);
+		assert!(warnings.iter().all(|w| w.kind == ScraperErrorKind::Validation));
+		assert!(warnings.iter().any(|w| w.message.contains("Licensed")));
+		assert!(warnings.iter().any(|w| w.message.contains("sometime")));
+		assert!(warnings.iter().any(|w| w.message.starts_with("page url")));
+	}
+
+	#[test]
+	fn test_normalize_genre() {
+		assert_eq!(normalize_genre("Slice-of-Life").as_deref(), Some("Slice of Life"));
+		assert_eq!(normalize_genre("boys' love").as_deref(), Some("Boys' Love"));
+		assert_eq!(normalize_genre("  dark   FANTASY ").as_deref(), Some("Dark Fantasy"));
+		assert_eq!(normalize_genre(" "), None);
+	}
+
+	#[test]
+	fn test_resolve_url() {
+		let base = Url::parse("https://example.com/manga/").unwrap();
+		assert_eq!(resolve_url("ch-1", Some(&base)).unwrap(), "https://example.com/manga/ch-1");
+		assert_eq!(
+			resolve_url("//img.example.com/a.png", None).unwrap(),
+			"https://img.example.com/a.png"
+		);
+		assert!(resolve_url("javascript:void(0)", Some(&base)).is_err());
+		assert!(resolve_url("/ch-1", None).is_err());
+	}
+}
@@ -0,0 +1,265 @@
+#[cfg(feature = "lua")]
+use mlua::{FromLua, IntoLua, Lua, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::{PageStatus, ScraperError, ScraperResult};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+	Relevance,
+	Latest,
+	Popular,
+	Alphabetical,
+	Rating,
+}
+
+impl SortOrder {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			SortOrder::Relevance => "relevance",
+			SortOrder::Latest => "latest",
+			SortOrder::Popular => "popular",
+			SortOrder::Alphabetical => "alphabetical",
+			SortOrder::Rating => "rating",
+		}
+	}
+
+	/// Case-insensitive, ignoring surrounding whitespace.
+	pub fn parse(s: &str) -> Option<Self> {
+		match s.trim().to_lowercase().as_str() {
+			"relevance" => Some(SortOrder::Relevance),
+			"latest" => Some(SortOrder::Latest),
+			"popular" => Some(SortOrder::Popular),
+			"alphabetical" => Some(SortOrder::Alphabetical),
+			"rating" => Some(SortOrder::Rating),
+			_ => None,
+		}
+	}
+}
+
+/// Filters a scraper accepts in `scrape_search`, declared in its [`ScraperInfo`](crate::ScraperInfo).
+/// Empty lists and `false` mean the filter is not supported.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilterSpec {
+	#[serde(default)]
+	pub genres: Vec<String>,
+	/// Whether genres from `genres` can also be excluded.
+	#[serde(default)]
+	pub exclude_genres: bool,
+	#[serde(default)]
+	pub statuses: Vec<PageStatus>,
+	#[serde(default)]
+	pub types: Vec<String>,
+	#[serde(default)]
+	pub sort_orders: Vec<SortOrder>,
+	#[serde(default)]
+	pub year: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilters {
+	#[serde(default)]
+	pub include_genres: Vec<String>,
+	#[serde(default)]
+	pub exclude_genres: Vec<String>,
+	#[serde(default)]
+	pub status: Option<PageStatus>,
+	#[serde(default)]
+	pub page_type: Option<String>,
+	#[serde(default)]
+	pub sort: Option<SortOrder>,
+	#[serde(default)]
+	pub year: Option<u32>,
+}
+
+impl SearchFilters {
+	pub fn is_empty(&self) -> bool {
+		*self == SearchFilters::default()
+	}
+}
+
+/// Finds `value` in `declared` ignoring case, returning the scraper's own spelling.
+fn declared<'a>(declared: &'a [String], value: &str, what: &str) -> ScraperResult<&'a String> {
+	declared
+		.iter()
+		.find(|d| d.eq_ignore_ascii_case(value.trim()))
+		.ok_or_else(|| ScraperError::validation(format!("unsupported {} '{}'", what, value)))
+}
+
+impl SearchFilterSpec {
+	/// Checks `filters` only uses what the scraper declared, rewriting genres and types into its spelling.
+	pub fn resolve(&self, filters: SearchFilters) -> ScraperResult<SearchFilters> {
+		let include_genres = filters
+			.include_genres
+			.iter()
+			.map(|genre| declared(&self.genres, genre, "genre").cloned())
+			.collect::<ScraperResult<Vec<_>>>()?;
+
+		if !filters.exclude_genres.is_empty() && !self.exclude_genres {
+			return Err(ScraperError::validation("excluding genres is not supported"));
+		}
+		let exclude_genres = filters
+			.exclude_genres
+			.iter()
+			.map(|genre| declared(&self.genres, genre, "genre").cloned())
+			.collect::<ScraperResult<Vec<_>>>()?;
+
+		if let Some(status) = filters.status
+			&& !self.statuses.contains(&status)
+		{
+			return Err(ScraperError::validation(format!("unsupported status '{}'", status)));
+		}
+
+		let page_type = match filters.page_type {
+			Some(ref page_type) => Some(declared(&self.types, page_type, "type")?.clone()),
+			None => None,
+		};
+
+		if let Some(sort) = filters.sort
+			&& !self.sort_orders.contains(&sort)
+		{
+			return Err(ScraperError::validation(format!(
+				"unsupported sort order '{}'",
+				sort.as_str()
+			)));
+		}
+
+		if filters.year.is_some() && !self.year {
+			return Err(ScraperError::validation("filtering by year is not supported"));
+		}
+
+		Ok(SearchFilters {
+			include_genres,
+			exclude_genres,
+			status: filters.status,
+			page_type,
+			sort: filters.sort,
+			year: filters.year,
+		})
+	}
+}
+
+#[cfg(feature = "lua")]
+impl IntoLua for SearchFilters {
+	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+		let table = lua.create_table()?;
+		table.set("include_genres", self.include_genres)?;
+		table.set("exclude_genres", self.exclude_genres)?;
+		table.set("status", self.status.map(|s| s.as_str()))?;
+		table.set("page_type", self.page_type)?;
+		table.set("sort", self.sort.map(|s| s.as_str()))?;
+		table.set("year", self.year)?;
+		Ok(Value::Table(table))
+	}
+}
+
+#[cfg(feature = "lua")]
+impl IntoLua for SearchFilterSpec {
+	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+		let table = lua.create_table()?;
+		table.set("genres", self.genres)?;
+		table.set("exclude_genres", self.exclude_genres)?;
+		table.set("statuses", self.statuses.iter().map(|s| s.as_str()).collect::<Vec<_>>())?;
+		table.set("types", self.types)?;
+		table.set("sort_orders", self.sort_orders.iter().map(|s| s.as_str()).collect::<Vec<_>>())?;
+		table.set("year", self.year)?;
+		Ok(Value::Table(table))
+	}
+}
+
+#[cfg(feature = "lua")]
+impl FromLua for SearchFilterSpec {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		let table: mlua::Table = FromLua::from_lua(value, lua)?;
+		let statuses: Vec<String> = table.get("statuses").ok().unwrap_or_default();
+		let sort_orders: Vec<String> = table.get("sort_orders").ok().unwrap_or_default();
+
+		Ok(SearchFilterSpec {
+			genres: table.get("genres").ok().unwrap_or_default(),
+			exclude_genres: table.get("exclude_genres").ok().unwrap_or_default(),
+			statuses: statuses.iter().filter_map(|s| PageStatus::parse(s)).collect(),
+			types: table.get("types").ok().unwrap_or_default(),
+			sort_orders: sort_orders.iter().filter_map(|s| SortOrder::parse(s)).collect(),
+			year: table.get("year").ok().unwrap_or_default(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::ScraperErrorKind;
+
+	fn spec() -> SearchFilterSpec {
+		SearchFilterSpec {
+			genres: vec!["Action".to_string(), "Slice of Life".to_string()],
+			exclude_genres: false,
+			statuses: vec![PageStatus::Ongoing, PageStatus::Completed],
+			types: vec!["Manhwa".to_string()],
+			sort_orders: vec![SortOrder::Latest],
+			year: true,
+		}
+	}
+
+	#[test]
+	fn test_sort_order_parse() {
+		assert_eq!(SortOrder::parse("latest"), Some(SortOrder::Latest));
+		assert_eq!(SortOrder::parse(" Latest "), Some(SortOrder::Latest));
+		assert_eq!(SortOrder::parse("RATING"), Some(SortOrder::Rating));
+		assert_eq!(SortOrder::parse("newest"), None);
+	}
+
+	#[test]
+	fn test_resolve_uses_declared_spelling() {
+		let filters = SearchFilters {
+			include_genres: vec!["slice of life".to_string()],
+			status: Some(PageStatus::Completed),
+			page_type: Some("manhwa".to_string()),
+			sort: Some(SortOrder::Latest),
+			year: Some(2020),
+			..Default::default()
+		};
+
+		let resolved = spec().resolve(filters).expect("filters should be supported");
+		assert_eq!(resolved.include_genres, vec!["Slice of Life"]);
+		assert_eq!(resolved.page_type.as_deref(), Some("Manhwa"));
+		assert_eq!(resolved.year, Some(2020));
+	}
+
+	#[test]
+	fn test_resolve_rejects_undeclared_filters() {
+		let unsupported = [
+			SearchFilters {
+				include_genres: vec!["Horror".to_string()],
+				..Default::default()
+			},
+			SearchFilters {
+				exclude_genres: vec!["Action".to_string()],
+				..Default::default()
+			},
+			SearchFilters {
+				status: Some(PageStatus::Hiatus),
+				..Default::default()
+			},
+			SearchFilters {
+				sort: Some(SortOrder::Rating),
+				..Default::default()
+			},
+		];
+
+		for filters in unsupported {
+			let err = spec().resolve(filters).expect_err("filter should be rejected");
+			assert_eq!(err.kind, ScraperErrorKind::Validation);
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "lua")]
+	fn test_spec_lua_roundtrip() {
+		let lua = Lua::new();
+		let value = spec().into_lua(&lua).expect("IntoLua failed");
+		let recovered: SearchFilterSpec = FromLua::from_lua(value, &lua).expect("FromLua failed");
+		assert_eq!(recovered, spec());
+	}
+}
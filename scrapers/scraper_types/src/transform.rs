@@ -0,0 +1,85 @@
+#[cfg(feature = "lua")]
+use mlua::{FromLua, IntoLua, Lua, Value};
+use serde::{Deserialize, Serialize};
+
+/// A chapter page downloaded by the image proxy and handed to the plugin's page transform.
+///
+/// Plugin-specific data, such as a descrambling key, can travel in the url fragment, which is never sent upstream.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PageImage {
+	pub url: String,
+	pub referer: Option<String>,
+	pub content_type: String,
+	pub data: Vec<u8>,
+}
+
+impl PageImage {
+	/// The part of `url` after `#`, if any.
+	pub fn fragment(&self) -> Option<&str> {
+		self.url.split_once('#').map(|(_, fragment)| fragment)
+	}
+}
+
+/// Image served in place of the downloaded page.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ProcessedImage {
+	pub content_type: String,
+	pub data: Vec<u8>,
+}
+
+#[cfg(feature = "lua")]
+impl IntoLua for PageImage {
+	fn into_lua(self, lua: &Lua) -> mlua::Result<Value> {
+		let table = lua.create_table()?;
+		table.set("url", self.url)?;
+		table.set("referer", self.referer)?;
+		table.set("content_type", self.content_type)?;
+		// Lua strings are byte strings, so the image is passed as one.
+		table.set("data", lua.create_string(&self.data)?)?;
+		Ok(Value::Table(table))
+	}
+}
+
+#[cfg(feature = "lua")]
+impl FromLua for ProcessedImage {
+	fn from_lua(value: Value, lua: &Lua) -> mlua::Result<Self> {
+		let table: mlua::Table = FromLua::from_lua(value, lua)?;
+		let data: mlua::String = table.get("data")?;
+		Ok(ProcessedImage {
+			content_type: table.get("content_type")?,
+			data: data.as_bytes().to_vec(),
+		})
+	}
+}
+
+#[cfg(all(test, feature = "lua"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_page_image_lua_roundtrip() {
+		let lua = Lua::new();
+		let transform: mlua::Function = lua
+			.load(
+				r#"
+				return function(page)
+					return { content_type = page.content_type, data = string.reverse(page.data) }
+				end
+				"#,
+			)
+			.eval()
+			.expect("failed to load transform");
+
+		let page = PageImage {
+			url: "https://example.com/1.png#key=42".to_string(),
+			referer: None,
+			content_type: "image/png".to_string(),
+			data: vec![0, 159, 146, 150, 255],
+		};
+		assert_eq!(page.fragment(), Some("key=42"));
+
+		let processed: ProcessedImage = transform.call(page).expect("transform failed");
+		assert_eq!(processed.content_type, "image/png");
+		assert_eq!(processed.data, vec![255, 150, 146, 159, 0]);
+	}
+}
@@ -2,17 +2,58 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use database_connection::Database;
-use scheduler::MangaUpdateScheduler;
+use scheduler::{MaintenanceScheduler, MangaUpdateScheduler};
 use scraper_core::ScraperManager;
-use tracing_subscriber::FmtSubscriber;
 
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 const MANGA_VAULT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Services a process runs, picked with `--role`. Processes sharing a database split the work, so scraping can run on
+/// other machines than the API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+	/// The GraphQL API and the website.
+	Api,
+	/// A scheduler worker.
+	Scheduler,
+	All,
+}
+
+impl Role {
+	fn from_args() -> Result<Self, String> {
+		let mut args = std::env::args().skip(1);
+		let mut role = Role::All;
+		while let Some(arg) = args.next() {
+			let value = match arg.strip_prefix("--role=") {
+				Some(value) => value.to_string(),
+				None if arg == "--role" => args.next().ok_or("Missing value for --role")?,
+				None => return Err(format!("Unknown argument: {}", arg)),
+			};
+			role = match value.as_str() {
+				"api" => Role::Api,
+				"scheduler" => Role::Scheduler,
+				"all" => Role::All,
+				_ => return Err(format!("Unknown role `{}`, expected api, scheduler or all", value)),
+			};
+		}
+		Ok(role)
+	}
+
+	fn runs_api(self) -> bool {
+		self != Role::Scheduler
+	}
+
+	fn runs_scheduler(self) -> bool {
+		self != Role::Api
+	}
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-	let subscriber = FmtSubscriber::builder().with_max_level(tracing::Level::INFO).finish();
-	tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+	let _telemetry = telemetry::init("manga-vault");
+
+	let role = Role::from_args()?;
+	tracing::info!("Starting as {:?}", role);
 
 	let latest_release = version_check::get_latest_release(PACKAGE_NAME).await;
 
@@ -51,25 +92,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 	let db = Database::new().await?;
 	let scraper_manager = ScraperManager::new(update).await?;
 
-	let scheduler_fut = Arc::new(MangaUpdateScheduler::new(
-		db.clone(),
-		scraper_manager.clone(),
-		5,
-		Duration::from_secs(30 * 60),
-		Duration::from_secs(10),
-	))
-	.start();
-	let gql_fut = gql_api::run(db.clone(), scraper_manager.clone());
-	let web_fut = website_server::run();
-
-	match tokio::try_join!(gql_fut, web_fut, scheduler_fut) {
-		Ok((_, _, _)) => {
-			tracing::info!("Servers exited gracefully");
+	let scheduler_fut = async {
+		if !role.runs_scheduler() {
+			return Ok(());
 		}
-		Err(e) => {
-			tracing::error!("One of the servers exited with error: {:?}", e);
+		// The API runs maintenance and serves metrics itself; scheduler-only processes do it here.
+		if !role.runs_api() {
+			tokio::spawn(MaintenanceScheduler::new(db.clone(), scraper_manager.clone()).start());
+			tokio::spawn(async {
+				if let Err(e) = telemetry::serve_metrics().await {
+					tracing::error!("Metrics server failed: {:#}", e);
+				}
+			});
+		}
+		Arc::new(MangaUpdateScheduler::new(
+			db.clone(),
+			scraper_manager.clone(),
+			5,
+			Duration::from_secs(30 * 60),
+		))
+		.start()
+		.await
+	};
+	let gql_fut = async {
+		if !role.runs_api() {
+			return Ok(());
+		}
+		gql_api::run(db.clone(), scraper_manager.clone()).await
+	};
+	let web_fut = async {
+		if !role.runs_api() {
+			return Ok(());
+		}
+		website_server::run().await
+	};
+
+	tokio::select! {
+		result = async { tokio::try_join!(gql_fut, web_fut, scheduler_fut) } => match result {
+			Ok((_, _, _)) => {
+				tracing::info!("Servers exited gracefully");
+			}
+			Err(e) => {
+				tracing::error!("One of the servers exited with error: {:?}", e);
+			}
+		},
+		_ = tokio::signal::ctrl_c() => {
+			tracing::info!("Shutting down");
 		}
 	}
+	scraper_manager.shutdown();
 
 	Ok(())
 }
@@ -0,0 +1,111 @@
+#![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub use crate::logging::{
+	LogFileConfig, LogFormat, LogRotation, LoggingConfig, log_event, log_filter, reload_logging, set_log_filter,
+};
+
+mod logging;
+pub mod metrics;
+
+/// Standard variable the OTLP exporter reads its endpoint from when the config has none.
+const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+#[derive(Debug, Default, Deserialize, Serialize, config_derive::Config)]
+#[config(name = "telemetry")]
+pub struct Config {
+	/// OTLP/HTTP endpoint spans are exported to, e.g. `http://otel-collector:4318/v1/traces`. Spans are only logged
+	/// when neither this nor `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+	#[serde(default)]
+	pub otlp_endpoint: Option<String>,
+	/// Address of a separate `/metrics` server, e.g. `0.0.0.0:9464`, for processes that don't serve the API.
+	#[serde(default)]
+	pub metrics_address: Option<String>,
+	#[serde(default)]
+	pub logging: LoggingConfig,
+}
+
+/// Keeps the span exporter running. Dropping it sends the spans still buffered.
+pub struct TelemetryGuard {
+	provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+	fn drop(&mut self) {
+		if let Some(provider) = self.provider.take()
+			&& let Err(e) = provider.shutdown()
+		{
+			eprintln!("Failed to flush trace spans: {}", e);
+		}
+	}
+}
+
+/// Logs as configured under `logging` and, when an OTLP endpoint is configured, exports spans as `service_name`.
+pub fn init(service_name: &str) -> TelemetryGuard {
+	let config = Config::load();
+
+	let provider = match tracer_provider(&config, service_name) {
+		Ok(provider) => provider,
+		Err(e) => {
+			eprintln!("Failed to set up trace export, only logging: {}", e);
+			None
+		}
+	};
+	let otel_layer = provider
+		.as_ref()
+		.map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.to_string())));
+
+	let (filter, output) = logging::layers(&config.logging, service_name);
+	let _ = tracing_subscriber::registry()
+		.with(filter)
+		.with(output)
+		.with(otel_layer)
+		.try_init();
+
+	if provider.is_some() {
+		tracing::info!("Exporting trace spans over OTLP");
+	}
+	TelemetryGuard { provider }
+}
+
+fn tracer_provider(config: &Config, service_name: &str) -> anyhow::Result<Option<SdkTracerProvider>> {
+	let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_http();
+	match &config.otlp_endpoint {
+		Some(endpoint) => exporter = exporter.with_endpoint(endpoint),
+		None if std::env::var_os(OTLP_ENDPOINT_ENV).is_some() => {}
+		None => return Ok(None),
+	}
+
+	let provider = SdkTracerProvider::builder()
+		.with_batch_exporter(exporter.build()?)
+		.with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+		.build();
+	Ok(Some(provider))
+}
+
+/// Serves `/metrics` on `metrics_address`, if configured. Processes serving the API expose it there instead.
+pub async fn serve_metrics() -> anyhow::Result<()> {
+	let Some(address) = Config::load().metrics_address else {
+		return Ok(());
+	};
+
+	let app = axum::Router::new().route("/metrics", axum::routing::get(render_metrics));
+	let listener = tokio::net::TcpListener::bind(&address).await?;
+	tracing::info!("Serving metrics on http://{}/metrics", address);
+	axum::serve(listener, app).await?;
+	Ok(())
+}
+
+/// `/metrics` handler rendering the process-wide registry.
+pub async fn render_metrics() -> impl axum::response::IntoResponse {
+	(
+		[(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+		metrics::registry().gather().await,
+	)
+}
@@ -0,0 +1,347 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tracing::callsite::{Callsite, Identifier};
+use tracing::field::{FieldSet, Value};
+use tracing::metadata::Kind;
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::{EnvFilter, Layer, Registry, reload};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoggingConfig {
+	/// Level of every target without a filter of its own.
+	#[serde(default = "default_level")]
+	pub level: String,
+	/// Levels by target, e.g. `{ "scheduler": "debug", "plugin::mangadex": "trace" }`. Plugins log under
+	/// `plugin::<file name>`.
+	#[serde(default)]
+	pub filters: BTreeMap<String, String>,
+	#[serde(default)]
+	pub format: LogFormat,
+	/// Also writes logs to rotating files when set.
+	#[serde(default)]
+	pub file: Option<LogFileConfig>,
+}
+
+impl Default for LoggingConfig {
+	fn default() -> Self {
+		Self {
+			level: default_level(),
+			filters: BTreeMap::new(),
+			format: LogFormat::default(),
+			file: None,
+		}
+	}
+}
+
+fn default_level() -> String {
+	"info".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+	/// One line per event.
+	#[default]
+	Text,
+	/// Several lines per event, with its fields and spans spelled out.
+	Pretty,
+	/// One JSON object per line, for log collectors.
+	Json,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogFileConfig {
+	pub directory: String,
+	/// Start of the file names, the process name by default.
+	#[serde(default)]
+	pub prefix: Option<String>,
+	#[serde(default)]
+	pub rotation: LogRotation,
+	/// Rotated files kept before the oldest is removed. Keeps them all when unset.
+	#[serde(default)]
+	pub max_files: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+	Hourly,
+	#[default]
+	Daily,
+	Weekly,
+	Never,
+}
+
+impl LogRotation {
+	fn rotation(self) -> Rotation {
+		match self {
+			LogRotation::Hourly => Rotation::HOURLY,
+			LogRotation::Daily => Rotation::DAILY,
+			LogRotation::Weekly => Rotation::WEEKLY,
+			LogRotation::Never => Rotation::NEVER,
+		}
+	}
+}
+
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type Output = Vec<Box<dyn Layer<Filtered> + Send + Sync>>;
+
+/// Handles swapping the filter and outputs of the running subscriber.
+struct Reload {
+	service_name: String,
+	filter: reload::Handle<EnvFilter, Registry>,
+	output: reload::Handle<Output, Filtered>,
+}
+
+static RELOAD: OnceLock<Reload> = OnceLock::new();
+
+/// Builds the filter and outputs of `config`, falling back to logging at `info` to stdout when it is invalid.
+pub(crate) fn layers(
+	config: &LoggingConfig,
+	service_name: &str,
+) -> (reload::Layer<EnvFilter, Registry>, reload::Layer<Output, Filtered>) {
+	let filter = filter(config).unwrap_or_else(|e| {
+		eprintln!("Invalid log filter, logging at info: {:#}", e);
+		EnvFilter::new(default_level())
+	});
+	let output = output(config, service_name).unwrap_or_else(|e| {
+		eprintln!("Failed to set up log files, only logging to stdout: {:#}", e);
+		vec![fmt_layer(config.format, std::io::stdout, true)]
+	});
+
+	let (filter, filter_handle) = reload::Layer::new(filter);
+	let (output, output_handle) = reload::Layer::new(output);
+	let _ = RELOAD.set(Reload {
+		service_name: service_name.to_string(),
+		filter: filter_handle,
+		output: output_handle,
+	});
+	(filter, output)
+}
+
+fn filter(config: &LoggingConfig) -> anyhow::Result<EnvFilter> {
+	let mut directives = vec![config.level.clone()];
+	directives.extend(config.filters.iter().map(|(target, level)| format!("{}={}", target, level)));
+	parse_filter(&directives.join(","))
+}
+
+fn parse_filter(directives: &str) -> anyhow::Result<EnvFilter> {
+	EnvFilter::builder()
+		.parse(directives)
+		.with_context(|| format!("Invalid log filter `{}`", directives))
+}
+
+fn output(config: &LoggingConfig, service_name: &str) -> anyhow::Result<Output> {
+	let mut layers = vec![fmt_layer(config.format, std::io::stdout, true)];
+	if let Some(file) = &config.file {
+		std::fs::create_dir_all(&file.directory)
+			.with_context(|| format!("Failed to create log directory {}", file.directory))?;
+		let mut appender = RollingFileAppender::builder()
+			.rotation(file.rotation.rotation())
+			.filename_prefix(file.prefix.as_deref().unwrap_or(service_name))
+			.filename_suffix("log");
+		if let Some(max_files) = file.max_files {
+			appender = appender.max_log_files(max_files.max(1));
+		}
+		let appender = appender
+			.build(&file.directory)
+			.with_context(|| format!("Failed to open log files in {}", file.directory))?;
+		layers.push(fmt_layer(config.format, appender, false));
+	}
+	Ok(layers)
+}
+
+fn fmt_layer<W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<Filtered> + Send + Sync>
+where
+	W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+	let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(ansi);
+	match format {
+		LogFormat::Text => Box::new(layer),
+		LogFormat::Pretty => Box::new(layer.pretty()),
+		LogFormat::Json => Box::new(layer.json()),
+	}
+}
+
+fn reload_handles() -> anyhow::Result<&'static Reload> {
+	RELOAD.get().context("Logging was not set up by telemetry::init")
+}
+
+/// Reads `logging` from `telemetry.json` again and applies it, outputs included. Returns the filter now in effect.
+pub fn reload_logging() -> anyhow::Result<String> {
+	let handles = reload_handles()?;
+	let config = std::panic::catch_unwind(crate::Config::load)
+		.map_err(|_| anyhow::anyhow!("Failed to read telemetry.json"))?
+		.logging;
+
+	let filter = filter(&config)?;
+	let directives = filter.to_string();
+	let output = output(&config, &handles.service_name)?;
+	handles.filter.reload(filter)?;
+	handles.output.reload(output)?;
+
+	tracing::info!("Reloaded logging, filter is now `{}`", directives);
+	Ok(directives)
+}
+
+/// Applies `directives`, e.g. `info,plugin::mangadex=debug`, until the next reload or restart.
+pub fn set_log_filter(directives: &str) -> anyhow::Result<String> {
+	let handles = reload_handles()?;
+	let filter = parse_filter(directives)?;
+	let directives = filter.to_string();
+	handles.filter.reload(filter)?;
+
+	tracing::info!("Log filter set to `{}`", directives);
+	Ok(directives)
+}
+
+/// The filter in effect, if logging was set up.
+pub fn log_filter() -> Option<String> {
+	RELOAD.get()?.filter.with_current(|filter| filter.to_string()).ok()
+}
+
+/// Callsite of events whose target is only known at runtime, one per target and level.
+struct DynamicCallsite {
+	metadata: OnceLock<Metadata<'static>>,
+}
+
+impl Callsite for DynamicCallsite {
+	fn set_interest(&self, _: Interest) {}
+
+	fn metadata(&self) -> &Metadata<'_> {
+		self.metadata
+			.get()
+			.expect("metadata is set before the callsite is registered")
+	}
+}
+
+static CALLSITES: LazyLock<Mutex<HashMap<(String, Level), &'static DynamicCallsite>>> =
+	LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn callsite(target: &str, level: Level) -> &'static DynamicCallsite {
+	let mut callsites = CALLSITES.lock().unwrap_or_else(|e| e.into_inner());
+	if let Some(callsite) = callsites.get(&(target.to_string(), level)) {
+		return callsite;
+	}
+
+	// Leaked once per target and level, like the statics the `tracing` macros expand to.
+	let callsite: &'static DynamicCallsite = Box::leak(Box::new(DynamicCallsite {
+		metadata: OnceLock::new(),
+	}));
+	let target: &'static str = Box::leak(target.to_string().into_boxed_str());
+	let _ = callsite.metadata.set(Metadata::new(
+		"event",
+		target,
+		level,
+		None,
+		None,
+		None,
+		FieldSet::new(&["message"], Identifier(callsite)),
+		Kind::EVENT,
+	));
+	tracing::callsite::register(callsite);
+
+	callsites.insert((target.to_string(), level), callsite);
+	callsite
+}
+
+/// Logs `message` under `target`, which the `tracing` macros only take as a constant. Lets filters pick out the
+/// logs of a single plugin.
+pub fn log_event(target: &str, level: Level, message: &str) {
+	if level > tracing::level_filters::LevelFilter::current() {
+		return;
+	}
+
+	let metadata = callsite(target, level).metadata();
+	if !tracing::dispatcher::get_default(|dispatch| dispatch.enabled(metadata)) {
+		return;
+	}
+
+	let fields = metadata.fields();
+	let Some(field) = fields.field("message") else {
+		return;
+	};
+	let message = format_args!("{}", message);
+	Event::dispatch(metadata, &fields.value_set(&[(&field, Some(&message as &dyn Value))]));
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+
+	#[derive(Clone, Default)]
+	struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+	impl std::io::Write for Buffer {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl Buffer {
+		fn contents(&self) -> String {
+			String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+		}
+	}
+
+	#[test]
+	fn test_log_event_filters_by_target() {
+		let buffer = Buffer::default();
+		let writer = buffer.clone();
+		let subscriber = tracing_subscriber::fmt()
+			.with_env_filter("warn,plugin::mangadex=debug")
+			.with_writer(move || writer.clone())
+			.with_ansi(false)
+			.finish();
+
+		tracing::subscriber::with_default(subscriber, || {
+			log_event("plugin::mangadex", Level::DEBUG, "found 12 chapters");
+			log_event("plugin::other", Level::DEBUG, "hidden");
+			log_event("plugin::other", Level::WARN, "rate limited");
+		});
+
+		let logs = buffer.contents();
+		assert!(logs.contains("DEBUG plugin::mangadex: found 12 chapters"));
+		assert!(!logs.contains("hidden"));
+		assert!(logs.contains("WARN plugin::other: rate limited"));
+		assert!(std::ptr::eq(
+			callsite("plugin::mangadex", Level::DEBUG),
+			callsite("plugin::mangadex", Level::DEBUG)
+		));
+	}
+
+	#[test]
+	fn test_filter_from_config() {
+		let config: LoggingConfig = serde_json::from_str(
+			r#"{ "level": "warn", "filters": { "scheduler": "debug", "plugin::mangadex": "trace" }, "format": "json" }"#,
+		)
+		.unwrap();
+		assert_eq!(config.format, LogFormat::Json);
+
+		let directives = filter(&config).unwrap().to_string();
+		assert!(directives.contains("scheduler=debug"));
+		assert!(directives.contains("plugin::mangadex=trace"));
+		assert!(directives.contains("warn"));
+
+		let config = LoggingConfig {
+			filters: BTreeMap::from([("scheduler".to_string(), "loud".to_string())]),
+			..Default::default()
+		};
+		assert!(filter(&config).is_err());
+	}
+}
@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock, Mutex};
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::default);
+
+/// Registry every crate of the process records to, rendered on `/metrics`.
+pub fn registry() -> &'static Registry {
+	&REGISTRY
+}
+
+/// Upper bounds of the histogram buckets, in seconds.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Refreshes gauges right before a scrape, e.g. from the database, and renders metrics kept elsewhere.
+pub type Collector = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = String> + Send>> + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+	Counter,
+	Gauge,
+	Histogram,
+}
+
+impl Kind {
+	fn name(self) -> &'static str {
+		match self {
+			Kind::Counter => "counter",
+			Kind::Gauge => "gauge",
+			Kind::Histogram => "histogram",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+	buckets: [u64; BUCKETS.len()],
+	sum: f64,
+	count: u64,
+}
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug)]
+struct Family {
+	help: &'static str,
+	kind: Kind,
+	values: BTreeMap<Labels, f64>,
+	histograms: BTreeMap<Labels, Histogram>,
+}
+
+/// Counters, gauges and histograms by name and labels, in the Prometheus text format.
+#[derive(Default)]
+pub struct Registry {
+	families: Mutex<BTreeMap<&'static str, Family>>,
+	collectors: Mutex<BTreeMap<&'static str, Collector>>,
+}
+
+impl Registry {
+	fn update(&self, name: &'static str, help: &'static str, kind: Kind, update: impl FnOnce(&mut Family)) {
+		let mut families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+		let family = families.entry(name).or_insert_with(|| Family {
+			help,
+			kind,
+			values: BTreeMap::new(),
+			histograms: BTreeMap::new(),
+		});
+		if family.kind != kind {
+			tracing::warn!("Metric {} is a {}, not a {}", name, family.kind.name(), kind.name());
+			return;
+		}
+		update(family);
+	}
+
+	/// Adds one to a counter.
+	pub fn increment(&self, name: &'static str, help: &'static str, labels: &[(&'static str, &str)]) {
+		self.update(name, help, Kind::Counter, |family| {
+			*family.values.entry(owned(labels)).or_default() += 1.0;
+		});
+	}
+
+	pub fn set(&self, name: &'static str, help: &'static str, labels: &[(&'static str, &str)], value: f64) {
+		self.update(name, help, Kind::Gauge, |family| {
+			family.values.insert(owned(labels), value);
+		});
+	}
+
+	/// Drops every value of a gauge, so labels that went away are no longer rendered.
+	pub fn clear(&self, name: &'static str) {
+		let mut families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+		if let Some(family) = families.get_mut(name) {
+			family.values.clear();
+		}
+	}
+
+	/// Records a duration, in seconds, in a histogram.
+	pub fn observe(&self, name: &'static str, help: &'static str, labels: &[(&'static str, &str)], seconds: f64) {
+		self.update(name, help, Kind::Histogram, |family| {
+			let histogram = family.histograms.entry(owned(labels)).or_default();
+			for (bucket, bound) in histogram.buckets.iter_mut().zip(BUCKETS) {
+				if seconds <= bound {
+					*bucket += 1;
+				}
+			}
+			histogram.sum += seconds;
+			histogram.count += 1;
+		});
+	}
+
+	/// Runs `collector` before every scrape. Registering another collector under the same name replaces it.
+	pub fn register_collector(&self, name: &'static str, collector: Collector) {
+		let mut collectors = self.collectors.lock().unwrap_or_else(|e| e.into_inner());
+		collectors.insert(name, collector);
+	}
+
+	/// Runs the collectors, then renders every metric.
+	pub async fn gather(&self) -> String {
+		let collectors: Vec<Collector> = {
+			let collectors = self.collectors.lock().unwrap_or_else(|e| e.into_inner());
+			collectors.values().cloned().collect()
+		};
+
+		let mut collected = String::new();
+		for collector in collectors {
+			collected.push_str(&collector().await);
+		}
+
+		let mut out = self.render();
+		out.push_str(&collected);
+		out
+	}
+
+	pub fn render(&self) -> String {
+		let families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+		let mut out = String::new();
+
+		for (name, family) in families.iter() {
+			let _ = writeln!(out, "# HELP {} {}", name, family.help);
+			let _ = writeln!(out, "# TYPE {} {}", name, family.kind.name());
+
+			for (labels, value) in &family.values {
+				let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), value);
+			}
+
+			for (labels, histogram) in &family.histograms {
+				for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+					let le = bound.to_string();
+					let _ = writeln!(out, "{}_bucket{} {}", name, render_labels(labels, Some(&le)), count);
+				}
+				let _ = writeln!(
+					out,
+					"{}_bucket{} {}",
+					name,
+					render_labels(labels, Some("+Inf")),
+					histogram.count
+				);
+				let _ = writeln!(out, "{}_sum{} {}", name, render_labels(labels, None), histogram.sum);
+				let _ = writeln!(out, "{}_count{} {}", name, render_labels(labels, None), histogram.count);
+			}
+		}
+
+		out
+	}
+}
+
+fn owned(labels: &[(&'static str, &str)]) -> Labels {
+	labels.iter().map(|(name, value)| (*name, value.to_string())).collect()
+}
+
+fn render_labels(labels: &Labels, le: Option<&str>) -> String {
+	let mut pairs: Vec<String> = labels
+		.iter()
+		.map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+		.collect();
+	if let Some(le) = le {
+		pairs.push(format!("le=\"{}\"", le));
+	}
+
+	if pairs.is_empty() {
+		String::new()
+	} else {
+		format!("{{{}}}", pairs.join(","))
+	}
+}
+
+fn escape(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_render() {
+		let registry = Registry::default();
+		registry.increment("jobs_total", "Jobs run.", &[("scraper", "mangadex")]);
+		registry.increment("jobs_total", "Jobs run.", &[("scraper", "mangadex")]);
+		registry.set("queue_depth", "Queued jobs.", &[], 3.0);
+		registry.set("queue_depth", "Queued jobs.", &[("scraper", "odd \"name\"")], 1.0);
+		registry.observe("call_seconds", "Call latency.", &[("call", "scrape")], 0.2);
+		// A gauge recorded as a counter is ignored rather than rendered twice.
+		registry.increment("queue_depth", "Queued jobs.", &[]);
+
+		let metrics = registry.render();
+		assert!(metrics.contains("# TYPE jobs_total counter\njobs_total{scraper=\"mangadex\"} 2\n"));
+		assert!(metrics.contains("queue_depth 3\n"));
+		assert!(metrics.contains("queue_depth{scraper=\"odd \\\"name\\\"\"} 1\n"));
+		assert!(metrics.contains("call_seconds_bucket{call=\"scrape\",le=\"0.1\"} 0\n"));
+		assert!(metrics.contains("call_seconds_bucket{call=\"scrape\",le=\"0.25\"} 1\n"));
+		assert!(metrics.contains("call_seconds_bucket{call=\"scrape\",le=\"+Inf\"} 1\n"));
+		assert!(metrics.contains("call_seconds_count{call=\"scrape\"} 1\n"));
+
+		registry.clear("queue_depth");
+		assert!(!registry.render().contains("queue_depth 3"));
+	}
+
+	#[tokio::test]
+	async fn test_gather_runs_collectors() {
+		let registry = Arc::new(Registry::default());
+		let target = Arc::clone(&registry);
+		registry.register_collector(
+			"pool",
+			Arc::new(move || {
+				target.set("pool_connections", "Open connections.", &[], 4.0);
+				Box::pin(async { "external_metric 1\n".to_string() })
+			}),
+		);
+
+		let metrics = registry.gather().await;
+		assert!(metrics.contains("pool_connections 4\n"));
+		assert!(metrics.ends_with("external_metric 1\n"));
+	}
+}